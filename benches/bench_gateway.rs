@@ -0,0 +1,71 @@
+#![feature(test)]
+
+#[cfg(test)]
+mod benches {
+    extern crate test;
+
+    use serde::Deserialize;
+    use serde_json::Value;
+    use serenity::gateway::GatewayPayload;
+    use serenity::model::event::GatewayEvent;
+    use self::test::Bencher;
+
+    fn dispatch(kind: &str, data: &str) -> String {
+        format!(r#"{{"t":"{}","s":1,"op":0,"d":{}}}"#, kind, data)
+    }
+
+    fn guild_create() -> String {
+        dispatch("GUILD_CREATE", include_str!("../tests/resources/guild_create_1.json"))
+    }
+
+    fn message_create() -> String {
+        dispatch("MESSAGE_CREATE", include_str!("../tests/resources/message_create_1.json"))
+    }
+
+    // Frames come off the socket as owned strings, so every bench starts each
+    // iteration from a fresh copy, keeping its cost the same on both sides.
+    fn received(text: &str) -> String {
+        text.to_owned()
+    }
+
+    #[bench]
+    fn guild_create_through_value(b: &mut Bencher) {
+        let text = guild_create();
+
+        b.iter(|| {
+            let value: Value = serde_json::from_str(&received(&text)).unwrap();
+            GatewayEvent::deserialize(value).unwrap()
+        })
+    }
+
+    #[bench]
+    fn guild_create_from_payload(b: &mut Bencher) {
+        let text = guild_create();
+
+        b.iter(|| GatewayPayload::from_json(received(&text)).unwrap().into_event().unwrap())
+    }
+
+    #[bench]
+    fn guild_create_envelope_only(b: &mut Bencher) {
+        let text = guild_create();
+
+        b.iter(|| GatewayPayload::from_json(received(&text)).unwrap())
+    }
+
+    #[bench]
+    fn message_create_through_value(b: &mut Bencher) {
+        let text = message_create();
+
+        b.iter(|| {
+            let value: Value = serde_json::from_str(&received(&text)).unwrap();
+            GatewayEvent::deserialize(value).unwrap()
+        })
+    }
+
+    #[bench]
+    fn message_create_from_payload(b: &mut Bencher) {
+        let text = message_create();
+
+        b.iter(|| GatewayPayload::from_json(received(&text)).unwrap().into_event().unwrap())
+    }
+}
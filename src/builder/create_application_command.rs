@@ -0,0 +1,184 @@
+use crate::internal::prelude::*;
+use crate::model::interactions::ApplicationCommandOptionType;
+use crate::utils;
+use std::collections::HashMap;
+
+/// A builder to create or edit an [`ApplicationCommand`].
+///
+/// This is used by [`ApplicationCommand::create_global_application_command`],
+/// [`ApplicationCommand::edit_global_application_command`],
+/// [`GuildId::create_application_command`] and
+/// [`GuildId::edit_application_command`].
+///
+/// # Examples
+///
+/// Create a guild command taking a required user option:
+///
+/// ```rust,no_run
+/// # use serenity::{model::{id::GuildId, interactions::ApplicationCommandOptionType}, http::Http};
+/// # use std::sync::Arc;
+/// #
+/// # let http = Arc::new(Http::default());
+/// # let guild_id = GuildId(2);
+/// #
+/// let command = guild_id.create_application_command(&http, 1, |c| {
+///     c.name("wave")
+///         .description("Waves at a user")
+///         .create_option(|o| {
+///             o.name("user")
+///                 .description("The user to wave at")
+///                 .kind(ApplicationCommandOptionType::User)
+///                 .required(true)
+///         })
+/// });
+/// ```
+///
+/// [`ApplicationCommand`]: ../model/interactions/struct.ApplicationCommand.html
+/// [`ApplicationCommand::create_global_application_command`]: ../model/interactions/struct.ApplicationCommand.html#method.create_global_application_command
+/// [`ApplicationCommand::edit_global_application_command`]: ../model/interactions/struct.ApplicationCommand.html#method.edit_global_application_command
+/// [`GuildId::create_application_command`]: ../model/id/struct.GuildId.html#method.create_application_command
+/// [`GuildId::edit_application_command`]: ../model/id/struct.GuildId.html#method.edit_application_command
+#[derive(Clone, Debug, Default)]
+pub struct CreateApplicationCommand(pub HashMap<&'static str, Value>);
+
+impl CreateApplicationCommand {
+    /// The name of the command, 3 to 32 characters long.
+    ///
+    /// **Note**: Must be lowercase.
+    pub fn name<S: ToString>(&mut self, name: S) -> &mut Self {
+        self.0.insert("name", Value::String(name.to_string()));
+        self
+    }
+
+    /// The description of the command, 1 to 100 characters long.
+    pub fn description<S: ToString>(&mut self, description: S) -> &mut Self {
+        self.0.insert("description", Value::String(description.to_string()));
+        self
+    }
+
+    /// Creates a parameter for the command and appends it to the existing
+    /// ones.
+    ///
+    /// Refer to [`CreateApplicationCommandOption`] for more information.
+    ///
+    /// [`CreateApplicationCommandOption`]: struct.CreateApplicationCommandOption.html
+    pub fn create_option<F>(&mut self, f: F) -> &mut Self
+        where F: FnOnce(&mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+        let mut option = CreateApplicationCommandOption::default();
+        f(&mut option);
+
+        self.add_option(option)
+    }
+
+    /// Appends an already built parameter to the existing ones.
+    pub fn add_option(&mut self, option: CreateApplicationCommandOption) -> &mut Self {
+        let option = Value::Object(utils::hashmap_to_json_map(option.0));
+
+        let options = self.0.entry("options").or_insert_with(|| Value::Array(Vec::new()));
+
+        if let Value::Array(ref mut options) = *options {
+            options.push(option);
+        }
+
+        self
+    }
+
+    /// Sets all parameters of the command, replacing existing ones.
+    pub fn set_options(&mut self, options: Vec<CreateApplicationCommandOption>) -> &mut Self {
+        let options = options
+            .into_iter()
+            .map(|o| Value::Object(utils::hashmap_to_json_map(o.0)))
+            .collect();
+
+        self.0.insert("options", Value::Array(options));
+        self
+    }
+}
+
+/// A builder for a parameter of an [`ApplicationCommand`], used by
+/// [`CreateApplicationCommand::create_option`].
+///
+/// [`ApplicationCommand`]: ../model/interactions/struct.ApplicationCommand.html
+/// [`CreateApplicationCommand::create_option`]: struct.CreateApplicationCommand.html#method.create_option
+#[derive(Clone, Debug, Default)]
+pub struct CreateApplicationCommandOption(pub HashMap<&'static str, Value>);
+
+impl CreateApplicationCommandOption {
+    /// The type of the parameter.
+    pub fn kind(&mut self, kind: ApplicationCommandOptionType) -> &mut Self {
+        self.0.insert("type", Value::Number(Number::from(kind.num())));
+        self
+    }
+
+    /// The name of the parameter, 1 to 32 characters long.
+    ///
+    /// **Note**: Must be lowercase.
+    pub fn name<S: ToString>(&mut self, name: S) -> &mut Self {
+        self.0.insert("name", Value::String(name.to_string()));
+        self
+    }
+
+    /// The description of the parameter, 1 to 100 characters long.
+    pub fn description<S: ToString>(&mut self, description: S) -> &mut Self {
+        self.0.insert("description", Value::String(description.to_string()));
+        self
+    }
+
+    /// Whether this is the first required parameter. Only one parameter of a
+    /// command may be the default.
+    pub fn default_option(&mut self, default: bool) -> &mut Self {
+        self.0.insert("default", Value::Bool(default));
+        self
+    }
+
+    /// Whether the parameter must be filled in.
+    pub fn required(&mut self, required: bool) -> &mut Self {
+        self.0.insert("required", Value::Bool(required));
+        self
+    }
+
+    /// Adds a choice for a [`String`] parameter.
+    ///
+    /// [`String`]: ../model/interactions/enum.ApplicationCommandOptionType.html#variant.String
+    pub fn add_string_choice<N: ToString, V: ToString>(&mut self, name: N, value: V) -> &mut Self {
+        self.add_choice(name, Value::String(value.to_string()))
+    }
+
+    /// Adds a choice for an [`Integer`] parameter.
+    ///
+    /// [`Integer`]: ../model/interactions/enum.ApplicationCommandOptionType.html#variant.Integer
+    pub fn add_int_choice<N: ToString>(&mut self, name: N, value: i64) -> &mut Self {
+        self.add_choice(name, Value::Number(Number::from(value)))
+    }
+
+    fn add_choice<N: ToString>(&mut self, name: N, value: Value) -> &mut Self {
+        let mut choice = JsonMap::new();
+        choice.insert("name".to_string(), Value::String(name.to_string()));
+        choice.insert("value".to_string(), value);
+
+        self.push("choices", Value::Object(choice))
+    }
+
+    /// Creates a nested parameter, for parameters of type [`SubCommand`] or
+    /// [`SubCommandGroup`].
+    ///
+    /// [`SubCommand`]: ../model/interactions/enum.ApplicationCommandOptionType.html#variant.SubCommand
+    /// [`SubCommandGroup`]: ../model/interactions/enum.ApplicationCommandOptionType.html#variant.SubCommandGroup
+    pub fn create_sub_option<F>(&mut self, f: F) -> &mut Self
+        where F: FnOnce(&mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+        let mut option = CreateApplicationCommandOption::default();
+        f(&mut option);
+
+        self.push("options", Value::Object(utils::hashmap_to_json_map(option.0)))
+    }
+
+    fn push(&mut self, key: &'static str, value: Value) -> &mut Self {
+        let values = self.0.entry(key).or_insert_with(|| Value::Array(Vec::new()));
+
+        if let Value::Array(ref mut values) = *values {
+            values.push(value);
+        }
+
+        self
+    }
+}
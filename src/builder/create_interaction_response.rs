@@ -0,0 +1,96 @@
+use crate::internal::prelude::*;
+use crate::model::interactions::InteractionResponseType;
+use crate::utils;
+use std::collections::HashMap;
+
+/// A builder for the initial response to an [`Interaction`].
+///
+/// Refer to [`Interaction::create_interaction_response`] for an example.
+///
+/// [`Interaction`]: ../model/interactions/struct.Interaction.html
+/// [`Interaction::create_interaction_response`]: ../model/interactions/struct.Interaction.html#method.create_interaction_response
+#[derive(Clone, Debug)]
+pub struct CreateInteractionResponse(pub HashMap<&'static str, Value>);
+
+impl CreateInteractionResponse {
+    /// The type of the response.
+    ///
+    /// Defaults to [`ChannelMessageWithSource`].
+    ///
+    /// [`ChannelMessageWithSource`]: ../model/interactions/enum.InteractionResponseType.html#variant.ChannelMessageWithSource
+    pub fn kind(&mut self, kind: InteractionResponseType) -> &mut Self {
+        self.0.insert("type", Value::Number(Number::from(kind.num())));
+        self
+    }
+
+    /// Sets the message sent with the response.
+    ///
+    /// Refer to [`CreateInteractionResponseData`] for more information.
+    ///
+    /// [`CreateInteractionResponseData`]: struct.CreateInteractionResponseData.html
+    pub fn interaction_response_data<F>(&mut self, f: F) -> &mut Self
+        where F: FnOnce(&mut CreateInteractionResponseData) -> &mut CreateInteractionResponseData {
+        let mut data = CreateInteractionResponseData::default();
+        f(&mut data);
+        let map = utils::hashmap_to_json_map(data.0);
+
+        self.0.insert("data", Value::Object(map));
+        self
+    }
+}
+
+impl Default for CreateInteractionResponse {
+    /// Returns a response of type [`ChannelMessageWithSource`] without any
+    /// data.
+    ///
+    /// [`ChannelMessageWithSource`]: ../model/interactions/enum.InteractionResponseType.html#variant.ChannelMessageWithSource
+    fn default() -> CreateInteractionResponse {
+        let mut map = HashMap::new();
+        map.insert(
+            "type",
+            Value::Number(Number::from(InteractionResponseType::ChannelMessageWithSource.num())),
+        );
+
+        CreateInteractionResponse(map)
+    }
+}
+
+/// A builder for the message of an interaction response, used by
+/// [`CreateInteractionResponse::interaction_response_data`] and
+/// [`Interaction::edit_original_interaction_response`].
+///
+/// [`CreateInteractionResponse::interaction_response_data`]: struct.CreateInteractionResponse.html#method.interaction_response_data
+/// [`Interaction::edit_original_interaction_response`]: ../model/interactions/struct.Interaction.html#method.edit_original_interaction_response
+#[derive(Clone, Debug, Default)]
+pub struct CreateInteractionResponseData(pub HashMap<&'static str, Value>);
+
+impl CreateInteractionResponseData {
+    /// Set the content of the message.
+    ///
+    /// **Note**: Message contents must be under 2000 unicode code points.
+    pub fn content<D: ToString>(&mut self, content: D) -> &mut Self {
+        self.0.insert("content", Value::String(content.to_string()));
+        self
+    }
+
+    /// Set the embeds of the message, created via [`Embed::fake`].
+    ///
+    /// [`Embed::fake`]: ../model/channel/struct.Embed.html#method.fake
+    pub fn embeds(&mut self, embeds: Vec<Value>) -> &mut Self {
+        self.0.insert("embeds", Value::Array(embeds));
+        self
+    }
+
+    /// Set whether the message is text-to-speech.
+    pub fn tts(&mut self, tts: bool) -> &mut Self {
+        self.0.insert("tts", Value::Bool(tts));
+        self
+    }
+
+    /// Set the message flags, e.g. `64` to make the message only visible to
+    /// the invoking user.
+    pub fn flags(&mut self, flags: u64) -> &mut Self {
+        self.0.insert("flags", Value::Number(Number::from(flags)));
+        self
+    }
+}
@@ -5,8 +5,10 @@
 //! optional, and/or sane default values for required parameters can be applied
 //! by a builder.
 
+mod create_application_command;
 mod create_embed;
 mod create_channel;
+mod create_interaction_response;
 mod create_invite;
 mod create_message;
 mod edit_channel;
@@ -19,8 +21,10 @@ mod execute_webhook;
 mod get_messages;
 
 pub use self::{
+    create_application_command::{CreateApplicationCommand, CreateApplicationCommandOption},
     create_embed::{CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter},
     create_channel::CreateChannel,
+    create_interaction_response::{CreateInteractionResponse, CreateInteractionResponseData},
     create_invite::CreateInvite,
     create_message::CreateMessage,
     edit_channel::EditChannel,
@@ -0,0 +1,583 @@
+//! Adapters running event handlers and frameworks that return futures.
+//!
+//! The methods of an [`EventHandler`] are called on the client's threadpool,
+//! so a handler waiting on I/O occupies one of its threads until it is done,
+//! and a few slow handlers delay every other event. The methods of an
+//! [`AsyncEventHandler`] instead return futures, which the [`Async`] adapter
+//! runs on an [`Executor`]. A future awaiting another one, such as a channel
+//! or a timer, does not occupy a thread meanwhile.
+//!
+//! This does not make the library's I/O asynchronous: [`Http`] requests,
+//! including those model methods make, still block the thread making them.
+//! Async handlers make them via [`AsyncContext::blocking`], which runs them
+//! on a separate pool of fixed size, 32 threads by default. Each request
+//! occupies one of its threads until it returns, and once all of them are
+//! busy, further requests wait for one to be free; async handlers make no
+//! more requests at once than that many threads could. The size of the pool
+//! is set via [`ClientBuilder::blocking_threads`], or
+//! [`Executor::with_blocking_threads`] for adapters created directly.
+//!
+//! # Examples
+//!
+//! Reply to messages without blocking the client's threads while the
+//! message is sent, making at most 8 requests at once:
+//!
+//! ```rust,no_run
+//! use serenity::client::{AsyncContext, AsyncEventHandler, BoxFuture, Client, FutureExt};
+//! use serenity::model::channel::Message;
+//! use std::sync::Arc;
+//!
+//! struct Handler;
+//!
+//! impl AsyncEventHandler for Handler {
+//!     fn message(self: Arc<Self>, ctx: AsyncContext, msg: Message) -> BoxFuture<'static, ()> {
+//!         async move {
+//!             if msg.content == "!ping" {
+//!                 let sent = ctx.blocking(move |ctx| msg.channel_id.say(&ctx.http, "Pong!")).await;
+//!
+//!                 if let Err(why) = sent {
+//!                     println!("Error sending message: {:?}", why);
+//!                 }
+//!             }
+//!         }.boxed()
+//!     }
+//! }
+//!
+//! # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = Client::builder()
+//!     .token("my token")
+//!     .async_event_handler(Handler)
+//!     .blocking_threads(8)
+//!     .build()?;
+//! client.start()?;
+//! #     Ok(())
+//! # }
+//! #
+//! # fn main() {
+//! #     try_main().unwrap();
+//! # }
+//! ```
+//!
+//! [`Async`]: struct.Async.html
+//! [`AsyncContext::blocking`]: struct.AsyncContext.html#method.blocking
+//! [`AsyncEventHandler`]: trait.AsyncEventHandler.html
+//! [`ClientBuilder::blocking_threads`]: struct.ClientBuilder.html#method.blocking_threads
+//! [`EventHandler`]: trait.EventHandler.html
+//! [`Executor`]: struct.Executor.html
+//! [`Executor::with_blocking_threads`]: struct.Executor.html#method.with_blocking_threads
+//! [`Http`]: ../http/raw/struct.Http.html
+
+use futures::{
+    channel::oneshot,
+    executor::ThreadPool,
+    future::{self, BoxFuture, FutureExt},
+    task::{Context as TaskContext, Poll},
+};
+use log::error;
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    ops::Deref,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::Arc,
+    thread,
+};
+use typemap::ShareMap;
+use crate::internal::prelude::*;
+use crate::model::prelude::*;
+use super::{
+    bridge::gateway::event::*,
+    panic::{panic_message, report, HandlerPanic, PanicSource},
+    Context,
+    EventHandler,
+    RawEventHandler,
+};
+
+/// The number of threads blocking calls are run on by default.
+pub(crate) const BLOCKING_THREADS: usize = 32;
+
+/// The thread pools [`Async`] handlers are run on.
+///
+/// Futures are run on a pool with a thread per CPU, while the closures given
+/// to [`AsyncContext::blocking`] are run on a separate pool, so that they do
+/// not hold up other futures. Each closure occupies a thread of that pool
+/// until it returns, so its size limits how many blocking calls run at once.
+///
+/// An executor is cheap to clone; clones share their pools.
+///
+/// [`Async`]: struct.Async.html
+/// [`AsyncContext::blocking`]: struct.AsyncContext.html#method.blocking
+#[derive(Clone, Debug)]
+pub struct Executor {
+    futures: ThreadPool,
+    blocking: ThreadPool,
+}
+
+impl Executor {
+    /// Creates an executor with a thread per CPU for futures, and 32 threads
+    /// for blocking calls.
+    pub fn new() -> Result<Self> {
+        Self::with_blocking_threads(BLOCKING_THREADS)
+    }
+
+    /// Creates an executor with a thread per CPU for futures, and the given
+    /// number of threads for blocking calls.
+    pub fn with_blocking_threads(blocking_threads: usize) -> Result<Self> {
+        let futures = ThreadPool::builder().name_prefix("serenity-async-").create()?;
+
+        Self::with_futures_pool(futures, blocking_threads)
+    }
+
+    /// Creates an executor with the given number of threads for futures and
+    /// for blocking calls.
+    pub fn with_threads(threads: usize, blocking_threads: usize) -> Result<Self> {
+        let futures = ThreadPool::builder()
+            .name_prefix("serenity-async-")
+            .pool_size(threads)
+            .create()?;
+
+        Self::with_futures_pool(futures, blocking_threads)
+    }
+
+    fn with_futures_pool(futures: ThreadPool, blocking_threads: usize) -> Result<Self> {
+        let blocking = ThreadPool::builder()
+            .name_prefix("serenity-blocking-")
+            .pool_size(blocking_threads)
+            .create()?;
+
+        Ok(Executor {
+            futures,
+            blocking,
+        })
+    }
+
+    /// Runs a future to completion in the background.
+    ///
+    /// A panic in the future is logged, rather than taking down the thread it
+    /// runs on. Panics in futures spawned via [`AsyncContext::spawn`] are
+    /// instead given to the client's panic hook.
+    ///
+    /// [`AsyncContext::spawn`]: struct.AsyncContext.html#method.spawn
+    pub fn spawn<F>(&self, future: F) where F: Future<Output = ()> + Send + 'static {
+        self.futures.spawn_ok(AssertUnwindSafe(future).catch_unwind().map(|result| {
+            if let Err(payload) = result {
+                let message = panic_message(&*payload).unwrap_or("Box<Any>");
+                error!("[Executor] Future panicked: {}", message);
+            }
+        }));
+    }
+
+    // Runs a future of a handler, reporting a panic in it to the panic hook
+    // in the client's data.
+    fn spawn_reporting<F>(&self, data: Arc<RwLock<ShareMap>>, shard_id: u64, source: PanicSource, future: F)
+        where F: Future<Output = ()> + Send + 'static {
+        self.futures.spawn_ok(AssertUnwindSafe(future).catch_unwind().map(move |result| {
+            if let Err(payload) = result {
+                report(&data, &HandlerPanic {
+                    source,
+                    shard_id,
+                    payload: &*payload,
+                });
+            }
+        }));
+    }
+
+    /// Runs a blocking closure on the blocking pool, returning a future
+    /// resolving to its result.
+    ///
+    /// The closure runs whether or not the future is polled. If the closure
+    /// panics, so does polling the future.
+    pub fn blocking<F, T>(&self, f: F) -> Blocking<T>
+        where F: FnOnce() -> T + Send + 'static, T: Send + 'static {
+        let (tx, rx) = oneshot::channel();
+
+        self.blocking.spawn_ok(future::lazy(move |_| {
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        }));
+
+        Blocking {
+            rx,
+        }
+    }
+
+    fn context(&self, context: Context, source: PanicSource) -> AsyncContext {
+        AsyncContext {
+            context,
+            executor: self.clone(),
+            source,
+        }
+    }
+}
+
+/// A future resolving to the result of a closure run on an [`Executor`]'s
+/// blocking pool.
+///
+/// [`Executor`]: struct.Executor.html
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled, though the closure still runs"]
+pub struct Blocking<T> {
+    rx: oneshot::Receiver<thread::Result<T>>,
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<T> {
+        match Pin::new(&mut self.rx).poll(cx) {
+            Poll::Ready(Ok(Ok(value))) => Poll::Ready(value),
+            Poll::Ready(Ok(Err(payload))) => panic::resume_unwind(payload),
+            // The task only drops the sender without sending once the pool
+            // has been shut down.
+            Poll::Ready(Err(_)) => panic!("The blocking pool shut down"),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// The [`Context`] given to [`Async`] handlers, which additionally allows
+/// running blocking calls as futures.
+///
+/// It dereferences to the [`Context`], so its methods and fields can be used
+/// directly.
+///
+/// [`Async`]: struct.Async.html
+/// [`Context`]: struct.Context.html
+#[derive(Clone)]
+pub struct AsyncContext {
+    context: Context,
+    executor: Executor,
+    source: PanicSource,
+}
+
+impl AsyncContext {
+    /// Runs a blocking closure, such as one making [`Http`] requests, on the
+    /// executor's blocking pool, returning a future resolving to its result.
+    ///
+    /// The closure is given a clone of the context. It occupies a thread of
+    /// the blocking pool until it returns; if all of them are busy, it waits
+    /// for one to be free.
+    ///
+    /// # Examples
+    ///
+    /// Send a message from within an async handler:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::client::AsyncContext;
+    /// # use serenity::model::id::ChannelId;
+    /// #
+    /// # async fn run(ctx: AsyncContext) {
+    /// let sent = ctx.blocking(|ctx| ChannelId(7).say(&ctx.http, "Hello!")).await;
+    /// # }
+    /// ```
+    ///
+    /// [`Http`]: ../http/raw/struct.Http.html
+    pub fn blocking<F, T>(&self, f: F) -> Blocking<T>
+        where F: FnOnce(&Context) -> T + Send + 'static, T: Send + 'static {
+        let context = self.context.clone();
+
+        self.executor.blocking(move || f(&context))
+    }
+
+    /// Runs a future in the background, concurrently with the handler that
+    /// spawned it.
+    ///
+    /// A panic in the future is given to the client's panic hook, as one in
+    /// the handler would be.
+    pub fn spawn<F>(&self, future: F) where F: Future<Output = ()> + Send + 'static {
+        let data = Arc::clone(&self.context.data);
+
+        self.executor.spawn_reporting(data, self.context.shard_id, self.source.clone(), future);
+    }
+
+    /// The executor this context's handler runs on.
+    pub fn executor(&self) -> &Executor {
+        &self.executor
+    }
+}
+
+impl Deref for AsyncContext {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        &self.context
+    }
+}
+
+/// Adapts an [`AsyncEventHandler`], [`AsyncRawEventHandler`] or
+/// [`AsyncFramework`] for use by a [`Client`].
+///
+/// The adapter implements the corresponding synchronous trait by running the
+/// returned futures on an [`Executor`], returning right away.
+///
+/// **Note**: Graceful shutdowns do not wait for these futures to complete.
+///
+/// [`AsyncEventHandler`]: trait.AsyncEventHandler.html
+/// [`AsyncFramework`]: ../framework/trait.AsyncFramework.html
+/// [`AsyncRawEventHandler`]: trait.AsyncRawEventHandler.html
+/// [`Client`]: struct.Client.html
+/// [`Executor`]: struct.Executor.html
+#[derive(Debug)]
+pub struct Async<T> {
+    pub(crate) inner: Arc<T>,
+    pub(crate) executor: Executor,
+}
+
+impl<T> Async<T> {
+    /// Adapts a handler or framework, running it on a new [`Executor`].
+    ///
+    /// [`Executor`]: struct.Executor.html
+    pub fn new(inner: T) -> Result<Self> {
+        Ok(Self::with_executor(inner, &Executor::new()?))
+    }
+
+    /// Adapts a handler or framework, running it on the given executor.
+    ///
+    /// This allows an event handler and a framework to share one executor.
+    pub fn with_executor(inner: T, executor: &Executor) -> Self {
+        Async {
+            inner: Arc::new(inner),
+            executor: executor.clone(),
+        }
+    }
+
+    /// The executor the adapted handler or framework runs on.
+    pub fn executor(&self) -> &Executor {
+        &self.executor
+    }
+
+    /// Runs the future returned by the method of the given name, reporting a
+    /// panic in it to the client's panic hook.
+    pub(crate) fn run(
+        &self,
+        context: Context,
+        method: &'static str,
+        f: impl FnOnce(Arc<T>, AsyncContext) -> BoxFuture<'static, ()>,
+    ) {
+        let data = Arc::clone(&context.data);
+        let shard_id = context.shard_id;
+        let source = PanicSource::Async(method);
+        let future = f(Arc::clone(&self.inner), self.executor.context(context, source.clone()));
+
+        self.executor.spawn_reporting(data, shard_id, source, future);
+    }
+}
+
+// Declares the methods of `AsyncEventHandler`, and implements `EventHandler`
+// for `Async` by forwarding to them.
+macro_rules! async_event_handler {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident($($arg:ident: $kind:ty),*);
+    )*) => {
+        /// The async flavour of [`EventHandler`], whose methods return futures.
+        ///
+        /// Refer to [`EventHandler`] for when each method is called. The
+        /// methods take the handler as an `Arc`, so that the returned futures
+        /// can use it.
+        ///
+        /// Handlers are given to a [`Client`] via the [`Async`] adapter.
+        ///
+        /// [`Async`]: struct.Async.html
+        /// [`Client`]: struct.Client.html
+        /// [`EventHandler`]: trait.EventHandler.html
+        pub trait AsyncEventHandler: Send + Sync + 'static {
+            /// Returns the types of the events this handler handles, or `None`
+            /// to receive all of them.
+            ///
+            /// Refer to [`EventHandler::event_types`] for details.
+            ///
+            /// [`EventHandler::event_types`]: trait.EventHandler.html#method.event_types
+            fn event_types(&self) -> Option<HashSet<EventType>> { None }
+
+            $(
+                $(#[$attr])*
+                fn $name(self: Arc<Self>, ctx: AsyncContext, $($arg: $kind),*) -> BoxFuture<'static, ()> {
+                    let _ = (ctx, $($arg),*);
+
+                    future::ready(()).boxed()
+                }
+            )*
+        }
+
+        impl<H: AsyncEventHandler> EventHandler for Async<H> {
+            fn event_types(&self) -> Option<HashSet<EventType>> {
+                self.inner.event_types()
+            }
+
+            $(
+                $(#[$attr])*
+                fn $name(&self, ctx: Context, $($arg: $kind),*) {
+                    self.run(ctx, stringify!($name), move |handler, ctx| handler.$name(ctx, $($arg),*));
+                }
+            )*
+        }
+    };
+}
+
+event_handler_methods!(async_event_handler);
+
+/// The async flavour of [`RawEventHandler`], whose method returns a future.
+///
+/// Handlers are given to a [`Client`] via the [`Async`] adapter.
+///
+/// [`Async`]: struct.Async.html
+/// [`Client`]: struct.Client.html
+/// [`RawEventHandler`]: trait.RawEventHandler.html
+pub trait AsyncRawEventHandler: Send + Sync + 'static {
+    /// Dispatched when any event occurs
+    fn raw_event(self: Arc<Self>, ctx: AsyncContext, ev: Event) -> BoxFuture<'static, ()> {
+        let _ = (ctx, ev);
+
+        future::ready(()).boxed()
+    }
+}
+
+impl<H: AsyncRawEventHandler> RawEventHandler for Async<H> {
+    fn raw_event(&self, ctx: Context, ev: Event) {
+        self.run(ctx, "raw_event", move |handler, ctx| handler.raw_event(ctx, ev));
+    }
+}
+
+#[cfg(all(test, feature = "cache"))]
+mod test {
+    use futures::{
+        channel::oneshot,
+        future::{BoxFuture, FutureExt},
+    };
+    use parking_lot::{Mutex, RwLock};
+    use std::{
+        panic::AssertUnwindSafe,
+        sync::{mpsc, Arc},
+        time::Duration,
+    };
+    use typemap::ShareMap;
+    use super::{Async, AsyncContext, AsyncEventHandler, Executor};
+    use crate::cache::Cache;
+    use crate::client::{
+        panic::{HandlerPanic, PanicHookKey, PanicSource},
+        Context,
+        EventHandler,
+    };
+    use crate::http::Http;
+    use crate::model::{event::TypingStartEvent, id::{ChannelId, UserId}};
+
+    fn context() -> Context {
+        let (runner_tx, _) = mpsc::channel();
+
+        Context::new(
+            Arc::new(RwLock::new(ShareMap::custom())),
+            runner_tx,
+            0,
+            Arc::new(RwLock::new(Cache::default())),
+            Arc::new(Http::new_with_token("token")),
+        )
+    }
+
+    fn typing(user_id: u64) -> TypingStartEvent {
+        TypingStartEvent {
+            channel_id: ChannelId(1),
+            timestamp: 0,
+            user_id: UserId(user_id),
+            _nonexhaustive: (),
+        }
+    }
+
+    // The first typing event waits for the second one, which is only
+    // possible if the first does not hold the executor's only thread.
+    struct Handler {
+        waiting: Mutex<Option<oneshot::Receiver<()>>>,
+        release: Mutex<Option<oneshot::Sender<()>>>,
+        done: Mutex<mpsc::Sender<u64>>,
+    }
+
+    impl AsyncEventHandler for Handler {
+        fn typing_start(self: Arc<Self>, ctx: AsyncContext, event: TypingStartEvent) -> BoxFuture<'static, ()> {
+            async move {
+                let waiting = self.waiting.lock().take();
+
+                if let Some(waiting) = waiting {
+                    waiting.await.unwrap();
+                } else if let Some(release) = self.release.lock().take() {
+                    let _ = release.send(());
+                }
+
+                let user_id = ctx.blocking(move |ctx| (ctx.shard_id, event.user_id.0)).await.1;
+                let _ = self.done.lock().send(user_id);
+            }.boxed()
+        }
+    }
+
+    #[test]
+    fn waiting_handlers_do_not_hold_up_others() {
+        let (release, waiting) = oneshot::channel();
+        let (done_tx, done_rx) = mpsc::channel();
+        let handler = Handler {
+            waiting: Mutex::new(Some(waiting)),
+            release: Mutex::new(Some(release)),
+            done: Mutex::new(done_tx),
+        };
+        let handler = Async::with_executor(handler, &Executor::with_threads(1, 1).unwrap());
+
+        handler.typing_start(context(), typing(1));
+        handler.typing_start(context(), typing(2));
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(done_rx.recv_timeout(timeout), Ok(2));
+        assert_eq!(done_rx.recv_timeout(timeout), Ok(1));
+    }
+
+    struct Panicking;
+
+    impl AsyncEventHandler for Panicking {
+        fn typing_start(self: Arc<Self>, ctx: AsyncContext, _: TypingStartEvent) -> BoxFuture<'static, ()> {
+            async move {
+                ctx.spawn(async { panic!("expected panic") });
+                panic!("expected panic");
+            }.boxed()
+        }
+    }
+
+    #[test]
+    fn panics_are_reported_to_the_hook() {
+        let ctx = context();
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        ctx.data.write().insert::<PanicHookKey>(Arc::new(move |panic: &HandlerPanic<'_>| {
+            let _ = tx.lock().send((panic.source.clone(), panic.shard_id));
+        }));
+
+        let handler = Async::with_executor(Panicking, &Executor::with_threads(1, 1).unwrap());
+        handler.typing_start(ctx, typing(1));
+
+        let timeout = Duration::from_secs(5);
+        let expected = (PanicSource::Async("typing_start"), 0);
+        assert_eq!(rx.recv_timeout(timeout), Ok(expected.clone()));
+        assert_eq!(rx.recv_timeout(timeout), Ok(expected));
+    }
+
+    #[test]
+    fn blocking_closures_panic_when_awaited() {
+        let executor = Executor::with_threads(1, 1).unwrap();
+        let (tx, rx) = mpsc::channel();
+
+        let blocking = executor.blocking(|| -> u64 { panic!("expected panic") });
+        executor.spawn(async move {
+            let result = AssertUnwindSafe(blocking).catch_unwind().await;
+            let _ = tx.send(result.is_err());
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(true));
+
+        // The executor's thread survives the panic.
+        let (tx, rx) = mpsc::channel();
+        let value = executor.blocking(|| 7);
+        executor.spawn(async move {
+            let _ = tx.send(value.await);
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(7));
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// The period over which the gateway counts the commands sent over a
+/// connection.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// The number of commands the gateway accepts per `WINDOW` before
+/// disconnecting.
+const COMMANDS_PER_WINDOW: usize = 120;
+
+/// The number of commands per `WINDOW` left for the heartbeats, identifies
+/// and resumes a shard sends itself, which are never held back.
+const RESERVED_COMMANDS: usize = 10;
+
+/// The number of presence updates the gateway accepts per `WINDOW`.
+const PRESENCES_PER_WINDOW: usize = 5;
+
+/// The gateway commands a [`ShardRunner`] is holding back to stay within the
+/// gateway's ratelimit.
+///
+/// [`ShardRunner`]: struct.ShardRunner.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct QueuedCommands {
+    /// The number of commands waiting to be sent, other than a presence
+    /// update.
+    pub commands: usize,
+    /// Whether a presence update is waiting to be sent.
+    ///
+    /// Only the latest presence is sent, so at most one update is queued.
+    pub presence: bool,
+}
+
+// A command that may be sent now.
+#[derive(Debug, Eq, PartialEq)]
+pub(super) enum Command<T> {
+    // An update to the shard's current presence.
+    Presence,
+    Queued(T),
+}
+
+// Holds back commands sent by a shard runner, so that the gateway does not
+// disconnect it for sending too many.
+#[derive(Debug)]
+pub(super) struct CommandLimiter<T> {
+    queue: VecDeque<T>,
+    presence: bool,
+    // when each command and presence update in the current window was sent
+    sent: VecDeque<Instant>,
+    presences_sent: VecDeque<Instant>,
+    status: Arc<Mutex<QueuedCommands>>,
+}
+
+impl<T> CommandLimiter<T> {
+    pub(super) fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            presence: false,
+            sent: VecDeque::new(),
+            presences_sent: VecDeque::new(),
+            status: Arc::new(Mutex::new(QueuedCommands::default())),
+        }
+    }
+
+    // The state of the queue, kept up to date as commands are queued and
+    // sent.
+    pub(super) fn status(&self) -> Arc<Mutex<QueuedCommands>> {
+        Arc::clone(&self.status)
+    }
+
+    pub(super) fn push(&mut self, command: T) {
+        self.queue.push_back(command);
+        self.update_status();
+    }
+
+    // Queues an update to the shard's current presence, replacing any
+    // queued one.
+    pub(super) fn push_presence(&mut self) {
+        self.presence = true;
+        self.update_status();
+    }
+
+    // Returns the next command that may be sent at `now`, counting it as
+    // sent.
+    pub(super) fn next(&mut self, now: Instant) -> Option<Command<T>> {
+        expire(&mut self.sent, now);
+        expire(&mut self.presences_sent, now);
+
+        if self.sent.len() >= COMMANDS_PER_WINDOW - RESERVED_COMMANDS {
+            return None;
+        }
+
+        let command = if self.presence && self.presences_sent.len() < PRESENCES_PER_WINDOW {
+            self.presence = false;
+            self.presences_sent.push_back(now);
+
+            Command::Presence
+        } else {
+            Command::Queued(self.queue.pop_front()?)
+        };
+
+        self.sent.push_back(now);
+        self.update_status();
+
+        Some(command)
+    }
+
+    fn update_status(&self) {
+        *self.status.lock() = QueuedCommands {
+            commands: self.queue.len(),
+            presence: self.presence,
+        };
+    }
+}
+
+// Forgets the commands sent before the current window.
+fn expire(sent: &mut VecDeque<Instant>, now: Instant) {
+    while let Some(&at) = sent.front() {
+        if now.duration_since(at) < WINDOW {
+            break;
+        }
+
+        sent.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+    use super::{
+        Command,
+        CommandLimiter,
+        QueuedCommands,
+        COMMANDS_PER_WINDOW,
+        PRESENCES_PER_WINDOW,
+        RESERVED_COMMANDS,
+        WINDOW,
+    };
+
+    #[test]
+    fn presence_updates_are_coalesced_and_limited() {
+        let mut limiter = CommandLimiter::<()>::new();
+        let now = Instant::now();
+
+        for _ in 0..PRESENCES_PER_WINDOW {
+            limiter.push_presence();
+            limiter.push_presence();
+            assert_eq!(limiter.next(now), Some(Command::Presence));
+            assert_eq!(limiter.next(now), None);
+        }
+
+        limiter.push_presence();
+        assert_eq!(limiter.next(now), None);
+        assert_eq!(*limiter.status().lock(), QueuedCommands {
+            commands: 0,
+            presence: true,
+        });
+
+        assert_eq!(limiter.next(now + WINDOW), Some(Command::Presence));
+        assert_eq!(*limiter.status().lock(), QueuedCommands::default());
+    }
+
+    #[test]
+    fn commands_leave_headroom_for_heartbeats() {
+        let mut limiter = CommandLimiter::new();
+        let now = Instant::now();
+
+        for i in 0..COMMANDS_PER_WINDOW {
+            limiter.push(i);
+        }
+
+        let allowed = COMMANDS_PER_WINDOW - RESERVED_COMMANDS;
+
+        for i in 0..allowed {
+            assert_eq!(limiter.next(now), Some(Command::Queued(i)));
+        }
+
+        assert_eq!(limiter.next(now + Duration::from_secs(59)), None);
+        assert_eq!(limiter.status().lock().commands, RESERVED_COMMANDS);
+
+        // A queued presence update waits for the window as well.
+        limiter.push_presence();
+        assert_eq!(limiter.next(now), None);
+
+        assert_eq!(limiter.next(now + WINDOW), Some(Command::Presence));
+        assert_eq!(limiter.next(now + WINDOW), Some(Command::Queued(allowed)));
+    }
+}
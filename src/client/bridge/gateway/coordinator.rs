@@ -0,0 +1,482 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+    time::Instant,
+};
+use log::{debug, info, warn};
+use thiserror::Error;
+use crate::internal::prelude::*;
+
+use super::{identify_schedule::WAIT_BETWEEN_IDENTIFIES, IdentifyLock};
+
+/// An error returned by a [`CoordinatorClient`].
+///
+/// [`CoordinatorClient`]: struct.CoordinatorClient.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Error)]
+pub enum CoordinatorError {
+    /// The coordinator closed the connection.
+    #[error("The coordinator closed the connection")]
+    Closed,
+
+    /// Every shard range has already been handed out to another process.
+    #[error("No shard range is available")]
+    NoShardsAvailable,
+
+    /// The coordinator responded with something other than what the request
+    /// expects.
+    #[error("Unexpected response from the coordinator")]
+    UnexpectedResponse,
+
+    #[doc(hidden)]
+    #[error("unreachable")]
+    __Nonexhaustive,
+}
+
+/// The shards a process was assigned by a [`Coordinator`].
+///
+/// [`Coordinator`]: struct.Coordinator.html
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ShardAssignment {
+    /// The first and last shard ID of the range, inclusive, as accepted by
+    /// [`Client::start_shard_range`].
+    ///
+    /// [`Client::start_shard_range`]: ../../struct.Client.html#method.start_shard_range
+    pub range: [u64; 2],
+    /// The total number of shards in use across all processes.
+    pub total: u64,
+}
+
+/// Options for a [`Coordinator`].
+///
+/// [`Coordinator`]: struct.Coordinator.html
+#[derive(Clone, Copy, Debug)]
+pub struct CoordinatorOptions {
+    /// The total number of shards in use across all processes.
+    pub total_shards: u64,
+    /// The number of shards each process is assigned. The last process may
+    /// be assigned fewer.
+    pub shards_per_process: u64,
+    /// The number of shards that may identify at the same time, as retrieved
+    /// via [`Http::get_bot_gateway`].
+    ///
+    /// [`Http::get_bot_gateway`]: ../../../http/raw/struct.Http.html#method.get_bot_gateway
+    pub max_concurrency: u64,
+}
+
+/// A request sent by a [`CoordinatorClient`], one JSON object per line.
+///
+/// [`CoordinatorClient`]: struct.CoordinatorClient.html
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request {
+    Join,
+    Identify { shard_id: u64 },
+}
+
+/// A response sent by a [`Coordinator`], one JSON object per line.
+///
+/// [`Coordinator`]: struct.Coordinator.html
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Response {
+    Assigned(ShardAssignment),
+    Full,
+    Proceed,
+}
+
+#[derive(Debug)]
+struct State {
+    // Whether each shard range is currently held by a connected process.
+    assigned: Vec<bool>,
+    // The instant each bucket was last allowed to identify at.
+    buckets: HashMap<u64, Instant>,
+}
+
+/// A TCP server coordinating shards across processes.
+///
+/// Processes connect with a [`CoordinatorClient`] and [join] to be handed the
+/// next free range of shards. A range is handed out again once the process
+/// holding it disconnects, so the cluster scales by starting or stopping
+/// processes.
+///
+/// The coordinator also serves as the [`IdentifyLock`] for all processes:
+/// shards in the same `max_concurrency` bucket are allowed to identify at most
+/// once every 5 seconds, regardless of which process they run in.
+///
+/// # Examples
+///
+/// Run a coordinator for 16 shards, 4 per process:
+///
+/// ```rust,no_run
+/// use serenity::client::bridge::gateway::{Coordinator, CoordinatorOptions};
+///
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// let coordinator = Coordinator::bind("0.0.0.0:7878", CoordinatorOptions {
+///     total_shards: 16,
+///     shards_per_process: 4,
+///     max_concurrency: 1,
+/// })?;
+///
+/// coordinator.run()?;
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`CoordinatorClient`]: struct.CoordinatorClient.html
+/// [`IdentifyLock`]: trait.IdentifyLock.html
+/// [join]: struct.CoordinatorClient.html#method.join
+#[derive(Debug)]
+pub struct Coordinator {
+    listener: TcpListener,
+    options: CoordinatorOptions,
+    state: Arc<Mutex<State>>,
+}
+
+impl Coordinator {
+    /// Binds a coordinator to the given address.
+    ///
+    /// A `shards_per_process` or `max_concurrency` of `0` is treated as `1`.
+    pub fn bind(addr: impl ToSocketAddrs, mut options: CoordinatorOptions) -> Result<Self> {
+        options.shards_per_process = options.shards_per_process.max(1);
+        options.max_concurrency = options.max_concurrency.max(1);
+
+        let ranges = (options.total_shards + options.shards_per_process - 1)
+            / options.shards_per_process;
+
+        Ok(Coordinator {
+            listener: TcpListener::bind(addr)?,
+            options,
+            state: Arc::new(Mutex::new(State {
+                assigned: vec![false; ranges as usize],
+                buckets: HashMap::new(),
+            })),
+        })
+    }
+
+    /// Returns the address the coordinator is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener.local_addr().map_err(From::from)
+    }
+
+    /// Accepts processes until an error occurs, serving each connection in its
+    /// own thread.
+    pub fn run(self) -> Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let options = self.options;
+            let state = Arc::clone(&self.state);
+
+            thread::spawn(move || {
+                let peer = stream.peer_addr().ok();
+
+                if let Err(why) = serve(stream, options, &state) {
+                    warn!("[Coordinator] Err serving {:?}: {:?}", peer, why);
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn serve(stream: TcpStream, options: CoordinatorOptions, state: &Mutex<State>) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let mut held = None;
+
+    let result = serve_requests(&mut reader, &mut writer, options, state, &mut held);
+
+    // Free the range for the next process to join, whether the connection was
+    // closed cleanly or not.
+    if let Some(index) = held {
+        info!("[Coordinator] Range {} released", index);
+
+        state.lock().assigned[index] = false;
+    }
+
+    result
+}
+
+fn serve_requests(
+    reader: &mut BufReader<TcpStream>,
+    writer: &mut TcpStream,
+    options: CoordinatorOptions,
+    state: &Mutex<State>,
+    held: &mut Option<usize>,
+) -> Result<()> {
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        let response = match serde_json::from_str(&line)? {
+            Request::Join => join(options, state, held),
+            Request::Identify { shard_id } => {
+                identify(shard_id, options, state);
+
+                Response::Proceed
+            },
+        };
+
+        write_line(writer, &response)?;
+    }
+}
+
+fn join(options: CoordinatorOptions, state: &Mutex<State>, held: &mut Option<usize>) -> Response {
+    let index = match *held {
+        Some(index) => index,
+        None => {
+            let mut state = state.lock();
+
+            match state.assigned.iter().position(|assigned| !assigned) {
+                Some(index) => {
+                    state.assigned[index] = true;
+                    *held = Some(index);
+
+                    index
+                },
+                None => return Response::Full,
+            }
+        },
+    };
+
+    let first = index as u64 * options.shards_per_process;
+    let last = (first + options.shards_per_process).min(options.total_shards) - 1;
+
+    info!("[Coordinator] Range {} assigned: {} - {}", index, first, last);
+
+    Response::Assigned(ShardAssignment {
+        range: [first, last],
+        total: options.total_shards,
+    })
+}
+
+// Reserves the shard's bucket for the next free slot and waits for it.
+fn identify(shard_id: u64, options: CoordinatorOptions, state: &Mutex<State>) {
+    let now = Instant::now();
+    let at = {
+        let mut state = state.lock();
+        let bucket = shard_id % options.max_concurrency;
+
+        let at = state.buckets
+            .get(&bucket)
+            .map_or(now, |last| (*last + WAIT_BETWEEN_IDENTIFIES).max(now));
+        state.buckets.insert(bucket, at);
+
+        at
+    };
+
+    debug!("[Coordinator] Shard {} may identify in {:?}", shard_id, at - now);
+
+    thread::sleep(at - now);
+}
+
+fn write_line<T: Serialize>(writer: &mut TcpStream, value: &T) -> Result<()> {
+    let mut bytes = serde_json::to_vec(value)?;
+    bytes.push(b'\n');
+    writer.write_all(&bytes)?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+/// A connection to a [`Coordinator`], used to join the cluster and as the
+/// [`IdentifyLock`] of the process's shards.
+///
+/// The process keeps its shard range for as long as the connection is open,
+/// so the client must be kept alive for as long as the shards run.
+///
+/// # Examples
+///
+/// Join a cluster and start the assigned shards:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # struct Handler;
+/// #
+/// # impl EventHandler for Handler {}
+/// use serenity::client::bridge::gateway::CoordinatorClient;
+/// use serenity::client::Client;
+/// use std::env;
+///
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// let coordinator = CoordinatorClient::connect("10.0.0.1:7878")?;
+/// let assignment = coordinator.join()?;
+///
+/// let mut client = Client::builder()
+///     .token(env::var("DISCORD_TOKEN")?)
+///     .event_handler(Handler)
+///     .identify_lock(coordinator)
+///     .build()?;
+///
+/// client.start_shard_range(assignment.range, assignment.total)?;
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`Coordinator`]: struct.Coordinator.html
+/// [`IdentifyLock`]: trait.IdentifyLock.html
+#[derive(Clone, Debug)]
+pub struct CoordinatorClient {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl CoordinatorClient {
+    /// Connects to the coordinator at the given address.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        let writer = TcpStream::connect(addr)?;
+        let reader = BufReader::new(writer.try_clone()?);
+
+        Ok(CoordinatorClient {
+            connection: Arc::new(Mutex::new(Connection { reader, writer })),
+        })
+    }
+
+    /// Joins the cluster, returning the shards this process should start.
+    ///
+    /// Joining again returns the same assignment.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::NoShardsAvailable`] if every range is held
+    /// by another process.
+    ///
+    /// [`CoordinatorError::NoShardsAvailable`]: enum.CoordinatorError.html#variant.NoShardsAvailable
+    pub fn join(&self) -> Result<ShardAssignment> {
+        match self.request(&Request::Join)? {
+            Response::Assigned(assignment) => Ok(assignment),
+            Response::Full => Err(CoordinatorError::NoShardsAvailable.into()),
+            Response::Proceed => Err(CoordinatorError::UnexpectedResponse.into()),
+        }
+    }
+
+    fn request(&self, request: &Request) -> Result<Response> {
+        let mut connection = self.connection.lock();
+        write_line(&mut connection.writer, request)?;
+
+        let mut line = String::new();
+
+        if connection.reader.read_line(&mut line)? == 0 {
+            return Err(CoordinatorError::Closed.into());
+        }
+
+        serde_json::from_str(&line).map_err(From::from)
+    }
+}
+
+impl IdentifyLock for CoordinatorClient {
+    fn acquire(&self, shard_id: u64) -> Result<()> {
+        match self.request(&Request::Identify { shard_id })? {
+            Response::Proceed => Ok(()),
+            _ => Err(CoordinatorError::UnexpectedResponse.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::{Duration, Instant}};
+    use super::{
+        Coordinator,
+        CoordinatorClient,
+        CoordinatorError,
+        CoordinatorOptions,
+        IdentifyLock,
+        ShardAssignment,
+        WAIT_BETWEEN_IDENTIFIES,
+    };
+
+    fn coordinator(total_shards: u64, shards_per_process: u64) -> CoordinatorClient {
+        let coordinator = Coordinator::bind("127.0.0.1:0", CoordinatorOptions {
+            total_shards,
+            shards_per_process,
+            max_concurrency: 2,
+        }).unwrap();
+        let addr = coordinator.local_addr().unwrap();
+
+        thread::spawn(move || coordinator.run());
+
+        CoordinatorClient::connect(addr).unwrap()
+    }
+
+    fn assignment(range: [u64; 2], total: u64) -> ShardAssignment {
+        ShardAssignment { range, total }
+    }
+
+    #[test]
+    fn hands_out_ranges() {
+        let first = coordinator(5, 2);
+        let addr = first.connection.lock().writer.peer_addr().unwrap();
+        let second = CoordinatorClient::connect(addr).unwrap();
+        let third = CoordinatorClient::connect(addr).unwrap();
+        let fourth = CoordinatorClient::connect(addr).unwrap();
+
+        assert_eq!(first.join().unwrap(), assignment([0, 1], 5));
+        assert_eq!(first.join().unwrap(), assignment([0, 1], 5));
+        assert_eq!(second.join().unwrap(), assignment([2, 3], 5));
+        assert_eq!(third.join().unwrap(), assignment([4, 4], 5));
+
+        let why = fourth.join().unwrap_err();
+        assert_eq!(why.downcast_ref(), Some(&CoordinatorError::NoShardsAvailable));
+
+        // The range is freed once the coordinator notices the disconnect.
+        drop(second);
+
+        let rejoined = (0..100).find_map(|_| match fourth.join() {
+            Ok(assignment) => Some(assignment),
+            Err(_) => {
+                thread::sleep(Duration::from_millis(10));
+
+                None
+            },
+        });
+
+        assert_eq!(rejoined, Some(assignment([2, 3], 5)));
+    }
+
+    #[test]
+    fn identifies_buckets_in_parallel() {
+        let client = coordinator(4, 4);
+
+        // Shards 0 and 1 are in different buckets, so neither waits.
+        let start = Instant::now();
+        client.acquire(0).unwrap();
+        client.acquire(1).unwrap();
+
+        assert!(start.elapsed() < WAIT_BETWEEN_IDENTIFIES / 5);
+    }
+
+    #[test]
+    fn identifies_one_shard_per_bucket_at_a_time() {
+        let client = coordinator(4, 4);
+
+        // Shards 0 and 2 are in the same bucket, so the second one waits.
+        let start = Instant::now();
+        client.acquire(0).unwrap();
+        client.acquire(2).unwrap();
+
+        assert!(start.elapsed() >= WAIT_BETWEEN_IDENTIFIES);
+    }
+}
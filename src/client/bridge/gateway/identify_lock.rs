@@ -0,0 +1,29 @@
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use crate::internal::prelude::*;
+
+/// A lock that the [`ShardQueuer`] acquires before starting each shard, so
+/// that processes sharing a bot token do not identify at the same time.
+///
+/// Each process only spaces out the identifies of its own shards. When shards
+/// are split across processes or machines, identifies from different
+/// processes must be coordinated externally, or Discord invalidates the
+/// sessions.
+///
+/// Refer to [`CoordinatorClient`] for an implementation backed by a
+/// [`Coordinator`].
+///
+/// [`Coordinator`]: struct.Coordinator.html
+/// [`CoordinatorClient`]: struct.CoordinatorClient.html
+/// [`ShardQueuer`]: struct.ShardQueuer.html
+pub trait IdentifyLock: Send + Sync {
+    /// Blocks until the shard with the given ID may identify.
+    ///
+    /// If an error is returned, the shard is not started and is queued again.
+    fn acquire(&self, shard_id: u64) -> Result<()>;
+}
+
+impl Debug for dyn IdentifyLock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("IdentifyLock")
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::model::gateway::SessionStartLimit;
+
+/// The time a bucket must wait between two identifies.
+pub(super) const WAIT_BETWEEN_IDENTIFIES: Duration = Duration::from_secs(5);
+
+/// The length of the session start limit's ratelimit period, assumed when the
+/// period resets before an updated [`SessionStartLimit`] is known.
+///
+/// [`SessionStartLimit`]: ../../../model/gateway/struct.SessionStartLimit.html
+const SESSION_START_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Tracks when shards may identify with the gateway.
+///
+/// Shards are grouped into buckets by `shard_id % max_concurrency`. Shards in
+/// different buckets may identify at the same time, while each bucket may only
+/// identify once every 5 seconds. Once the daily [`SessionStartLimit`] runs
+/// out, identifies are delayed until it resets.
+///
+/// The [`ShardQueuer`] uses this to start shards; a snapshot can be retrieved
+/// via [`ShardManager::identify_schedule`] to estimate when all shards will be
+/// online.
+///
+/// # Examples
+///
+/// Estimate how long 96 shards take to start with a `max_concurrency` of 16:
+///
+/// ```rust
+/// use serenity::client::bridge::gateway::IdentifySchedule;
+/// use std::time::Duration;
+///
+/// let schedule = IdentifySchedule::new(16);
+/// let estimate = schedule.estimate(0..96);
+///
+/// assert_eq!(estimate.last(), Some(&(95, Duration::from_secs(25))));
+/// ```
+///
+/// [`SessionStartLimit`]: ../../../model/gateway/struct.SessionStartLimit.html
+/// [`ShardManager::identify_schedule`]: struct.ShardManager.html#method.identify_schedule
+/// [`ShardQueuer`]: struct.ShardQueuer.html
+#[derive(Clone, Debug)]
+pub struct IdentifySchedule {
+    buckets: HashMap<u64, Instant>,
+    limit: Option<Limit>,
+    max_concurrency: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Limit {
+    remaining: u64,
+    reset_at: Instant,
+    total: u64,
+}
+
+impl IdentifySchedule {
+    /// Creates a schedule for the given number of concurrent identifies,
+    /// without a limit on the number of session starts.
+    ///
+    /// A `max_concurrency` of `0` is treated as `1`.
+    pub fn new(max_concurrency: u64) -> Self {
+        IdentifySchedule {
+            buckets: HashMap::new(),
+            limit: None,
+            max_concurrency: max_concurrency.max(1),
+        }
+    }
+
+    /// Creates a schedule from the limit retrieved via
+    /// [`Http::get_bot_gateway`].
+    ///
+    /// [`Http::get_bot_gateway`]: ../../../http/raw/struct.Http.html#method.get_bot_gateway
+    pub fn from_session_start_limit(limit: &SessionStartLimit) -> Self {
+        let mut schedule = Self::new(limit.max_concurrency);
+        schedule.update(limit);
+
+        schedule
+    }
+
+    /// Updates the concurrency and the remaining session starts, keeping track
+    /// of when each bucket last identified.
+    pub fn update(&mut self, limit: &SessionStartLimit) {
+        self.max_concurrency = limit.max_concurrency.max(1);
+        self.limit = Some(Limit {
+            remaining: limit.remaining,
+            reset_at: Instant::now() + Duration::from_millis(limit.reset_after),
+            total: limit.total,
+        });
+    }
+
+    /// The number of shards that may identify at the same time.
+    pub fn max_concurrency(&self) -> u64 {
+        self.max_concurrency
+    }
+
+    /// The number of session starts remaining, if known.
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.as_ref().map(|limit| limit.remaining)
+    }
+
+    /// The bucket a shard identifies in.
+    pub fn bucket(&self, shard_id: u64) -> u64 {
+        shard_id % self.max_concurrency
+    }
+
+    /// The earliest instant the shard may identify at, which may be in the
+    /// past.
+    pub fn next_start(&self, shard_id: u64) -> Instant {
+        self.next_start_after(shard_id, Instant::now())
+    }
+
+    /// Records that the shard was started at the current instant.
+    ///
+    /// `identified` is whether the shard used up a session start, which is not
+    /// the case when it resumed a session instead.
+    pub fn record_start(&mut self, shard_id: u64, identified: bool) {
+        self.record_start_at(shard_id, identified, Instant::now());
+    }
+
+    /// Estimates how long from now each of the given shards will start, if
+    /// they are started in the given order.
+    ///
+    /// This assumes every shard identifies rather than resumes a session, and
+    /// that no shard starts before the one queued ahead of it.
+    pub fn estimate(&self, shard_ids: impl IntoIterator<Item = u64>) -> Vec<(u64, Duration)> {
+        let mut schedule = self.clone();
+        let now = Instant::now();
+        let mut last = now;
+
+        shard_ids.into_iter().map(|shard_id| {
+            let at = schedule.next_start_after(shard_id, now).max(last);
+            schedule.record_start_at(shard_id, true, at);
+            last = at;
+
+            (shard_id, at - now)
+        }).collect()
+    }
+
+    fn next_start_after(&self, shard_id: u64, now: Instant) -> Instant {
+        let bucket = self.buckets
+            .get(&self.bucket(shard_id))
+            .map(|last| *last + WAIT_BETWEEN_IDENTIFIES)
+            .map_or(now, |next| next.max(now));
+
+        match self.limit {
+            Some(ref limit) if limit.remaining == 0 => bucket.max(limit.reset_at),
+            _ => bucket,
+        }
+    }
+
+    fn record_start_at(&mut self, shard_id: u64, identified: bool, at: Instant) {
+        self.buckets.insert(self.bucket(shard_id), at);
+
+        if !identified {
+            return;
+        }
+
+        if let Some(ref mut limit) = self.limit {
+            if at >= limit.reset_at {
+                limit.remaining = limit.total;
+                limit.reset_at = at + SESSION_START_PERIOD;
+            }
+
+            limit.remaining = limit.remaining.saturating_sub(1);
+
+            if limit.remaining == 0 {
+                warn!(
+                    "Session start limit exhausted; delaying identifies for {:?}",
+                    limit.reset_at - at,
+                );
+            }
+        }
+    }
+}
+
+impl Default for IdentifySchedule {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use super::IdentifySchedule;
+    use crate::model::gateway::SessionStartLimit;
+
+    fn limit(max_concurrency: u64, remaining: u64, reset_after: u64) -> SessionStartLimit {
+        SessionStartLimit {
+            max_concurrency,
+            remaining,
+            reset_after,
+            total: 1000,
+            _nonexhaustive: (),
+        }
+    }
+
+    fn secs(estimate: &[(u64, Duration)]) -> Vec<u64> {
+        estimate.iter().map(|(_, d)| d.as_secs()).collect()
+    }
+
+    #[test]
+    fn waits_between_identifies_without_concurrency() {
+        let estimate = IdentifySchedule::default().estimate(0..4);
+
+        assert_eq!(secs(&estimate), vec![0, 5, 10, 15]);
+    }
+
+    #[test]
+    fn starts_buckets_in_parallel() {
+        let schedule = IdentifySchedule::new(16);
+        let estimate = schedule.estimate(0..96);
+
+        assert_eq!(schedule.bucket(17), 1);
+        assert!(estimate[..16].iter().all(|(_, d)| *d == Duration::from_secs(0)));
+        assert_eq!(estimate[16], (16, Duration::from_secs(5)));
+        assert_eq!(estimate[95], (95, Duration::from_secs(25)));
+    }
+
+    #[test]
+    fn resumed_shards_do_not_use_up_session_starts() {
+        let mut schedule = IdentifySchedule::from_session_start_limit(&limit(1, 2, 60_000));
+
+        schedule.record_start(0, false);
+        assert_eq!(schedule.remaining(), Some(2));
+
+        schedule.record_start(1, true);
+        assert_eq!(schedule.remaining(), Some(1));
+    }
+
+    #[test]
+    fn delays_starts_until_limit_resets() {
+        let schedule = IdentifySchedule::from_session_start_limit(&limit(2, 2, 60_000));
+        let estimate = schedule.estimate(0..4);
+
+        assert_eq!(secs(&estimate)[..2], [0, 0]);
+        // The remaining starts wait for the reset, give or take the time
+        // passed since creating the schedule.
+        assert!(estimate[2..].iter().all(|(_, d)| d.as_secs() >= 59 && d.as_secs() <= 60));
+    }
+}
@@ -33,6 +33,12 @@
 //! This is, for example, whether to reconnect, resume, or identify with the
 //! gateway.
 //!
+//! ### [`Coordinator`]
+//!
+//! The coordinator lets shards run across several processes or machines by
+//! handing out shard ranges to processes as they join, and acting as the
+//! [`IdentifyLock`] so that their identifies do not collide.
+//!
 //! ### In Conclusion
 //!
 //! For almost every - if not every - use case, you only need to _possibly_ be
@@ -40,6 +46,8 @@
 //!
 //! [client]: ../../index.html
 //! [`Client`]: ../../struct.Client.html
+//! [`Coordinator`]: struct.Coordinator.html
+//! [`IdentifyLock`]: trait.IdentifyLock.html
 //! [`Shard`]: ../../../gateway/struct.Shard.html
 //! [`ShardManager`]: struct.ShardManager.html
 //! [`ShardManager::restart`]: struct.ShardManager.html#method.restart
@@ -50,6 +58,11 @@
 
 pub mod event;
 
+mod command_limiter;
+mod coordinator;
+mod identify_lock;
+mod identify_schedule;
+mod reshard;
 mod shard_manager;
 mod shard_manager_monitor;
 mod shard_messenger;
@@ -57,6 +70,17 @@ mod shard_queuer;
 mod shard_runner;
 mod shard_runner_message;
 
+pub use self::command_limiter::QueuedCommands;
+pub use self::coordinator::{
+    Coordinator,
+    CoordinatorClient,
+    CoordinatorError,
+    CoordinatorOptions,
+    ShardAssignment,
+};
+pub use self::identify_lock::IdentifyLock;
+pub use self::identify_schedule::IdentifySchedule;
+pub use self::reshard::{ReshardError, ShardGenerations, StagedShards};
 pub use self::shard_manager::{ShardManager, ShardManagerOptions};
 pub use self::shard_manager_monitor::ShardManagerMonitor;
 pub use self::shard_messenger::ShardMessenger;
@@ -65,15 +89,28 @@ pub use self::shard_runner::{ShardRunner, ShardRunnerOptions};
 pub use self::shard_runner_message::ShardRunnerMessage;
 
 use std::{
+    collections::HashSet,
     fmt::{
         Display,
         Formatter,
         Result as FmtResult
     },
-    sync::mpsc::Sender,
+    sync::{mpsc::Sender, Arc},
     time::Duration as StdDuration
 };
-use crate::gateway::{ConnectionStage, InterMessage};
+use crate::gateway::{
+    ConnectionStage,
+    Connector,
+    GatewayEncoding,
+    IdentifyOptions,
+    InterMessage,
+    SessionStore,
+    TrafficRecorder,
+    TransportCompression,
+};
+use crate::model::{event::EventType, gateway::GatewayIntents};
+use parking_lot::Mutex;
+use super::super::DispatchOrder;
 
 /// A message either for a [`ShardManager`] or a [`ShardRunner`].
 ///
@@ -102,6 +139,11 @@ pub enum ShardManagerMessage {
     ///
     /// [`ShardManagerMonitor`]: struct.ShardManagerMonitor.html
     Restart(ShardId),
+    /// Indicator that a shard has received all of the guilds it was sent in
+    /// its [`Ready`] event, either as available or as deleted.
+    ///
+    /// [`Ready`]: ../../../model/gateway/struct.Ready.html
+    GuildsReceived(ShardId),
     /// An update from a shard runner,
     ShardUpdate {
         id: ShardId,
@@ -113,6 +155,9 @@ pub enum ShardManagerMessage {
     ///
     /// [`ShardManagerMonitor`]: struct.ShardManagerMonitor.html
     Shutdown(ShardId),
+    /// Indicator that a shard should shut down, closing its connection with a
+    /// code that keeps the session resumable.
+    ShutdownResumable(ShardId),
     /// Indicator that a [`ShardManagerMonitor`] should fully shutdown all shards
     /// and end its monitoring process for the [`ShardManager`].
     ///
@@ -141,6 +186,44 @@ pub enum ShardQueuerMessage {
     /// Message to start a shard, where the 0-index element is the ID of the
     /// Shard to start and the 1-index element is the total shards in use.
     Start(ShardId, ShardId),
+    /// Message to set the gateway intents sent by shards started afterwards.
+    SetIntents(Option<GatewayIntents>),
+    /// Message to set the options sent by shards started afterwards when
+    /// identifying.
+    SetIdentifyOptions(IdentifyOptions),
+    /// Message to set the transport compression used by shards started
+    /// afterwards.
+    SetCompression(TransportCompression),
+    /// Message to set the payload encoding used by shards started afterwards.
+    SetEncoding(GatewayEncoding),
+    /// Message to set the store that shards save their sessions to and resume
+    /// them from.
+    SetSessionStore(Option<Arc<dyn SessionStore>>),
+    /// Message to set the lock acquired before each shard is started.
+    SetIdentifyLock(Option<Arc<dyn IdentifyLock>>),
+    /// Message to set the connector used by shards started afterwards to
+    /// connect to the gateway.
+    SetConnector(Arc<dyn Connector>),
+    /// Message to set the recorder that shards started afterwards record the
+    /// payloads they receive to.
+    SetRecorder(Option<TrafficRecorder>),
+    /// Message to set the types of events that shards started afterwards
+    /// deserialize and dispatch.
+    SetEventTypes(Option<HashSet<EventType>>),
+    /// Message to set the order in which shards started afterwards run the
+    /// handlers of events.
+    SetDispatchOrder(DispatchOrder),
+    /// Message to begin staging a new set of shards for a reshard.
+    Stage(StagedShards),
+    /// Message to start a shard of the staged set, where the 0-index element
+    /// is the ID of the shard to start and the 1-index element is the new
+    /// total shards in use.
+    StartStaged(ShardId, ShardId),
+    /// Message to switch to the staged set of shards, dropping queued starts
+    /// of the previous set.
+    Promote,
+    /// Message to cancel staging, dropping queued starts of the staged set.
+    Unstage,
     /// Message to shutdown the shard queuer.
     Shutdown,
 }
@@ -173,4 +256,7 @@ pub struct ShardRunnerInfo {
     pub runner_tx: Sender<InterMessage>,
     /// The current connection stage of the shard.
     pub stage: ConnectionStage,
+    /// The gateway commands the shard runner is holding back to stay within
+    /// the gateway's ratelimit, such as presence updates and member requests.
+    pub queued_commands: Arc<Mutex<QueuedCommands>>,
 }
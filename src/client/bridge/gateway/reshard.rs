@@ -0,0 +1,274 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use thiserror::Error;
+
+use crate::gateway::GatewayPayload;
+
+use super::{ShardId, ShardManagerMessage, ShardRunnerInfo};
+
+/// How long the retired set of shards keeps dispatching after a reshard
+/// switched over, so that events it received before the new set are not
+/// lost.
+pub(super) const RESHARD_OVERLAP: Duration = Duration::from_secs(5);
+
+/// How long the events dispatched while both sets of shards are connected are
+/// remembered, so that the other set skips them.
+const DEDUPLICATION_WINDOW: Duration = Duration::from_secs(10);
+
+/// An error returned by [`ShardManager::reshard`].
+///
+/// [`ShardManager::reshard`]: struct.ShardManager.html#method.reshard
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Error)]
+pub enum ReshardError {
+    /// Another reshard is already in progress.
+    #[error("A reshard is already in progress")]
+    InProgress,
+
+    /// Not every new shard received all of its guilds before the timeout.
+    ///
+    /// The new shards were shut down, and the current ones kept running.
+    #[error("Timed out after {received} of {expected} new shards received their guilds")]
+    TimedOut {
+        /// The number of new shards that received all of their guilds.
+        received: u64,
+        /// The number of new shards started.
+        expected: u64,
+    },
+
+    #[doc(hidden)]
+    #[error("unreachable")]
+    __Nonexhaustive,
+}
+
+/// Decides which shard runners dispatch events while a [`ShardManager`]
+/// reshards.
+///
+/// Every runner belongs to a generation, which is increased for each set of
+/// shards started by a reshard. Only runners of the active generation
+/// dispatch events; a new set is staged, connecting without dispatching,
+/// until it is switched to.
+///
+/// Around the switch, both sets are connected and receive the same events.
+/// Both dispatch for a short while, and an event is skipped if the other set
+/// dispatched it already, so that no event is lost or duplicated.
+///
+/// [`ShardManager`]: struct.ShardManager.html
+#[derive(Debug, Default)]
+pub struct ShardGenerations {
+    active: AtomicU64,
+    next: AtomicU64,
+    deduplicating: AtomicBool,
+    recent: Mutex<RecentEvents>,
+}
+
+#[derive(Debug, Default)]
+struct RecentEvents {
+    // the generation that dispatched each event, by fingerprint
+    generations: HashMap<u64, u64>,
+    expiry: VecDeque<(Instant, u64)>,
+}
+
+impl ShardGenerations {
+    /// Returns the generation of the shards that dispatch events.
+    pub fn active(&self) -> u64 {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether a reshard is staging or switching over to a new set of
+    /// shards.
+    pub fn is_resharding(&self) -> bool {
+        self.deduplicating.load(Ordering::SeqCst)
+    }
+
+    // Allocates the generation of a new set of shards, and starts remembering
+    // dispatched events.
+    pub(super) fn stage(&self) -> u64 {
+        // Generations of cancelled reshards are skipped, so that a runner of
+        // one that is still shutting down is never mistaken for a new one.
+        let generation = self.next.fetch_add(1, Ordering::SeqCst) + 1;
+        self.deduplicating.store(true, Ordering::SeqCst);
+
+        generation
+    }
+
+    // Makes the given generation the one that dispatches events.
+    pub(super) fn activate(&self, generation: u64) {
+        self.active.store(generation, Ordering::SeqCst);
+    }
+
+    // Stops remembering dispatched events, once only one set of shards is
+    // connected.
+    pub(super) fn finish(&self) {
+        self.deduplicating.store(false, Ordering::SeqCst);
+
+        let mut recent = self.recent.lock();
+        recent.generations.clear();
+        recent.expiry.clear();
+    }
+
+    // Retires every runner, so that none dispatches events or restarts while
+    // the shard manager shuts down.
+    pub(super) fn retire_all(&self) {
+        self.finish();
+        self.active.store(std::u64::MAX, Ordering::SeqCst);
+    }
+
+    // Returns whether a runner of a generation older than the active one
+    // belongs to a set of shards being shut down.
+    pub(super) fn is_retired(&self, generation: u64) -> bool {
+        generation < self.active()
+    }
+
+    // Returns whether a runner of the given generation should dispatch the
+    // event in the given payload.
+    pub(super) fn should_dispatch(&self, generation: u64, payload: &GatewayPayload) -> bool {
+        let active = self.active();
+
+        if !self.is_resharding() || generation > active {
+            return generation == active;
+        }
+
+        let kind = match payload.kind() {
+            Some(kind) => kind,
+            None => return true,
+        };
+
+        let mut hasher = DefaultHasher::new();
+        kind.hash(&mut hasher);
+        // The data is hashed in a canonical form, as the same event received
+        // over two connections need not be serialized alike.
+        payload.data_value().ok().and_then(|data| data).map(|data| data.to_string()).hash(&mut hasher);
+        let fingerprint = hasher.finish();
+
+        let mut recent = self.recent.lock();
+        let now = Instant::now();
+
+        while let Some(&(at, expired)) = recent.expiry.front() {
+            if now.duration_since(at) < DEDUPLICATION_WINDOW {
+                break;
+            }
+
+            recent.expiry.pop_front();
+            recent.generations.remove(&expired);
+        }
+
+        match recent.generations.get(&fingerprint) {
+            Some(&other) if other != generation => {
+                recent.generations.remove(&fingerprint);
+
+                false
+            },
+            _ => {
+                recent.generations.insert(fingerprint, generation);
+                recent.expiry.push_back((now, fingerprint));
+
+                true
+            },
+        }
+    }
+}
+
+/// A set of shards being started by a reshard, before they are switched to.
+///
+/// Sent to the [`ShardQueuer`] via [`ShardQueuerMessage::Stage`].
+///
+/// [`ShardQueuer`]: struct.ShardQueuer.html
+/// [`ShardQueuerMessage::Stage`]: enum.ShardQueuerMessage.html#variant.Stage
+#[derive(Clone, Debug)]
+pub struct StagedShards {
+    /// The generation of the runners of the new shards.
+    pub generation: u64,
+    /// The channel the runners of the new shards report to, instead of the
+    /// [`ShardManagerMonitor`].
+    ///
+    /// [`ShardManagerMonitor`]: struct.ShardManagerMonitor.html
+    pub manager_tx: Sender<ShardManagerMessage>,
+    /// The runners of the new shards started so far.
+    pub runners: Arc<Mutex<HashMap<ShardId, ShardRunnerInfo>>>,
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use super::ShardGenerations;
+    use crate::gateway::GatewayPayload;
+
+    fn message(id: u64) -> GatewayPayload {
+        let value = json!({"op": 0, "s": id, "t": "MESSAGE_CREATE", "d": {"id": id.to_string()}});
+
+        GatewayPayload::from_json(value.to_string()).unwrap()
+    }
+
+    #[test]
+    fn only_the_active_generation_dispatches() {
+        let generations = ShardGenerations::default();
+
+        assert!(generations.should_dispatch(0, &message(1)));
+
+        let staged = generations.stage();
+        assert_eq!(staged, 1);
+        assert!(generations.should_dispatch(0, &message(2)));
+        assert!(!generations.should_dispatch(staged, &message(2)));
+        assert!(!generations.is_retired(0));
+
+        generations.activate(staged);
+        generations.finish();
+        assert!(generations.is_retired(0));
+        assert!(!generations.should_dispatch(0, &message(3)));
+        assert!(generations.should_dispatch(staged, &message(3)));
+    }
+
+    #[test]
+    fn events_are_dispatched_once_around_the_switch() {
+        let generations = ShardGenerations::default();
+        let staged = generations.stage();
+
+        // Received by the old set right before the switch, and by the new set
+        // right after it.
+        assert!(generations.should_dispatch(0, &message(1)));
+        generations.activate(staged);
+        assert!(!generations.should_dispatch(staged, &message(1)));
+
+        // Received by the new set first, and then by the old one.
+        assert!(generations.should_dispatch(staged, &message(2)));
+        assert!(!generations.should_dispatch(0, &message(2)));
+
+        // Received only by the old set before it is shut down.
+        assert!(generations.should_dispatch(0, &message(3)));
+
+        // Non-dispatch payloads are always handled.
+        assert!(generations.should_dispatch(0, &GatewayPayload::from_json(r#"{"op":11}"#.to_string()).unwrap()));
+    }
+
+    #[test]
+    fn retired_runners_do_not_dispatch() {
+        let generations = ShardGenerations::default();
+        let staged = generations.stage();
+
+        generations.retire_all();
+        assert!(!generations.is_resharding());
+        assert!(generations.is_retired(0));
+        assert!(generations.is_retired(staged));
+        assert!(!generations.should_dispatch(0, &message(1)));
+        assert!(!generations.should_dispatch(staged, &message(1)));
+    }
+
+    #[test]
+    fn generations_are_not_reused_after_a_cancelled_reshard() {
+        let generations = ShardGenerations::default();
+
+        assert_eq!(generations.stage(), 1);
+        generations.finish();
+        assert_eq!(generations.stage(), 2);
+    }
+}
@@ -1,11 +1,13 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    cmp,
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
     sync::{
-        mpsc::{self, channel, Sender, Receiver},
+        mpsc::{self, channel, Sender, Receiver, RecvTimeoutError},
         Arc,
     },
     thread,
-    time::Duration
+    time::{Duration, Instant}
 };
 
 use parking_lot::Mutex;
@@ -14,23 +16,45 @@ use threadpool::ThreadPool;
 use typemap::ShareMap;
 use log::{info, warn};
 
-use crate::gateway::InterMessage;
+use crate::gateway::{
+    Connector,
+    GatewayEncoding,
+    IdentifyOptions,
+    InterMessage,
+    SessionStore,
+    TrafficRecorder,
+    TransportCompression,
+    WebSocketConnector,
+};
+use crate::internal::prelude::*;
+use crate::model::{
+    event::EventType,
+    gateway::{GatewayIntents, SessionStartLimit},
+};
 use crate::CacheAndHttp;
 
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
 #[cfg(feature = "voice")]
 use crate::client::bridge::voice::ClientVoiceManager;
+#[cfg(feature = "metrics")]
+use crate::metrics::ShardMetrics;
 
-use super::super::super::{EventHandler, RawEventHandler};
+use super::super::super::{DispatchOrder, EventHandler, EventPipeline, RawEventHandler};
 use super::{
+    reshard::RESHARD_OVERLAP,
+    IdentifyLock,
+    IdentifySchedule,
+    ReshardError,
     ShardClientMessage,
     ShardId,
     ShardManagerMessage,
     ShardManagerMonitor,
     ShardQueuer,
     ShardQueuerMessage,
+    ShardGenerations,
     ShardRunnerInfo,
+    StagedShards,
 };
 
 /// A manager for handling the status of shards by starting them, restarting
@@ -59,7 +83,7 @@ use super::{
 /// #
 /// use parking_lot::{Mutex, RwLock};
 /// use serenity::client::bridge::gateway::{ShardManager, ShardManagerOptions};
-/// use serenity::client::{EventHandler, RawEventHandler};
+/// use serenity::client::{EventHandler, EventPipeline, RawEventHandler};
 /// // Of note, this imports `typemap`'s `ShareMap` type.
 /// use serenity::prelude::*;
 /// use serenity::http::Http;
@@ -88,6 +112,27 @@ use super::{
 ///     event_handler: &Some(event_handler),
 ///     raw_event_handler: &None::<Arc<Handler>>,
 ///     framework: &framework,
+///     // pass events straight to the handlers
+///     pipeline: &Arc::new(EventPipeline::new()),
+///     // use the default payload compression
+///     compression: Default::default(),
+///     // connect to the gateway over the network
+///     connector: None,
+///     // use JSON rather than ETF
+///     encoding: Default::default(),
+///     // receive every event
+///     intents: None,
+///     // identify with the default large threshold and no initial presence
+///     identify_options: Default::default(),
+///     // identify every shard anew
+///     session_store: None,
+///     // don't record gateway traffic
+///     recorder: None,
+///     event_types: None,
+///     // run handlers as events are received
+///     dispatch_order: Default::default(),
+///     // don't coordinate identifies with other processes
+///     identify_lock: None,
 ///     // the shard index to start initiating from
 ///     shard_index: 0,
 ///     // the number of shards to initiate (this initiates 0, 1, and 2)
@@ -116,7 +161,14 @@ use super::{
 /// [`Client`]: ../../struct.Client.html
 #[derive(Debug)]
 pub struct ShardManager {
+    compression: TransportCompression,
+    encoding: GatewayEncoding,
+    generations: Arc<ShardGenerations>,
+    intents: Option<GatewayIntents>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<ShardMetrics>,
     monitor_tx: Sender<ShardManagerMessage>,
+    schedule: Arc<Mutex<IdentifySchedule>>,
     /// The shard runners currently managed.
     ///
     /// **Note**: It is highly unrecommended to mutate this yourself unless you
@@ -131,6 +183,8 @@ pub struct ShardManager {
     shard_total: u64,
     shard_queuer: Sender<ShardQueuerMessage>,
     shard_shutdown: Receiver<ShardId>,
+    resharding: bool,
+    threadpool: ThreadPool,
 }
 
 impl ShardManager {
@@ -145,6 +199,10 @@ impl ShardManager {
         let (shard_queue_tx, shard_queue_rx) = mpsc::channel();
 
         let runners = Arc::new(Mutex::new(HashMap::new()));
+        let schedule = Arc::new(Mutex::new(IdentifySchedule::default()));
+        let generations = Arc::new(ShardGenerations::default());
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(ShardMetrics::default());
 
         let mut shard_queuer = ShardQueuer {
             data: Arc::clone(opt.data),
@@ -152,12 +210,29 @@ impl ShardManager {
             raw_event_handler: opt.raw_event_handler.as_ref().map(|rh| Arc::clone(rh)),
             #[cfg(feature = "framework")]
             framework: Arc::clone(opt.framework),
+            pipeline: Arc::clone(opt.pipeline),
+            compression: opt.compression,
+            connector: opt.connector.unwrap_or_else(|| Arc::new(WebSocketConnector)),
+            encoding: opt.encoding,
+            generations: Arc::clone(&generations),
+            identify_lock: opt.identify_lock,
+            identify_options: opt.identify_options,
+            intents: opt.intents,
             last_start: None,
             manager_tx: thread_tx.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::clone(&metrics),
+            recorder: opt.recorder,
+            event_types: opt.event_types,
+            dispatch_order: opt.dispatch_order,
+            session_store: opt.session_store,
             queue: VecDeque::new(),
+            staged: None,
+            staged_queue: VecDeque::new(),
             runners: Arc::clone(&runners),
             rx: shard_queue_rx,
-            threadpool: opt.threadpool,
+            schedule: Arc::clone(&schedule),
+            threadpool: opt.threadpool.clone(),
             #[cfg(feature = "voice")]
             voice_manager: Arc::clone(opt.voice_manager),
             ws_url: Arc::clone(opt.ws_url),
@@ -170,13 +245,22 @@ impl ShardManager {
 
         let (shutdown_send, shutdown_recv) = channel();
         let manager = Arc::new(Mutex::new(Self {
+            compression: opt.compression,
+            encoding: opt.encoding,
+            generations,
+            intents: opt.intents,
+            #[cfg(feature = "metrics")]
+            metrics,
             monitor_tx: thread_tx,
             shard_index: opt.shard_index,
             shard_init: opt.shard_init,
             shard_queuer: shard_queue_tx,
             shard_total: opt.shard_total,
             shard_shutdown: shutdown_recv,
+            resharding: false,
             runners,
+            schedule,
+            threadpool: opt.threadpool,
         }));
 
         (Arc::clone(&manager), ShardManagerMonitor {
@@ -205,6 +289,8 @@ impl ShardManager {
     ///
     /// [`ShardQueuer`]: struct.ShardQueuer.html
     pub fn initialize(&mut self) {
+        warn_about_intents(self.intents);
+
         let shard_to = self.shard_index + self.shard_init;
 
         for shard_id in self.shard_index..shard_to {
@@ -214,6 +300,175 @@ impl ShardManager {
         }
     }
 
+    /// Returns the compression used by shards for data received from the
+    /// gateway.
+    pub fn compression(&self) -> TransportCompression {
+        self.compression
+    }
+
+    /// Sets the compression used by shards for data received from the
+    /// gateway.
+    ///
+    /// **Note**: This only affects shards started afterwards. Use [`restart`]
+    /// to apply the compression to an already running shard.
+    ///
+    /// [`restart`]: #method.restart
+    pub fn set_compression(&mut self, compression: TransportCompression) {
+        self.compression = compression;
+
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetCompression(compression));
+    }
+
+    /// Returns the encoding of payloads sent over the gateway by shards.
+    pub fn encoding(&self) -> GatewayEncoding {
+        self.encoding
+    }
+
+    /// Sets the encoding of payloads sent over the gateway by shards.
+    ///
+    /// **Note**: This only affects shards started afterwards. Use [`restart`]
+    /// to apply the encoding to an already running shard.
+    ///
+    /// [`restart`]: #method.restart
+    pub fn set_encoding(&mut self, encoding: GatewayEncoding) {
+        self.encoding = encoding;
+
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetEncoding(encoding));
+    }
+
+    /// Sets the store that shards save their sessions to when shut down, and
+    /// resume them from when started.
+    ///
+    /// If `None`, sessions are not saved and every shard identifies anew.
+    pub fn set_session_store(&mut self, session_store: Option<Arc<dyn SessionStore>>) {
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetSessionStore(session_store));
+    }
+
+    /// Sets the options sent by shards when identifying, such as the presence
+    /// to appear with right away.
+    ///
+    /// **Note**: This only affects shards started afterwards. Use [`restart`]
+    /// to apply the options to an already running shard.
+    ///
+    /// [`restart`]: #method.restart
+    pub fn set_identify_options(&mut self, options: IdentifyOptions) {
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetIdentifyOptions(options));
+    }
+
+    /// Sets the recorder that shards record every payload they receive to,
+    /// for replaying them later via a [`Replayer`].
+    ///
+    /// If `None`, payloads are not recorded.
+    ///
+    /// **Note**: This only affects shards started afterwards.
+    ///
+    /// [`Replayer`]: ../../struct.Replayer.html
+    pub fn set_recorder(&mut self, recorder: Option<TrafficRecorder>) {
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetRecorder(recorder));
+    }
+
+    /// Sets the types of events that shards deserialize and dispatch,
+    /// discarding the others as they are received.
+    ///
+    /// If `None`, every event is. Refer to [`EventHandler::event_types`] for
+    /// the events that are received regardless.
+    ///
+    /// **Note**: This only affects shards started afterwards.
+    ///
+    /// [`EventHandler::event_types`]: ../../trait.EventHandler.html#method.event_types
+    pub fn set_event_types(&mut self, event_types: Option<HashSet<EventType>>) {
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetEventTypes(event_types));
+    }
+
+    /// Sets the order in which shards run the handlers of events.
+    ///
+    /// **Note**: This only affects shards started afterwards.
+    pub fn set_dispatch_order(&mut self, dispatch_order: DispatchOrder) {
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetDispatchOrder(dispatch_order));
+    }
+
+    /// Sets the connector used by shards to connect to the gateway, such as a
+    /// [`FakeGateway`] for testing.
+    ///
+    /// **Note**: This only affects shards started or reconnected afterwards.
+    ///
+    /// [`FakeGateway`]: ../../../gateway/fake/struct.FakeGateway.html
+    pub fn set_connector(&mut self, connector: Arc<dyn Connector>) {
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetConnector(connector));
+    }
+
+    /// Sets the lock acquired before each shard is started, coordinating
+    /// identifies with other processes sharing the bot token.
+    ///
+    /// If `None`, identifies are only spaced out within this process.
+    pub fn set_identify_lock(&mut self, identify_lock: Option<Arc<dyn IdentifyLock>>) {
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetIdentifyLock(identify_lock));
+    }
+
+    /// Returns the gateway intents sent by shards when identifying.
+    pub fn intents(&self) -> Option<GatewayIntents> {
+        self.intents
+    }
+
+    /// Sets the gateway intents sent by shards when identifying.
+    ///
+    /// If `None`, no intents are sent and Discord dispatches every event.
+    ///
+    /// **Note**: This only affects shards started afterwards. Use [`restart`]
+    /// to apply the intents to an already running shard.
+    ///
+    /// [`restart`]: #method.restart
+    pub fn set_intents(&mut self, intents: Option<GatewayIntents>) {
+        self.intents = intents;
+
+        let _ = self.shard_queuer.send(ShardQueuerMessage::SetIntents(intents));
+    }
+
+    /// Returns the counters updated by the shards, such as the number of
+    /// events received.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Arc<ShardMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Returns a snapshot of when shards may identify.
+    pub fn identify_schedule(&self) -> IdentifySchedule {
+        self.schedule.lock().clone()
+    }
+
+    /// Sets the number of shards that may identify at the same time, and the
+    /// number of session starts remaining, as retrieved via
+    /// [`Http::get_bot_gateway`].
+    ///
+    /// Without this, shards are started one every 5 seconds.
+    ///
+    /// [`Http::get_bot_gateway`]: ../../../http/raw/struct.Http.html#method.get_bot_gateway
+    pub fn set_session_start_limit(&mut self, limit: &SessionStartLimit) {
+        self.schedule.lock().update(limit);
+    }
+
+    /// Estimates how long from now each shard the manager is responsible for,
+    /// but which is not running yet, will be started.
+    ///
+    /// The estimate assumes every shard identifies rather than resumes a
+    /// session; the last entry is roughly when all shards will be online.
+    pub fn estimate_startup(&self) -> Vec<(ShardId, Duration)> {
+        let pending = {
+            let runners = self.runners.lock();
+
+            (self.shard_index..self.shard_index + self.shard_init)
+                .filter(|id| !runners.contains_key(&ShardId(*id)))
+                .collect::<Vec<_>>()
+        };
+
+        self.schedule
+            .lock()
+            .estimate(pending)
+            .into_iter()
+            .map(|(id, duration)| (ShardId(id), duration))
+            .collect()
+    }
+
     /// Sets the new sharding information for the manager.
     ///
     /// This will shutdown all existing shards.
@@ -227,6 +482,165 @@ impl ShardManager {
         self.shard_total = total;
     }
 
+    /// Reshards without downtime, switching to a new set of shards once all
+    /// of them are ready.
+    ///
+    /// Unlike [`set_shards`], the current shards keep dispatching events while
+    /// the new set - shards `index` through `index + init` of `total` - is
+    /// started in the background. Once every new shard has received all of
+    /// its guilds, events are dispatched by the new set instead, and the
+    /// current shards are shut down. Events received by both sets around the
+    /// switch are only dispatched once.
+    ///
+    /// This blocks until the current shards are shut down, but only locks the
+    /// manager briefly, so it should be called in its own thread.
+    ///
+    /// # Examples
+    ///
+    /// Reshard to the number of shards recommended by Discord, giving the new
+    /// shards 10 minutes to start:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::*;
+    /// # struct Handler;
+    /// #
+    /// # impl EventHandler for Handler {}
+    /// #
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use serenity::client::bridge::gateway::ShardManager;
+    /// use std::{sync::Arc, thread, time::Duration};
+    ///
+    /// let mut client = Client::new("my token", Handler)?;
+    /// let shard_manager = Arc::clone(&client.shard_manager);
+    /// let http = Arc::clone(&client.cache_and_http.http);
+    ///
+    /// thread::spawn(move || {
+    ///     let total = http.get_bot_gateway().unwrap().shards;
+    ///
+    ///     if let Err(why) = ShardManager::reshard(&shard_manager, 0, total, total, Duration::from_secs(600)) {
+    ///         println!("Err resharding: {:?}", why);
+    ///     }
+    /// });
+    /// #     Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ReshardError::InProgress`] if another reshard has not
+    /// finished yet.
+    ///
+    /// Returns [`ReshardError::TimedOut`] if not every new shard received its
+    /// guilds within the `timeout`, in which case the new shards are shut
+    /// down and the current ones keep running.
+    ///
+    /// [`ReshardError::InProgress`]: enum.ReshardError.html#variant.InProgress
+    /// [`ReshardError::TimedOut`]: enum.ReshardError.html#variant.TimedOut
+    /// [`set_shards`]: #method.set_shards
+    pub fn reshard(
+        manager: &Arc<Mutex<ShardManager>>,
+        index: u64,
+        init: u64,
+        total: u64,
+        timeout: Duration,
+    ) -> Result<()> {
+        let (staged_tx, staged_rx) = mpsc::channel();
+
+        let (staged, shard_queuer) = {
+            let mut manager = manager.lock();
+
+            if manager.resharding {
+                return Err(ReshardError::InProgress.into());
+            }
+
+            manager.resharding = true;
+
+            let staged = StagedShards {
+                generation: manager.generations.stage(),
+                manager_tx: staged_tx,
+                runners: Arc::new(Mutex::new(HashMap::new())),
+            };
+
+            info!("Resharding to shards {} through {} of {}", index, index + init, total);
+
+            let _ = manager.shard_queuer.send(ShardQueuerMessage::Stage(staged.clone()));
+
+            for shard_id in index..index + init {
+                let msg = ShardQueuerMessage::StartStaged(ShardId(shard_id), ShardId(total));
+                let _ = manager.shard_queuer.send(msg);
+            }
+
+            (staged, manager.shard_queuer.clone())
+        };
+
+        let waited = wait_for_guilds(&staged, &staged_rx, &shard_queuer, init, total, timeout);
+
+        if let Err(why) = waited {
+            warn!("Cancelling reshard: {:?}", why);
+
+            let mut manager = manager.lock();
+            let _ = manager.shard_queuer.send(ShardQueuerMessage::Unstage);
+
+            // The staged runners report to the reshard, so their shutdown is
+            // not awaited.
+            for (shard_id, runner) in staged.runners.lock().drain() {
+                let shutdown = ShardManagerMessage::Shutdown(shard_id);
+                let msg = InterMessage::Client(Box::new(ShardClientMessage::Manager(shutdown)));
+                let _ = runner.runner_tx.send(msg);
+            }
+
+            manager.generations.finish();
+            manager.resharding = false;
+
+            return Err(why);
+        }
+
+        let retired = {
+            let mut manager = manager.lock();
+            let _ = manager.shard_queuer.send(ShardQueuerMessage::Promote);
+
+            let runners = mem::replace(&mut *staged.runners.lock(), HashMap::new());
+            let retired = mem::replace(&mut *manager.runners.lock(), runners);
+            manager.generations.activate(staged.generation);
+            manager.shard_index = index;
+            manager.shard_init = init;
+            manager.shard_total = total;
+
+            // The new runners keep reporting over the staged channel.
+            let monitor_tx = manager.monitor_tx.clone();
+            thread::spawn(move || {
+                for msg in staged_rx {
+                    if monitor_tx.send(msg).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            retired
+        };
+
+        info!("Switched to {} new shards", init);
+
+        // Events the retired shards received before the new ones are still
+        // dispatched for a while.
+        thread::sleep(RESHARD_OVERLAP);
+
+        let mut manager = manager.lock();
+
+        for (shard_id, runner) in retired {
+            manager.shutdown_runner(shard_id, &runner);
+        }
+
+        manager.generations.finish();
+        manager.resharding = false;
+
+        Ok(())
+    }
+
     /// Restarts a shard runner.
     ///
     /// This sends a shutdown signal to a shard's associated [`ShardRunner`],
@@ -292,36 +706,41 @@ impl ShardManager {
         info!("Shutting down shard {}", shard_id);
 
         if let Some(runner) = self.runners.lock().get(&shard_id) {
-            let shutdown = ShardManagerMessage::Shutdown(shard_id);
-            let client_msg = ShardClientMessage::Manager(shutdown);
-            let msg = InterMessage::Client(Box::new(client_msg));
-
-            if let Err(why) = runner.runner_tx.send(msg) {
-                warn!(
-                    "Failed to cleanly shutdown shard {}: {:?}",
-                    shard_id,
-                    why,
-                );
-            }
-            match self.shard_shutdown.recv_timeout(Duration::from_secs(5)) {
-                Ok(shutdown_shard_id) =>
-                    if shutdown_shard_id != shard_id {
-                        warn!(
-                            "Failed to cleanly shutdown shard {}: Shutdown channel sent incorrect ID",
-                            shard_id,
-                        );
-                    },
-                Err(why) => warn!(
-                    "Failed to cleanly shutdown shard {}: {:?}",
-                    shard_id,
-                    why,
-                )
-            }
+            self.shutdown_runner(shard_id, runner);
         }
 
         self.runners.lock().remove(&shard_id).is_some()
     }
 
+    // Tells a shard runner to shut down, waiting for it to finish.
+    fn shutdown_runner(&self, shard_id: ShardId, runner: &ShardRunnerInfo) {
+        let shutdown = ShardManagerMessage::Shutdown(shard_id);
+        let client_msg = ShardClientMessage::Manager(shutdown);
+        let msg = InterMessage::Client(Box::new(client_msg));
+
+        if let Err(why) = runner.runner_tx.send(msg) {
+            warn!(
+                "Failed to cleanly shutdown shard {}: {:?}",
+                shard_id,
+                why,
+            );
+        }
+        match self.shard_shutdown.recv_timeout(Duration::from_secs(5)) {
+            Ok(shutdown_shard_id) =>
+                if shutdown_shard_id != shard_id {
+                    warn!(
+                        "Failed to cleanly shutdown shard {}: Shutdown channel sent incorrect ID",
+                        shard_id,
+                    );
+                },
+            Err(why) => warn!(
+                "Failed to cleanly shutdown shard {}: {:?}",
+                shard_id,
+                why,
+            )
+        }
+    }
+
     /// Sends a shutdown message for all shards that the manager is responsible
     /// for that are still known to be running.
     ///
@@ -350,6 +769,113 @@ impl ShardManager {
         let _ = self.monitor_tx.send(ShardManagerMessage::ShutdownInitiated);
     }
 
+    /// Shuts down all shards, waiting for the event handlers and commands
+    /// still running to finish.
+    ///
+    /// Shards stop dispatching events, and are closed with a code that keeps
+    /// their sessions resumable. Then, the handlers running on the threadpool
+    /// are waited for until the `timeout` passes, after which [`Client::start`]
+    /// returns.
+    ///
+    /// Returns whether every handler finished before the `timeout`.
+    ///
+    /// **Note**: The handlers are waited for while this runs, so calling it
+    /// from within a handler waits until the `timeout` passes.
+    ///
+    /// # Examples
+    ///
+    /// Shutting down from another thread, giving handlers 10 seconds to
+    /// finish:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::client::{Client, EventHandler};
+    /// # use std::{env, thread, time::Duration};
+    /// #
+    /// # struct Handler;
+    /// #
+    /// # impl EventHandler for Handler { }
+    /// #
+    /// use serenity::client::bridge::gateway::ShardManager;
+    ///
+    /// let token = env::var("DISCORD_TOKEN").unwrap();
+    /// let mut client = Client::new(&token, Handler).unwrap();
+    /// let manager = client.shard_manager.clone();
+    ///
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(60));
+    ///
+    ///     if !ShardManager::shutdown_gracefully(&manager, Duration::from_secs(10)) {
+    ///         println!("Some handlers did not finish in time");
+    ///     }
+    /// });
+    ///
+    /// let _ = client.start();
+    /// ```
+    ///
+    /// [`Client::start`]: ../../struct.Client.html#method.start
+    pub fn shutdown_gracefully(manager: &Arc<Mutex<ShardManager>>, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        let (mut remaining, threadpool) = {
+            let manager = manager.lock();
+
+            info!("Shutting down all shards gracefully");
+
+            manager.generations.retire_all();
+            let _ = manager.shard_queuer.send(ShardQueuerMessage::Shutdown);
+
+            let runners = manager.runners.lock();
+
+            for (&shard_id, runner) in runners.iter() {
+                let shutdown = ShardManagerMessage::ShutdownResumable(shard_id);
+                let msg = InterMessage::Client(Box::new(ShardClientMessage::Manager(shutdown)));
+
+                if let Err(why) = runner.runner_tx.send(msg) {
+                    warn!("Failed to cleanly shutdown shard {}: {:?}", shard_id, why);
+                }
+            }
+
+            (runners.keys().cloned().collect::<HashSet<_>>(), manager.threadpool.clone())
+        };
+
+        // The manager is only locked briefly, as the monitor locks it to
+        // forward the runners' shutdowns.
+        let close_deadline = cmp::min(deadline, Instant::now() + Duration::from_secs(5));
+
+        while !remaining.is_empty() && Instant::now() < close_deadline {
+            while let Ok(shard_id) = manager.lock().shard_shutdown.try_recv() {
+                remaining.remove(&shard_id);
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        for shard_id in remaining {
+            warn!("Failed to cleanly shutdown shard {}: Timed out", shard_id);
+        }
+
+        let (joined_tx, joined_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            threadpool.join();
+            let _ = joined_tx.send(());
+        });
+
+        let now = Instant::now();
+        let wait = if deadline > now { deadline - now } else { Duration::from_secs(0) };
+        let drained = joined_rx.recv_timeout(wait).is_ok();
+
+        if !drained {
+            warn!("Timed out waiting for event handlers to finish");
+        }
+
+        let manager = manager.lock();
+        manager.runners.lock().clear();
+        let _ = manager.monitor_tx.send(ShardManagerMessage::ShutdownInitiated);
+
+        drained
+    }
+
     fn boot(&mut self, shard_info: [ShardId; 2]) {
         info!("Telling shard queuer to start shard {}", shard_info[0]);
 
@@ -381,6 +907,35 @@ pub struct ShardManagerOptions<'a, H: EventHandler + Send + Sync + 'static, RH:
     pub raw_event_handler: &'a Option<Arc<RH>>,
     #[cfg(feature = "framework")]
     pub framework: &'a Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
+    /// The middleware and raw handlers that events pass through before being
+    /// dispatched to the event handler and framework.
+    pub pipeline: &'a Arc<EventPipeline>,
+    /// The compression to use for data received from the gateway.
+    pub compression: TransportCompression,
+    /// The connector used to connect to the gateway. If `None`, shards connect
+    /// over the network.
+    pub connector: Option<Arc<dyn Connector>>,
+    /// The encoding of payloads sent over the gateway.
+    pub encoding: GatewayEncoding,
+    /// The gateway intents to send when identifying. If `None`, every event
+    /// is received.
+    pub intents: Option<GatewayIntents>,
+    /// The options sent when identifying, other than the intents.
+    pub identify_options: IdentifyOptions,
+    /// The store to save sessions to and resume them from. If `None`, every
+    /// shard identifies anew.
+    pub session_store: Option<Arc<dyn SessionStore>>,
+    /// The recorder to record received payloads to. If `None`, payloads are
+    /// not recorded.
+    pub recorder: Option<TrafficRecorder>,
+    /// The types of events to deserialize and dispatch. If `None`, every
+    /// event is.
+    pub event_types: Option<HashSet<EventType>>,
+    /// The order in which the handlers of events are run.
+    pub dispatch_order: DispatchOrder,
+    /// The lock acquired before each shard is started. If `None`, identifies
+    /// are only spaced out within this process.
+    pub identify_lock: Option<Arc<dyn IdentifyLock>>,
     pub shard_index: u64,
     pub shard_init: u64,
     pub shard_total: u64,
@@ -390,3 +945,95 @@ pub struct ShardManagerOptions<'a, H: EventHandler + Send + Sync + 'static, RH:
     pub ws_url: &'a Arc<Mutex<String>>,
     pub cache_and_http: &'a Arc<CacheAndHttp>,
 }
+
+// Waits until every staged shard received all of its guilds, restarting the
+// staged shards that request it.
+fn wait_for_guilds(
+    staged: &StagedShards,
+    staged_rx: &Receiver<ShardManagerMessage>,
+    shard_queuer: &Sender<ShardQueuerMessage>,
+    init: u64,
+    total: u64,
+    timeout: Duration,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    let mut received = HashSet::new();
+
+    while (received.len() as u64) < init {
+        let now = Instant::now();
+        let remaining = if deadline > now { deadline - now } else { Duration::from_secs(0) };
+
+        match staged_rx.recv_timeout(remaining) {
+            Ok(ShardManagerMessage::GuildsReceived(shard_id)) => {
+                received.insert(shard_id);
+            },
+            Ok(ShardManagerMessage::ShardUpdate { id, latency, stage }) => {
+                if let Some(runner) = staged.runners.lock().get_mut(&id) {
+                    runner.latency = latency;
+                    runner.stage = stage;
+                }
+            },
+            Ok(ShardManagerMessage::Restart(shard_id)) => {
+                // The runner already stopped, and the restarted shard has to
+                // receive its guilds again.
+                staged.runners.lock().remove(&shard_id);
+                received.remove(&shard_id);
+
+                let _ = shard_queuer.send(ShardQueuerMessage::StartStaged(shard_id, ShardId(total)));
+            },
+            Ok(_) => {},
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                return Err(ReshardError::TimedOut {
+                    received: received.len() as u64,
+                    expected: init,
+                }.into());
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Logs which events will be missing with the given intents, since handlers
+/// for them are silently never called.
+fn warn_about_intents(intents: Option<GatewayIntents>) {
+    let intents = match intents {
+        Some(intents) => intents,
+        None => return,
+    };
+
+    if !intents.contains(GatewayIntents::GUILDS) {
+        warn!(
+            "Gateway intents {:?} lack GUILDS; guild, channel and role events \
+             will not be received and the cache will not know about guilds",
+            intents,
+        );
+    }
+
+    if !intents.intersects(GatewayIntents::GUILD_MESSAGES | GatewayIntents::DIRECT_MESSAGES) {
+        warn!(
+            "Gateway intents {:?} lack GUILD_MESSAGES and DIRECT_MESSAGES; \
+             message events and framework commands will not be received",
+            intents,
+        );
+    }
+
+    #[cfg(feature = "cache")]
+    {
+        if !intents.contains(GatewayIntents::GUILD_MEMBERS) {
+            info!("Gateway intents lack GUILD_MEMBERS; cached member lists will only be partial");
+        }
+
+        if !intents.contains(GatewayIntents::GUILD_PRESENCES) {
+            info!("Gateway intents lack GUILD_PRESENCES; cached presences will not be updated");
+        }
+    }
+
+    if intents.is_privileged() {
+        info!(
+            "Requesting privileged gateway intents {:?}; shards will fail to \
+             identify unless they are enabled for the application",
+            intents & GatewayIntents::privileged(),
+        );
+    }
+}
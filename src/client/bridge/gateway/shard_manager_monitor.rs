@@ -51,6 +51,8 @@ impl ShardManagerMonitor {
                 ShardManagerMessage::Restart(shard_id) => {
                     self.manager.lock().restart(shard_id);
                 },
+                // Only awaited for shards started by a reshard.
+                ShardManagerMessage::GuildsReceived(_) => {},
                 ShardManagerMessage::ShardUpdate { id, latency, stage } => {
                     let manager = self.manager.lock();
                     let mut runners = manager.runners.lock();
@@ -60,7 +62,8 @@ impl ShardManagerMonitor {
                         runner.stage = stage;
                     }
                 }
-                ShardManagerMessage::Shutdown(shard_id) => {
+                ShardManagerMessage::Shutdown(shard_id) |
+                ShardManagerMessage::ShutdownResumable(shard_id) => {
                     self.manager.lock().shutdown(shard_id);
                 },
                 ShardManagerMessage::ShutdownAll => {
@@ -1,8 +1,21 @@
-use std::sync::mpsc::Sender;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    time::{Duration, Instant},
+};
 
 use tungstenite::Message;
 
-use crate::gateway::InterMessage;
+use crate::client::EventCollector;
+use crate::gateway::{
+    InterMessage,
+    MemberRequest,
+    MemberRequestError,
+    RequestedMembers,
+};
 use crate::model::prelude::*;
 use crate::internal::prelude::*;
 
@@ -14,7 +27,13 @@ use super::{ShardClientMessage, ShardRunnerMessage};
 /// [`ShardRunner`]. This can be used for actions such as setting the activity
 /// via [`set_activity`] or shutting down via [`shutdown`].
 ///
+/// Commands sent to the gateway, such as presence updates and member
+/// requests, are held back by the runner to stay within the gateway's
+/// ratelimit. Only the latest presence is sent. Refer to
+/// [`ShardRunnerInfo::queued_commands`] for the commands currently held back.
+///
 /// [`ShardRunner`]: struct.ShardRunner.html
+/// [`ShardRunnerInfo::queued_commands`]: struct.ShardRunnerInfo.html#structfield.queued_commands
 /// [`set_activity`]: #method.set_activity
 /// [`shutdown`]: #method.shutdown
 #[derive(Clone, Debug)]
@@ -122,6 +141,69 @@ impl ShardMessenger {
         });
     }
 
+    /// Requests members of a guild and waits for all of them to arrive, or for
+    /// the timeout to pass.
+    ///
+    /// Unlike [`chunk_guilds`], this correlates the [`GuildMembersChunkEvent`]s
+    /// sent in response with the request, and combines them. The chunks are
+    /// dispatched to event handlers and update the cache as usual.
+    ///
+    /// **Note**: This blocks the current thread. Do not call it from a thread
+    /// that the shard's runner is waiting on.
+    ///
+    /// # Examples
+    ///
+    /// Find members whose username starts with `"zey"` in the guild a message
+    /// was sent in:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::*;
+    /// # use serenity::model::channel::Message;
+    /// #
+    /// use serenity::gateway::MemberRequest;
+    /// use std::time::Duration;
+    ///
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {
+    ///     fn message(&self, ctx: Context, msg: Message) {
+    ///         let guild_id = match msg.guild_id {
+    ///             Some(guild_id) => guild_id,
+    ///             None => return,
+    ///         };
+    ///         let request = MemberRequest::query(guild_id, "zey", 10);
+    ///
+    ///         match ctx.shard.request_members(request, Duration::from_secs(10)) {
+    ///             Ok(found) => println!("Found {} members", found.members.len()),
+    ///             Err(why) => println!("Error requesting members: {:?}", why),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MemberRequestError::TimedOut`] if not all chunks arrived
+    /// in time, and a [`MemberRequestError::Disconnected`] if the shard
+    /// disconnected in the meantime.
+    ///
+    /// [`GuildMembersChunkEvent`]: ../../../model/event/struct.GuildMembersChunkEvent.html
+    /// [`MemberRequestError::Disconnected`]: ../../../gateway/enum.MemberRequestError.html#variant.Disconnected
+    /// [`MemberRequestError::TimedOut`]: ../../../gateway/enum.MemberRequestError.html#variant.TimedOut
+    /// [`chunk_guilds`]: #method.chunk_guilds
+    pub fn request_members(&self, request: MemberRequest, timeout: Duration) -> Result<RequestedMembers> {
+        let (tx, rx) = mpsc::channel();
+
+        self.send(ShardRunnerMessage::RequestMembers {
+            request,
+            nonce: next_nonce(),
+            chunks: tx,
+            timeout,
+        }).map_err(|_| MemberRequestError::Disconnected)?;
+
+        collect_members(&rx, timeout)
+    }
+
     /// Sets the user's current activity, if any.
     ///
     /// Other presence settings are maintained.
@@ -256,9 +338,205 @@ impl ShardMessenger {
         let _ = self.send(ShardRunnerMessage::Message(message));
     }
 
+    /// Registers a collector with the shard's runner, which sends it the
+    /// events it dispatches from then on.
+    ///
+    /// Collectors are created via a [`MessageCollectorBuilder`] or a
+    /// [`ReactionCollectorBuilder`], which call this.
+    ///
+    /// [`MessageCollectorBuilder`]: ../../struct.MessageCollectorBuilder.html
+    /// [`ReactionCollectorBuilder`]: ../../struct.ReactionCollectorBuilder.html
+    pub fn add_collector(&self, collector: EventCollector) {
+        let _ = self.send(ShardRunnerMessage::AddCollector(collector));
+    }
+
     #[inline]
     fn send(&self, msg: ShardRunnerMessage)
         -> Result<()> {
         self.tx.send(InterMessage::Client(Box::new(ShardClientMessage::Runner(msg)))).map_err(Error::from)
     }
 }
+
+impl AsRef<ShardMessenger> for ShardMessenger {
+    fn as_ref(&self) -> &ShardMessenger { self }
+}
+
+// Creates a nonce that is unique within the process, for correlating member
+// chunks with their request.
+fn next_nonce() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    format!("serenity-{}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+// Receives chunks until every chunk of the request arrived, or the timeout
+// passes.
+fn collect_members(
+    chunks: &Receiver<GuildMembersChunkEvent>,
+    timeout: Duration,
+) -> Result<RequestedMembers> {
+    let deadline = Instant::now() + timeout;
+    let mut members = RequestedMembers::default();
+    let mut received = HashSet::new();
+    let mut expected = None;
+
+    loop {
+        let now = Instant::now();
+        let result = if deadline > now {
+            chunks.recv_timeout(deadline - now)
+        } else {
+            Err(RecvTimeoutError::Timeout)
+        };
+
+        match result {
+            Ok(chunk) => {
+                let chunk_count = chunk.chunk_count;
+                received.insert(chunk.chunk_index);
+                expected = Some(chunk_count);
+                members.members.extend(chunk.members);
+                members.not_found.extend(chunk.not_found);
+                members.presences.extend(chunk.presences);
+
+                if received.len() as u32 >= chunk_count {
+                    return Ok(members);
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(MemberRequestError::TimedOut {
+                    received: received.len() as u32,
+                    expected,
+                }.into());
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(MemberRequestError::Disconnected.into());
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+    use std::{
+        sync::mpsc::{self, Sender},
+        thread,
+        time::Duration,
+    };
+    use crate::gateway::{InterMessage, MemberRequest, MemberRequestError};
+    use crate::model::{
+        event::GuildMembersChunkEvent,
+        id::{GuildId, UserId},
+    };
+    use super::{collect_members, ShardClientMessage, ShardMessenger, ShardRunnerMessage};
+
+    fn member(id: u64) -> Value {
+        json!({
+            "deaf": false,
+            "joined_at": "2017-04-15T22:09:16.110563+00:00",
+            "mute": false,
+            "roles": [],
+            "user": {
+                "avatar": null,
+                "discriminator": "0001",
+                "id": id.to_string(),
+                "username": format!("user {}", id),
+            },
+        })
+    }
+
+    fn chunk(index: u32, count: u32, members: &[u64], not_found: &[u64]) -> GuildMembersChunkEvent {
+        serde_json::from_value(json!({
+            "guild_id": "1",
+            "members": members.iter().map(|&id| member(id)).collect::<Vec<_>>(),
+            "chunk_index": index,
+            "chunk_count": count,
+            "not_found": not_found.iter().map(u64::to_string).collect::<Vec<_>>(),
+        })).unwrap()
+    }
+
+    fn send(chunks: &Sender<GuildMembersChunkEvent>, chunk: GuildMembersChunkEvent) {
+        chunks.send(chunk).unwrap();
+    }
+
+    #[test]
+    fn combines_chunks_in_any_order() {
+        let (tx, rx) = mpsc::channel();
+        send(&tx, chunk(1, 2, &[3], &[4]));
+        send(&tx, chunk(0, 2, &[2], &[]));
+
+        let found = collect_members(&rx, Duration::from_secs(5)).unwrap();
+        let mut ids = found.members.keys().map(|id| id.0).collect::<Vec<_>>();
+        ids.sort();
+
+        assert_eq!(ids, vec![2, 3]);
+        assert_eq!(found.not_found, vec![UserId(4)]);
+    }
+
+    #[test]
+    fn reports_the_chunks_received_before_timing_out() {
+        let (tx, rx) = mpsc::channel();
+        send(&tx, chunk(0, 3, &[2], &[]));
+        // A chunk sent again is only counted once.
+        send(&tx, chunk(0, 3, &[2], &[]));
+
+        match collect_members(&rx, Duration::from_millis(50)).unwrap_err().downcast_ref() {
+            Some(MemberRequestError::TimedOut { received: 1, expected: Some(3) }) => {},
+            other => panic!("Expected the request to time out, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn stops_waiting_once_the_runner_forgets_the_request() {
+        let (tx, rx) = mpsc::channel();
+        send(&tx, chunk(0, 2, &[2], &[]));
+        drop(tx);
+
+        match collect_members(&rx, Duration::from_secs(5)).unwrap_err().downcast_ref() {
+            Some(MemberRequestError::Disconnected) => {},
+            other => panic!("Expected the request to be disconnected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sends_member_requests_to_the_runner() {
+        let (runner_tx, runner_rx) = mpsc::channel();
+        let messenger = ShardMessenger::new(runner_tx);
+
+        let request = thread::spawn(move || {
+            let request = MemberRequest::query(GuildId(1), "zey", 10);
+
+            messenger.request_members(request, Duration::from_secs(5))
+        });
+
+        let message = runner_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        match message {
+            InterMessage::Client(message) => match *message {
+                ShardClientMessage::Runner(ShardRunnerMessage::RequestMembers { request, nonce, chunks, timeout }) => {
+                    assert_eq!(request, MemberRequest::query(GuildId(1), "zey", 10));
+                    assert!(nonce.starts_with("serenity-"));
+                    assert_eq!(timeout, Duration::from_secs(5));
+
+                    send(&chunks, chunk(0, 1, &[2], &[]));
+                },
+                other => panic!("Expected a member request, got {:?}", other),
+            },
+            other => panic!("Expected a member request, got {:?}", other),
+        }
+
+        assert_eq!(request.join().unwrap().unwrap().members.len(), 1);
+    }
+
+    #[test]
+    fn fails_member_requests_without_a_runner() {
+        let (runner_tx, runner_rx) = mpsc::channel();
+        drop(runner_rx);
+
+        let request = MemberRequest::query(GuildId(1), "zey", 10);
+        let result = ShardMessenger::new(runner_tx).request_members(request, Duration::from_secs(5));
+
+        match result.unwrap_err().downcast_ref() {
+            Some(MemberRequestError::Disconnected) => {},
+            other => panic!("Expected the request to be disconnected, got {:?}", other),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
     sync::{
         mpsc::{
             Receiver,
@@ -17,35 +18,54 @@ use log::{info, warn};
 use parking_lot::Mutex;
 use parking_lot::RwLock;
 
-use crate::gateway::Shard;
+use crate::gateway::{
+    Connector,
+    GatewayEncoding,
+    IdentifyOptions,
+    SessionStore,
+    Shard,
+    TrafficRecorder,
+    TransportCompression,
+};
 use crate::internal::prelude::*;
 use crate::CacheAndHttp;
 use crate::gateway::ConnectionStage;
+use crate::model::{event::EventType, gateway::GatewayIntents};
 
 #[cfg(feature = "voice")]
 use crate::client::bridge::voice::ClientVoiceManager;
 
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
+#[cfg(feature = "metrics")]
+use crate::metrics::ShardMetrics;
 
-use super::super::super::{EventHandler, RawEventHandler};
+use super::super::super::{DispatchOrder, EventHandler, EventPipeline, RawEventHandler};
 use super::{
+    identify_schedule::WAIT_BETWEEN_IDENTIFIES,
+    IdentifyLock,
+    IdentifySchedule,
     ShardId,
     ShardManagerMessage,
     ShardQueuerMessage,
     ShardRunner,
+    ShardGenerations,
     ShardRunnerInfo,
     ShardRunnerOptions,
+    StagedShards,
 };
 
-const WAIT_BETWEEN_BOOTS_IN_SECONDS: u64 = 5;
-
 /// The shard queuer is a simple loop that runs indefinitely to manage the
 /// startup of shards.
 ///
+/// Shards are started according to the [`IdentifySchedule`]: shards in
+/// different `max_concurrency` buckets are started right away, while each
+/// bucket waits 5 seconds between shard starts.
+///
 /// A shard queuer instance _should_ be run in its own thread, due to the
-/// blocking nature of the loop itself as well as a 5 second thread sleep
-/// between shard starts.
+/// blocking nature of the loop itself.
+///
+/// [`IdentifySchedule`]: struct.IdentifySchedule.html
 pub struct ShardQueuer<H: EventHandler + Send + Sync + 'static,
                        RH: RawEventHandler + Send + Sync + 'static> {
     /// A copy of [`Client::data`] to be given to runners for contextual
@@ -66,19 +86,65 @@ pub struct ShardQueuer<H: EventHandler + Send + Sync + 'static,
     /// A copy of the framework
     #[cfg(feature = "framework")]
     pub framework: Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
-    /// The instant that a shard was last started.
+    /// The middleware and raw handlers that events pass through before being
+    /// dispatched, such as those of the [`Client`].
+    ///
+    /// [`Client`]: ../../struct.Client.html
+    pub pipeline: Arc<EventPipeline>,
+    /// The compression used by shards for data received from the gateway.
+    pub compression: TransportCompression,
+    /// The connector used by shards to connect to the gateway.
+    pub connector: Arc<dyn Connector>,
+    /// The encoding of payloads sent over the gateway by shards.
+    pub encoding: GatewayEncoding,
+    /// The lock acquired before each shard is started, coordinating
+    /// identifies with other processes.
+    pub identify_lock: Option<Arc<dyn IdentifyLock>>,
+    /// The gateway intents sent by shards when identifying.
+    pub intents: Option<GatewayIntents>,
+    /// The options sent by shards when identifying.
+    pub identify_options: IdentifyOptions,
+    /// The generations of shard runners, deciding which of them dispatch
+    /// events, shared with the [`ShardManager`].
     ///
-    /// This is used to determine how long to wait between shard IDENTIFYs.
+    /// [`ShardManager`]: struct.ShardManager.html
+    pub generations: Arc<ShardGenerations>,
+    /// The instant that a shard was last started.
     pub last_start: Option<Instant>,
+    /// When shards may identify, shared with the [`ShardManager`].
+    ///
+    /// [`ShardManager`]: struct.ShardManager.html
+    pub schedule: Arc<Mutex<IdentifySchedule>>,
+    /// The store that shards save their sessions to and resume them from.
+    pub session_store: Option<Arc<dyn SessionStore>>,
+    /// The recorder that shards record the payloads they receive to.
+    pub recorder: Option<TrafficRecorder>,
+    /// The types of events that shards deserialize and dispatch. If `None`,
+    /// every event is.
+    pub event_types: Option<HashSet<EventType>>,
+    /// The order in which shards run the handlers of events.
+    pub dispatch_order: DispatchOrder,
     /// A copy of the sender channel to communicate with the
     /// [`ShardManagerMonitor`].
     ///
     /// [`ShardManagerMonitor`]: struct.ShardManagerMonitor.html
     pub manager_tx: Sender<ShardManagerMessage>,
+    /// The counters updated by shard runners, shared with the
+    /// [`ShardManager`].
+    ///
+    /// [`ShardManager`]: struct.ShardManager.html
+    #[cfg(feature = "metrics")]
+    pub metrics: Arc<ShardMetrics>,
     /// The shards that are queued for booting.
     ///
     /// This will typically be filled with previously failed boots.
     pub queue: VecDeque<(u64, u64)>,
+    /// The set of shards being started by a reshard, if any.
+    pub staged: Option<StagedShards>,
+    /// The shards of the [`staged`] set that are queued for booting.
+    ///
+    /// [`staged`]: #structfield.staged
+    pub staged_queue: VecDeque<(u64, u64)>,
     /// A copy of the map of shard runners.
     pub runners: Arc<Mutex<HashMap<ShardId, ShardRunnerInfo>>>,
     /// A receiver channel for the shard queuer to be told to start shards.
@@ -106,12 +172,10 @@ impl<H: EventHandler + Send + Sync + 'static,
     /// This will loop over the internal [`rx`] for [`ShardQueuerMessage`]s,
     /// blocking for messages on what to do.
     ///
-    /// If a [`ShardQueuerMessage::Start`] is received, this will:
-    ///
-    /// 1. Check how much time has passed since the last shard was started
-    /// 2. If the amount of time is less than the ratelimit, it will sleep until
-    /// that time has passed
-    /// 3. Start the shard by ID
+    /// If a [`ShardQueuerMessage::Start`] is received, the shard is queued, and
+    /// every queued shard whose bucket may identify according to the
+    /// [`schedule`] is started, once the [`identify_lock`] - if any - is
+    /// acquired.
     ///
     /// If a [`ShardQueuerMessage::Shutdown`] is received, this will return and
     /// the loop will be over.
@@ -122,74 +186,177 @@ impl<H: EventHandler + Send + Sync + 'static,
     /// [`ShardQueuerMessage`]: enum.ShardQueuerMessage.html
     /// [`ShardQueuerMessage::Shutdown`]: enum.ShardQueuerMessage.html#variant.Shutdown
     /// [`ShardQueuerMessage::Start`]: enum.ShardQueuerMessage.html#variant.Start
+    /// [`identify_lock`]: #structfield.identify_lock
     /// [`rx`]: #structfield.rx
+    /// [`schedule`]: #structfield.schedule
     pub fn run(&mut self) {
-        // The duration to timeout from reads over the Rx channel. This can be
-        // done in a loop, and if the read times out then a shard can be
-        // started if one is presently waiting in the queue.
-        let wait_duration = Duration::from_secs(WAIT_BETWEEN_BOOTS_IN_SECONDS);
-
         loop {
-            match self.rx.recv_timeout(wait_duration) {
+            // Reads over the Rx channel time out once the next queued shard
+            // may be started.
+            match self.rx.recv_timeout(self.next_wait()) {
                 Ok(ShardQueuerMessage::Shutdown) => break,
                 Ok(ShardQueuerMessage::Start(id, total)) => {
-                    self.checked_start(id.0, total.0);
+                    self.queue.push_back((id.0, total.0));
+                },
+                Ok(ShardQueuerMessage::SetIntents(intents)) => {
+                    self.intents = intents;
+                },
+                Ok(ShardQueuerMessage::SetIdentifyOptions(options)) => {
+                    self.identify_options = options;
+                },
+                Ok(ShardQueuerMessage::SetCompression(compression)) => {
+                    self.compression = compression;
+                },
+                Ok(ShardQueuerMessage::SetEncoding(encoding)) => {
+                    self.encoding = encoding;
+                },
+                Ok(ShardQueuerMessage::SetSessionStore(session_store)) => {
+                    self.session_store = session_store;
+                },
+                Ok(ShardQueuerMessage::SetIdentifyLock(identify_lock)) => {
+                    self.identify_lock = identify_lock;
+                },
+                Ok(ShardQueuerMessage::SetConnector(connector)) => {
+                    self.connector = connector;
+                },
+                Ok(ShardQueuerMessage::SetRecorder(recorder)) => {
+                    self.recorder = recorder;
+                },
+                Ok(ShardQueuerMessage::SetEventTypes(event_types)) => {
+                    self.event_types = event_types;
+                },
+                Ok(ShardQueuerMessage::SetDispatchOrder(dispatch_order)) => {
+                    self.dispatch_order = dispatch_order;
+                },
+                Ok(ShardQueuerMessage::Stage(staged)) => {
+                    self.staged = Some(staged);
+                    self.staged_queue.clear();
+                },
+                Ok(ShardQueuerMessage::StartStaged(id, total)) => {
+                    self.staged_queue.push_back((id.0, total.0));
+                },
+                Ok(ShardQueuerMessage::Promote) => {
+                    // The previous set is being shut down, so its restarts
+                    // are dropped; those of the staged set now start as part
+                    // of the active one.
+                    self.staged = None;
+                    self.queue = mem::replace(&mut self.staged_queue, VecDeque::new());
+                },
+                Ok(ShardQueuerMessage::Unstage) => {
+                    self.staged = None;
+                    self.staged_queue.clear();
                 },
                 Err(RecvTimeoutError::Disconnected) => {
                     // If the sender half has disconnected then the queuer's
                     // lifespan has passed and can shutdown.
                     break;
                 },
-                Err(RecvTimeoutError::Timeout) => {
-                    if let Some((id, total)) = self.queue.pop_front() {
-                        self.checked_start(id, total);
-                    }
-                }
+                Err(RecvTimeoutError::Timeout) => {},
             }
+
+            self.start_ready();
         }
     }
 
-    fn check_last_start(&mut self) {
-        let instant = match self.last_start {
-            Some(instant) => instant,
-            None => return,
-        };
+    // Returns how long until the next queued shard may be started.
+    fn next_wait(&self) -> Duration {
+        let schedule = self.schedule.lock();
+        let now = Instant::now();
 
-        // We must wait 5 seconds between IDENTIFYs to avoid session
-        // invalidations.
-        let duration = Duration::from_secs(WAIT_BETWEEN_BOOTS_IN_SECONDS);
-        let elapsed = instant.elapsed();
+        self.queue
+            .iter()
+            .chain(&self.staged_queue)
+            .map(|&(id, _)| {
+                let next = schedule.next_start(id);
 
-        if elapsed >= duration {
-            return;
-        }
+                if next > now { next - now } else { Duration::from_secs(0) }
+            })
+            .min()
+            .unwrap_or(WAIT_BETWEEN_IDENTIFIES)
+    }
 
-        let to_sleep = duration - elapsed;
+    // Starts every queued shard that may be started now, keeping the others
+    // queued in order.
+    fn start_ready(&mut self) {
+        let queue = mem::replace(&mut self.queue, VecDeque::new());
+        let staged_queue = mem::replace(&mut self.staged_queue, VecDeque::new());
 
-        thread::sleep(to_sleep);
-    }
+        let queued = queue.into_iter().map(|(id, total)| (id, total, false))
+            .chain(staged_queue.into_iter().map(|(id, total)| (id, total, true)));
 
-    fn checked_start(&mut self, id: u64, total: u64) {
-        self.check_last_start();
+        for (id, total, staged) in queued {
+            let ready = self.schedule.lock().next_start(id) <= Instant::now();
 
-        if let Err(why) = self.start(id, total) {
-            warn!("Err starting shard {}: {:?}", id, why);
-            info!("Re-queueing start of shard {}", id);
+            if ready {
+                self.checked_start(id, total, staged);
+            } else {
+                self.requeue(id, total, staged);
+            }
+        }
+    }
 
+    fn requeue(&mut self, id: u64, total: u64, staged: bool) {
+        if staged {
+            self.staged_queue.push_back((id, total));
+        } else {
             self.queue.push_back((id, total));
         }
+    }
+
+    fn checked_start(&mut self, id: u64, total: u64, staged: bool) {
+        // A shard that failed to start is recorded as well, so that it is
+        // retried once its bucket may identify again.
+        let identified = match self.start(id, total, staged) {
+            Ok(resumed) => !resumed,
+            Err(why) => {
+                warn!("Err starting shard {}: {:?}", id, why);
+                info!("Re-queueing start of shard {}", id);
+
+                self.requeue(id, total, staged);
 
+                false
+            },
+        };
+
+        self.schedule.lock().record_start(id, identified);
         self.last_start = Some(Instant::now());
     }
 
-    fn start(&mut self, shard_id: u64, shard_total: u64) -> Result<()> {
+    // Starts a shard, returning whether it resumed a saved session rather than
+    // identifying.
+    //
+    // A shard of the staged set reports to the reshard rather than the
+    // monitor, and always identifies, as saved sessions belong to the
+    // previous set.
+    fn start(&mut self, shard_id: u64, shard_total: u64, staged: bool) -> Result<bool> {
         let shard_info = [shard_id, shard_total];
 
-        let shard = Shard::new(
+        let (generation, manager_tx, runners) = match self.staged {
+            Some(ref set) if staged => {
+                (set.generation, set.manager_tx.clone(), Arc::clone(&set.runners))
+            },
+            _ => (self.generations.active(), self.manager_tx.clone(), Arc::clone(&self.runners)),
+        };
+
+        if let Some(ref identify_lock) = self.identify_lock {
+            identify_lock.acquire(shard_id)?;
+        }
+
+        let mut shard = Shard::new_with_connector(
             Arc::clone(&self.ws_url),
             &self.cache_and_http.http.token,
             shard_info,
+            self.compression,
+            self.encoding,
+            Arc::clone(&self.connector),
         )?;
+        shard.set_intents(self.intents);
+        shard.set_identify_options(self.identify_options.clone());
+
+        let resumed = match self.session_store {
+            Some(ref session_store) if !staged => restore_session(&mut shard, &**session_store),
+            _ => false,
+        };
 
         let mut runner = ShardRunner::new(ShardRunnerOptions {
             data: Arc::clone(&self.data),
@@ -197,7 +364,16 @@ impl<H: EventHandler + Send + Sync + 'static,
             raw_event_handler: self.raw_event_handler.as_ref().map(|rh| Arc::clone(rh)),
             #[cfg(feature = "framework")]
             framework: Arc::clone(&self.framework),
-            manager_tx: self.manager_tx.clone(),
+            pipeline: Arc::clone(&self.pipeline),
+            event_types: self.event_types.clone(),
+            dispatch_order: self.dispatch_order,
+            generation,
+            generations: Arc::clone(&self.generations),
+            manager_tx,
+            #[cfg(feature = "metrics")]
+            metrics: Arc::clone(&self.metrics),
+            recorder: self.recorder.clone(),
+            session_store: self.session_store.clone(),
             threadpool: self.threadpool.clone(),
             #[cfg(feature = "voice")]
             voice_manager: Arc::clone(&self.voice_manager),
@@ -209,14 +385,45 @@ impl<H: EventHandler + Send + Sync + 'static,
             latency: None,
             runner_tx: runner.runner_tx(),
             stage: ConnectionStage::Disconnected,
+            queued_commands: runner.queued_commands(),
         };
 
         thread::spawn(move || {
             let _ = runner.run();
         });
 
-        self.runners.lock().insert(ShardId(shard_id), runner_info);
+        runners.lock().insert(ShardId(shard_id), runner_info);
 
-        Ok(())
+        Ok(resumed)
     }
 }
+
+// Resumes the session the shard saved when it was last shut down, if any.
+//
+// The session is taken out of the store, so that a session rejected by the
+// gateway is not attempted again when the shard restarts to identify.
+//
+// Returns whether a RESUME was sent.
+fn restore_session(shard: &mut Shard, session_store: &dyn SessionStore) -> bool {
+    let shard_id = shard.shard_info()[0];
+
+    let session = match session_store.load(shard_id) {
+        Ok(Some(session)) => session,
+        Ok(None) => return false,
+        Err(why) => {
+            warn!("Err loading session of shard {}: {:?}", shard_id, why);
+
+            return false;
+        },
+    };
+
+    if let Err(why) = session_store.remove(shard_id) {
+        warn!("Err removing session of shard {}: {:?}", shard_id, why);
+    }
+
+    shard.restore_session(session).unwrap_or_else(|why| {
+        warn!("Err resuming session of shard {}: {:?}", shard_id, why);
+
+        false
+    })
+}
@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::{HashMap, HashSet},
     sync::{
         mpsc::{
             self,
@@ -9,32 +10,56 @@ use std::{
         },
         Arc,
     },
+    time::Instant,
 };
 
 use parking_lot::Mutex;
 use parking_lot::RwLock;
-use serde::Deserialize;
 use threadpool::ThreadPool;
 use tungstenite::{
     error::Error as TungsteniteError,
     protocol::frame::CloseFrame,
 };
 use typemap::ShareMap;
-use log::{error, debug, warn};
+use log::{error, debug, info, warn};
 
-use crate::gateway::{InterMessage, ReconnectType, Shard, ShardAction};
+use crate::gateway::{
+    ConnectionStage,
+    GatewayPayload,
+    InterMessage,
+    ReconnectType,
+    SessionStore,
+    Shard,
+    ShardAction,
+    TrafficRecorder,
+};
 use crate::internal::prelude::*;
-use crate::internal::ws_impl::{ReceiverExt, SenderExt};
-use crate::model::event::{Event, GatewayEvent};
+use crate::internal::ws_impl::{is_timeout, SenderExt};
+use crate::model::{
+    event::{Event, EventType, GatewayEvent, GuildMembersChunkEvent},
+    id::GuildId,
+};
 use crate::CacheAndHttp;
 
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
+#[cfg(feature = "metrics")]
+use crate::metrics::ShardMetrics;
 
+use super::super::super::collector::Collectors;
 use super::super::super::dispatch::{DispatchEvent, dispatch};
-use super::super::super::{EventHandler, RawEventHandler};
+use super::super::super::dispatch_order::{DispatchOrder, DispatchQueues};
+use super::super::super::{EventHandler, EventPipeline, RawEventHandler};
 use super::event::{ClientEvent, ShardStageUpdateEvent};
-use super::{ShardClientMessage, ShardId, ShardManagerMessage, ShardRunnerMessage};
+use super::{
+    command_limiter::{Command, CommandLimiter},
+    QueuedCommands,
+    ShardClientMessage,
+    ShardGenerations,
+    ShardId,
+    ShardManagerMessage,
+    ShardRunnerMessage,
+};
 
 #[cfg(feature = "voice")]
 use super::super::voice::ClientVoiceManager;
@@ -50,11 +75,30 @@ pub struct ShardRunner<H: EventHandler + Send + Sync + 'static,
     raw_event_handler: Option<Arc<RH>>,
     #[cfg(feature = "framework")]
     framework: Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
+    pipeline: Arc<EventPipeline>,
+    // the collectors sent the events dispatched by the runner
+    collectors: Collectors,
+    // the queues ordering the handlers of related events
+    queues: DispatchQueues,
+    // the commands held back to stay within the gateway's ratelimit
+    commands: CommandLimiter<InterMessage>,
+    // the types of events to deserialize, including those always required
+    event_types: Option<HashSet<EventType>>,
+    generation: u64,
+    generations: Arc<ShardGenerations>,
     manager_tx: Sender<ShardManagerMessage>,
+    // the guilds sent in the last Ready event that were not received yet
+    pending_guilds: Option<HashSet<GuildId>>,
+    // the requests for guild members awaiting chunks, by nonce
+    member_requests: HashMap<String, PendingMembers>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<ShardMetrics>,
     // channel to receive messages from the shard manager and dispatches
     runner_rx: Receiver<InterMessage>,
     // channel to send messages to the shard runner from the shard manager
     runner_tx: Sender<InterMessage>,
+    recorder: Option<TrafficRecorder>,
+    session_store: Option<Arc<dyn SessionStore>>,
     shard: Shard,
     threadpool: ThreadPool,
     #[cfg(feature = "voice")]
@@ -68,6 +112,12 @@ impl<H: EventHandler + Send + Sync + 'static,
     pub fn new(opt: ShardRunnerOptions<H, RH>) -> Self {
         let (tx, rx) = mpsc::channel();
 
+        let mut event_types = opt.event_types;
+
+        if let Some(ref mut event_types) = event_types {
+            event_types.extend(required_event_types());
+        }
+
         Self {
             runner_rx: rx,
             runner_tx: tx,
@@ -76,7 +126,20 @@ impl<H: EventHandler + Send + Sync + 'static,
             raw_event_handler: opt.raw_event_handler,
             #[cfg(feature = "framework")]
             framework: opt.framework,
+            pipeline: opt.pipeline,
+            collectors: Collectors::default(),
+            queues: DispatchQueues::new(opt.dispatch_order, opt.threadpool.clone()),
+            commands: CommandLimiter::new(),
+            event_types,
+            generation: opt.generation,
+            generations: opt.generations,
             manager_tx: opt.manager_tx,
+            member_requests: HashMap::new(),
+            #[cfg(feature = "metrics")]
+            metrics: opt.metrics,
+            pending_guilds: None,
+            recorder: opt.recorder,
+            session_store: opt.session_store,
             shard: opt.shard,
             threadpool: opt.threadpool,
             #[cfg(feature = "voice")]
@@ -120,6 +183,8 @@ impl<H: EventHandler + Send + Sync + 'static,
                 return Ok(());
             }
 
+            self.expire_member_requests();
+
             // check heartbeat
             if !self.shard.check_heartbeat() {
                 warn!(
@@ -138,12 +203,16 @@ impl<H: EventHandler + Send + Sync + 'static,
             if post != pre {
                 self.update_manager();
 
-                let e = ClientEvent::ShardStageUpdate(ShardStageUpdateEvent {
-                    new: post,
-                    old: pre,
-                    shard_id: ShardId(self.shard.shard_info()[0]),
-                });
-                self.dispatch(DispatchEvent::Client(e));
+                // Shards of a staged or retired set are not reported, so
+                // that handlers only see the stages of the active one.
+                if self.generation == self.generations.active() {
+                    let e = ClientEvent::ShardStageUpdate(ShardStageUpdateEvent {
+                        new: post,
+                        old: pre,
+                        shard_id: ShardId(self.shard.shard_info()[0]),
+                    });
+                    self.dispatch(DispatchEvent::Client(e));
+                }
             }
 
             match action {
@@ -157,6 +226,10 @@ impl<H: EventHandler + Send + Sync + 'static,
                 None => {},
             }
 
+            if let Some(Event::GuildMembersChunk(ref chunk)) = event {
+                self.forward_member_chunk(chunk);
+            }
+
             if let Some(event) = event {
                 self.dispatch(DispatchEvent::Model(event));
             }
@@ -173,6 +246,12 @@ impl<H: EventHandler + Send + Sync + 'static,
         self.runner_tx.clone()
     }
 
+    /// Returns the state of the commands held back by the runner, which is
+    /// kept up to date while it runs.
+    pub(super) fn queued_commands(&self) -> Arc<Mutex<QueuedCommands>> {
+        self.commands.status()
+    }
+
     /// Takes an action that a [`Shard`] has determined should happen and then
     /// does it.
     ///
@@ -189,6 +268,10 @@ impl<H: EventHandler + Send + Sync + 'static,
                 Ok(())
             },
             ShardAction::Reconnect(ReconnectType::Resume) => {
+                #[cfg(feature = "metrics")]
+                self.metrics.record_reconnect(self.shard.shard_info()[0], "resume");
+
+                self.forget_member_requests();
                 self.shard.resume()
             },
             ShardAction::Reconnect(ReconnectType::__Nonexhaustive) => unreachable!(),
@@ -205,7 +288,10 @@ impl<H: EventHandler + Send + Sync + 'static,
     // Returns whether the WebSocket client is still active.
     //
     // If true, the WebSocket client was _not_ shutdown. If false, it was.
-    fn checked_shutdown(&mut self, id: ShardId) -> bool {
+    //
+    // If `resumable`, the session is kept resumable even if it is not saved,
+    // such as for another process to resume it.
+    fn checked_shutdown(&mut self, id: ShardId, resumable: bool) -> bool {
         // First verify the ID so we know for certain this runner is
         // to shutdown.
         if id.0 != self.shard.shard_info()[0] {
@@ -214,9 +300,15 @@ impl<H: EventHandler + Send + Sync + 'static,
             return true;
         }
 
+        // Closing with 1000 invalidates the session, so a saved session, or
+        // one to be resumed elsewhere, is closed with another code to keep it
+        // resumable.
+        let saved = self.save_session();
+        let code = if saved || resumable { 4000 } else { 1000 };
+
         // Send a Close Frame to Discord, which allows a bot to "log off"
         let _ = self.shard.client.close(Some(CloseFrame {
-            code: 1000.into(),
+            code: code.into(),
             reason: Cow::from(""),
         }));
 
@@ -247,10 +339,49 @@ impl<H: EventHandler + Send + Sync + 'static,
         false
     }
 
+    // Saves the shard's session to the session store, if there is one.
+    //
+    // Returns whether the session was saved.
+    fn save_session(&self) -> bool {
+        let session_store = match self.session_store {
+            Some(ref session_store) => session_store,
+            None => return false,
+        };
+
+        let session = match self.shard.session_info() {
+            Some(session) => session,
+            None => return false,
+        };
+
+        match session_store.save(session.shard_info[0], &session) {
+            Ok(()) => {
+                info!(
+                    "[ShardRunner {:?}] Saved session at seq {}",
+                    self.shard.shard_info(),
+                    session.seq,
+                );
+
+                true
+            },
+            Err(why) => {
+                warn!(
+                    "[ShardRunner {:?}] Err saving session: {:?}",
+                    self.shard.shard_info(),
+                    why,
+                );
+
+                false
+            },
+        }
+    }
+
     #[inline]
     fn dispatch(&self, event: DispatchEvent) {
         dispatch(
             event,
+            &self.pipeline,
+            &self.collectors,
+            &self.queues,
             #[cfg(feature = "framework")]
             &self.framework,
             &self.data,
@@ -274,7 +405,10 @@ impl<H: EventHandler + Send + Sync + 'static,
             InterMessage::Client(value) => match *value {
                     ShardClientMessage::Manager(ShardManagerMessage::Restart(id)) |
                     ShardClientMessage::Manager(ShardManagerMessage::Shutdown(id)) => {
-                        self.checked_shutdown(id)
+                        self.checked_shutdown(id, false)
+                    },
+                    ShardClientMessage::Manager(ShardManagerMessage::ShutdownResumable(id)) => {
+                        self.checked_shutdown(id, true)
                     },
                     ShardClientMessage::Manager(ShardManagerMessage::ShutdownAll) => {
                         // This variant should never be received.
@@ -285,6 +419,11 @@ impl<H: EventHandler + Send + Sync + 'static,
 
                         true
                     },
+                    ShardClientMessage::Manager(ShardManagerMessage::GuildsReceived(_)) => {
+                        // nb: not sent here
+
+                        true
+                    },
                     ShardClientMessage::Manager(ShardManagerMessage::ShardUpdate { .. }) => {
                         // nb: not sent here
 
@@ -300,12 +439,10 @@ impl<H: EventHandler + Send + Sync + 'static,
 
                         true
                     },
-                ShardClientMessage::Runner(ShardRunnerMessage::ChunkGuilds { guild_ids, limit, query }) => {
-                    self.shard.chunk_guilds(
-                        guild_ids,
-                        limit,
-                        query.as_ref().map(String::as_str),
-                    ).is_ok()
+                ShardClientMessage::Runner(ShardRunnerMessage::AddCollector(collector)) => {
+                    self.collectors.add(collector);
+
+                    true
                 },
                 ShardClientMessage::Runner(ShardRunnerMessage::Close(code, reason)) => {
                     let reason = reason.unwrap_or_else(String::new);
@@ -315,41 +452,102 @@ impl<H: EventHandler + Send + Sync + 'static,
                     };
                     self.shard.client.close(Some(close)).is_ok()
                 },
-                ShardClientMessage::Runner(ShardRunnerMessage::Message(msg)) => {
-                    self.shard.client.write_message(msg).is_ok()
-                },
+                // Only the latest presence is sent, so the shard's current
+                // presence is updated right away, and sent once the
+                // ratelimit allows.
                 ShardClientMessage::Runner(ShardRunnerMessage::SetActivity(activity)) => {
-                    // To avoid a clone of `activity`, we do a little bit of
-                    // trickery here:
-                    //
-                    // First, we obtain a reference to the current presence of
-                    // the shard, and create a new presence tuple of the new
-                    // activity we received over the channel as well as the
-                    // online status that the shard already had.
-                    //
-                    // We then (attempt to) send the websocket message with the
-                    // status update, expressively returning:
-                    //
-                    // - whether the message successfully sent
-                    // - the original activity we received over the channel
                     self.shard.set_activity(activity);
+                    self.commands.push_presence();
 
-                    self.shard.update_presence().is_ok()
+                    true
                 },
                 ShardClientMessage::Runner(ShardRunnerMessage::SetPresence(status, activity)) => {
                     self.shard.set_presence(status, activity);
+                    self.commands.push_presence();
 
-                    self.shard.update_presence().is_ok()
+                    true
                 },
                 ShardClientMessage::Runner(ShardRunnerMessage::SetStatus(status)) => {
                     self.shard.set_status(status);
+                    self.commands.push_presence();
 
-                    self.shard.update_presence().is_ok()
+                    true
+                },
+                command @ ShardClientMessage::Runner(_) => {
+                    self.commands.push(InterMessage::Client(Box::new(command)));
+
+                    true
                 },
             },
+            command @ InterMessage::Json(_) => {
+                self.commands.push(command);
+
+                true
+            },
+            InterMessage::__Nonexhaustive => unreachable!(),
+        }
+    }
+
+    // Sends the held back commands that the gateway's ratelimit allows, once
+    // the shard is connected.
+    //
+    // Returns whether the WebSocket client is still active.
+    fn send_commands(&mut self) -> bool {
+        if self.shard.stage() != ConnectionStage::Connected {
+            return true;
+        }
+
+        while let Some(command) = self.commands.next(Instant::now()) {
+            let sent = match command {
+                Command::Presence => self.shard.update_presence().is_ok(),
+                Command::Queued(value) => self.send_command(value),
+            };
+
+            if !sent {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Sends a command over the WebSocket, returning whether it was sent.
+    fn send_command(&mut self, value: InterMessage) -> bool {
+        match value {
+            InterMessage::Client(value) => match *value {
+                ShardClientMessage::Runner(ShardRunnerMessage::ChunkGuilds { guild_ids, limit, query }) => {
+                    self.shard.chunk_guilds(
+                        guild_ids,
+                        limit,
+                        query.as_ref().map(String::as_str),
+                    ).is_ok()
+                },
+                ShardClientMessage::Runner(ShardRunnerMessage::RequestMembers { request, nonce, chunks, timeout }) => {
+                    let sent = self.shard.request_members(&request, &nonce).is_ok();
+
+                    // Dropping the sender otherwise lets the requester know
+                    // that no chunks will arrive.
+                    if sent {
+                        self.member_requests.insert(nonce, PendingMembers {
+                            chunks,
+                            received: HashSet::new(),
+                            expires: Instant::now() + timeout,
+                        });
+                    }
+
+                    sent
+                },
+                ShardClientMessage::Runner(ShardRunnerMessage::Message(msg)) => {
+                    self.shard.client.write_message(msg).is_ok()
+                },
+                // Other messages are handled as they are received.
+                _ => true,
+            },
             InterMessage::Json(value) => {
                 // Value must be forwarded over the websocket
-                self.shard.client.send_json(&value).is_ok()
+                let encoding = self.shard.encoding();
+
+                self.shard.client.send_encoded(&value, encoding).is_ok()
             },
             InterMessage::__Nonexhaustive => unreachable!(),
         }
@@ -421,46 +619,104 @@ impl<H: EventHandler + Send + Sync + 'static,
 
         // There are no longer any values available.
 
-        Ok(true)
+        Ok(self.send_commands())
+    }
+
+    // Returns whether the payload is a dispatch of an event type that is not
+    // wanted, in which case only its sequence number is handled.
+    fn discard_unwanted(&mut self, payload: &GatewayPayload) -> bool {
+        let event_types = match self.event_types {
+            Some(ref event_types) => event_types,
+            None => return false,
+        };
+
+        let kind = match payload.event_type() {
+            Some(kind) => kind,
+            None => return false,
+        };
+
+        if event_types.contains(&kind) || self.needed_anyway(kind) {
+            return false;
+        }
+
+        match payload.seq() {
+            Some(seq) => {
+                self.shard.handle_discarded_dispatch(seq);
+
+                true
+            },
+            None => false,
+        }
+    }
+
+    // Whether events of a type that is not wanted are still needed by the
+    // framework, the cache or collectors, as set when the event is received.
+    fn needed_anyway(&self, kind: EventType) -> bool {
+        match kind {
+            // Commands are parsed from messages.
+            #[cfg(feature = "framework")]
+            EventType::MessageCreate if self.framework.lock().is_some() => true,
+            #[cfg(feature = "cache")]
+            EventType::MessageCreate | EventType::MessageUpdate => {
+                self.cache_and_http.cache.as_ref().read().settings().max_messages > 0
+                    || self.collectors.wants(kind)
+            },
+            _ => self.collectors.wants(kind),
+        }
     }
 
     /// Returns a received event, as well as whether reading the potentially
     /// present event was successful.
+    ///
+    /// Dispatch events are only returned if this runner's generation should
+    /// dispatch them.
     fn recv_event(&mut self) -> (Option<Event>, Option<ShardAction>, bool) {
-        let gw_event = match self.shard.client.recv_json().map_err(Error::from) {
-            Ok(Some(value)) => {
-                GatewayEvent::deserialize(value).map(Some).map_err(From::from)
+        let mut dispatch = true;
+
+        let gw_event = match self.shard.recv_payload() {
+            Ok(Some(payload)) => {
+                self.record(&payload);
+
+                #[cfg(feature = "metrics")]
+                {
+                    if let Some(kind) = payload.kind() {
+                        self.metrics.record_event(kind);
+                    }
+                }
+
+                if self.discard_unwanted(&payload) {
+                    return (None, None, true);
+                }
+
+                dispatch = self.generations.should_dispatch(self.generation, &payload);
+
+                payload.into_event().map(Some)
             },
             Ok(None) => Ok(None),
             Err(e) => match e.downcast() {
-                Ok(TungsteniteError::Io(_)) => {
-                    // Check that an amount of time at least double the
-                    // heartbeat_interval has passed.
-                    //
-                    // If not, continue on trying to receive messages.
-                    //
-                    // If it has, attempt to auto-reconnect.
-                    {
-                        let last = self.shard.last_heartbeat_ack();
-                        let interval = self.shard.heartbeat_interval();
-
-                        if let (Some(last_heartbeat_ack), Some(interval)) = (last, interval) {
-                            let seconds_passed = last_heartbeat_ack.elapsed().as_secs();
-                            let interval_in_secs = interval / 1000;
-
-                            if seconds_passed <= interval_in_secs * 2 {
-                                return (None, None, true);
-                            }
-                        } else {
-                            return (None, None, true);
-                        }
-                    }
-
+                // Check that an amount of time at least double the
+                // heartbeat_interval has passed since a read timed out.
+                //
+                // If not, continue on trying to receive messages.
+                //
+                // If it has, or if the connection was lost, attempt to
+                // auto-reconnect.
+                Ok(TungsteniteError::Io(ref why)) if is_timeout(why) && !self.heartbeat_overdue() => {
+                    return (None, None, true);
+                },
+                Ok(TungsteniteError::Io(_)) |
+                Ok(TungsteniteError::ConnectionClosed) |
+                Ok(TungsteniteError::AlreadyClosed) => {
                     debug!("Attempting to auto-reconnect");
 
                     match self.shard.reconnection_type() {
                         ReconnectType::Reidentify => return (None, None, false),
                         ReconnectType::Resume => {
+                            #[cfg(feature = "metrics")]
+                            self.metrics.record_reconnect(self.shard.shard_info()[0], "resume");
+
+                            self.forget_member_requests();
+
                             if let Err(why) = self.shard.resume() {
                                 warn!("Failed to resume: {:?}", why);
 
@@ -497,24 +753,151 @@ impl<H: EventHandler + Send + Sync + 'static,
             self.update_manager();
         }
 
+        if let Ok(GatewayEvent::Dispatch(_, ref event)) = event {
+            self.track_guilds(event);
+        }
+
         #[cfg(feature = "voice")]
         {
             if let Ok(GatewayEvent::Dispatch(_, ref event)) = event {
-                self.handle_voice_event(&event);
+                if dispatch {
+                    self.handle_voice_event(&event);
+                }
             }
         }
 
         let event = match event {
-            Ok(GatewayEvent::Dispatch(_, event)) => Some(event),
+            Ok(GatewayEvent::Dispatch(_, event)) if dispatch => Some(event),
             _ => None,
         };
 
         (event, action, true)
     }
 
-    fn request_restart(&self) {
+    // Forwards a member chunk to the request it was sent for, if it is still
+    // awaiting chunks.
+    fn forward_member_chunk(&mut self, chunk: &GuildMembersChunkEvent) {
+        let nonce = match chunk.nonce {
+            Some(ref nonce) => nonce,
+            None => return,
+        };
+
+        let complete = match self.member_requests.get_mut(nonce) {
+            Some(pending) => {
+                pending.received.insert(chunk.chunk_index);
+
+                // If the requester stopped waiting, forget the request right
+                // away.
+                pending.chunks.send(chunk.clone()).is_err()
+                    || pending.received.len() as u32 >= chunk.chunk_count
+            },
+            None => return,
+        };
+
+        if complete {
+            self.member_requests.remove(nonce);
+        }
+    }
+
+    // Forgets the member requests whose requesters stopped waiting, as some
+    // chunks may never arrive.
+    fn expire_member_requests(&mut self) {
+        if self.member_requests.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+
+        self.member_requests.retain(|_, pending| pending.expires > now);
+    }
+
+    // Forgets all member requests, as the chunks for them are not sent after
+    // reconnecting. Dropping the senders lets the requesters know.
+    fn forget_member_requests(&mut self) {
+        self.member_requests.clear();
+    }
+
+    // Tracks which of the guilds sent in the Ready event were received, and
+    // lets the manager know once all of them were.
+    //
+    // Guilds that stay unavailable are received as deleted.
+    fn track_guilds(&mut self, event: &Event) {
+        match *event {
+            Event::Ready(ref event) => {
+                let guilds = event.ready.guilds.iter().map(|guild| guild.id()).collect();
+
+                self.pending_guilds = Some(guilds);
+            },
+            Event::GuildCreate(ref event) => {
+                if let Some(ref mut pending) = self.pending_guilds {
+                    pending.remove(&event.guild.id);
+                }
+            },
+            Event::GuildDelete(ref event) => {
+                if let Some(ref mut pending) = self.pending_guilds {
+                    pending.remove(&event.guild.id);
+                }
+            },
+            _ => return,
+        }
+
+        if self.pending_guilds.as_ref().map_or(false, HashSet::is_empty) {
+            self.pending_guilds = None;
+
+            let id = ShardId(self.shard.shard_info()[0]);
+            let _ = self.manager_tx.send(ShardManagerMessage::GuildsReceived(id));
+        }
+    }
+
+    // Records a payload received from the gateway, if a recorder is set.
+    fn record(&self, payload: &GatewayPayload) {
+        if let Some(ref recorder) = self.recorder {
+            if let Err(why) = recorder.record(self.shard.shard_info()[0], payload) {
+                warn!(
+                    "[ShardRunner {:?}] Error recording payload: {:?}",
+                    self.shard.shard_info(),
+                    why,
+                );
+            }
+        }
+    }
+
+    // Returns whether more than twice the heartbeat interval has passed since
+    // the last heartbeat acknowledgement.
+    fn heartbeat_overdue(&self) -> bool {
+        let last = self.shard.last_heartbeat_ack();
+        let interval = self.shard.heartbeat_interval();
+
+        match (last, interval) {
+            (Some(last_heartbeat_ack), Some(interval)) => {
+                let seconds_passed = last_heartbeat_ack.elapsed().as_secs();
+                let interval_in_secs = interval / 1000;
+
+                seconds_passed > interval_in_secs * 2
+            },
+            _ => false,
+        }
+    }
+
+    fn request_restart(&mut self) {
+        self.forget_member_requests();
+
+        // A retired shard is about to be shut down, and its replacement is
+        // already running.
+        if self.generations.is_retired(self.generation) {
+            debug!(
+                "[ShardRunner {:?}] Retired; not restarting",
+                self.shard.shard_info(),
+            );
+
+            return;
+        }
+
         self.update_manager();
 
+        #[cfg(feature = "metrics")]
+        self.metrics.record_reconnect(self.shard.shard_info()[0], "restart");
+
         debug!(
             "[ShardRunner {:?}] Requesting restart",
             self.shard.shard_info(),
@@ -538,6 +921,15 @@ impl<H: EventHandler + Send + Sync + 'static,
     }
 }
 
+// A request for guild members awaiting chunks.
+struct PendingMembers {
+    chunks: Sender<GuildMembersChunkEvent>,
+    // the indices of the chunks received so far
+    received: HashSet<u32>,
+    // when the requester stops waiting for chunks
+    expires: Instant,
+}
+
 /// Options to be passed to [`ShardRunner::new`].
 ///
 /// [`ShardRunner::new`]: struct.ShardRunner.html#method.new
@@ -548,7 +940,23 @@ pub struct ShardRunnerOptions<H: EventHandler + Send + Sync + 'static,
     pub raw_event_handler: Option<Arc<RH>>,
     #[cfg(feature = "framework")]
     pub framework: Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
+    /// The middleware and raw handlers that events pass through before being
+    /// dispatched.
+    pub pipeline: Arc<EventPipeline>,
+    /// The types of events to deserialize and dispatch. If `None`, every
+    /// event is.
+    pub event_types: Option<HashSet<EventType>>,
+    /// The order in which the handlers of events are run.
+    pub dispatch_order: DispatchOrder,
+    /// The generation of the set of shards the runner belongs to.
+    pub generation: u64,
+    /// The generations deciding whether the runner dispatches events.
+    pub generations: Arc<ShardGenerations>,
     pub manager_tx: Sender<ShardManagerMessage>,
+    #[cfg(feature = "metrics")]
+    pub metrics: Arc<ShardMetrics>,
+    pub recorder: Option<TrafficRecorder>,
+    pub session_store: Option<Arc<dyn SessionStore>>,
     pub shard: Shard,
     pub threadpool: ThreadPool,
     #[cfg(feature = "voice")]
@@ -556,3 +964,536 @@ pub struct ShardRunnerOptions<H: EventHandler + Send + Sync + 'static,
     #[cfg(any(feature = "cache", feature = "http"))]
     pub cache_and_http: Arc<CacheAndHttp>,
 }
+
+// The types of events that are deserialized regardless of the wanted ones, as
+// the shard, the runner or the cache rely on them.
+#[cfg_attr(not(feature = "cache"), allow(unused_mut))]
+fn required_event_types() -> Vec<EventType> {
+    let mut event_types = vec![
+        EventType::Ready,
+        EventType::Resumed,
+        // tracked while resharding
+        EventType::GuildCreate,
+        EventType::GuildDelete,
+        // forwarded to member requests
+        EventType::GuildMembersChunk,
+        // forwarded to the voice manager
+        EventType::VoiceServerUpdate,
+        EventType::VoiceStateUpdate,
+    ];
+
+    // Presences, messages and other frequent events are left out, as the
+    // cache stays consistent without them.
+    #[cfg(feature = "cache")]
+    event_types.extend(vec![
+        EventType::ChannelCreate,
+        EventType::ChannelDelete,
+        EventType::ChannelPinsUpdate,
+        EventType::ChannelRecipientAdd,
+        EventType::ChannelRecipientRemove,
+        EventType::ChannelUpdate,
+        EventType::GuildEmojisUpdate,
+        EventType::GuildMemberAdd,
+        EventType::GuildMemberRemove,
+        EventType::GuildMemberUpdate,
+        EventType::GuildRoleCreate,
+        EventType::GuildRoleDelete,
+        EventType::GuildRoleUpdate,
+        EventType::GuildUnavailable,
+        EventType::GuildUpdate,
+        EventType::UserUpdate,
+    ]);
+
+    event_types
+}
+
+#[cfg(test)]
+mod test {
+    use parking_lot::{Mutex, RwLock};
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashSet,
+        env,
+        fs,
+        sync::{
+            mpsc::{self, Receiver, Sender},
+            Arc,
+        },
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+    use threadpool::ThreadPool;
+    use typemap::ShareMap;
+    use crate::client::{
+        Context,
+        DispatchOrder,
+        EventHandler,
+        EventPipeline,
+        RawEventHandler,
+        ReactionCollectorBuilder,
+    };
+    use crate::constants::OpCode;
+    use crate::gateway::{
+        fake::{FakeConnection, FakeGateway},
+        GatewayEncoding,
+        InterMessage,
+        MemberRequest,
+        MemberRequestError,
+        Shard,
+        TrafficLog,
+        TrafficRecorder,
+        TransportCompression,
+    };
+    use crate::model::{
+        event::{EventType, ResumedEvent, TypingStartEvent},
+        gateway::Ready,
+        id::{GuildId, MessageId, UserId},
+        user::OnlineStatus,
+    };
+    use crate::CacheAndHttp;
+    use super::super::{
+        ShardClientMessage,
+        ShardGenerations,
+        ShardId,
+        ShardManagerMessage,
+        ShardMessenger,
+    };
+    use super::{ShardRunner, ShardRunnerOptions};
+
+    // How long to wait for the runner to do something.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    // Reports the events dispatched to it.
+    struct Handler(Mutex<Sender<&'static str>>);
+
+    impl EventHandler for Handler {
+        fn ready(&self, _: Context, _: Ready) {
+            let _ = self.0.lock().send("ready");
+        }
+
+        fn resume(&self, _: Context, _: ResumedEvent) {
+            let _ = self.0.lock().send("resume");
+        }
+
+        fn typing_start(&self, _: Context, _: TypingStartEvent) {
+            let _ = self.0.lock().send("typing");
+        }
+    }
+
+    struct RawHandler;
+
+    impl RawEventHandler for RawHandler {}
+
+    // Builds a runner of shard 0 of 1, connected to a fake gateway, which
+    // reports the Ready, Resumed and TypingStart events dispatched to it.
+    struct RunnerFixture {
+        event_types: Option<HashSet<EventType>>,
+        generation: u64,
+        generations: Arc<ShardGenerations>,
+        recorder: Option<TrafficRecorder>,
+    }
+
+    impl RunnerFixture {
+        fn new() -> Self {
+            RunnerFixture {
+                event_types: None,
+                generation: 0,
+                generations: Arc::new(ShardGenerations::default()),
+                recorder: None,
+            }
+        }
+
+        // Sets the only types of events to deserialize.
+        fn event_types(mut self, event_types: &[EventType]) -> Self {
+            self.event_types = Some(event_types.iter().cloned().collect());
+
+            self
+        }
+
+        // Sets the generation the runner's shard belongs to.
+        fn generation(mut self, generations: &Arc<ShardGenerations>, generation: u64) -> Self {
+            self.generations = Arc::clone(generations);
+            self.generation = generation;
+
+            self
+        }
+
+        // Sets the recorder of the received payloads.
+        fn recorder(mut self, recorder: TrafficRecorder) -> Self {
+            self.recorder = Some(recorder);
+
+            self
+        }
+
+        // Starts the runner on its own thread.
+        fn run(self) -> Running {
+            let gateway = FakeGateway::new();
+            let shard = Shard::new_with_connector(
+                Arc::new(Mutex::new(FakeGateway::URL.to_string())),
+                "token",
+                [0, 1],
+                TransportCompression::None,
+                GatewayEncoding::Json,
+                Arc::new(gateway.clone()),
+            ).unwrap();
+
+            let (events_tx, events) = mpsc::channel();
+            let (manager_tx, manager) = mpsc::channel();
+            #[cfg(any(feature = "cache", feature = "http"))]
+            let cache_and_http = Arc::new(CacheAndHttp {
+                #[cfg(feature = "cache")]
+                cache: Arc::new(RwLock::new(Default::default())),
+                #[cfg(feature = "cache")]
+                update_cache_timeout: None,
+                #[cfg(feature = "http")]
+                http: Arc::new(Default::default()),
+                __nonexhaustive: (),
+            });
+
+            let mut runner = ShardRunner::new(ShardRunnerOptions::<Handler, RawHandler> {
+                data: Arc::new(RwLock::new(ShareMap::custom())),
+                event_handler: Some(Arc::new(Handler(Mutex::new(events_tx)))),
+                raw_event_handler: None,
+                #[cfg(feature = "framework")]
+                framework: Arc::new(Mutex::new(None)),
+                pipeline: Arc::new(EventPipeline::new()),
+                event_types: self.event_types,
+                dispatch_order: DispatchOrder::default(),
+                generation: self.generation,
+                generations: self.generations,
+                manager_tx,
+                #[cfg(feature = "metrics")]
+                metrics: Default::default(),
+                recorder: self.recorder,
+                session_store: None,
+                shard,
+                threadpool: ThreadPool::new(1),
+                #[cfg(feature = "voice")]
+                voice_manager: Arc::new(Mutex::new(crate::client::bridge::voice::ClientVoiceManager::new(
+                    0,
+                    UserId(0),
+                ))),
+                #[cfg(any(feature = "cache", feature = "http"))]
+                cache_and_http: Arc::clone(&cache_and_http),
+            });
+
+            Running {
+                #[cfg(feature = "cache")]
+                cache_and_http,
+                events,
+                gateway,
+                manager,
+                messenger: ShardMessenger::new(runner.runner_tx()),
+                runner_tx: runner.runner_tx(),
+                runner: thread::spawn(move || runner.run().unwrap()),
+            }
+        }
+    }
+
+    // A running runner, and the ends of the channels it reports to.
+    struct Running {
+        #[cfg(feature = "cache")]
+        cache_and_http: Arc<CacheAndHttp>,
+        // The names of the events dispatched to the handler.
+        events: Receiver<&'static str>,
+        gateway: FakeGateway,
+        manager: Receiver<ShardManagerMessage>,
+        messenger: ShardMessenger,
+        runner: JoinHandle<()>,
+        runner_tx: Sender<InterMessage>,
+    }
+
+    impl Running {
+        // Performs a handshake, starting a session with the ID `"session"`.
+        fn identify(&self) -> FakeConnection {
+            let mut connection = self.gateway.accept().unwrap();
+
+            connection.send_hello(41250);
+            assert!(connection.recv_op(OpCode::Identify).is_some());
+            connection.send_ready("session");
+            assert_eq!(self.events.recv_timeout(TIMEOUT), Ok("ready"));
+
+            connection
+        }
+
+        // Completes a resume of the session after its Ready event, returning
+        // the new connection.
+        fn resume(&self) -> FakeConnection {
+            let mut connection = self.gateway.accept().unwrap();
+            let resume = connection.recv_op(OpCode::Resume).unwrap();
+
+            assert_eq!(resume["d"]["session_id"], "session");
+            assert_eq!(resume["d"]["seq"], 1);
+
+            connection.send_hello(41250);
+            connection.send_resumed();
+            assert_eq!(self.events.recv_timeout(TIMEOUT), Ok("resume"));
+
+            connection
+        }
+
+        // Waits for the runner to end, returning whether it requested a
+        // restart.
+        fn restarted(self) -> bool {
+            self.runner.join().unwrap();
+
+            self.manager.try_iter().any(|message| message == ShardManagerMessage::Restart(ShardId(0)))
+        }
+    }
+
+    #[test]
+    fn resumes_after_the_gateway_closes() {
+        let running = RunnerFixture::new().run();
+
+        running.identify().close(4000);
+        running.resume().send_reconnect();
+
+        assert!(running.restarted());
+    }
+
+    #[test]
+    fn resumes_after_the_connection_drops() {
+        let running = RunnerFixture::new().run();
+
+        running.identify().drop_connection();
+        running.resume().send_reconnect();
+
+        assert!(running.restarted());
+    }
+
+    #[test]
+    fn closes_resumably_when_shut_down_gracefully() {
+        let running = RunnerFixture::new().run();
+        let connection = running.identify();
+
+        let shutdown = ShardManagerMessage::ShutdownResumable(ShardId(0));
+        let msg = InterMessage::Client(Box::new(ShardClientMessage::Manager(shutdown)));
+        running.runner_tx.send(msg).unwrap();
+
+        assert_eq!(connection.recv_close(), Some(4000));
+        connection.close(4000);
+
+        assert!(!running.restarted());
+    }
+
+    #[test]
+    fn restarts_after_an_invalid_session() {
+        let running = RunnerFixture::new().run();
+
+        running.identify().send_invalid_session(false);
+
+        assert!(running.restarted());
+    }
+
+    #[test]
+    fn discards_unwanted_events_but_resumes_after_them() {
+        let running = RunnerFixture::new().event_types(&[EventType::MessageCreate]).run();
+
+        // Ready is received regardless.
+        let mut connection = running.identify();
+        let typing = json!({"channel_id": "1", "timestamp": 1, "user_id": "2"});
+        connection.send_dispatch("TYPING_START", typing);
+        connection.drop_connection();
+
+        let mut connection = running.gateway.accept().unwrap();
+        let resume = connection.recv_op(OpCode::Resume).unwrap();
+        assert_eq!(resume["d"]["seq"], 2);
+
+        connection.send_hello(41250);
+        connection.send_resumed();
+        assert_eq!(running.events.recv_timeout(TIMEOUT), Ok("resume"));
+
+        connection.send_reconnect();
+        assert!(running.restarted());
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn keeps_unwanted_messages_for_the_message_cache() {
+        let running = RunnerFixture::new().event_types(&[EventType::TypingStart]).run();
+        let mut connection = running.identify();
+
+        // Enabled after the runner started.
+        running.cache_and_http.cache.as_ref().write().settings_mut().max_messages(10);
+
+        let author = json!({"id": "1", "username": "user", "discriminator": "0001", "avatar": null});
+        connection.send_dispatch("MESSAGE_CREATE", json!({
+            "id": "3",
+            "channel_id": "2",
+            "author": author,
+            "content": "hello",
+            "timestamp": "2020-01-01T00:00:00+00:00",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        }));
+        connection.send_dispatch("TYPING_START", json!({"channel_id": "2", "timestamp": 1, "user_id": "1"}));
+        assert_eq!(running.events.recv_timeout(TIMEOUT), Ok("typing"));
+
+        assert!(running.cache_and_http.cache.as_ref().read().message(2, 3).is_some());
+    }
+
+    fn member(id: u64) -> Value {
+        json!({
+            "deaf": false,
+            "joined_at": "2017-04-15T22:09:16.110563+00:00",
+            "mute": false,
+            "roles": [],
+            "user": {
+                "avatar": null,
+                "discriminator": "0001",
+                "id": id.to_string(),
+                "username": format!("user {}", id),
+            },
+        })
+    }
+
+    #[test]
+    fn forwards_member_chunks_to_their_request() {
+        let running = RunnerFixture::new().run();
+        let mut connection = running.identify();
+
+        let messenger = running.messenger.clone();
+        let request = thread::spawn(move || {
+            let request = MemberRequest::user_ids(GuildId(1), vec![UserId(2), UserId(3)]);
+
+            messenger.request_members(request, TIMEOUT)
+        });
+
+        let payload = connection.recv_op(OpCode::GetGuildMembers).unwrap();
+        let nonce = payload["d"]["nonce"].clone();
+        assert_eq!(payload["d"]["user_ids"], json!([2, 3]));
+
+        // A chunk for another request is not forwarded.
+        for (id, nonce) in [(4, json!("other")), (2, nonce.clone()), (3, nonce)].iter() {
+            connection.send_dispatch("GUILD_MEMBERS_CHUNK", json!({
+                "guild_id": "1",
+                "members": [member(*id)],
+                "chunk_index": if *id == 3 { 1 } else { 0 },
+                "chunk_count": 2,
+                "nonce": nonce,
+            }));
+        }
+
+        let found = request.join().unwrap().unwrap();
+        let mut ids = found.members.keys().map(|id| id.0).collect::<Vec<_>>();
+        ids.sort();
+
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn forgets_member_requests_when_reconnecting() {
+        let running = RunnerFixture::new().run();
+        let connection = running.identify();
+
+        let messenger = running.messenger.clone();
+        let request = thread::spawn(move || {
+            let request = MemberRequest::query(GuildId(1), "zey", 10);
+
+            messenger.request_members(request, TIMEOUT)
+        });
+
+        assert!(connection.recv_op(OpCode::GetGuildMembers).is_some());
+        connection.drop_connection();
+        running.resume();
+
+        match request.join().unwrap().unwrap_err().downcast_ref() {
+            Some(MemberRequestError::Disconnected) => {},
+            other => panic!("Expected the request to be disconnected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn holds_back_presence_updates_until_identified() {
+        let running = RunnerFixture::new().run();
+
+        running.messenger.set_status(OnlineStatus::DoNotDisturb);
+        running.messenger.set_status(OnlineStatus::Idle);
+
+        // A presence sent before identifying would be skipped here.
+        let connection = running.identify();
+
+        let presence = connection.recv_op(OpCode::StatusUpdate).unwrap();
+        assert_eq!(presence["d"]["status"], "idle");
+    }
+
+    #[test]
+    fn staged_runners_report_their_guilds_without_dispatching() {
+        let generations = Arc::new(ShardGenerations::default());
+        let generation = generations.stage();
+        let running = RunnerFixture::new().generation(&generations, generation).run();
+
+        let mut connection = running.gateway.accept().unwrap();
+        connection.send_hello(41250);
+        assert!(connection.recv_op(OpCode::Identify).is_some());
+
+        // The Ready event has no guilds, so all of them were received.
+        connection.send_ready("session");
+        let received = (0..)
+            .map(|_| running.manager.recv_timeout(TIMEOUT))
+            .take_while(Result::is_ok)
+            .any(|message| message == Ok(ShardManagerMessage::GuildsReceived(ShardId(0))));
+        assert!(received);
+        assert!(running.events.try_recv().is_err());
+
+        generations.activate(generation);
+        connection.send_resumed();
+        assert_eq!(running.events.recv_timeout(TIMEOUT), Ok("resume"));
+
+        connection.send_reconnect();
+        assert!(running.restarted());
+    }
+
+    #[test]
+    fn keeps_unwanted_events_for_collectors() {
+        // Reactions are otherwise discarded.
+        let running = RunnerFixture::new().event_types(&[EventType::TypingStart]).run();
+        let mut connection = running.identify();
+
+        let collector = ReactionCollectorBuilder::new(&running.messenger)
+            .message_id(3)
+            .limit(1)
+            .timeout(TIMEOUT)
+            .start();
+
+        // The runner handles messages in order, so the collector is added by
+        // the time the presence is sent.
+        running.messenger.set_status(OnlineStatus::Idle);
+        assert!(connection.recv_op(OpCode::StatusUpdate).is_some());
+
+        connection.send_dispatch("MESSAGE_REACTION_ADD", json!({
+            "channel_id": "1",
+            "emoji": {"id": null, "name": "👍"},
+            "message_id": "3",
+            "user_id": "4",
+        }));
+
+        let reactions = collector.map(|action| action.reaction().message_id).collect::<Vec<_>>();
+        assert_eq!(reactions, vec![MessageId(3)]);
+    }
+
+    #[test]
+    fn records_received_payloads() {
+        let path = env::temp_dir().join(format!("serenity-runner-traffic-{}.log.gz", std::process::id()));
+        let running = RunnerFixture::new().recorder(TrafficRecorder::create(&path).unwrap()).run();
+
+        running.identify().send_reconnect();
+        assert!(running.restarted());
+
+        let payloads = TrafficLog::open(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        let ops = payloads.iter().map(|payload| payload.payload["op"].as_u64()).collect::<Vec<_>>();
+
+        assert_eq!(ops, vec![Some(10), Some(0), Some(7)]);
+        assert_eq!(payloads[1].payload["t"], "READY");
+        assert!(payloads.iter().all(|payload| payload.shard_id == 0));
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,8 +1,12 @@
+use crate::client::EventCollector;
+use crate::gateway::MemberRequest;
 use crate::model::{
+    event::GuildMembersChunkEvent,
     gateway::Activity,
     id::GuildId,
     user::OnlineStatus,
 };
+use std::{sync::mpsc::Sender, time::Duration};
 use tungstenite::Message;
 
 /// A message to send from a shard over a WebSocket.
@@ -10,6 +14,9 @@ use tungstenite::Message;
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug)]
 pub enum ShardRunnerMessage {
+    /// Indicates that the client is to send the events it dispatches to the
+    /// given collector, until it is done collecting.
+    AddCollector(EventCollector),
     /// Indicates that the client is to send a member chunk message.
     ChunkGuilds {
         /// The IDs of the [`Guild`]s to chunk.
@@ -40,6 +47,20 @@ pub enum ShardRunnerMessage {
     Close(u16, Option<String>),
     /// Indicates that the client is to send a custom WebSocket message.
     Message(Message),
+    /// Indicates that the client is to request guild members, and forward the
+    /// [`GuildMembersChunkEvent`]s received for the nonce to `chunks`.
+    ///
+    /// [`GuildMembersChunkEvent`]: ../../../model/event/struct.GuildMembersChunkEvent.html
+    RequestMembers {
+        /// The members to request.
+        request: MemberRequest,
+        /// The nonce identifying the chunks sent in response.
+        nonce: String,
+        /// The channel to forward the chunks to.
+        chunks: Sender<GuildMembersChunkEvent>,
+        /// How long to forward chunks for.
+        timeout: Duration,
+    },
     /// Indicates that the client is to update the shard's presence's activity.
     SetActivity(Option<Activity>),
     /// Indicates that the client is to update the shard's presence in its
@@ -0,0 +1,890 @@
+use parking_lot::{Mutex, RwLock};
+use std::sync::Arc;
+use thiserror::Error;
+use threadpool::ThreadPool;
+use typemap::ShareMap;
+use crate::gateway::{
+    Connector,
+    GatewayEncoding,
+    IdentifyOptions,
+    SessionStore,
+    TrafficRecorder,
+    TransportCompression,
+};
+use crate::http::Http;
+use crate::internal::prelude::*;
+use crate::model::gateway::GatewayIntents;
+use crate::CacheAndHttp;
+use super::bridge::gateway::{IdentifyLock, ShardManager, ShardManagerOptions};
+use super::{
+    handlers,
+    Client,
+    DispatchOrder,
+    DummyRawEventHandler,
+    EventHandler,
+    EventHandlers,
+    EventMiddleware,
+    EventPipeline,
+    RawEventHandler,
+    Scheduler,
+    TaskContext,
+};
+
+#[cfg(feature = "cache")]
+use crate::cache::{Cache, Settings};
+#[cfg(feature = "framework")]
+use crate::framework::Framework;
+#[cfg(feature = "voice")]
+use crate::model::id::UserId;
+#[cfg(feature = "voice")]
+use super::bridge::voice::ClientVoiceManager;
+#[cfg(feature = "cache")]
+use std::time::Duration;
+#[cfg(feature = "async")]
+use super::{
+    async_handler::BLOCKING_THREADS,
+    Async,
+    AsyncEventHandler,
+    AsyncRawEventHandler,
+    Executor,
+};
+
+// Adds an async handler once the executor it runs on exists.
+#[cfg(feature = "async")]
+type AddAsyncHandler = Box<dyn FnOnce(&Executor)>;
+
+/// An error returned when building a [`Client`] from an invalid
+/// [`ClientBuilder`].
+///
+/// [`Client`]: struct.Client.html
+/// [`ClientBuilder`]: struct.ClientBuilder.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Error)]
+pub enum ClientBuilderError {
+    /// Neither a token nor an [`Http`] instance was given.
+    ///
+    /// [`Http`]: ../http/raw/struct.Http.html
+    #[error("Neither a token nor an Http instance was given")]
+    MissingToken,
+
+    /// Both a token and an [`Http`] instance were given, and the instance
+    /// uses a different token.
+    ///
+    /// [`Http`]: ../http/raw/struct.Http.html
+    #[error("The token differs from that of the Http instance")]
+    ConflictingToken,
+
+    /// The shards to start are not within the total number of shards.
+    #[error("Shards {first} to {last} are not within the {total} shards")]
+    InvalidShardRange {
+        /// The ID of the first shard to start.
+        first: u64,
+        /// The ID of the last shard to start.
+        last: u64,
+        /// The total number of shards.
+        total: u64,
+    },
+
+    /// The threadpool, or the blocking pool of async handlers, was given no
+    /// threads.
+    #[error("The threadpool must have at least one thread")]
+    EmptyThreadpool,
+
+    /// The large threshold of the identify options is not between 50 and
+    /// 250.
+    #[error("The large threshold {0} is not between 50 and 250")]
+    InvalidLargeThreshold(u8),
+
+    #[doc(hidden)]
+    #[error("unreachable")]
+    __Nonexhaustive,
+}
+
+/// A builder for a [`Client`], setting up everything which is fixed once the
+/// client exists.
+///
+/// Everything but the token or [`Http`] instance is optional. The
+/// combination is validated by [`build`], before connecting to anything.
+///
+/// # Examples
+///
+/// Build a client with a framework, a larger threadpool and message cache,
+/// running shards 4 through 7 of 10:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::EventHandler;
+/// # use std::error::Error;
+/// #
+/// struct Handler;
+///
+/// impl EventHandler for Handler {}
+/// # fn try_main() -> Result<(), Box<dyn Error>> {
+/// use serenity::cache::Settings;
+/// use serenity::client::Client;
+/// use serenity::framework::StandardFramework;
+/// use serenity::model::gateway::GatewayIntents;
+/// use std::env;
+///
+/// let mut settings = Settings::new();
+/// settings.max_messages(100);
+///
+/// let mut client = Client::builder()
+///     .token(env::var("DISCORD_TOKEN")?)
+///     .event_handler(Handler)
+///     .framework(StandardFramework::new().configure(|c| c.prefix("~")))
+///     .intents(GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES)
+///     .cache_settings(settings)
+///     .threadpool_size(16)
+///     .shard_range([4, 7], 10)
+///     .build()?;
+///
+/// client.start()?;
+/// # Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`Client`]: struct.Client.html
+/// [`Http`]: ../http/raw/struct.Http.html
+/// [`build`]: #method.build
+pub struct ClientBuilder {
+    token: Option<String>,
+    http: Option<Http>,
+    event_handlers: Arc<EventHandlers>,
+    pipeline: Arc<EventPipeline>,
+    #[cfg(feature = "framework")]
+    framework: Option<Box<dyn Framework + Send>>,
+    #[cfg(feature = "cache")]
+    cache_settings: Settings,
+    #[cfg(feature = "cache")]
+    cache_update_timeout: Option<Duration>,
+    threadpool_size: usize,
+    intents: Option<GatewayIntents>,
+    identify_options: IdentifyOptions,
+    compression: TransportCompression,
+    encoding: GatewayEncoding,
+    session_store: Option<Arc<dyn SessionStore>>,
+    recorder: Option<TrafficRecorder>,
+    dispatch_order: DispatchOrder,
+    identify_lock: Option<Arc<dyn IdentifyLock>>,
+    connector: Option<Arc<dyn Connector>>,
+    shards: Option<[u64; 3]>,
+    #[cfg(feature = "voice")]
+    voice_manager: Option<Arc<Mutex<ClientVoiceManager>>>,
+    #[cfg(feature = "async")]
+    async_handlers: Vec<AddAsyncHandler>,
+    #[cfg(feature = "async")]
+    blocking_threads: usize,
+}
+
+impl ClientBuilder {
+    /// Creates a builder with no token, handlers or framework, a threadpool
+    /// of 5 threads and the default cache settings.
+    pub fn new() -> Self {
+        ClientBuilder {
+            token: None,
+            http: None,
+            event_handlers: Arc::new(EventHandlers::new()),
+            pipeline: Arc::new(EventPipeline::new()),
+            #[cfg(feature = "framework")]
+            framework: None,
+            #[cfg(feature = "cache")]
+            cache_settings: Settings::default(),
+            #[cfg(feature = "cache")]
+            cache_update_timeout: None,
+            threadpool_size: 5,
+            intents: None,
+            identify_options: IdentifyOptions::default(),
+            compression: TransportCompression::default(),
+            encoding: GatewayEncoding::default(),
+            session_store: None,
+            recorder: None,
+            dispatch_order: DispatchOrder::default(),
+            identify_lock: None,
+            connector: None,
+            shards: None,
+            #[cfg(feature = "voice")]
+            voice_manager: None,
+            #[cfg(feature = "async")]
+            async_handlers: Vec::new(),
+            #[cfg(feature = "async")]
+            blocking_threads: BLOCKING_THREADS,
+        }
+    }
+
+    /// Sets the bot's token.
+    ///
+    /// Discord has a requirement of prefixing bot tokens with `"Bot "`, which
+    /// is automatically done if not already included.
+    pub fn token(mut self, token: impl AsRef<str>) -> Self {
+        self.token = Some(bot_token(token.as_ref()));
+
+        self
+    }
+
+    /// Sets the HTTP client used for requests, such as one using a
+    /// preconfigured `reqwest` client. The token is taken from it if none is
+    /// set.
+    pub fn http(mut self, http: Http) -> Self {
+        self.http = Some(http);
+
+        self
+    }
+
+    /// Adds an event handler, dispatched to after the ones already added.
+    ///
+    /// Refer to [`Client::add_event_handler`] for more information.
+    ///
+    /// [`Client::add_event_handler`]: struct.Client.html#method.add_event_handler
+    pub fn event_handler<H>(self, handler: H) -> Self
+        where H: EventHandler + Send + Sync + 'static {
+        self.event_handlers.push(handler);
+
+        self
+    }
+
+    /// Adds a raw event handler, dispatched to after the ones already added.
+    ///
+    /// Refer to [`Client::add_raw_event_handler`] for more information.
+    ///
+    /// [`Client::add_raw_event_handler`]: struct.Client.html#method.add_raw_event_handler
+    pub fn raw_event_handler<H>(self, handler: H) -> Self
+        where H: RawEventHandler + Send + Sync + 'static {
+        self.pipeline.add_raw_event_handler(handler);
+
+        self
+    }
+
+    /// Adds an [`AsyncEventHandler`], run on the executor of the client's
+    /// async handlers.
+    ///
+    /// Async handlers are dispatched to after the ones added via
+    /// [`event_handler`], as the executor is only created once the client is
+    /// built.
+    ///
+    /// [`AsyncEventHandler`]: trait.AsyncEventHandler.html
+    /// [`event_handler`]: #method.event_handler
+    #[cfg(feature = "async")]
+    pub fn async_event_handler<H: AsyncEventHandler>(mut self, handler: H) -> Self {
+        let event_handlers = Arc::clone(&self.event_handlers);
+
+        self.async_handlers.push(Box::new(move |executor: &Executor| {
+            event_handlers.push(Async::with_executor(handler, executor));
+        }));
+
+        self
+    }
+
+    /// Adds an [`AsyncRawEventHandler`], run on the executor of the client's
+    /// async handlers.
+    ///
+    /// Async raw handlers are dispatched to after the ones added via
+    /// [`raw_event_handler`].
+    ///
+    /// [`AsyncRawEventHandler`]: trait.AsyncRawEventHandler.html
+    /// [`raw_event_handler`]: #method.raw_event_handler
+    #[cfg(feature = "async")]
+    pub fn async_raw_event_handler<H: AsyncRawEventHandler>(mut self, handler: H) -> Self {
+        let pipeline = Arc::clone(&self.pipeline);
+
+        self.async_handlers.push(Box::new(move |executor: &Executor| {
+            pipeline.add_raw_event_handler(Async::with_executor(handler, executor));
+        }));
+
+        self
+    }
+
+    /// Sets the number of threads the blocking calls of async handlers, made
+    /// via [`AsyncContext::blocking`], run on.
+    ///
+    /// Each call occupies a thread until it returns, so this limits how many
+    /// [`Http`] requests async handlers make at once. Defaults to 32
+    /// threads.
+    ///
+    /// [`AsyncContext::blocking`]: struct.AsyncContext.html#method.blocking
+    /// [`Http`]: ../http/raw/struct.Http.html
+    #[cfg(feature = "async")]
+    pub fn blocking_threads(mut self, threads: usize) -> Self {
+        self.blocking_threads = threads;
+
+        self
+    }
+
+    /// Adds middleware, run after the middleware already added.
+    ///
+    /// Refer to [`Client::add_middleware`] for more information.
+    ///
+    /// [`Client::add_middleware`]: struct.Client.html#method.add_middleware
+    pub fn middleware<M: EventMiddleware + 'static>(self, middleware: M) -> Self {
+        self.pipeline.add_middleware(middleware);
+
+        self
+    }
+
+    /// Sets the framework messages are passed to.
+    ///
+    /// Refer to [`Client::with_framework`] for more information.
+    ///
+    /// [`Client::with_framework`]: struct.Client.html#method.with_framework
+    #[cfg(feature = "framework")]
+    pub fn framework<F: Framework + Send + 'static>(mut self, framework: F) -> Self {
+        self.framework = Some(Box::new(framework));
+
+        self
+    }
+
+    /// Sets the settings of the cache, such as how many messages to keep per
+    /// channel.
+    #[cfg(feature = "cache")]
+    pub fn cache_settings(mut self, settings: Settings) -> Self {
+        self.cache_settings = settings;
+
+        self
+    }
+
+    /// Sets how long updating the cache waits for a write-lock, after which
+    /// the event is skipped and a deadlock warning is issued.
+    ///
+    /// By default, updating the cache waits until the lock is claimed, and
+    /// potentially deadlocks.
+    #[cfg(feature = "cache")]
+    pub fn cache_update_timeout(mut self, timeout: Duration) -> Self {
+        self.cache_update_timeout = Some(timeout);
+
+        self
+    }
+
+    /// Sets the number of threads handlers, framework commands and scheduled
+    /// tasks run on.
+    ///
+    /// Defaults to 5 threads, which should suffice small bots. Consider
+    /// increasing this number as your bot grows.
+    pub fn threadpool_size(mut self, threads: usize) -> Self {
+        self.threadpool_size = threads;
+
+        self
+    }
+
+    /// Sets the [gateway intents] shards send when identifying.
+    ///
+    /// Refer to [`Client::set_intents`] for more information.
+    ///
+    /// [`Client::set_intents`]: struct.Client.html#method.set_intents
+    /// [gateway intents]: ../model/gateway/struct.GatewayIntents.html
+    pub fn intents(mut self, intents: GatewayIntents) -> Self {
+        self.intents = Some(intents);
+
+        self
+    }
+
+    /// Runs a single shard out of the total number of shards when the client
+    /// is [started].
+    ///
+    /// [started]: struct.Client.html#method.start
+    pub fn shard(self, shard: u64, total_shards: u64) -> Self {
+        self.shard_range([shard, shard], total_shards)
+    }
+
+    /// Runs all of the given number of shards when the client is [started].
+    ///
+    /// [started]: struct.Client.html#method.start
+    pub fn shards(self, total_shards: u64) -> Self {
+        self.shard_range([0, total_shards.saturating_sub(1)], total_shards)
+    }
+
+    /// Runs the shards in the inclusive range out of the total number of
+    /// shards when the client is [started].
+    ///
+    /// [started]: struct.Client.html#method.start
+    pub fn shard_range(mut self, range: [u64; 2], total_shards: u64) -> Self {
+        self.shards = Some([range[0], range[1], total_shards]);
+
+        self
+    }
+
+    /// Sets the voice manager, such as to share it with code set up before
+    /// the client.
+    #[cfg(feature = "voice")]
+    pub fn voice_manager(mut self, voice_manager: Arc<Mutex<ClientVoiceManager>>) -> Self {
+        self.voice_manager = Some(voice_manager);
+
+        self
+    }
+
+    /// Sets the options shards send when identifying, such as the presence to
+    /// appear with right at login and whether to receive presence and typing
+    /// events.
+    ///
+    /// # Examples
+    ///
+    /// Appear as idle while starting up, without receiving presences:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use serenity::gateway::IdentifyOptions;
+    /// use serenity::model::{gateway::Activity, user::OnlineStatus};
+    /// use std::env;
+    ///
+    /// let mut client = Client::builder()
+    ///     .token(env::var("DISCORD_TOKEN")?)
+    ///     .event_handler(Handler)
+    ///     .identify_options(IdentifyOptions {
+    ///         presence: Some((Some(Activity::playing("starting up")), OnlineStatus::Idle)),
+    ///         guild_subscriptions: false,
+    ///         ..Default::default()
+    ///     })
+    ///     .build()?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    pub fn identify_options(mut self, options: IdentifyOptions) -> Self {
+        self.identify_options = options;
+
+        self
+    }
+
+    /// Sets the compression shards use for data received from the gateway.
+    ///
+    /// By default, [`TransportCompression::Payload`] is used. Choosing
+    /// [`TransportCompression::ZlibStream`] compresses the whole connection
+    /// instead, which considerably reduces bandwidth for bots in many guilds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use serenity::gateway::TransportCompression;
+    /// use std::env;
+    ///
+    /// let mut client = Client::builder()
+    ///     .token(env::var("DISCORD_TOKEN")?)
+    ///     .event_handler(Handler)
+    ///     .compression(TransportCompression::ZlibStream)
+    ///     .build()?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`TransportCompression::Payload`]: ../gateway/enum.TransportCompression.html#variant.Payload
+    /// [`TransportCompression::ZlibStream`]: ../gateway/enum.TransportCompression.html#variant.ZlibStream
+    pub fn compression(mut self, compression: TransportCompression) -> Self {
+        self.compression = compression;
+
+        self
+    }
+
+    /// Sets the encoding of payloads sent over the gateway.
+    ///
+    /// By default, [`GatewayEncoding::Json`] is used. For large bots, decoding
+    /// JSON can take up most of the time spent in shard threads; choosing
+    /// [`GatewayEncoding::Etf`] makes decoding considerably cheaper.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use serenity::gateway::GatewayEncoding;
+    /// use std::env;
+    ///
+    /// let mut client = Client::builder()
+    ///     .token(env::var("DISCORD_TOKEN")?)
+    ///     .event_handler(Handler)
+    ///     .encoding(GatewayEncoding::Etf)
+    ///     .build()?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`GatewayEncoding::Etf`]: ../gateway/enum.GatewayEncoding.html#variant.Etf
+    /// [`GatewayEncoding::Json`]: ../gateway/enum.GatewayEncoding.html#variant.Json
+    pub fn encoding(mut self, encoding: GatewayEncoding) -> Self {
+        self.encoding = encoding;
+
+        self
+    }
+
+    /// Sets the store that shards save their gateway sessions to when the
+    /// client shuts down, and resume them from when it starts again.
+    ///
+    /// Resuming instead of identifying makes restarts considerably cheaper,
+    /// as Discord does not send a [`GuildCreate`] for every guild again and
+    /// the [`SessionStartLimit`] is not used up. Sessions are only resumable
+    /// for a short while after shutting down; if Discord rejects a session,
+    /// the shard identifies as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use serenity::gateway::FileSessionStore;
+    /// use std::env;
+    ///
+    /// let mut client = Client::builder()
+    ///     .token(env::var("DISCORD_TOKEN")?)
+    ///     .event_handler(Handler)
+    ///     .session_store(FileSessionStore::new("sessions"))
+    ///     .build()?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`GuildCreate`]: ../model/event/struct.GuildCreateEvent.html
+    /// [`SessionStartLimit`]: ../model/gateway/struct.SessionStartLimit.html
+    pub fn session_store<S: SessionStore + 'static>(mut self, session_store: S) -> Self {
+        self.session_store = Some(Arc::new(session_store));
+
+        self
+    }
+
+    /// Sets a recorder that every payload received by the client's shards is
+    /// recorded to, along with when it was received and by which shard.
+    ///
+    /// The recorded traffic can be replayed through event handlers offline via
+    /// a [`Replayer`], such as to reproduce an inconsistency in the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use serenity::gateway::TrafficRecorder;
+    /// use std::env;
+    ///
+    /// let mut client = Client::builder()
+    ///     .token(env::var("DISCORD_TOKEN")?)
+    ///     .event_handler(Handler)
+    ///     .recorder(TrafficRecorder::create("traffic.log.gz")?)
+    ///     .build()?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`Replayer`]: struct.Replayer.html
+    pub fn recorder(mut self, recorder: TrafficRecorder) -> Self {
+        self.recorder = Some(recorder);
+
+        self
+    }
+
+    /// Sets the order in which the client's shards run the handlers of events.
+    ///
+    /// By default, handlers run concurrently as events are received. Refer to
+    /// [`DispatchOrder`] for the orders keeping related events in sequence.
+    ///
+    /// # Examples
+    ///
+    /// Handle the events of each guild in the order they are received:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::{Client, DispatchOrder};
+    /// use std::env;
+    ///
+    /// let mut client = Client::builder()
+    ///     .token(env::var("DISCORD_TOKEN")?)
+    ///     .event_handler(Handler)
+    ///     .dispatch_order(DispatchOrder::PerGuild)
+    ///     .build()?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`DispatchOrder`]: enum.DispatchOrder.html
+    pub fn dispatch_order(mut self, dispatch_order: DispatchOrder) -> Self {
+        self.dispatch_order = dispatch_order;
+
+        self
+    }
+
+    /// Sets the lock acquired before each shard is started, so that identifies
+    /// are coordinated with other processes running shards of the same bot.
+    ///
+    /// Refer to [`CoordinatorClient`] for an example of running shards across
+    /// several processes.
+    ///
+    /// [`CoordinatorClient`]: bridge/gateway/struct.CoordinatorClient.html
+    pub fn identify_lock<L: IdentifyLock + 'static>(mut self, identify_lock: L) -> Self {
+        self.identify_lock = Some(Arc::new(identify_lock));
+
+        self
+    }
+
+    /// Sets the connector shards use to connect to the gateway, instead of
+    /// connecting over the network.
+    ///
+    /// This is primarily useful for running the client against a
+    /// [`FakeGateway`] in tests, which requires the `fake_gateway` feature.
+    ///
+    /// [`FakeGateway`]: ../gateway/fake/struct.FakeGateway.html
+    pub fn connector<C: Connector + 'static>(mut self, connector: C) -> Self {
+        self.connector = Some(Arc::new(connector));
+
+        self
+    }
+
+    /// Validates the configuration and creates the client.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ClientBuilderError`] if the configuration is invalid, or an
+    /// error if the gateway URL could not be retrieved.
+    ///
+    /// [`ClientBuilderError`]: enum.ClientBuilderError.html
+    pub fn build(self) -> Result<Client> {
+        let http = match (self.token, self.http) {
+            (None, None) => return Err(ClientBuilderError::MissingToken.into()),
+            (Some(ref token), Some(ref http)) if *token != http.token => {
+                return Err(ClientBuilderError::ConflictingToken.into());
+            },
+            (_, Some(http)) => http,
+            (Some(token), None) => Http::new_with_token(&token),
+        };
+
+        if let Some([first, last, total]) = self.shards {
+            if first > last || last >= total {
+                return Err(ClientBuilderError::InvalidShardRange { first, last, total }.into());
+            }
+        }
+
+        if self.threadpool_size == 0 {
+            return Err(ClientBuilderError::EmptyThreadpool.into());
+        }
+
+        #[cfg(feature = "async")]
+        {
+            if self.blocking_threads == 0 {
+                return Err(ClientBuilderError::EmptyThreadpool.into());
+            }
+        }
+
+        let large_threshold = self.identify_options.large_threshold;
+
+        if !(50..=250).contains(&large_threshold) {
+            return Err(ClientBuilderError::InvalidLargeThreshold(large_threshold).into());
+        }
+
+        let name = "serenity client".to_owned();
+        let threadpool = ThreadPool::with_name(name, self.threadpool_size);
+        let url = Arc::new(Mutex::new(http.get_gateway()?.url));
+        let data = Arc::new(RwLock::new(ShareMap::custom()));
+        let event_handlers = self.event_handlers;
+        let pipeline = self.pipeline;
+
+        #[cfg(feature = "async")]
+        {
+            if !self.async_handlers.is_empty() {
+                let executor = Executor::with_blocking_threads(self.blocking_threads)?;
+
+                for add in self.async_handlers {
+                    add(&executor);
+                }
+            }
+        }
+
+        #[cfg(feature = "framework")]
+        let framework = Arc::new(Mutex::new(self.framework));
+        #[cfg(feature = "voice")]
+        let voice_manager = self.voice_manager.unwrap_or_else(|| {
+            Arc::new(Mutex::new(ClientVoiceManager::new(0, UserId(0))))
+        });
+
+        let cache_and_http = Arc::new(CacheAndHttp {
+            #[cfg(feature = "cache")]
+            cache: Arc::new(RwLock::new(Cache::new_with_settings(self.cache_settings))),
+            #[cfg(feature = "cache")]
+            update_cache_timeout: self.cache_update_timeout,
+            http: Arc::new(http),
+            __nonexhaustive: (),
+        });
+
+        let (shard_manager, shard_manager_worker) = {
+            ShardManager::new(ShardManagerOptions {
+                data: &data,
+                event_handler: &Some(Arc::clone(&event_handlers)),
+                raw_event_handler: &None::<Arc<DummyRawEventHandler>>,
+                #[cfg(feature = "framework")]
+                framework: &framework,
+                pipeline: &pipeline,
+                compression: self.compression,
+                connector: self.connector,
+                encoding: self.encoding,
+                intents: self.intents,
+                identify_options: self.identify_options,
+                session_store: self.session_store,
+                recorder: self.recorder,
+                event_types: handlers::combine_event_types(event_handlers.event_types(), pipeline.event_types()),
+                dispatch_order: self.dispatch_order,
+                identify_lock: self.identify_lock,
+                shard_index: 0,
+                shard_init: 0,
+                shard_total: 0,
+                threadpool: threadpool.clone(),
+                #[cfg(feature = "voice")]
+                voice_manager: &voice_manager,
+                ws_url: &url,
+                cache_and_http: &cache_and_http,
+            })
+        };
+
+        let scheduler = Scheduler::new(
+            TaskContext::new(&data, &cache_and_http, &shard_manager.lock().runners),
+            threadpool.clone(),
+        )?;
+
+        Ok(Client {
+            ws_uri: url,
+            #[cfg(feature = "framework")]
+            framework,
+            event_handlers,
+            pipeline,
+            event_types: None,
+            data,
+            shard_manager,
+            shard_manager_worker,
+            shards: self.shards,
+            threadpool,
+            scheduler,
+            #[cfg(feature = "voice")]
+            voice_manager,
+            cache_and_http,
+        })
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        ClientBuilder::new()
+    }
+}
+
+// Prefixes the token with `"Bot "`, as Discord requires, unless it already is.
+fn bot_token(token: &str) -> String {
+    let token = token.trim();
+
+    if token.starts_with("Bot ") {
+        token.to_string()
+    } else {
+        format!("Bot {}", token)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClientBuilder, ClientBuilderError};
+    use crate::gateway::IdentifyOptions;
+    use crate::http::Http;
+
+    fn error(builder: ClientBuilder) -> ClientBuilderError {
+        match builder.build() {
+            Ok(_) => panic!("built an invalid client"),
+            Err(why) => why.downcast().unwrap(),
+        }
+    }
+
+    #[test]
+    fn validates_before_connecting() {
+        assert_eq!(error(ClientBuilder::new()), ClientBuilderError::MissingToken);
+        assert_eq!(
+            error(ClientBuilder::new().token("a").http(Http::new_with_token("Bot b"))),
+            ClientBuilderError::ConflictingToken,
+        );
+        assert_eq!(
+            error(ClientBuilder::new().token("a").shard_range([4, 10], 10)),
+            ClientBuilderError::InvalidShardRange { first: 4, last: 10, total: 10 },
+        );
+        assert_eq!(
+            error(ClientBuilder::new().token("a").shards(0)),
+            ClientBuilderError::InvalidShardRange { first: 0, last: 0, total: 0 },
+        );
+        assert_eq!(error(ClientBuilder::new().token("a").threadpool_size(0)), ClientBuilderError::EmptyThreadpool);
+        #[cfg(feature = "async")]
+        assert_eq!(error(ClientBuilder::new().token("a").blocking_threads(0)), ClientBuilderError::EmptyThreadpool);
+
+        let options = IdentifyOptions {
+            large_threshold: 251,
+            ..Default::default()
+        };
+        assert_eq!(
+            error(ClientBuilder::new().token("a").identify_options(options)),
+            ClientBuilderError::InvalidLargeThreshold(251),
+        );
+    }
+}
@@ -0,0 +1,778 @@
+//! Collectors waiting for later events, such as the reply to a question asked
+//! by a command.
+
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use crate::model::prelude::*;
+use super::bridge::gateway::ShardMessenger;
+
+/// The events that a collector registered with a shard's runner is sent.
+///
+/// These are created by [`MessageCollectorBuilder::start`] and
+/// [`ReactionCollectorBuilder::start`], and are only exposed as part of
+/// [`ShardRunnerMessage::AddCollector`].
+///
+/// [`MessageCollectorBuilder::start`]: struct.MessageCollectorBuilder.html#method.start
+/// [`ReactionCollectorBuilder::start`]: struct.ReactionCollectorBuilder.html#method.start
+/// [`ShardRunnerMessage::AddCollector`]: bridge/gateway/enum.ShardRunnerMessage.html#variant.AddCollector
+#[derive(Clone)]
+pub struct EventCollector {
+    sink: Arc<dyn Fn(&Event) -> Delivery + Send + Sync>,
+    event_types: &'static [EventType],
+    deadline: Option<Instant>,
+    remaining: Option<u32>,
+    stopped: Arc<AtomicBool>,
+}
+
+// The outcome of offering an event to a collector.
+enum Delivery {
+    Skipped,
+    Delivered,
+    Closed,
+}
+
+impl EventCollector {
+    // Offers the event to the collector, returning whether it is still
+    // collecting afterwards.
+    fn offer(&mut self, event: &Event, now: Instant) -> bool {
+        if !self.is_collecting(now) {
+            return false;
+        }
+
+        match (self.sink)(event) {
+            Delivery::Skipped => true,
+            Delivery::Delivered => {
+                if let Some(ref mut remaining) = self.remaining {
+                    *remaining -= 1;
+                }
+
+                self.is_collecting(now)
+            },
+            Delivery::Closed => false,
+        }
+    }
+
+    fn is_collecting(&self, now: Instant) -> bool {
+        !self.stopped.load(Ordering::Relaxed)
+            && self.remaining != Some(0)
+            && self.deadline.map_or(true, |deadline| now < deadline)
+    }
+}
+
+impl fmt::Debug for EventCollector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventCollector")
+            .field("deadline", &self.deadline)
+            .field("remaining", &self.remaining)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+/// The collectors registered with a shard's runner.
+#[derive(Default)]
+pub(crate) struct Collectors {
+    collectors: Mutex<Vec<EventCollector>>,
+}
+
+impl Collectors {
+    pub(crate) fn add(&self, collector: EventCollector) {
+        self.collectors.lock().push(collector);
+    }
+
+    /// Whether a collector that is still collecting needs events of the type,
+    /// so that they must not be discarded.
+    pub(crate) fn wants(&self, kind: EventType) -> bool {
+        let now = Instant::now();
+
+        self.collectors
+            .lock()
+            .iter()
+            .any(|collector| collector.event_types.contains(&kind) && collector.is_collecting(now))
+    }
+
+    /// Offers the event to each collector, forgetting those that are done.
+    pub(crate) fn process(&self, event: &Event) {
+        let mut collectors = self.collectors.lock();
+
+        if collectors.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut index = 0;
+
+        while index < collectors.len() {
+            if collectors[index].offer(event, now) {
+                index += 1;
+            } else {
+                collectors.swap_remove(index);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.collectors.lock().len()
+    }
+}
+
+/// Receives the items a collector was sent, in the order their events were
+/// received.
+///
+/// Items are returned by iterating over the collector, which blocks until the
+/// next one arrives. Iterating ends once the collector's timeout passed, its
+/// limit was reached, or the shard's runner stopped - such as when the shard
+/// restarts.
+///
+/// **Note**: This blocks the current thread. Do not wait on a collector from
+/// the thread of the shard's runner, such as from [`EventMiddleware`].
+///
+/// [`EventMiddleware`]: trait.EventMiddleware.html
+pub struct Collector<T> {
+    items: Receiver<T>,
+    deadline: Option<Instant>,
+    stopped: Arc<AtomicBool>,
+}
+
+/// A collector of messages, created via a [`MessageCollectorBuilder`].
+///
+/// [`MessageCollectorBuilder`]: struct.MessageCollectorBuilder.html
+pub type MessageCollector = Collector<Message>;
+
+/// A collector of reactions, created via a [`ReactionCollectorBuilder`].
+///
+/// [`ReactionCollectorBuilder`]: struct.ReactionCollectorBuilder.html
+pub type ReactionCollector = Collector<ReactionAction>;
+
+impl<T> Collector<T> {
+    /// Waits for the first item, returning `None` if there was none before the
+    /// collector stopped.
+    pub fn wait(mut self) -> Option<T> {
+        self.next()
+    }
+
+    /// Stops collecting. Items that were already collected are still
+    /// returned.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> Iterator for Collector<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let now = Instant::now();
+
+        match self.deadline {
+            _ if self.stopped.load(Ordering::Relaxed) => self.items.try_recv().ok(),
+            Some(deadline) if deadline <= now => self.items.try_recv().ok(),
+            Some(deadline) => self.items.recv_timeout(deadline - now).ok(),
+            None => self.items.recv().ok(),
+        }
+    }
+}
+
+impl<T> Drop for Collector<T> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl<T> fmt::Debug for Collector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Collector")
+            .field("deadline", &self.deadline)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+/// A reaction received by a [`ReactionCollector`].
+///
+/// [`ReactionCollector`]: type.ReactionCollector.html
+#[derive(Clone, Debug)]
+pub enum ReactionAction {
+    /// The reaction was added.
+    Added(Reaction),
+    /// The reaction was removed, which is only collected when requested via
+    /// [`ReactionCollectorBuilder::removed`].
+    ///
+    /// [`ReactionCollectorBuilder::removed`]: struct.ReactionCollectorBuilder.html#method.removed
+    Removed(Reaction),
+}
+
+impl ReactionAction {
+    /// The reaction that was added or removed.
+    pub fn reaction(&self) -> &Reaction {
+        match *self {
+            ReactionAction::Added(ref reaction) | ReactionAction::Removed(ref reaction) => reaction,
+        }
+    }
+
+    /// Whether the reaction was added, rather than removed.
+    pub fn is_added(&self) -> bool {
+        match *self {
+            ReactionAction::Added(_) => true,
+            ReactionAction::Removed(_) => false,
+        }
+    }
+}
+
+type Filter<T> = Arc<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// Collects the messages received by a shard that match all of the given
+/// conditions.
+///
+/// # Examples
+///
+/// Ask a question, and wait up to 30 seconds for its author to reply:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # use serenity::model::channel::Message;
+/// #
+/// use serenity::client::MessageCollectorBuilder;
+/// use std::time::Duration;
+///
+/// struct Handler;
+///
+/// impl EventHandler for Handler {
+///     fn message(&self, ctx: Context, msg: Message) {
+///         if msg.content != "!quiz" {
+///             return;
+///         }
+///
+///         let answer = MessageCollectorBuilder::new(&ctx)
+///             .author_id(msg.author.id)
+///             .channel_id(msg.channel_id)
+///             .timeout(Duration::from_secs(30))
+///             .start();
+///
+///         let _ = msg.channel_id.say(&ctx.http, "What is 6 times 7?");
+///
+///         let reply = match answer.wait() {
+///             Some(answer) if answer.content == "42" => "Correct!",
+///             Some(_) => "Wrong!",
+///             None => "Too slow!",
+///         };
+///
+///         let _ = msg.channel_id.say(&ctx.http, reply);
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MessageCollectorBuilder {
+    shard: ShardMessenger,
+    author_id: Option<UserId>,
+    channel_id: Option<ChannelId>,
+    guild_id: Option<GuildId>,
+    filter: Option<Filter<Message>>,
+    timeout: Option<Duration>,
+    limit: Option<u32>,
+}
+
+impl MessageCollectorBuilder {
+    /// Creates a builder collecting every message received by a shard, such
+    /// as that of a [`Context`], until stopped.
+    ///
+    /// [`Context`]: struct.Context.html
+    pub fn new(shard: impl AsRef<ShardMessenger>) -> Self {
+        MessageCollectorBuilder {
+            shard: shard.as_ref().clone(),
+            author_id: None,
+            channel_id: None,
+            guild_id: None,
+            filter: None,
+            timeout: None,
+            limit: None,
+        }
+    }
+
+    /// Only collects messages sent by the given user.
+    pub fn author_id<U: Into<UserId>>(mut self, author_id: U) -> Self {
+        self.author_id = Some(author_id.into());
+
+        self
+    }
+
+    /// Only collects messages sent in the given channel.
+    pub fn channel_id<C: Into<ChannelId>>(mut self, channel_id: C) -> Self {
+        self.channel_id = Some(channel_id.into());
+
+        self
+    }
+
+    /// Only collects messages sent in the given guild.
+    pub fn guild_id<G: Into<GuildId>>(mut self, guild_id: G) -> Self {
+        self.guild_id = Some(guild_id.into());
+
+        self
+    }
+
+    /// Only collects messages for which the function returns `true`.
+    ///
+    /// The function is called on the thread of the shard's runner, so should
+    /// return quickly.
+    pub fn filter<F>(mut self, filter: F) -> Self
+        where F: Fn(&Message) -> bool + Send + Sync + 'static {
+        self.filter = Some(Arc::new(filter));
+
+        self
+    }
+
+    /// Stops collecting once the given time has passed since starting.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    /// Stops collecting once the given number of messages was collected.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+
+        self
+    }
+
+    /// Starts collecting, returning the collector to receive messages from.
+    ///
+    /// Only messages received after this are collected.
+    pub fn start(self) -> MessageCollector {
+        let MessageCollectorBuilder { shard, author_id, channel_id, guild_id, filter, timeout, limit } = self;
+
+        start(&shard, &[EventType::MessageCreate], timeout, limit, move |event| {
+            let message = match *event {
+                Event::MessageCreate(ref event) => &event.message,
+                _ => return None,
+            };
+
+            let matches = author_id.map_or(true, |id| message.author.id == id)
+                && channel_id.map_or(true, |id| message.channel_id == id)
+                && guild_id.map_or(true, |id| message.guild_id == Some(id))
+                && filter.as_ref().map_or(true, |filter| filter(message));
+
+            if matches {
+                Some(message.clone())
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Collects the reactions received by a shard that match all of the given
+/// conditions.
+///
+/// By default, only added reactions are collected.
+///
+/// # Examples
+///
+/// Have the author of a message confirm an action by reacting to it:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # use serenity::model::channel::Message;
+/// #
+/// use serenity::client::ReactionCollectorBuilder;
+/// use std::time::Duration;
+///
+/// struct Handler;
+///
+/// impl EventHandler for Handler {
+///     fn message(&self, ctx: Context, msg: Message) {
+///         if msg.content != "!reset" {
+///             return;
+///         }
+///
+///         let prompt = match msg.channel_id.say(&ctx.http, "React with ✅ to confirm.") {
+///             Ok(prompt) => prompt,
+///             Err(_) => return,
+///         };
+///
+///         let confirmed = ReactionCollectorBuilder::new(&ctx)
+///             .author_id(msg.author.id)
+///             .message_id(prompt.id)
+///             .emoji('✅')
+///             .timeout(Duration::from_secs(60))
+///             .start()
+///             .wait()
+///             .is_some();
+///
+///         if confirmed {
+///             // Reset things.
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ReactionCollectorBuilder {
+    shard: ShardMessenger,
+    author_id: Option<UserId>,
+    channel_id: Option<ChannelId>,
+    guild_id: Option<GuildId>,
+    message_id: Option<MessageId>,
+    emoji: Option<ReactionType>,
+    filter: Option<Filter<Reaction>>,
+    removed: bool,
+    timeout: Option<Duration>,
+    limit: Option<u32>,
+}
+
+impl ReactionCollectorBuilder {
+    /// Creates a builder collecting every reaction added on a shard, such as
+    /// that of a [`Context`], until stopped.
+    ///
+    /// [`Context`]: struct.Context.html
+    pub fn new(shard: impl AsRef<ShardMessenger>) -> Self {
+        ReactionCollectorBuilder {
+            shard: shard.as_ref().clone(),
+            author_id: None,
+            channel_id: None,
+            guild_id: None,
+            message_id: None,
+            emoji: None,
+            filter: None,
+            removed: false,
+            timeout: None,
+            limit: None,
+        }
+    }
+
+    /// Only collects reactions by the given user.
+    pub fn author_id<U: Into<UserId>>(mut self, author_id: U) -> Self {
+        self.author_id = Some(author_id.into());
+
+        self
+    }
+
+    /// Only collects reactions in the given channel.
+    pub fn channel_id<C: Into<ChannelId>>(mut self, channel_id: C) -> Self {
+        self.channel_id = Some(channel_id.into());
+
+        self
+    }
+
+    /// Only collects reactions in the given guild.
+    pub fn guild_id<G: Into<GuildId>>(mut self, guild_id: G) -> Self {
+        self.guild_id = Some(guild_id.into());
+
+        self
+    }
+
+    /// Only collects reactions to the given message.
+    pub fn message_id<M: Into<MessageId>>(mut self, message_id: M) -> Self {
+        self.message_id = Some(message_id.into());
+
+        self
+    }
+
+    /// Only collects reactions with the given emoji.
+    pub fn emoji<R: Into<ReactionType>>(mut self, emoji: R) -> Self {
+        self.emoji = Some(emoji.into());
+
+        self
+    }
+
+    /// Only collects reactions for which the function returns `true`.
+    ///
+    /// The function is called on the thread of the shard's runner, so should
+    /// return quickly.
+    pub fn filter<F>(mut self, filter: F) -> Self
+        where F: Fn(&Reaction) -> bool + Send + Sync + 'static {
+        self.filter = Some(Arc::new(filter));
+
+        self
+    }
+
+    /// Whether to also collect removed reactions, as
+    /// [`ReactionAction::Removed`].
+    ///
+    /// [`ReactionAction::Removed`]: enum.ReactionAction.html#variant.Removed
+    pub fn removed(mut self, removed: bool) -> Self {
+        self.removed = removed;
+
+        self
+    }
+
+    /// Stops collecting once the given time has passed since starting.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    /// Stops collecting once the given number of reactions was collected.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+
+        self
+    }
+
+    /// Starts collecting, returning the collector to receive reactions from.
+    ///
+    /// Only reactions received after this are collected.
+    pub fn start(self) -> ReactionCollector {
+        let ReactionCollectorBuilder {
+            shard,
+            author_id,
+            channel_id,
+            guild_id,
+            message_id,
+            emoji,
+            filter,
+            removed,
+            timeout,
+            limit,
+        } = self;
+
+        let event_types: &[EventType] = if removed {
+            &[EventType::ReactionAdd, EventType::ReactionRemove]
+        } else {
+            &[EventType::ReactionAdd]
+        };
+
+        start(&shard, event_types, timeout, limit, move |event| {
+            let (reaction, added) = match *event {
+                Event::ReactionAdd(ref event) => (&event.reaction, true),
+                Event::ReactionRemove(ref event) if removed => (&event.reaction, false),
+                _ => return None,
+            };
+
+            let matches = author_id.map_or(true, |id| reaction.user_id == id)
+                && channel_id.map_or(true, |id| reaction.channel_id == id)
+                && guild_id.map_or(true, |id| reaction.guild_id == Some(id))
+                && message_id.map_or(true, |id| reaction.message_id == id)
+                && emoji.as_ref().map_or(true, |emoji| reaction.emoji == *emoji)
+                && filter.as_ref().map_or(true, |filter| filter(reaction));
+
+            match (matches, added) {
+                (true, true) => Some(ReactionAction::Added(reaction.clone())),
+                (true, false) => Some(ReactionAction::Removed(reaction.clone())),
+                (false, _) => None,
+            }
+        })
+    }
+}
+
+// Registers a collector of the items the function picks from events of the
+// given types with the shard's runner.
+fn start<T, F>(
+    shard: &ShardMessenger,
+    event_types: &'static [EventType],
+    timeout: Option<Duration>,
+    limit: Option<u32>,
+    pick: F,
+) -> Collector<T>
+    where T: Send + 'static,
+          F: Fn(&Event) -> Option<T> + Send + Sync + 'static {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let sink = move |event: &Event| match pick(event) {
+        Some(item) => match tx.lock().send(item) {
+            Ok(()) => Delivery::Delivered,
+            Err(_) => Delivery::Closed,
+        },
+        None => Delivery::Skipped,
+    };
+
+    // If the runner is gone, the sender is dropped along with the message, so
+    // that the collector ends right away.
+    shard.add_collector(EventCollector {
+        sink: Arc::new(sink),
+        event_types,
+        deadline,
+        remaining: limit,
+        stopped: Arc::clone(&stopped),
+    });
+
+    Collector {
+        items: rx,
+        deadline,
+        stopped,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use std::{
+        sync::mpsc::{self, Receiver},
+        time::{Duration, Instant},
+    };
+    use super::{Collectors, MessageCollectorBuilder, ReactionCollectorBuilder};
+    use crate::client::bridge::gateway::{ShardClientMessage, ShardMessenger, ShardRunnerMessage};
+    use crate::gateway::InterMessage;
+    use crate::model::prelude::*;
+
+    // A messenger whose collectors are added to the returned registry by
+    // `register`, as a shard's runner does.
+    fn messenger() -> (ShardMessenger, Receiver<InterMessage>) {
+        let (tx, rx) = mpsc::channel();
+
+        (ShardMessenger::new(tx), rx)
+    }
+
+    fn register(rx: &Receiver<InterMessage>, collectors: &Collectors) {
+        match rx.try_recv() {
+            Ok(InterMessage::Client(message)) => match *message {
+                ShardClientMessage::Runner(ShardRunnerMessage::AddCollector(collector)) => {
+                    collectors.add(collector);
+                },
+                other => panic!("Expected a collector, got {:?}", other),
+            },
+            other => panic!("Expected a collector, got {:?}", other),
+        }
+    }
+
+    fn message(id: u64, author_id: u64, channel_id: u64) -> Event {
+        Event::MessageCreate(serde_json::from_value(json!({
+            "id": id.to_string(),
+            "channel_id": channel_id.to_string(),
+            "author": {
+                "id": author_id.to_string(),
+                "username": "user",
+                "discriminator": "0001",
+                "avatar": null,
+            },
+            "content": "hello",
+            "timestamp": "2020-01-01T00:00:00+00:00",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        })).unwrap())
+    }
+
+    fn reaction(added: bool, user_id: u64, message_id: u64, emoji: &str) -> Event {
+        let reaction = json!({
+            "channel_id": "1",
+            "emoji": {"id": null, "name": emoji},
+            "guild_id": "2",
+            "message_id": message_id.to_string(),
+            "user_id": user_id.to_string(),
+        });
+
+        if added {
+            Event::ReactionAdd(serde_json::from_value(reaction).unwrap())
+        } else {
+            Event::ReactionRemove(serde_json::from_value(reaction).unwrap())
+        }
+    }
+
+    #[test]
+    fn collects_matching_messages_up_to_the_limit() {
+        let (messenger, rx) = messenger();
+        let collectors = Collectors::default();
+
+        let collector = MessageCollectorBuilder::new(&messenger)
+            .author_id(1)
+            .channel_id(2)
+            .limit(2)
+            .start();
+        register(&rx, &collectors);
+
+        for event in &[message(1, 3, 2), message(2, 1, 4), message(3, 1, 2), message(4, 1, 2), message(5, 1, 2)] {
+            collectors.process(event);
+        }
+
+        let ids = collector.map(|message| message.id.0).collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 4]);
+        assert_eq!(collectors.len(), 0);
+    }
+
+    #[test]
+    fn collects_reactions_by_message_and_emoji() {
+        let (messenger, rx) = messenger();
+        let collectors = Collectors::default();
+
+        let collector = ReactionCollectorBuilder::new(&messenger)
+            .guild_id(2)
+            .message_id(5)
+            .emoji('✅')
+            .removed(true)
+            .timeout(Duration::from_millis(100))
+            .start();
+        register(&rx, &collectors);
+
+        for event in &[reaction(true, 1, 6, "✅"), reaction(true, 1, 5, "❌"), reaction(true, 1, 5, "✅"), reaction(false, 1, 5, "✅")] {
+            collectors.process(event);
+        }
+
+        let actions = collector.map(|action| (action.is_added(), action.reaction().user_id.0)).collect::<Vec<_>>();
+        assert_eq!(actions, vec![(true, 1), (false, 1)]);
+    }
+
+    #[test]
+    fn waiting_ends_at_the_timeout() {
+        let (messenger, rx) = messenger();
+        let collectors = Collectors::default();
+
+        let started = Instant::now();
+        let collector = ReactionCollectorBuilder::new(&messenger)
+            .timeout(Duration::from_millis(50))
+            .start();
+        register(&rx, &collectors);
+
+        assert!(collector.wait().is_none());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // The runner forgets the collector with the next event.
+        collectors.process(&reaction(true, 1, 1, "✅"));
+        assert_eq!(collectors.len(), 0);
+    }
+
+    #[test]
+    fn wants_the_events_of_active_collectors() {
+        let (messenger, rx) = messenger();
+        let collectors = Collectors::default();
+        assert!(!collectors.wants(EventType::MessageCreate));
+
+        let messages = MessageCollectorBuilder::new(&messenger).start();
+        register(&rx, &collectors);
+        let _reactions = ReactionCollectorBuilder::new(&messenger).start();
+        register(&rx, &collectors);
+
+        assert!(collectors.wants(EventType::MessageCreate));
+        assert!(collectors.wants(EventType::ReactionAdd));
+        assert!(!collectors.wants(EventType::ReactionRemove));
+
+        messages.stop();
+        assert!(!collectors.wants(EventType::MessageCreate));
+    }
+
+    #[test]
+    fn dropped_collectors_are_forgotten() {
+        let (messenger, rx) = messenger();
+        let collectors = Collectors::default();
+
+        drop(MessageCollectorBuilder::new(&messenger).start());
+        register(&rx, &collectors);
+
+        let collector = MessageCollectorBuilder::new(&messenger).start();
+        register(&rx, &collectors);
+        collector.stop();
+
+        collectors.process(&message(1, 1, 1));
+        assert_eq!(collectors.len(), 0);
+
+        assert!(collector.wait().is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::client::bridge::gateway::ShardMessenger;
+use crate::client::store::{Store, StoreKey};
 use crate::gateway::InterMessage;
 use crate::model::prelude::*;
 use parking_lot::RwLock;
@@ -369,6 +370,28 @@ impl Context {
     pub fn set_presence(&self, activity: Option<Activity>, status: OnlineStatus) {
         self.shard.set_presence(activity, status);
     }
+
+    /// Returns the client's key-value store, for values belonging to a guild,
+    /// user or channel.
+    ///
+    /// Unless another backend was set via [`Client::set_store`], values are
+    /// kept in memory.
+    ///
+    /// Refer to [`Store`] for an example.
+    ///
+    /// [`Client::set_store`]: struct.Client.html#method.set_store
+    /// [`Store`]: struct.Store.html
+    pub fn store(&self) -> Store {
+        if let Some(store) = self.data.read().get::<StoreKey>() {
+            return store.clone();
+        }
+
+        self.data.write().entry::<StoreKey>().or_insert_with(Store::default).clone()
+    }
+}
+
+impl AsRef<ShardMessenger> for Context {
+    fn as_ref(&self) -> &ShardMessenger { &self.shard }
 }
 
 #[cfg(feature = "http")]
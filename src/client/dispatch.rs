@@ -1,7 +1,7 @@
 use crate::gateway::InterMessage;
 use crate::model::{
     channel::{Channel, Message},
-    event::Event,
+    event::{Event, EventType},
     guild::Member,
 };
 use std::{sync::{Arc, mpsc::Sender}};
@@ -9,6 +9,10 @@ use parking_lot::{Mutex, RwLock};
 use super::{
     bridge::gateway::event::ClientEvent,
     event_handler::{EventHandler, RawEventHandler},
+    collector::Collectors,
+    dispatch_order::{DispatchQueues, Queue},
+    handlers::EventPipeline,
+    panic::{self, GuardedPool, PanicSource},
     Context
 };
 use threadpool::ThreadPool;
@@ -105,16 +109,134 @@ pub(crate) enum DispatchEvent {
 
 #[cfg(feature = "framework")]
 #[allow(clippy::too_many_arguments)]
+pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
+                       RH: RawEventHandler + Send + Sync + 'static>(
+    event: DispatchEvent,
+    pipeline: &EventPipeline,
+    collectors: &Collectors,
+    queues: &DispatchQueues,
+    framework: &Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
+    data: &Arc<RwLock<ShareMap>>,
+    event_handler: &Option<Arc<H>>,
+    raw_event_handler: &Option<Arc<RH>>,
+    runner_tx: &Sender<InterMessage>,
+    threadpool: &ThreadPool,
+    shard_id: u64,
+    cache_and_http: Arc<CacheAndHttp>,
+) {
+    let queue = match event {
+        DispatchEvent::Model(ref event) => queues.queue(event),
+        _ => None,
+    };
+
+    let event = match run_pipeline(event, pipeline, data, runner_tx, threadpool, queue, shard_id, &cache_and_http) {
+        Some(event) => event,
+        None => return,
+    };
+
+    if let DispatchEvent::Model(ref event) = event {
+        collectors.process(event);
+    }
+
+    dispatch_to_handlers(
+        event,
+        framework,
+        data,
+        event_handler,
+        raw_event_handler,
+        runner_tx,
+        threadpool,
+        queue,
+        shard_id,
+        cache_and_http,
+    );
+}
+
+#[cfg(not(feature = "framework"))]
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
                        RH: RawEventHandler + Send + Sync + 'static>(
     event: DispatchEvent,
+    pipeline: &EventPipeline,
+    collectors: &Collectors,
+    queues: &DispatchQueues,
+    data: &Arc<RwLock<ShareMap>>,
+    event_handler: &Option<Arc<H>>,
+    raw_event_handler: &Option<Arc<RH>>,
+    runner_tx: &Sender<InterMessage>,
+    threadpool: &ThreadPool,
+    shard_id: u64,
+    cache_and_http: Arc<CacheAndHttp>,
+) {
+    let queue = match event {
+        DispatchEvent::Model(ref event) => queues.queue(event),
+        _ => None,
+    };
+
+    let event = match run_pipeline(event, pipeline, data, runner_tx, threadpool, queue, shard_id, &cache_and_http) {
+        Some(event) => event,
+        None => return,
+    };
+
+    if let DispatchEvent::Model(ref event) = event {
+        collectors.process(event);
+    }
+
+    dispatch_to_handlers(
+        event,
+        data,
+        event_handler,
+        raw_event_handler,
+        runner_tx,
+        threadpool,
+        queue,
+        shard_id,
+        cache_and_http,
+    );
+}
+
+// Runs events from the gateway through the client's middleware, returning
+// `None` if they are discarded.
+#[allow(clippy::too_many_arguments)]
+fn run_pipeline(
+    event: DispatchEvent,
+    pipeline: &EventPipeline,
+    data: &Arc<RwLock<ShareMap>>,
+    runner_tx: &Sender<InterMessage>,
+    threadpool: &ThreadPool,
+    queue: Option<Queue<'_>>,
+    shard_id: u64,
+    cache_and_http: &Arc<CacheAndHttp>,
+) -> Option<DispatchEvent> {
+    match event {
+        DispatchEvent::Model(event) if !pipeline.is_empty() => {
+            #[cfg(not(any(feature = "cache", feature = "http")))]
+            let context = context(data, runner_tx, shard_id);
+            #[cfg(all(feature = "cache", not(feature = "http")))]
+            let context = context(data, runner_tx, shard_id, &cache_and_http.cache);
+            #[cfg(all(not(feature = "cache"), feature = "http"))]
+            let context = context(data, runner_tx, shard_id, &cache_and_http.http);
+            #[cfg(all(feature = "cache", feature = "http"))]
+            let context = context(data, runner_tx, shard_id, &cache_and_http.cache, &cache_and_http.http);
+
+            pipeline.run(&context, event, threadpool, queue).map(DispatchEvent::Model)
+        },
+        other => Some(other),
+    }
+}
+
+#[cfg(feature = "framework")]
+#[allow(clippy::too_many_arguments)]
+fn dispatch_to_handlers<H: EventHandler + Send + Sync + 'static,
+                        RH: RawEventHandler + Send + Sync + 'static>(
+    event: DispatchEvent,
     framework: &Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
     data: &Arc<RwLock<ShareMap>>,
     event_handler: &Option<Arc<H>>,
     raw_event_handler: &Option<Arc<RH>>,
     runner_tx: &Sender<InterMessage>,
     threadpool: &ThreadPool,
+    queue: Option<Queue<'_>>,
     shard_id: u64,
     cache_and_http: Arc<CacheAndHttp>,
 ) {
@@ -139,9 +261,15 @@ pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
                         event.message.clone(),
                         h,
                         threadpool,
+                        queue,
                     );
+                    // The framework runs hooks such as dynamic prefixes and
+                    // checks on the shard's thread, before handing commands
+                    // to the threadpool.
                     if let Some(ref mut framework) = *framework.lock() {
-                        framework.dispatch(context, event.message, threadpool);
+                        panic::catch(data, shard_id, PanicSource::Event(EventType::MessageCreate), || {
+                            framework.dispatch(context, event.message, threadpool);
+                        });
                     }
                 },
                 other => {
@@ -151,6 +279,7 @@ pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
                         h,
                         runner_tx,
                         threadpool,
+                        queue,
                         shard_id,
                         cache_and_http,
                     );
@@ -169,45 +298,51 @@ pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
                 let context = context(data, runner_tx, shard_id, &cache_and_http.cache, &cache_and_http.http);
 
                 let event_handler = Arc::clone(rh);
-                threadpool.execute(move || {
+                let source = PanicSource::Event(e.event_type());
+                let pool = GuardedPool::new(threadpool, data, shard_id, source).ordered(queue);
+                pool.execute(move || {
                     event_handler.raw_event(context, e);
                 });
             }
         },
         (Some(_), Some(_)) => {
             if let DispatchEvent::Model(ref e) = event {
-                    dispatch(DispatchEvent::Model(e.clone()),
-                             framework,
-                             data,
-                             &None::<Arc<H>>,
-                             raw_event_handler,
-                             runner_tx,
-                             threadpool,
-                             shard_id,
-                             Arc::clone(&cache_and_http))
+                    dispatch_to_handlers(DispatchEvent::Model(e.clone()),
+                                         framework,
+                                         data,
+                                         &None::<Arc<H>>,
+                                         raw_event_handler,
+                                         runner_tx,
+                                         threadpool,
+                                         queue,
+                                         shard_id,
+                                         Arc::clone(&cache_and_http))
             }
-            dispatch(event,
-                     framework,
-                     data,
-                     event_handler,
-                     &None::<Arc<RH>>,
-                     runner_tx,
-                     threadpool,
-                     shard_id,
-                     cache_and_http);
+            dispatch_to_handlers(event,
+                                 framework,
+                                 data,
+                                 event_handler,
+                                 &None::<Arc<RH>>,
+                                 runner_tx,
+                                 threadpool,
+                                 queue,
+                                 shard_id,
+                                 cache_and_http);
         }
     };
 }
 
 #[cfg(not(feature = "framework"))]
-pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
-                       RH: RawEventHandler + Send + Sync + 'static>(
+#[allow(clippy::too_many_arguments)]
+fn dispatch_to_handlers<H: EventHandler + Send + Sync + 'static,
+                        RH: RawEventHandler + Send + Sync + 'static>(
     event: DispatchEvent,
     data: &Arc<RwLock<ShareMap>>,
     event_handler: &Option<Arc<H>>,
     raw_event_handler: &Option<Arc<RH>>,
     runner_tx: &Sender<InterMessage>,
     threadpool: &ThreadPool,
+    queue: Option<Queue<'_>>,
     shard_id: u64,
     cache_and_http: Arc<CacheAndHttp>,
 ) {
@@ -232,6 +367,7 @@ pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
                         event.message.clone(),
                         h,
                         threadpool,
+                        queue,
                     );
                 },
                 other => {
@@ -241,6 +377,7 @@ pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
                         h,
                         runner_tx,
                         threadpool,
+                        queue,
                         shard_id,
                         cache_and_http,
                     );
@@ -260,7 +397,9 @@ pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
                     let context = context(data, runner_tx, shard_id, &cache_and_http.cache, &cache_and_http.http);
 
                     let event_handler = Arc::clone(rh);
-                    threadpool.execute(move || {
+                    let source = PanicSource::Event(e.event_type());
+                    let pool = GuardedPool::new(threadpool, data, shard_id, source).ordered(queue);
+                    pool.execute(move || {
                         event_handler.raw_event(context, e);
                     });
                 },
@@ -270,24 +409,26 @@ pub(crate) fn dispatch<H: EventHandler + Send + Sync + 'static,
         (Some(ref h), Some(ref rh)) => {
             match event {
                 DispatchEvent::Model(ref e) =>
-                    dispatch(DispatchEvent::Model(e.clone()),
-                             data,
-                             &None::<Arc<H>>,
-                             raw_event_handler,
-                             runner_tx,
-                             threadpool,
-                             shard_id,
-                             Arc::clone(&cache_and_http)),
+                    dispatch_to_handlers(DispatchEvent::Model(e.clone()),
+                                         data,
+                                         &None::<Arc<H>>,
+                                         raw_event_handler,
+                                         runner_tx,
+                                         threadpool,
+                                         queue,
+                                         shard_id,
+                                         Arc::clone(&cache_and_http)),
                 _ => {}
             }
-            dispatch(event,
-                     data,
-                     event_handler,
-                     &None::<Arc<RH>>,
-                     runner_tx,
-                     threadpool,
-                     shard_id,
-                     cache_and_http);
+            dispatch_to_handlers(event,
+                                 data,
+                                 event_handler,
+                                 &None::<Arc<RH>>,
+                                 runner_tx,
+                                 threadpool,
+                                 queue,
+                                 shard_id,
+                                 cache_and_http);
         }
     };
 }
@@ -297,6 +438,7 @@ fn dispatch_message<H>(
     mut message: Message,
     event_handler: &Arc<H>,
     threadpool: &ThreadPool,
+    queue: Option<Queue<'_>>,
 ) where H: EventHandler + Send + Sync + 'static {
     #[cfg(feature = "model")]
     {
@@ -304,8 +446,11 @@ fn dispatch_message<H>(
     }
 
     let event_handler = Arc::clone(event_handler);
+    let data = Arc::clone(&context.data);
+    let source = PanicSource::Event(EventType::MessageCreate);
+    let pool = GuardedPool::new(threadpool, &data, context.shard_id, source).ordered(queue);
 
-    threadpool.execute(move || {
+    pool.execute(move || {
         event_handler.message(context, message);
     });
 }
@@ -317,6 +462,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
     event_handler: &Arc<H>,
     runner_tx: &Sender<InterMessage>,
     threadpool: &ThreadPool,
+    queue: Option<Queue<'_>>,
     shard_id: u64,
     cache_and_http: Arc<CacheAndHttp>,
 ) {
@@ -329,11 +475,18 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
     #[cfg(all(feature = "cache", feature = "http"))]
     let context = context(data, runner_tx, shard_id, &cache_and_http.cache, &cache_and_http.http);
 
+    let source = match event {
+        DispatchEvent::Client(ClientEvent::ShardStageUpdate(_)) => PanicSource::ShardStageUpdate,
+        DispatchEvent::Model(ref event) => PanicSource::Event(event.event_type()),
+        _ => PanicSource::__Nonexhaustive,
+    };
+    let pool = GuardedPool::new(threadpool, data, shard_id, source).ordered(queue);
+
     match event {
         DispatchEvent::Client(ClientEvent::ShardStageUpdate(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.shard_stage_update(context, event);
             });
         }
@@ -346,7 +499,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
                 Channel::Private(channel) => {
                     let event_handler = Arc::clone(event_handler);
 
-                    threadpool.execute(move || {
+                    pool.execute(move || {
                         event_handler.private_channel_create(context, channel);
                     });
                 },
@@ -354,14 +507,14 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
                 Channel::Guild(channel) => {
                     let event_handler = Arc::clone(event_handler);
 
-                    threadpool.execute(move || {
+                    pool.execute(move || {
                         event_handler.channel_create(context, channel);
                     });
                 },
                 Channel::Category(channel) => {
                     let event_handler = Arc::clone(event_handler);
 
-                    threadpool.execute(move || {
+                    pool.execute(move || {
                         event_handler.category_create(context, channel);
                     });
                 },
@@ -376,14 +529,14 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
                 Channel::Guild(channel) => {
                     let event_handler = Arc::clone(event_handler);
 
-                    threadpool.execute(move || {
+                    pool.execute(move || {
                         event_handler.channel_delete(context, channel);
                     });
                 },
                 Channel::Category(channel) => {
                     let event_handler = Arc::clone(event_handler);
 
-                    threadpool.execute(move || {
+                    pool.execute(move || {
                         event_handler.category_delete(context, channel);
                     });
                 },
@@ -394,7 +547,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.channel_pins_update(context, event);
             });
         },
@@ -403,7 +556,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.channel_recipient_addition(
                     context,
                     event.channel_id,
@@ -416,7 +569,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.channel_recipient_removal(
                     context,
                     event.channel_id,
@@ -427,7 +580,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
         DispatchEvent::Model(Event::ChannelUpdate(mut event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     let before = cache_and_http.cache.as_ref().read().channel(event.channel.id());
                     update(&cache_and_http, &mut event);
@@ -443,7 +596,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
         DispatchEvent::Model(Event::GuildBanAdd(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_ban_addition(context, event.guild_id, event.user);
             });
         },
@@ -451,7 +604,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_ban_removal(context, event.guild_id, event.user);
             });
         },
@@ -478,7 +631,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
                         .collect::<Vec<GuildId>>();
                     let event_handler = Arc::clone(event_handler);
 
-                    threadpool.execute(move || {
+                    pool.execute(move || {
                         event_handler.cache_ready(context, guild_amount);
                     });
                 }
@@ -486,7 +639,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     event_handler.guild_create(context, event.guild, _is_new);
                 } else {
@@ -498,7 +651,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             let _full = update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     event_handler.guild_delete(context, event.guild, _full);
                 } else {
@@ -510,14 +663,14 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_emojis_update(context, event.guild_id, event.emojis);
             });
         },
         DispatchEvent::Model(Event::GuildIntegrationsUpdate(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_integrations_update(context, event.guild_id);
             });
         },
@@ -526,7 +679,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_member_addition(context, event.guild_id, event.member);
             });
         },
@@ -534,7 +687,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             let _member = update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     event_handler.guild_member_removal(context, event.guild_id, event.user, _member);
                 } else {
@@ -552,7 +705,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     if let Some(after) = _after {
                         event_handler.guild_member_update(context, _before, after);
@@ -566,7 +719,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_members_chunk(context, event.guild_id, event.members);
             });
         },
@@ -574,7 +727,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_role_create(context, event.guild_id, event.role);
             });
         },
@@ -582,7 +735,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             let _role = update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     event_handler.guild_role_delete(context, event.guild_id, event.role_id, _role);
                 } else {
@@ -594,7 +747,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             let _before = update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     event_handler.guild_role_update(context, event.guild_id, _before, event.role);
                 } else {
@@ -606,14 +759,14 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.guild_unavailable(context, event.guild_id);
             });
         },
         DispatchEvent::Model(Event::GuildUpdate(mut event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     let before = cache_and_http.cache.as_ref().read()
                         .guilds
@@ -634,14 +787,14 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
         DispatchEvent::Model(Event::MessageDeleteBulk(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.message_delete_bulk(context, event.channel_id, event.ids);
             });
         },
         DispatchEvent::Model(Event::MessageDelete(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.message_delete(context, event.channel_id, event.message_id);
             });
         },
@@ -649,7 +802,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             let _before = update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     let _after = cache_and_http.cache.as_ref().read().message(event.channel_id, event.id);
                     event_handler.message_update(context, _before, _after, event);
@@ -662,7 +815,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.presence_replace(context, event.presences);
             });
         },
@@ -671,28 +824,35 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
 
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.presence_update(context, event);
             });
         },
         DispatchEvent::Model(Event::ReactionAdd(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.reaction_add(context, event.reaction);
             });
         },
         DispatchEvent::Model(Event::ReactionRemove(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.reaction_remove(context, event.reaction);
             });
         },
+        DispatchEvent::Model(Event::InteractionCreate(event)) => {
+            let event_handler = Arc::clone(event_handler);
+
+            pool.execute(move || {
+                event_handler.interaction_create(context, event.interaction);
+            });
+        },
         DispatchEvent::Model(Event::ReactionRemoveAll(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.reaction_remove_all(context, event.channel_id, event.message_id);
             });
         },
@@ -700,7 +860,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(&event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.ready(context, event.ready);
             });
         },
@@ -710,14 +870,14 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
         DispatchEvent::Model(Event::TypingStart(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.typing_start(context, event);
             });
         },
         DispatchEvent::Model(Event::Unknown(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.unknown(context, event.kind, event.value);
             });
         },
@@ -725,7 +885,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             let _before = update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     event_handler.user_update(context, _before.unwrap(), event.current_user);
                 } else {
@@ -736,7 +896,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
         DispatchEvent::Model(Event::VoiceServerUpdate(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.voice_server_update(context, event);
             });
         },
@@ -744,7 +904,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
             let _before = update(&cache_and_http, &mut event);
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 feature_cache! {{
                     event_handler.voice_state_update(context, event.guild_id, _before, event.voice_state);
                 } else {
@@ -755,7 +915,7 @@ fn handle_event<H: EventHandler + Send + Sync + 'static>(
         DispatchEvent::Model(Event::WebhookUpdate(event)) => {
             let event_handler = Arc::clone(event_handler);
 
-            threadpool.execute(move || {
+            pool.execute(move || {
                 event_handler.webhook_update(context, event.guild_id, event.channel_id);
             });
         },
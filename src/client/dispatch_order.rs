@@ -0,0 +1,366 @@
+//! Running the handlers of related events one after another, in the order the
+//! events were received.
+
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use threadpool::ThreadPool;
+use crate::model::{
+    channel::Channel,
+    event::{ChannelCreateEvent, ChannelUpdateEvent, Event},
+    id::{ChannelId, GuildId},
+};
+
+/// The order in which a shard's events are given to event handlers.
+///
+/// By default, handlers are run on the client's threadpool as events are
+/// received, so that a [`message_update`] may be handled before the
+/// [`message`] it edits. The other orders have events that belong together
+/// handled one after another, in the order they were received, while unrelated
+/// events are still handled concurrently.
+///
+/// Events belonging to no guild or channel, such as [`ready`], are not
+/// ordered. Neither are framework commands, which run once the message is
+/// passed to the framework.
+///
+/// Set via [`ClientBuilder::dispatch_order`].
+///
+/// [`ClientBuilder::dispatch_order`]: struct.ClientBuilder.html#method.dispatch_order
+/// [`message`]: trait.EventHandler.html#method.message
+/// [`message_update`]: trait.EventHandler.html#method.message_update
+/// [`ready`]: trait.EventHandler.html#method.ready
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DispatchOrder {
+    /// Events are handled concurrently, in no particular order.
+    Unordered,
+    /// Events of the same guild are handled in order. Direct messages are
+    /// ordered per channel.
+    ///
+    /// Events naming only a channel, such as [`MessageUpdate`], are related
+    /// to its guild through the events introducing the channel.
+    ///
+    /// [`MessageUpdate`]: ../model/event/enum.Event.html#variant.MessageUpdate
+    PerGuild,
+    /// Events of the same channel are handled in order. Guild events naming
+    /// no channel, such as [`GuildMemberUpdate`], are ordered per guild.
+    ///
+    /// [`GuildMemberUpdate`]: ../model/event/enum.Event.html#variant.GuildMemberUpdate
+    PerChannel,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Default for DispatchOrder {
+    fn default() -> Self {
+        DispatchOrder::Unordered
+    }
+}
+
+/// What an event's handlers are ordered by.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum OrderKey {
+    Guild(GuildId),
+    Channel(ChannelId),
+}
+
+/// The queue an event's handlers are run in.
+#[derive(Clone, Copy)]
+pub(crate) struct Queue<'a> {
+    pub(crate) queues: &'a DispatchQueues,
+    pub(crate) key: OrderKey,
+}
+
+impl<'a> Queue<'a> {
+    pub(crate) fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        self.queues.execute(self.key, job);
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Queues of handlers waiting for those of earlier related events, run on the
+/// threadpool.
+///
+/// Handlers with the same key are run one after another by a single job of
+/// the threadpool, which takes them from the key's queue until it is empty.
+pub(crate) struct DispatchQueues {
+    order: DispatchOrder,
+    queues: Arc<Queues>,
+    // The guilds of the channels seen, relating events naming only a channel
+    // to their guild.
+    channel_guilds: Mutex<HashMap<ChannelId, GuildId>>,
+}
+
+struct Queues {
+    threadpool: ThreadPool,
+    // A key is present while a job is running its handlers.
+    waiting: Mutex<HashMap<OrderKey, VecDeque<Job>>>,
+}
+
+impl DispatchQueues {
+    pub(crate) fn new(order: DispatchOrder, threadpool: ThreadPool) -> Self {
+        DispatchQueues {
+            order,
+            queues: Arc::new(Queues {
+                threadpool,
+                waiting: Mutex::new(HashMap::new()),
+            }),
+            channel_guilds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns what the handlers of the event are ordered by, if anything.
+    ///
+    /// This must be called for every event, in the order they are received, so
+    /// that channels are related to their guilds.
+    pub(crate) fn key(&self, event: &Event) -> Option<OrderKey> {
+        match self.order {
+            DispatchOrder::PerGuild => {
+                self.learn_channels(event);
+
+                match (guild_id(event), channel_id(event)) {
+                    (Some(guild_id), _) => Some(OrderKey::Guild(guild_id)),
+                    (None, Some(channel_id)) => Some(match self.channel_guilds.lock().get(&channel_id) {
+                        Some(&guild_id) => OrderKey::Guild(guild_id),
+                        None => OrderKey::Channel(channel_id),
+                    }),
+                    (None, None) => None,
+                }
+            },
+            DispatchOrder::PerChannel => channel_id(event)
+                .map(OrderKey::Channel)
+                .or_else(|| guild_id(event).map(OrderKey::Guild)),
+            DispatchOrder::Unordered | DispatchOrder::__Nonexhaustive => None,
+        }
+    }
+
+    /// Returns the queue the handlers of the event are run in, if they are
+    /// ordered.
+    pub(crate) fn queue(&self, event: &Event) -> Option<Queue<'_>> {
+        self.key(event).map(|key| Queue {
+            queues: self,
+            key,
+        })
+    }
+
+    /// Runs the job once the jobs queued before it with the same key have run.
+    ///
+    /// The job must not panic, as the jobs queued after it would never run.
+    pub(crate) fn execute<F: FnOnce() + Send + 'static>(&self, key: OrderKey, job: F) {
+        {
+            let mut waiting = self.queues.waiting.lock();
+
+            if let Some(queue) = waiting.get_mut(&key) {
+                queue.push_back(Box::new(job));
+
+                return;
+            }
+
+            waiting.insert(key, VecDeque::new());
+        }
+
+        let queues = Arc::clone(&self.queues);
+
+        self.queues.threadpool.execute(move || {
+            let mut job: Job = Box::new(job);
+
+            loop {
+                job();
+
+                let mut waiting = queues.waiting.lock();
+                let next = waiting.get_mut(&key).and_then(VecDeque::pop_front);
+
+                job = match next {
+                    Some(next) => next,
+                    None => {
+                        waiting.remove(&key);
+
+                        return;
+                    },
+                };
+            }
+        });
+    }
+
+    fn learn_channels(&self, event: &Event) {
+        let mut channel_guilds = self.channel_guilds.lock();
+
+        match *event {
+            Event::GuildCreate(ref event) => {
+                for &channel_id in event.guild.channels.keys() {
+                    channel_guilds.insert(channel_id, event.guild.id);
+                }
+            },
+            Event::GuildDelete(ref event) => {
+                let guild_id = event.guild.id;
+
+                channel_guilds.retain(|_, &mut channel_guild| channel_guild != guild_id);
+            },
+            Event::ChannelCreate(ChannelCreateEvent { channel: Channel::Guild(ref channel), .. }) |
+            Event::ChannelUpdate(ChannelUpdateEvent { channel: Channel::Guild(ref channel), .. }) => {
+                let channel = channel.read();
+
+                channel_guilds.insert(channel.id, channel.guild_id);
+            },
+            Event::ChannelDelete(ref event) => {
+                channel_guilds.remove(&event.channel.id());
+            },
+            Event::MessageCreate(ref event) => {
+                if let Some(guild_id) = event.message.guild_id {
+                    channel_guilds.insert(event.message.channel_id, guild_id);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+fn guild_id(event: &Event) -> Option<GuildId> {
+    match *event {
+        Event::ChannelCreate(ref event) => channel_guild_id(&event.channel),
+        Event::ChannelDelete(ref event) => channel_guild_id(&event.channel),
+        Event::ChannelUpdate(ref event) => channel_guild_id(&event.channel),
+        Event::GuildBanAdd(ref event) => Some(event.guild_id),
+        Event::GuildBanRemove(ref event) => Some(event.guild_id),
+        Event::GuildCreate(ref event) => Some(event.guild.id),
+        Event::GuildDelete(ref event) => Some(event.guild.id),
+        Event::GuildEmojisUpdate(ref event) => Some(event.guild_id),
+        Event::GuildIntegrationsUpdate(ref event) => Some(event.guild_id),
+        Event::GuildMemberAdd(ref event) => Some(event.guild_id),
+        Event::GuildMemberRemove(ref event) => Some(event.guild_id),
+        Event::GuildMemberUpdate(ref event) => Some(event.guild_id),
+        Event::GuildMembersChunk(ref event) => Some(event.guild_id),
+        Event::GuildRoleCreate(ref event) => Some(event.guild_id),
+        Event::GuildRoleDelete(ref event) => Some(event.guild_id),
+        Event::GuildRoleUpdate(ref event) => Some(event.guild_id),
+        Event::GuildUnavailable(ref event) => Some(event.guild_id),
+        Event::GuildUpdate(ref event) => Some(event.guild.id),
+        Event::MessageCreate(ref event) => event.message.guild_id,
+        Event::PresenceUpdate(ref event) => event.guild_id,
+        Event::ReactionAdd(ref event) => event.reaction.guild_id,
+        Event::ReactionRemove(ref event) => event.reaction.guild_id,
+        Event::InteractionCreate(ref event) => event.interaction.guild_id,
+        Event::VoiceServerUpdate(ref event) => event.guild_id,
+        Event::VoiceStateUpdate(ref event) => event.guild_id,
+        Event::WebhookUpdate(ref event) => Some(event.guild_id),
+        _ => None,
+    }
+}
+
+fn channel_id(event: &Event) -> Option<ChannelId> {
+    match *event {
+        Event::ChannelCreate(ref event) => Some(event.channel.id()),
+        Event::ChannelDelete(ref event) => Some(event.channel.id()),
+        Event::ChannelPinsUpdate(ref event) => Some(event.channel_id),
+        Event::ChannelRecipientAdd(ref event) => Some(event.channel_id),
+        Event::ChannelRecipientRemove(ref event) => Some(event.channel_id),
+        Event::ChannelUpdate(ref event) => Some(event.channel.id()),
+        Event::MessageCreate(ref event) => Some(event.message.channel_id),
+        Event::MessageDelete(ref event) => Some(event.channel_id),
+        Event::MessageDeleteBulk(ref event) => Some(event.channel_id),
+        Event::MessageUpdate(ref event) => Some(event.channel_id),
+        Event::ReactionAdd(ref event) => Some(event.reaction.channel_id),
+        Event::ReactionRemove(ref event) => Some(event.reaction.channel_id),
+        Event::ReactionRemoveAll(ref event) => Some(event.channel_id),
+        Event::InteractionCreate(ref event) => event.interaction.channel_id,
+        Event::TypingStart(ref event) => Some(event.channel_id),
+        Event::WebhookUpdate(ref event) => Some(event.channel_id),
+        _ => None,
+    }
+}
+
+fn channel_guild_id(channel: &Channel) -> Option<GuildId> {
+    match *channel {
+        Channel::Guild(ref channel) => Some(channel.read().guild_id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use parking_lot::Mutex;
+    use std::{sync::Arc, thread, time::Duration};
+    use threadpool::ThreadPool;
+    use super::{DispatchOrder, DispatchQueues, OrderKey};
+    use crate::model::{
+        event::{Event, MessageUpdateEvent, TypingStartEvent},
+        id::{ChannelId, GuildId, MessageId, UserId},
+    };
+
+    fn typing(channel_id: u64) -> Event {
+        Event::TypingStart(TypingStartEvent {
+            channel_id: ChannelId(channel_id),
+            timestamp: 0,
+            user_id: UserId(1),
+            _nonexhaustive: (),
+        })
+    }
+
+    fn message_update(channel_id: u64) -> Event {
+        Event::MessageUpdate(MessageUpdateEvent {
+            id: MessageId(1),
+            channel_id: ChannelId(channel_id),
+            kind: None,
+            content: None,
+            nonce: None,
+            tts: None,
+            pinned: None,
+            timestamp: None,
+            edited_timestamp: None,
+            author: None,
+            mention_everyone: None,
+            mentions: None,
+            mention_roles: None,
+            attachments: None,
+            embeds: None,
+            _nonexhaustive: (),
+        })
+    }
+
+    #[test]
+    fn runs_jobs_with_the_same_key_in_order() {
+        let threadpool = ThreadPool::new(4);
+        let queues = DispatchQueues::new(DispatchOrder::PerChannel, threadpool.clone());
+        let runs = Arc::new(Mutex::new(Vec::new()));
+
+        for i in 0..20u64 {
+            let key = OrderKey::Channel(ChannelId(i % 2));
+            let runs = Arc::clone(&runs);
+
+            queues.execute(key, move || {
+                // Earlier jobs sleep longer, so that running them
+                // concurrently would reorder them.
+                thread::sleep(Duration::from_millis(20 - i));
+                runs.lock().push(i);
+            });
+        }
+
+        threadpool.join();
+
+        let runs = runs.lock();
+        let of = |parity| runs.iter().cloned().filter(|i| i % 2 == parity).collect::<Vec<_>>();
+        assert_eq!(runs.len(), 20);
+        assert_eq!(of(0), (0..20).filter(|i| i % 2 == 0).collect::<Vec<_>>());
+        assert_eq!(of(1), (0..20).filter(|i| i % 2 == 1).collect::<Vec<_>>());
+        assert!(queues.queues.waiting.lock().is_empty());
+    }
+
+    #[test]
+    fn keys_events_by_order() {
+        let unordered = DispatchQueues::new(DispatchOrder::Unordered, ThreadPool::new(1));
+        assert_eq!(unordered.key(&typing(1)), None);
+
+        let per_channel = DispatchQueues::new(DispatchOrder::PerChannel, ThreadPool::new(1));
+        assert_eq!(per_channel.key(&typing(1)), Some(OrderKey::Channel(ChannelId(1))));
+
+        // Channels are keyed by their guild once it is known.
+        let per_guild = DispatchQueues::new(DispatchOrder::PerGuild, ThreadPool::new(1));
+        assert_eq!(per_guild.key(&message_update(1)), Some(OrderKey::Channel(ChannelId(1))));
+
+        per_guild.channel_guilds.lock().insert(ChannelId(1), GuildId(2));
+        assert_eq!(per_guild.key(&message_update(1)), Some(OrderKey::Guild(GuildId(2))));
+        assert_eq!(per_guild.key(&typing(3)), Some(OrderKey::Channel(ChannelId(3))));
+    }
+}
@@ -2,13 +2,56 @@ use crate::model::prelude::*;
 use parking_lot::RwLock;
 use serde_json::Value;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::Arc
 };
 use super::context::Context;
 use crate::client::bridge::gateway::event::*;
 
 /// The core trait for handling events by serenity.
+///
+/// # Gateway intents
+///
+/// When [gateway intents] are set on the [`Client`], only the events of the
+/// given intents are received, and the methods of other events are never
+/// called. The methods require the following intents:
+///
+/// | Intent                     | Methods |
+/// |----------------------------|---------|
+/// | `GUILDS`                   | `cache_ready`, `guild_create`, `guild_delete`, `guild_update`, `guild_unavailable`, `guild_role_create`, `guild_role_update`, `guild_role_delete`, `channel_create`, `category_create`, `channel_update`, `channel_delete`, `category_delete`, `channel_pins_update` (in guilds) |
+/// | `GUILD_MEMBERS`            | `guild_member_addition`, `guild_member_update`, `guild_member_removal` |
+/// | `GUILD_BANS`               | `guild_ban_addition`, `guild_ban_removal` |
+/// | `GUILD_EMOJIS`             | `guild_emojis_update` |
+/// | `GUILD_INTEGRATIONS`       | `guild_integrations_update` |
+/// | `GUILD_WEBHOOKS`           | `webhook_update` |
+/// | `GUILD_VOICE_STATES`       | `voice_state_update` |
+/// | `GUILD_PRESENCES`          | `presence_update` |
+/// | `GUILD_MESSAGES`           | `message`, `message_update`, `message_delete`, `message_delete_bulk` (in guilds) |
+/// | `GUILD_MESSAGE_REACTIONS`  | `reaction_add`, `reaction_remove`, `reaction_remove_all` (in guilds) |
+/// | `GUILD_MESSAGE_TYPING`     | `typing_start` (in guilds) |
+/// | `DIRECT_MESSAGES`          | `message`, `message_update`, `message_delete`, `channel_pins_update` (in direct messages) |
+/// | `DIRECT_MESSAGE_REACTIONS` | `reaction_add`, `reaction_remove`, `reaction_remove_all` (in direct messages) |
+/// | `DIRECT_MESSAGE_TYPING`    | `typing_start` (in direct messages) |
+///
+/// All other methods, such as `ready`, `interaction_create` and
+/// `voice_server_update`, are called regardless of the intents.
+/// `guild_members_chunk` only requires `GUILD_MEMBERS` when requesting all
+/// members of a guild.
+///
+/// The cache is only updated by received events: without `GUILDS` it will not
+/// contain any guilds, and without `GUILD_MEMBERS` or `GUILD_PRESENCES` the
+/// members and presences of guilds are incomplete.
+///
+/// # Filtering events
+///
+/// Every received event is deserialized, even if its method is not
+/// implemented. Implement the handler through the [`event_handler!`] macro to
+/// discard the events of the methods it does not implement beforehand, which
+/// saves a lot of work for frequent events such as presence updates.
+///
+/// [`Client`]: struct.Client.html#method.set_intents
+/// [`event_handler!`]: ../macro.event_handler.html
+/// [gateway intents]: ../model/gateway/struct.GatewayIntents.html
 pub trait EventHandler {
     /// Dispatched when the cache has received and inserted all data from
     /// guilds.
@@ -73,6 +116,29 @@ pub trait EventHandler {
     #[cfg(not(feature = "cache"))]
     fn channel_update(&self, _ctx: Context, _new_data: Channel) {}
 
+    /// Returns the types of the events this handler handles, or `None` to
+    /// receive all of them.
+    ///
+    /// Events of other types are discarded without being deserialized, and
+    /// their methods are not called. Events that the client relies on, such
+    /// as [`EventType::Ready`] and those updating the cache, are still
+    /// received.
+    ///
+    /// This is called when the [`Client`] is created, and again whenever
+    /// handlers or middleware are added to it. Types set via
+    /// [`Client::set_event_types`] are used instead.
+    ///
+    /// **Note**: Only the methods of the returned types are called. Rather
+    /// than keeping this in sync with the implemented methods by hand,
+    /// implement the handler through the [`event_handler!`] macro, which
+    /// returns the types of the implemented methods.
+    ///
+    /// [`Client`]: struct.Client.html
+    /// [`Client::set_event_types`]: struct.Client.html#method.set_event_types
+    /// [`EventType::Ready`]: ../model/event/enum.EventType.html#variant.Ready
+    /// [`event_handler!`]: ../macro.event_handler.html
+    fn event_types(&self) -> Option<HashSet<EventType>> { None }
+
     /// Dispatched when a user is banned from a guild.
     ///
     /// Provides the guild's id and the banned user's data.
@@ -248,6 +314,11 @@ pub trait EventHandler {
     /// Provides the channel's id and the message's id.
     fn reaction_remove_all(&self, _ctx: Context, _channel_id: ChannelId, _removed_from_message_id: MessageId) {}
 
+    /// Dispatched when a user invokes an application command.
+    ///
+    /// Provides the interaction, which must be responded to within 3 seconds.
+    fn interaction_create(&self, _ctx: Context, _interaction: Interaction) {}
+
     fn presence_replace(&self, _ctx: Context, _: Vec<Presence>) {}
 
     /// Dispatched when a user's presence is updated (e.g off -> on).
@@ -318,3 +389,175 @@ pub trait RawEventHandler {
     /// Dispatched when any event occurs
     fn raw_event(&self, _ctx: Context, _ev: Event) {}
 }
+
+/// Implements [`EventHandler`], returning the types of the events of the
+/// implemented methods from [`EventHandler::event_types`], so that all other
+/// events are discarded before being deserialized.
+///
+/// The methods are written as in a plain `impl EventHandler` block, which
+/// must not implement `event_types` itself. Implementing `unknown` receives
+/// all events, as the types of unknown events can't be listed.
+///
+/// # Examples
+///
+/// Receive only messages, on top of the events the client relies on:
+///
+/// ```rust,no_run
+/// use serenity::event_handler;
+/// use serenity::model::channel::Message;
+/// use serenity::prelude::*;
+///
+/// struct Handler;
+///
+/// event_handler! {
+///     impl EventHandler for Handler {
+///         fn message(&self, ctx: Context, msg: Message) {
+///             if msg.content == "!ping" {
+///                 let _ = msg.channel_id.say(&ctx, "Pong!");
+///             }
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`EventHandler`]: client/trait.EventHandler.html
+/// [`EventHandler::event_types`]: client/trait.EventHandler.html#method.event_types
+#[macro_export]
+macro_rules! event_handler {
+    (
+        impl EventHandler for $handler:ty {
+            $(
+                $(#[$attr:meta])*
+                fn $name:ident($($arg:tt)*) $body:block
+            )*
+        }
+    ) => {
+        impl $crate::client::EventHandler for $handler {
+            $(
+                $(#[$attr])*
+                fn $name($($arg)*) $body
+            )*
+
+            fn event_types(&self)
+                -> ::std::option::Option<::std::collections::HashSet<$crate::model::event::EventType>> {
+                $crate::client::event_types_of_methods(&[$(stringify!($name)),*])
+            }
+        }
+    };
+}
+
+/// Returns the types of the events dispatched to the given methods of
+/// [`EventHandler`], or `None` if they need all events.
+///
+/// # Panics
+///
+/// Panics if a name is not one of the methods of [`EventHandler`].
+///
+/// [`EventHandler`]: trait.EventHandler.html
+#[doc(hidden)]
+pub fn event_types_of_methods(methods: &[&str]) -> Option<HashSet<EventType>> {
+    let mut event_types = HashSet::new();
+
+    for method in methods {
+        let types: &[EventType] = match *method {
+            "cache_ready" | "guild_create" => &[EventType::GuildCreate],
+            "channel_create" | "category_create" | "private_channel_create" => {
+                &[EventType::ChannelCreate]
+            },
+            "channel_delete" | "category_delete" => &[EventType::ChannelDelete],
+            "channel_pins_update" => &[EventType::ChannelPinsUpdate],
+            "channel_recipient_addition" => &[EventType::ChannelRecipientAdd],
+            "channel_recipient_removal" => &[EventType::ChannelRecipientRemove],
+            "channel_update" => &[EventType::ChannelUpdate],
+            "guild_ban_addition" => &[EventType::GuildBanAdd],
+            "guild_ban_removal" => &[EventType::GuildBanRemove],
+            "guild_delete" => &[EventType::GuildDelete],
+            "guild_emojis_update" => &[EventType::GuildEmojisUpdate],
+            "guild_integrations_update" => &[EventType::GuildIntegrationsUpdate],
+            "guild_member_addition" => &[EventType::GuildMemberAdd],
+            "guild_member_removal" => &[EventType::GuildMemberRemove],
+            "guild_member_update" => &[EventType::GuildMemberUpdate],
+            "guild_members_chunk" => &[EventType::GuildMembersChunk],
+            "guild_role_create" => &[EventType::GuildRoleCreate],
+            "guild_role_delete" => &[EventType::GuildRoleDelete],
+            "guild_role_update" => &[EventType::GuildRoleUpdate],
+            // Outages are received as guild creates and deletes.
+            "guild_unavailable" => {
+                &[EventType::GuildCreate, EventType::GuildDelete, EventType::GuildUnavailable]
+            },
+            "guild_update" => &[EventType::GuildUpdate],
+            "message" => &[EventType::MessageCreate],
+            "message_delete" => &[EventType::MessageDelete],
+            "message_delete_bulk" => &[EventType::MessageDeleteBulk],
+            "message_update" => &[EventType::MessageUpdate],
+            "reaction_add" => &[EventType::ReactionAdd],
+            "reaction_remove" => &[EventType::ReactionRemove],
+            "reaction_remove_all" => &[EventType::ReactionRemoveAll],
+            "interaction_create" => &[EventType::InteractionCreate],
+            "presence_replace" => &[EventType::PresencesReplace],
+            "presence_update" => &[EventType::PresenceUpdate],
+            "ready" => &[EventType::Ready],
+            "resume" => &[EventType::Resumed],
+            // Not a gateway event.
+            "shard_stage_update" => &[],
+            "typing_start" => &[EventType::TypingStart],
+            "unknown" => return None,
+            "user_update" => &[EventType::UserUpdate],
+            "voice_server_update" => &[EventType::VoiceServerUpdate],
+            "voice_state_update" => &[EventType::VoiceStateUpdate],
+            "webhook_update" => &[EventType::WebhookUpdate],
+            other => panic!("`{}` is not a method of EventHandler", other),
+        };
+
+        event_types.extend(types.iter().cloned());
+    }
+
+    Some(event_types)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::model::{channel::Message, event::EventType};
+    use super::{event_types_of_methods, Context, EventHandler};
+
+    struct Handler;
+
+    event_handler! {
+        impl EventHandler for Handler {
+            /// Doc comments and other attributes are kept.
+            fn message(&self, _ctx: Context, _msg: Message) {}
+
+            fn resume(&self, _ctx: Context, _: crate::model::event::ResumedEvent) {}
+        }
+    }
+
+    #[test]
+    fn derives_event_types_from_implemented_methods() {
+        let expected = vec![EventType::MessageCreate, EventType::Resumed]
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        assert_eq!(Handler.event_types(), Some(expected));
+    }
+
+    #[test]
+    fn knows_every_method() {
+        macro_rules! method_names {
+            ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $kind:ty),*);)*) => {
+                &[$(stringify!($name)),*]
+            };
+        }
+
+        assert!(event_types_of_methods(&["unknown"]).is_none());
+        assert!(event_types_of_methods(event_handler_methods!(method_names)).is_none());
+
+        let all_but_unknown = event_handler_methods!(method_names)
+            .iter()
+            .cloned()
+            .filter(|name| *name != "unknown")
+            .collect::<Vec<_>>();
+
+        assert!(event_types_of_methods(&all_but_unknown).is_some());
+    }
+}
@@ -0,0 +1,501 @@
+//! Registering several event handlers, and middleware that sees events
+//! before they do.
+
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+use threadpool::ThreadPool;
+use crate::model::prelude::*;
+use super::{
+    bridge::gateway::event::*,
+    dispatch_order::Queue,
+    panic::{report, GuardedPool, HandlerPanic, PanicSource, Panics},
+    Context,
+    EventHandler,
+    RawEventHandler,
+};
+
+/// Inspects, transforms or discards events before they are dispatched.
+///
+/// Middleware is added to a [`Client`] via [`Client::add_middleware`], and is
+/// called for every event, in the order it was added, on the thread of the
+/// shard receiving the event. Each middleware is given the event returned by
+/// the one before it; returning `None` discards the event, so that it does
+/// not update the cache and is not dispatched to any handler or framework.
+///
+/// As middleware holds up the shard, it should return quickly, leaving slow
+/// work to handlers.
+///
+/// Closures taking a [`Context`] and an [`Event`] are middleware.
+///
+/// # Examples
+///
+/// Ignore messages from bots, before any handler or command sees them:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # struct Handler;
+/// #
+/// # impl EventHandler for Handler {}
+/// #
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::client::Client;
+/// use serenity::model::event::Event;
+/// use std::env;
+///
+/// let mut client = Client::new(&env::var("DISCORD_TOKEN")?, Handler)?;
+///
+/// client.add_middleware(|_: &Context, event: Event| match event {
+///     Event::MessageCreate(ref create) if create.message.author.bot => None,
+///     event => Some(event),
+/// });
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`Client`]: struct.Client.html
+/// [`Client::add_middleware`]: struct.Client.html#method.add_middleware
+/// [`Context`]: struct.Context.html
+/// [`Event`]: ../model/event/enum.Event.html
+pub trait EventMiddleware: Send + Sync {
+    /// Returns the event to pass on, or `None` to discard it.
+    fn process(&self, ctx: &Context, event: Event) -> Option<Event>;
+
+    /// Returns the types of the events this middleware needs to see, or
+    /// `None` to see all of them.
+    ///
+    /// Refer to [`EventHandler::event_types`] for details.
+    ///
+    /// [`EventHandler::event_types`]: trait.EventHandler.html#method.event_types
+    fn event_types(&self) -> Option<HashSet<EventType>> { None }
+}
+
+impl<F> EventMiddleware for F where F: Fn(&Context, Event) -> Option<Event> + Send + Sync {
+    fn process(&self, ctx: &Context, event: Event) -> Option<Event> {
+        self(ctx, event)
+    }
+}
+
+/// A list of event handlers, dispatched to in the order they were added.
+///
+/// Each event is given to every handler in turn, on the same thread of the
+/// client's threadpool, so a handler is called once the ones before it have
+/// returned. A handler panicking is logged and does not prevent the handlers
+/// after it from being called.
+///
+/// This is how a [`Client`] dispatches to the handlers given to it via
+/// [`Client::add_event_handler`].
+///
+/// [`Client`]: struct.Client.html
+/// [`Client::add_event_handler`]: struct.Client.html#method.add_event_handler
+#[derive(Default)]
+pub struct EventHandlers {
+    handlers: RwLock<Vec<Arc<dyn EventHandler + Send + Sync>>>,
+}
+
+impl EventHandlers {
+    /// Creates an empty list of handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a handler, called after those already added.
+    pub fn push<H>(&self, handler: H) where H: EventHandler + Send + Sync + 'static {
+        self.handlers.write().push(Arc::new(handler));
+    }
+
+    /// The number of handlers.
+    pub fn len(&self) -> usize {
+        self.handlers.read().len()
+    }
+
+    /// Whether no handlers have been added.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.read().is_empty()
+    }
+
+    // Copied so that the lock is not held while handlers run.
+    fn handlers(&self) -> Vec<Arc<dyn EventHandler + Send + Sync>> {
+        self.handlers.read().clone()
+    }
+}
+
+// Implements `EventHandler` for `EventHandlers`, by calling each handler in
+// turn. The last handler is given the original arguments, so that a single
+// handler does not pay for cloning them.
+macro_rules! event_handlers {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident($($arg:ident: $kind:ty),*);
+    )*) => {
+        impl EventHandler for EventHandlers {
+            fn event_types(&self) -> Option<HashSet<EventType>> {
+                union(self.handlers().iter().map(|handler| handler.event_types()))
+            }
+
+            $(
+                $(#[$attr])*
+                fn $name(&self, ctx: Context, $($arg: $kind),*) {
+                    let handlers = self.handlers();
+
+                    let mut panics = Panics::default();
+
+                    if let Some((last, rest)) = handlers.split_last() {
+                        for handler in rest {
+                            panics.isolate(|| {
+                                handler.$name(ctx.clone(), $($arg.clone()),*)
+                            });
+                        }
+
+                        panics.isolate(|| last.$name(ctx, $($arg),*));
+                    }
+
+                    panics.resume();
+                }
+            )*
+        }
+    };
+}
+
+event_handler_methods!(event_handlers);
+
+/// The middleware and raw event handlers of a [`Client`], which see events
+/// before they are dispatched to its typed handlers and framework.
+///
+/// Middleware is run in order on the shard's thread, as described by
+/// [`EventMiddleware`]. Events passing all of it are then given to each raw
+/// handler in turn on a thread of the client's threadpool, where - as with
+/// [`EventHandlers`] - a raw handler panicking does not prevent the ones after
+/// it from being called.
+///
+/// [`Client`]: struct.Client.html
+/// [`EventHandlers`]: struct.EventHandlers.html
+/// [`EventMiddleware`]: trait.EventMiddleware.html
+#[derive(Default)]
+pub struct EventPipeline {
+    middleware: RwLock<Vec<Arc<dyn EventMiddleware>>>,
+    raw_handlers: RwLock<Vec<Arc<dyn RawEventHandler + Send + Sync>>>,
+}
+
+impl EventPipeline {
+    /// Creates a pipeline without middleware or raw handlers, which passes
+    /// events on unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds middleware, run after that already added.
+    pub fn add_middleware<M: EventMiddleware + 'static>(&self, middleware: M) {
+        self.middleware.write().push(Arc::new(middleware));
+    }
+
+    /// Adds a raw handler, called after those already added.
+    pub fn add_raw_event_handler<H>(&self, handler: H)
+        where H: RawEventHandler + Send + Sync + 'static {
+        self.raw_handlers.write().push(Arc::new(handler));
+    }
+
+    /// Whether the pipeline has neither middleware nor raw handlers.
+    pub fn is_empty(&self) -> bool {
+        self.middleware.read().is_empty() && self.raw_handlers.read().is_empty()
+    }
+
+    /// Returns the types of the events the middleware and raw handlers need
+    /// to see, or `None` if any of them needs all events.
+    ///
+    /// Raw handlers always need all events.
+    pub fn event_types(&self) -> Option<HashSet<EventType>> {
+        if !self.raw_handlers.read().is_empty() {
+            return None;
+        }
+
+        union(self.middleware.read().iter().map(|middleware| middleware.event_types()))
+    }
+
+    /// Runs the event through the middleware, then has the raw handlers
+    /// called with the result on the threadpool, in the queue if the event's
+    /// handlers are ordered.
+    ///
+    /// Returns the event to dispatch to typed handlers, if it was not
+    /// discarded.
+    pub(crate) fn run(
+        &self,
+        ctx: &Context,
+        event: Event,
+        threadpool: &ThreadPool,
+        queue: Option<Queue<'_>>,
+    ) -> Option<Event> {
+        let middleware = self.middleware.read().clone();
+        let mut event = event;
+
+        for middleware in middleware {
+            let event_type = event.event_type();
+            let processed = panic::catch_unwind(AssertUnwindSafe(|| middleware.process(ctx, event)));
+
+            event = match processed {
+                Ok(Some(event)) => event,
+                Ok(None) => return None,
+                Err(payload) => {
+                    report(&ctx.data, &HandlerPanic {
+                        source: PanicSource::Middleware(event_type),
+                        shard_id: ctx.shard_id,
+                        payload: &*payload,
+                    });
+
+                    return None;
+                },
+            };
+        }
+
+        let raw_handlers = self.raw_handlers.read().clone();
+
+        if !raw_handlers.is_empty() {
+            let source = PanicSource::Event(event.event_type());
+            let pool = GuardedPool::new(threadpool, &ctx.data, ctx.shard_id, source).ordered(queue);
+            let ctx = ctx.clone();
+            let raw_event = event.clone();
+
+            pool.execute(move || {
+                let mut panics = Panics::default();
+
+                if let Some((last, rest)) = raw_handlers.split_last() {
+                    for handler in rest {
+                        panics.isolate(|| handler.raw_event(ctx.clone(), raw_event.clone()));
+                    }
+
+                    panics.isolate(|| last.raw_event(ctx, raw_event));
+                }
+
+                panics.resume();
+            });
+        }
+
+        Some(event)
+    }
+}
+
+// The union of the event types of several handlers, or `None` if any of them
+// wants all events.
+fn union(event_types: impl Iterator<Item = Option<HashSet<EventType>>>) -> Option<HashSet<EventType>> {
+    let mut all = HashSet::new();
+
+    for event_types in event_types {
+        all.extend(event_types?);
+    }
+
+    Some(all)
+}
+
+/// Combines the event types of several handlers, as returned by their
+/// `event_types` methods.
+pub(crate) fn combine_event_types(
+    a: Option<HashSet<EventType>>,
+    b: Option<HashSet<EventType>>,
+) -> Option<HashSet<EventType>> {
+    union(vec![a, b].into_iter())
+}
+
+#[cfg(all(test, feature = "cache"))]
+mod test {
+    use parking_lot::{Mutex, RwLock};
+    use std::{
+        collections::HashSet,
+        panic::{self, AssertUnwindSafe},
+        sync::{mpsc, Arc},
+    };
+    use threadpool::ThreadPool;
+    use typemap::ShareMap;
+    use super::{EventHandlers, EventPipeline};
+    use crate::cache::Cache;
+    use crate::client::{
+        panic::{self as handler_panic, HandlerPanic, PanicHookKey, PanicSource},
+        Context,
+        EventHandler,
+        RawEventHandler,
+    };
+    use crate::http::Http;
+    use crate::model::{
+        event::{Event, EventType, TypingStartEvent},
+        id::{ChannelId, UserId},
+    };
+
+    fn context() -> Context {
+        context_with(Arc::new(RwLock::new(ShareMap::custom())))
+    }
+
+    fn context_with(data: Arc<RwLock<ShareMap>>) -> Context {
+        let (runner_tx, _) = mpsc::channel();
+
+        Context::new(
+            data,
+            runner_tx,
+            0,
+            Arc::new(RwLock::new(Cache::default())),
+            Arc::new(Http::new_with_token("token")),
+        )
+    }
+
+    fn typing(user_id: u64) -> TypingStartEvent {
+        TypingStartEvent {
+            channel_id: ChannelId(1),
+            timestamp: 0,
+            user_id: UserId(user_id),
+            _nonexhaustive: (),
+        }
+    }
+
+    // Records the users typing, optionally panicking afterwards.
+    struct Recorder {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<(&'static str, u64)>>>,
+        panics: bool,
+        event_types: Option<HashSet<EventType>>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, calls: &Arc<Mutex<Vec<(&'static str, u64)>>>) -> Self {
+            Recorder {
+                name,
+                calls: Arc::clone(calls),
+                panics: false,
+                event_types: None,
+            }
+        }
+    }
+
+    impl EventHandler for Recorder {
+        fn event_types(&self) -> Option<HashSet<EventType>> {
+            self.event_types.clone()
+        }
+
+        fn typing_start(&self, _: Context, event: TypingStartEvent) {
+            self.calls.lock().push((self.name, event.user_id.0));
+
+            if self.panics {
+                panic!("expected panic");
+            }
+        }
+    }
+
+    impl RawEventHandler for Recorder {
+        fn raw_event(&self, _: Context, event: Event) {
+            if let Event::TypingStart(event) = event {
+                self.calls.lock().push((self.name, event.user_id.0));
+            }
+        }
+    }
+
+    #[test]
+    fn handlers_are_called_in_order_despite_panics() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let handlers = EventHandlers::new();
+
+        handlers.push(Recorder::new("first", &calls));
+        handlers.push(Recorder { panics: true, ..Recorder::new("second", &calls) });
+        handlers.push(Recorder::new("third", &calls));
+
+        // The panic is resumed once all handlers were called, for the
+        // dispatcher to report.
+        for user_id in 1..=2 {
+            let call = panic::catch_unwind(AssertUnwindSafe(|| handlers.typing_start(context(), typing(user_id))));
+            assert!(call.is_err());
+        }
+
+        assert_eq!(*calls.lock(), vec![
+            ("first", 1), ("second", 1), ("third", 1),
+            ("first", 2), ("second", 2), ("third", 2),
+        ]);
+    }
+
+    #[test]
+    fn every_handler_panic_is_reported() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let data = Arc::new(RwLock::new(ShareMap::custom()));
+        let handlers = EventHandlers::new();
+
+        let hook_reports = Arc::clone(&reports);
+        data.write().insert::<PanicHookKey>(Arc::new(move |panic: &HandlerPanic<'_>| {
+            hook_reports.lock().push((panic.source.clone(), panic.message().map(String::from)));
+        }));
+
+        handlers.push(Recorder { panics: true, ..Recorder::new("first", &calls) });
+        handlers.push(Recorder { panics: true, ..Recorder::new("second", &calls) });
+
+        let source = PanicSource::Event(EventType::TypingStart);
+        let ctx = context_with(Arc::clone(&data));
+        handler_panic::catch(&data, 0, source.clone(), || handlers.typing_start(ctx, typing(1)));
+
+        assert_eq!(*calls.lock(), vec![("first", 1), ("second", 1)]);
+        assert_eq!(*reports.lock(), vec![
+            (source.clone(), Some("expected panic".to_string())),
+            (source, Some("expected panic".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn handlers_receive_the_union_of_their_event_types() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let handlers = EventHandlers::new();
+        let only = |event_type| Some(vec![event_type].into_iter().collect());
+
+        assert_eq!(handlers.event_types(), Some(HashSet::new()));
+
+        handlers.push(Recorder { event_types: only(EventType::TypingStart), ..Recorder::new("a", &calls) });
+        handlers.push(Recorder { event_types: only(EventType::MessageCreate), ..Recorder::new("b", &calls) });
+
+        let expected = vec![EventType::TypingStart, EventType::MessageCreate].into_iter().collect();
+        assert_eq!(handlers.event_types(), Some(expected));
+
+        handlers.push(Recorder::new("c", &calls));
+
+        assert_eq!(handlers.event_types(), None);
+    }
+
+    #[test]
+    fn middleware_transforms_and_discards_events() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let pipeline = EventPipeline::new();
+        let threadpool = ThreadPool::new(1);
+
+        pipeline.add_middleware(|_: &Context, event: Event| match event {
+            Event::TypingStart(ref event) if event.user_id.0 == 2 => None,
+            event => Some(event),
+        });
+        pipeline.add_middleware(|_: &Context, event: Event| match event {
+            Event::TypingStart(event) => Some(Event::TypingStart(typing(event.user_id.0 * 10))),
+            event => Some(event),
+        });
+        pipeline.add_raw_event_handler(Recorder::new("raw", &calls));
+
+        let processed = pipeline.run(&context(), Event::TypingStart(typing(1)), &threadpool, None);
+        match processed {
+            Some(Event::TypingStart(ref event)) => assert_eq!(event.user_id.0, 10),
+            other => panic!("Expected a TypingStart, got {:?}", other),
+        }
+
+        assert!(pipeline.run(&context(), Event::TypingStart(typing(2)), &threadpool, None).is_none());
+
+        threadpool.join();
+        assert_eq!(*calls.lock(), vec![("raw", 10)]);
+    }
+
+    #[test]
+    fn panicking_middleware_discards_events() {
+        let pipeline = EventPipeline::new();
+        let threadpool = ThreadPool::new(1);
+
+        pipeline.add_middleware(|_: &Context, _: Event| -> Option<Event> { panic!("expected panic") });
+
+        assert!(pipeline.run(&context(), Event::TypingStart(typing(1)), &threadpool, None).is_none());
+        assert!(!pipeline.is_empty());
+    }
+}
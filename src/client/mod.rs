@@ -20,17 +20,71 @@
 
 pub mod bridge;
 
+#[cfg(feature = "async")]
+mod async_handler;
+mod builder;
+mod collector;
 mod context;
 mod dispatch;
+mod dispatch_order;
 mod error;
 mod event_handler;
+mod handlers;
+mod panic;
+mod replay;
+mod scheduler;
+#[cfg(unix)]
+mod signal;
+mod store;
 
 pub use self::{
+    builder::{ClientBuilder, ClientBuilderError},
+    collector::{
+        Collector,
+        EventCollector,
+        MessageCollector,
+        MessageCollectorBuilder,
+        ReactionAction,
+        ReactionCollector,
+        ReactionCollectorBuilder,
+    },
     context::Context,
+    dispatch_order::DispatchOrder,
     error::ClientError,
-    event_handler::{EventHandler, RawEventHandler},
+    event_handler::{event_types_of_methods, EventHandler, RawEventHandler},
+    handlers::{EventHandlers, EventMiddleware, EventPipeline},
+    panic::{HandlerPanic, PanicSource},
+    replay::Replayer,
+    scheduler::{
+        Cron,
+        FileTaskStore,
+        Schedule,
+        Scheduler,
+        SchedulerError,
+        StoredTask,
+        TaskContext,
+        TaskHandle,
+        TaskStore,
+    },
+    store::{FileStore, MemoryStore, Scope, ScopedStore, Store, StoreBackend},
 };
 
+#[cfg(feature = "standard_framework")]
+pub(crate) use self::panic::GuardedPool;
+
+#[cfg(feature = "async")]
+pub use self::async_handler::{
+    Async,
+    AsyncContext,
+    AsyncEventHandler,
+    AsyncRawEventHandler,
+    Blocking,
+    Executor,
+};
+// The future async handlers return, and the trait boxing futures into it.
+#[cfg(feature = "async")]
+pub use futures::future::{BoxFuture, FutureExt};
+
 #[cfg(any(feature = "cache", feature = "http"))]
 pub use crate::CacheAndHttp;
 
@@ -42,23 +96,27 @@ pub use crate::http as rest;
 pub use crate::cache::{Cache, CacheRwLock};
 
 use crate::internal::prelude::*;
+use crate::model::{
+    event::EventType,
+    gateway::{GatewayIntents, SessionStartLimit},
+};
 use parking_lot::Mutex;
 use parking_lot::RwLock;
-use self::bridge::gateway::{ShardManager, ShardManagerMonitor, ShardManagerOptions};
-use std::sync::Arc;
+use self::bridge::gateway::{ShardManager, ShardManagerMonitor};
+use std::{collections::HashSet, sync::Arc};
 use threadpool::ThreadPool;
 use typemap::ShareMap;
-use log::debug;
+use log::{debug, warn};
 
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
 #[cfg(feature = "voice")]
-use crate::model::id::UserId;
-#[cfg(feature = "voice")]
 use self::bridge::voice::ClientVoiceManager;
-#[cfg(feature = "http")]
-use crate::http::Http;
-#[cfg(feature = "cache")]
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsExporter;
+#[cfg(feature = "metrics")]
+use std::net::{SocketAddr, ToSocketAddrs};
+#[cfg(any(feature = "cache", unix))]
 use std::time::Duration;
 
 struct DummyRawEventHandler;
@@ -78,6 +136,13 @@ impl RawEventHandler for DummyRawEventHandler {}
 /// Note that you do not need to manually handle events, as they are handled
 /// internally and then dispatched to your event handlers.
 ///
+/// # Configuration
+///
+/// Settings fixed for the client's lifetime, such as how shards connect to the
+/// gateway, are set on a [`ClientBuilder`]. The client's own setters, such as
+/// [`add_event_handler`] and [`set_intents`], may be called at any time, but
+/// only apply to shards started afterwards.
+///
 /// # Examples
 ///
 /// Creating a Client instance and adding a handler on every message
@@ -103,7 +168,10 @@ impl RawEventHandler for DummyRawEventHandler {}
 /// client.start().expect("Could not start client.");
 /// ```
 ///
+/// [`ClientBuilder`]: struct.ClientBuilder.html
 /// [`Shard`]: ../gateway/struct.Shard.html
+/// [`add_event_handler`]: #method.add_event_handler
+/// [`set_intents`]: #method.set_intents
 /// [`EventHandler::message`]: trait.EventHandler.html#tymethod.message
 /// [`Event::MessageCreate`]: ../model/event/enum.Event.html#variant.MessageCreate
 /// [sharding docs]: ../index.html#sharding
@@ -195,6 +263,13 @@ pub struct Client {
     /// [`Event::Ready`]: ../model/event/enum.Event.html#variant.Ready
     /// [`on_ready`]: #method.on_ready
     #[cfg(feature = "framework")] framework: Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
+    // The typed handlers, and the middleware and raw handlers that events pass
+    // through before reaching them.
+    event_handlers: Arc<EventHandlers>,
+    pipeline: Arc<EventPipeline>,
+    // the types of events set via `set_event_types`, used instead of those of
+    // the handlers
+    event_types: Option<HashSet<EventType>>,
     /// A HashMap of all shards instantiated by the Client.
     ///
     /// The key is the shard ID and the value is the shard itself.
@@ -288,11 +363,21 @@ pub struct Client {
     /// [`Client::start_shards`]: #method.start_shards
     pub shard_manager: Arc<Mutex<ShardManager>>,
     shard_manager_worker: ShardManagerMonitor,
+    // The shards started by `start`, if set via the builder.
+    shards: Option<[u64; 3]>,
     /// The threadpool shared by all shards.
     ///
     /// Defaults to 5 threads, which should suffice small bots. Consider
     /// increasing this number as your bot grows.
     pub threadpool: ThreadPool,
+    /// The scheduler running one-off and recurring tasks on the
+    /// [`threadpool`].
+    ///
+    /// Refer to [`Scheduler`] for more information.
+    ///
+    /// [`Scheduler`]: struct.Scheduler.html
+    /// [`threadpool`]: #structfield.threadpool
+    pub scheduler: Scheduler,
     /// The voice manager for the client.
     ///
     /// This is an ergonomic structure for interfacing over shards' voice
@@ -311,6 +396,16 @@ pub struct Client {
 }
 
 impl Client {
+    /// Creates a builder for a client, to set up handlers, the framework,
+    /// cache, threadpool and shards in one place.
+    ///
+    /// Refer to [`ClientBuilder`] for an example.
+    ///
+    /// [`ClientBuilder`]: struct.ClientBuilder.html
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
     /// Creates a Client for a bot user.
     ///
     /// Discord has a requirement of prefixing bot tokens with `"Bot "`, which
@@ -345,76 +440,28 @@ impl Client {
 
         Self::new_with_handlers(token.as_ref(), Some(handler), None::<DummyRawEventHandler>)
     }
-    /// Creates a client with an optional Handler. If you pass `None`, events are never parsed, but
-    /// they can be received by registering a RawHandler.
+    /// Creates a client with an optional handler and raw handler. If you pass
+    /// `None` for both, only the events the client relies on are parsed.
+    ///
+    /// More handlers can be added afterwards via [`add_event_handler`] and
+    /// [`add_raw_event_handler`].
+    ///
+    /// [`add_event_handler`]: #method.add_event_handler
+    /// [`add_raw_event_handler`]: #method.add_raw_event_handler
     pub fn new_with_handlers<H, RH>(token: impl AsRef<str>, handler: Option<H>, raw_handler: Option<RH>) -> Result<Self>
         where H: EventHandler + Send + Sync + 'static,
               RH: RawEventHandler + Send + Sync + 'static {
-        let token = token.as_ref().trim();
-
-        let token = if token.starts_with("Bot ") {
-            token.to_string()
-        } else {
-            format!("Bot {}", token)
-        };
+        let mut builder = ClientBuilder::new().token(token);
 
-        let http = Http::new_with_token(&token);
-
-        let name = "serenity client".to_owned();
-        let threadpool = ThreadPool::with_name(name, 5);
-        let url = Arc::new(Mutex::new(http.get_gateway()?.url));
-        let data = Arc::new(RwLock::new(ShareMap::custom()));
-        let event_handler = handler.map(Arc::new);
-        let raw_event_handler = raw_handler.map(Arc::new);
-
-        #[cfg(feature = "framework")]
-        let framework = Arc::new(Mutex::new(None));
-        #[cfg(feature = "voice")]
-        let voice_manager = Arc::new(Mutex::new(ClientVoiceManager::new(
-            0,
-            UserId(0),
-        )));
-
-        let cache_and_http = Arc::new(CacheAndHttp {
-            #[cfg(feature = "cache")]
-            cache: Arc::new(RwLock::new(Cache::default())),
-            #[cfg(feature = "cache")]
-            update_cache_timeout: None,
-            #[cfg(feature = "http")]
-            http: Arc::new(http),
-            __nonexhaustive: (),
-        });
+        if let Some(handler) = handler {
+            builder = builder.event_handler(handler);
+        }
 
-        let (shard_manager, shard_manager_worker) = {
-            ShardManager::new(ShardManagerOptions {
-                data: &data,
-                event_handler: &event_handler,
-                raw_event_handler: &raw_event_handler,
-                #[cfg(feature = "framework")]
-                framework: &framework,
-                shard_index: 0,
-                shard_init: 0,
-                shard_total: 0,
-                threadpool: threadpool.clone(),
-                #[cfg(feature = "voice")]
-                voice_manager: &voice_manager,
-                ws_url: &url,
-                cache_and_http: &cache_and_http,
-            })
-        };
+        if let Some(raw_handler) = raw_handler {
+            builder = builder.raw_event_handler(raw_handler);
+        }
 
-        Ok(Client {
-            ws_uri: url,
-            #[cfg(feature = "framework")]
-            framework,
-            data,
-            shard_manager,
-            shard_manager_worker,
-            threadpool,
-            #[cfg(feature = "voice")]
-            voice_manager,
-            cache_and_http,
-        })
+        builder.build()
     }
 
     /// Creates a Client for a bot user and sets a cache update timeout.
@@ -454,68 +501,165 @@ impl Client {
     #[cfg(all(feature = "cache", feature = "http"))]
     pub fn new_with_cache_update_timeout<H>(token: impl AsRef<str>, handler: H, duration: Option<Duration>) -> Result<Self>
         where H: EventHandler + Send + Sync + 'static {
-        let token = token.as_ref().trim();
+        let mut builder = ClientBuilder::new().token(token).event_handler(handler);
 
-        let token = if token.starts_with("Bot ") {
-            token.to_string()
-        } else {
-            format!("Bot {}", token)
-        };
+        if let Some(duration) = duration {
+            builder = builder.cache_update_timeout(duration);
+        }
 
-        let http = Http::new_with_token(&token);
+        builder.build()
+    }
 
-        let name = "serenity client".to_owned();
-        let threadpool = ThreadPool::with_name(name, 5);
-        let url = Arc::new(Mutex::new(http.get_gateway()?.url));
-        let data = Arc::new(RwLock::new(ShareMap::custom()));
-        let event_handler = Some(Arc::new(handler));
+    /// Adds an event handler, dispatched to after the handlers already added.
+    ///
+    /// Each event is given to every handler in the order they were added, on
+    /// the same thread of the threadpool: a handler is called once the ones
+    /// before it have returned, so handlers that take long should hand their
+    /// work off to other threads. A handler panicking is logged, and the
+    /// handlers after it are still called.
+    ///
+    /// Unless set via [`set_event_types`], the types of events the client's
+    /// shards deserialize are recomputed from [`EventHandler::event_types`].
+    ///
+    /// # Examples
+    ///
+    /// Keep logging separate from the bot's logic:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::*;
+    /// # use serenity::model::prelude::*;
+    /// # struct Bot;
+    /// #
+    /// # impl EventHandler for Bot {}
+    /// #
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use serenity::client::Client;
+    /// use std::env;
+    ///
+    /// struct Logger;
+    ///
+    /// impl EventHandler for Logger {
+    ///     fn message(&self, _: Context, msg: Message) {
+    ///         println!("{}: {}", msg.author.name, msg.content);
+    ///     }
+    /// }
+    ///
+    /// let mut client = Client::new(&env::var("DISCORD_TOKEN")?, Bot)?;
+    /// client.add_event_handler(Logger);
+    ///
+    /// client.start()?;
+    /// #     Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`EventHandler::event_types`]: trait.EventHandler.html#method.event_types
+    /// [`set_event_types`]: #method.set_event_types
+    pub fn add_event_handler<H>(&mut self, handler: H)
+        where H: EventHandler + Send + Sync + 'static {
+        self.event_handlers.push(handler);
+        self.update_event_types();
+    }
 
-        #[cfg(feature = "framework")]
-        let framework = Arc::new(Mutex::new(None));
-        #[cfg(feature = "voice")]
-        let voice_manager = Arc::new(Mutex::new(ClientVoiceManager::new(
-            0,
-            UserId(0),
-        )));
+    /// Adds a raw event handler, dispatched to after the raw handlers already
+    /// added.
+    ///
+    /// Raw handlers are called in order on a single thread of the threadpool,
+    /// with events that passed all middleware, and before the cache is updated
+    /// with them. As with [`add_event_handler`], a raw handler panicking does
+    /// not prevent the ones after it from being called.
+    ///
+    /// As raw handlers receive every event, adding one makes the client's
+    /// shards deserialize all events.
+    ///
+    /// [`add_event_handler`]: #method.add_event_handler
+    pub fn add_raw_event_handler<H>(&mut self, handler: H)
+        where H: RawEventHandler + Send + Sync + 'static {
+        self.pipeline.add_raw_event_handler(handler);
+        self.update_event_types();
+    }
+
+    /// Adds middleware, run after the middleware already added.
+    ///
+    /// Middleware sees every event first, on the thread of the shard receiving
+    /// it, and may change it or discard it. Events are then, in order:
+    ///
+    /// 1. given to the raw event handlers;
+    /// 2. applied to the cache;
+    /// 3. dispatched to the event handlers;
+    /// 4. for messages, passed to the framework.
+    ///
+    /// A discarded event goes through none of these. Middleware panicking is
+    /// logged, and discards the event.
+    ///
+    /// Refer to [`EventMiddleware`] for an example.
+    ///
+    /// [`EventMiddleware`]: trait.EventMiddleware.html
+    pub fn add_middleware<M: EventMiddleware + 'static>(&mut self, middleware: M) {
+        self.pipeline.add_middleware(middleware);
+        self.update_event_types();
+    }
+
+    /// Sets the backend of the store returned by [`Context::store`]. Values
+    /// stored in the previous backend are not carried over.
+    ///
+    /// Refer to [`FileStore`] for an example.
+    ///
+    /// [`Context::store`]: struct.Context.html#method.store
+    /// [`FileStore`]: struct.FileStore.html
+    pub fn set_store<B: StoreBackend + 'static>(&mut self, backend: B) {
+        self.data.write().insert::<store::StoreKey>(Store::new(backend));
+    }
 
-        let cache_and_http = Arc::new(CacheAndHttp {
-            cache: Arc::new(RwLock::new(Cache::default())),
-            update_cache_timeout: duration,
-            #[cfg(feature = "http")]
-            http: Arc::new(http),
-            __nonexhaustive: (),
-        });
+    /// Sets a hook called with panics caught in event handlers, middleware,
+    /// framework commands and scheduled tasks, such as to report them to an
+    /// error tracker.
+    ///
+    /// Panics are caught either way, so that they do not take down the thread
+    /// running the handler; without a hook, they are logged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::*;
+    /// # struct Handler;
+    /// #
+    /// # impl EventHandler for Handler {}
+    /// #
+    /// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use serenity::client::{Client, HandlerPanic};
+    /// use std::env;
+    ///
+    /// let mut client = Client::new(&env::var("DISCORD_TOKEN")?, Handler)?;
+    ///
+    /// client.set_panic_hook(|panic: &HandlerPanic<'_>| {
+    ///     eprintln!("{:?} panicked on shard {}: {:?}", panic.source, panic.shard_id, panic.message());
+    /// });
+    /// #     Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    pub fn set_panic_hook<F>(&mut self, hook: F)
+        where F: Fn(&HandlerPanic<'_>) + Send + Sync + 'static {
+        self.data.write().insert::<panic::PanicHookKey>(Arc::new(hook));
+    }
 
-        let (shard_manager, shard_manager_worker) = {
-            ShardManager::new(ShardManagerOptions {
-                data: &data,
-                event_handler: &event_handler,
-                raw_event_handler: &None::<Arc<DummyRawEventHandler>>,
-                #[cfg(feature = "framework")]
-                framework: &framework,
-                shard_index: 0,
-                shard_init: 0,
-                shard_total: 0,
-                threadpool: threadpool.clone(),
-                #[cfg(feature = "voice")]
-                voice_manager: &voice_manager,
-                ws_url: &url,
-                cache_and_http: &cache_and_http,
-            })
+    fn update_event_types(&self) {
+        let event_types = match self.event_types {
+            Some(ref event_types) => Some(event_types.clone()),
+            None => handlers::combine_event_types(
+                self.event_handlers.event_types(),
+                self.pipeline.event_types(),
+            ),
         };
 
-        Ok(Client {
-            ws_uri: url,
-            #[cfg(feature = "framework")]
-            framework,
-            data,
-            shard_manager,
-            shard_manager_worker,
-            threadpool,
-            #[cfg(feature = "voice")]
-            voice_manager,
-            cache_and_http,
-        })
+        self.shard_manager.lock().set_event_types(event_types);
     }
 
     /// Sets a framework to be used with the client. All message events will be
@@ -640,6 +784,186 @@ impl Client {
         *self.framework.lock() = Some(Box::new(f));
     }
 
+    /// Sets the [gateway intents] that shards send when identifying, limiting
+    /// the events received to the given groups.
+    ///
+    /// By default no intents are sent and every event is received. Refer to
+    /// the [`EventHandler`] documentation for which handler methods need
+    /// which intent.
+    ///
+    /// # Examples
+    ///
+    /// Only receive guild and message events:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use serenity::model::gateway::GatewayIntents;
+    /// use std::env;
+    ///
+    /// let token = env::var("DISCORD_TOKEN")?;
+    /// let mut client = Client::new(&token, Handler)?;
+    /// client.set_intents(GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES);
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`EventHandler`]: trait.EventHandler.html
+    /// [gateway intents]: ../model/gateway/struct.GatewayIntents.html
+    pub fn set_intents(&mut self, intents: GatewayIntents) {
+        self.shard_manager.lock().set_intents(Some(intents));
+    }
+
+    /// Sets the types of events that the client's shards deserialize and
+    /// dispatch, instead of those returned by [`EventHandler::event_types`],
+    /// including for handlers added later.
+    ///
+    /// Events of other types are discarded as they are received, which saves
+    /// a lot of work for frequent events that are not handled, such as
+    /// presence updates. Events that the client relies on, such as
+    /// [`EventType::Ready`] and those updating the cache, are still received.
+    ///
+    /// # Examples
+    ///
+    /// Only handle messages and reactions:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use serenity::model::event::EventType;
+    /// use std::env;
+    ///
+    /// let token = env::var("DISCORD_TOKEN")?;
+    /// let mut client = Client::new(&token, Handler)?;
+    /// client.set_event_types(vec![EventType::MessageCreate, EventType::ReactionAdd]);
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`EventHandler::event_types`]: trait.EventHandler.html#method.event_types
+    /// [`EventType::Ready`]: ../model/event/enum.EventType.html#variant.Ready
+    pub fn set_event_types<It>(&mut self, event_types: It)
+        where It: IntoIterator<Item = EventType> {
+        self.event_types = Some(event_types.into_iter().collect());
+        self.update_event_types();
+    }
+
+    /// Serves the client's [metrics] in the Prometheus text format on the
+    /// given address, in a background thread.
+    ///
+    /// Returns the address bound to.
+    ///
+    /// # Examples
+    ///
+    /// Serve metrics on port 9000 of the local machine only:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use std::env;
+    ///
+    /// let token = env::var("DISCORD_TOKEN")?;
+    /// let mut client = Client::new(&token, Handler)?;
+    /// client.serve_metrics("127.0.0.1:9000")?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [metrics]: ../metrics/index.html
+    #[cfg(feature = "metrics")]
+    pub fn serve_metrics(&self, addr: impl ToSocketAddrs) -> Result<SocketAddr> {
+        MetricsExporter::new(
+            Arc::clone(&self.shard_manager),
+            Arc::clone(&self.cache_and_http),
+            self.threadpool.clone(),
+        ).serve(addr)
+    }
+
+    /// Shuts down gracefully once the process receives SIGINT or SIGTERM,
+    /// such as from pressing Ctrl-C.
+    ///
+    /// Refer to [`ShardManager::shutdown_gracefully`] for what is waited for,
+    /// up to the `timeout`, before [`start`] returns. A second signal
+    /// terminates the process immediately.
+    ///
+    /// # Examples
+    ///
+    /// Give handlers up to 30 seconds to finish when the process is told to
+    /// stop:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::EventHandler;
+    /// # use std::error::Error;
+    /// #
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    /// # fn try_main() -> Result<(), Box<Error>> {
+    /// use serenity::client::Client;
+    /// use std::{env, time::Duration};
+    ///
+    /// let token = env::var("DISCORD_TOKEN")?;
+    /// let mut client = Client::new(&token, Handler)?;
+    /// client.shutdown_on_signals(Duration::from_secs(30))?;
+    ///
+    /// client.start()?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # fn main() {
+    /// #     try_main().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`ShardManager::shutdown_gracefully`]: bridge/gateway/struct.ShardManager.html#method.shutdown_gracefully
+    /// [`start`]: #method.start
+    #[cfg(unix)]
+    pub fn shutdown_on_signals(&self, timeout: Duration) -> Result<()> {
+        let manager = Arc::clone(&self.shard_manager);
+
+        signal::on_shutdown_signal(move || {
+            ShardManager::shutdown_gracefully(&manager, timeout);
+        })?;
+
+        Ok(())
+    }
+
     /// Establish the connection and start listening for events.
     ///
     /// This will start receiving events in a loop and start dispatching the
@@ -649,6 +973,9 @@ impl Client {
     /// less than 2500 guilds. If you have a reason for sharding and/or are in
     /// more than 2500 guilds, use one of these depending on your use case:
     ///
+    /// If shards were set via [`ClientBuilder::shard_range`] or a related
+    /// method, those shards are started instead of a single one.
+    ///
     /// Refer to the [Gateway documentation][gateway docs] for more information
     /// on effectively using sharding.
     ///
@@ -681,10 +1008,13 @@ impl Client {
     /// # }
     /// ```
     ///
+    /// [`ClientBuilder::shard_range`]: struct.ClientBuilder.html#method.shard_range
     /// [gateway docs]: ../gateway/index.html#sharding
     #[cfg(feature = "http")]
     pub fn start(&mut self) -> Result<()> {
-        self.start_connection([0, 0, 1])
+        let shards = self.shards.unwrap_or([0, 0, 1]);
+
+        self.start_connection(shards, None)
     }
 
     /// Establish the connection(s) and start listening for events.
@@ -737,13 +1067,13 @@ impl Client {
     /// [gateway docs]: ../gateway/index.html#sharding
     #[cfg(feature = "http")]
     pub fn start_autosharded(&mut self) -> Result<()> {
-        let (x, y) = {
+        let (x, y, limit) = {
             let res = self.cache_and_http.http.get_bot_gateway()?;
 
-            (res.shards as u64 - 1, res.shards as u64)
+            (res.shards as u64 - 1, res.shards as u64, res.session_start_limit)
         };
 
-        self.start_connection([0, x, y])
+        self.start_connection([0, x, y], Some(limit))
     }
 
     /// Establish a sharded connection and start listening for events.
@@ -825,7 +1155,7 @@ impl Client {
     /// [gateway docs]: ../gateway/index.html#sharding
     #[cfg(feature = "http")]
     pub fn start_shard(&mut self, shard: u64, shards: u64) -> Result<()> {
-        self.start_connection([shard, shard, shards])
+        self.start_connection([shard, shard, shards], None)
     }
 
     /// Establish sharded connections and start listening for events.
@@ -880,7 +1210,7 @@ impl Client {
     /// [Gateway docs]: ../gateway/index.html#sharding
     #[cfg(feature = "http")]
     pub fn start_shards(&mut self, total_shards: u64) -> Result<()> {
-        self.start_connection([0, total_shards - 1, total_shards])
+        self.start_connection([0, total_shards - 1, total_shards], None)
     }
 
     /// Establish a range of sharded connections and start listening for events.
@@ -951,7 +1281,7 @@ impl Client {
     /// [Gateway docs]: ../gateway/index.html#sharding
     #[cfg(feature = "http")]
     pub fn start_shard_range(&mut self, range: [u64; 2], total_shards: u64) -> Result<()> {
-        self.start_connection([range[0], range[1], total_shards])
+        self.start_connection([range[0], range[1], total_shards], None)
     }
 
     // Shard data layout is:
@@ -968,7 +1298,11 @@ impl Client {
     //
     // [`ClientError::Shutdown`]: enum.ClientError.html#variant.Shutdown
     #[cfg(feature = "http")]
-    fn start_connection(&mut self, shard_data: [u64; 3]) -> Result<()> {
+    fn start_connection(
+        &mut self,
+        shard_data: [u64; 3],
+        session_start_limit: Option<SessionStartLimit>,
+    ) -> Result<()> {
         #[cfg(feature = "voice")]
         self.voice_manager.lock().set_shard_count(shard_data[2]);
 
@@ -979,9 +1313,27 @@ impl Client {
             self.voice_manager.lock().set_user_id(user.id);
         }
 
+        // The concurrency is only an optimisation, so don't fail to start if
+        // it can't be retrieved.
+        let session_start_limit = match session_start_limit {
+            Some(limit) => Some(limit),
+            None => match self.cache_and_http.http.get_bot_gateway() {
+                Ok(res) => Some(res.session_start_limit),
+                Err(why) => {
+                    warn!("Failed to retrieve the session start limit: {:?}", why);
+
+                    None
+                },
+            },
+        };
+
         {
             let mut manager = self.shard_manager.lock();
 
+            if let Some(ref limit) = session_start_limit {
+                manager.set_session_start_limit(limit);
+            }
+
             let init = shard_data[1] - shard_data[0] + 1;
 
             manager.set_shards(shard_data[0], init, shard_data[2]);
@@ -0,0 +1,253 @@
+//! Catching panics in handlers and commands, and reporting them to a hook.
+
+use log::error;
+use parking_lot::RwLock;
+use std::{
+    any::Any,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+use threadpool::ThreadPool;
+use typemap::{Key, ShareMap};
+use crate::model::event::EventType;
+use super::dispatch_order::Queue;
+
+/// What was running when a [`HandlerPanic`] happened.
+///
+/// [`HandlerPanic`]: struct.HandlerPanic.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PanicSource {
+    /// An event handler or raw event handler, handling an event of the type.
+    Event(EventType),
+    /// Middleware, processing an event of the type.
+    Middleware(EventType),
+    /// The [`EventHandler::shard_stage_update`] handler.
+    ///
+    /// [`EventHandler::shard_stage_update`]: trait.EventHandler.html#method.shard_stage_update
+    ShardStageUpdate,
+    /// A framework command, by its name.
+    Command(&'static str),
+    /// A future returned by an async handler or framework, by the name of the
+    /// method returning it, or a future it spawned.
+    Async(&'static str),
+    /// A task run by the [`Scheduler`], by its ID.
+    ///
+    /// [`Scheduler`]: struct.Scheduler.html
+    Task(u64),
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+/// A panic caught in an event handler, middleware, framework command or
+/// scheduled task, as
+/// given to the hook set via [`Client::set_panic_hook`].
+///
+/// [`Client::set_panic_hook`]: struct.Client.html#method.set_panic_hook
+pub struct HandlerPanic<'a> {
+    /// What was running when the panic happened.
+    pub source: PanicSource,
+    /// The ID of the shard which received the event, or 0 for a
+    /// [`PanicSource::Task`], as tasks do not belong to a shard.
+    ///
+    /// [`PanicSource::Task`]: enum.PanicSource.html#variant.Task
+    pub shard_id: u64,
+    /// The value the panic was started with, as given to
+    /// [`std::panic::catch_unwind`].
+    ///
+    /// [`std::panic::catch_unwind`]: https://doc.rust-lang.org/std/panic/fn.catch_unwind.html
+    pub payload: &'a (dyn Any + Send),
+}
+
+impl<'a> HandlerPanic<'a> {
+    /// The message the panic was started with, if it was started with a
+    /// string, as `panic!` does.
+    pub fn message(&self) -> Option<&'a str> {
+        panic_message(self.payload)
+    }
+}
+
+impl<'a> Debug for HandlerPanic<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("HandlerPanic")
+            .field("source", &self.source)
+            .field("shard_id", &self.shard_id)
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+/// The message of a panic, if it was started with a string.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        Some(message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        Some(message)
+    } else {
+        None
+    }
+}
+
+pub(crate) type PanicHook = dyn Fn(&HandlerPanic<'_>) + Send + Sync;
+
+/// The key a client's panic hook is kept under in its data.
+pub(crate) struct PanicHookKey;
+
+impl Key for PanicHookKey {
+    type Value = Arc<PanicHook>;
+}
+
+/// Gives a caught panic to the client's panic hook, or logs it if there is
+/// none.
+pub(crate) fn report(data: &RwLock<ShareMap>, panic: &HandlerPanic<'_>) {
+    // Cloned so that the hook may use the data.
+    let hook = data.read().get::<PanicHookKey>().cloned();
+
+    match hook {
+        Some(hook) => {
+            if panic::catch_unwind(AssertUnwindSafe(|| hook(panic))).is_err() {
+                error!("[panic] Panic hook panicked reporting {:?}", panic);
+            }
+        },
+        None => error!(
+            "[panic] {:?} panicked on shard {}: {}",
+            panic.source,
+            panic.shard_id,
+            panic.message().unwrap_or("Box<Any>"),
+        ),
+    }
+}
+
+/// Runs a handler, reporting rather than propagating a panic.
+///
+/// Each of the panics resumed together by [`Panics::resume`] is reported on
+/// its own.
+///
+/// [`Panics::resume`]: struct.Panics.html#method.resume
+pub(crate) fn catch(data: &RwLock<ShareMap>, shard_id: u64, source: PanicSource, f: impl FnOnce()) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+        let payloads = match payload.downcast::<Panics>() {
+            Ok(panics) => panics.payloads,
+            Err(payload) => vec![payload],
+        };
+
+        for payload in payloads {
+            report(data, &HandlerPanic {
+                source: source.clone(),
+                shard_id,
+                payload: &*payload,
+            });
+        }
+    }
+}
+
+/// Isolates handlers called in turn from each other's panics, so that the
+/// handlers after a panicking one are still called.
+#[derive(Default)]
+pub(crate) struct Panics {
+    payloads: Vec<Box<dyn Any + Send>>,
+}
+
+impl Panics {
+    pub(crate) fn isolate(&mut self, f: impl FnOnce()) {
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+            self.payloads.push(payload);
+        }
+    }
+
+    /// Resumes the caught panics once all handlers were called, as one panic
+    /// for [`catch`] to report each of.
+    ///
+    /// [`catch`]: fn.catch.html
+    pub(crate) fn resume(mut self) {
+        match self.payloads.len() {
+            0 => {},
+            1 => panic::resume_unwind(self.payloads.remove(0)),
+            _ => panic::resume_unwind(Box::new(self)),
+        }
+    }
+}
+
+/// Runs handlers on a threadpool, reporting rather than propagating their
+/// panics.
+///
+/// A panicking job would otherwise only be printed to stderr, and take its
+/// thread down with it.
+pub(crate) struct GuardedPool<'a> {
+    threadpool: &'a ThreadPool,
+    queue: Option<Queue<'a>>,
+    data: &'a Arc<RwLock<ShareMap>>,
+    shard_id: u64,
+    source: PanicSource,
+}
+
+impl<'a> GuardedPool<'a> {
+    pub(crate) fn new(
+        threadpool: &'a ThreadPool,
+        data: &'a Arc<RwLock<ShareMap>>,
+        shard_id: u64,
+        source: PanicSource,
+    ) -> Self {
+        GuardedPool {
+            threadpool,
+            queue: None,
+            data,
+            shard_id,
+            source,
+        }
+    }
+
+    /// Runs the jobs in the queue, if any, after those of earlier related
+    /// events.
+    pub(crate) fn ordered(mut self, queue: Option<Queue<'a>>) -> Self {
+        self.queue = queue;
+
+        self
+    }
+
+    pub(crate) fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        let data = Arc::clone(self.data);
+        let shard_id = self.shard_id;
+        let source = self.source.clone();
+        let job = move || catch(&data, shard_id, source, job);
+
+        match self.queue {
+            Some(ref queue) => queue.execute(job),
+            None => self.threadpool.execute(job),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use parking_lot::{Mutex, RwLock};
+    use std::sync::Arc;
+    use threadpool::ThreadPool;
+    use typemap::ShareMap;
+    use super::{GuardedPool, HandlerPanic, PanicHookKey, PanicSource};
+    use crate::model::event::EventType;
+
+    #[test]
+    fn reports_panics_to_the_hook() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let data = Arc::new(RwLock::new(ShareMap::custom()));
+        let threadpool = ThreadPool::new(1);
+
+        let hook_reports = Arc::clone(&reports);
+        data.write().insert::<PanicHookKey>(Arc::new(move |panic: &HandlerPanic<'_>| {
+            hook_reports.lock().push((panic.source.clone(), panic.shard_id, panic.message().map(String::from)));
+        }));
+
+        let pool = GuardedPool::new(&threadpool, &data, 3, PanicSource::Event(EventType::TypingStart));
+        pool.execute(|| panic!("expected panic"));
+        pool.execute(|| std::panic::resume_unwind(Box::new(1u8)));
+        pool.execute(|| {});
+        threadpool.join();
+
+        assert_eq!(*reports.lock(), vec![
+            (PanicSource::Event(EventType::TypingStart), 3, Some("expected panic".to_string())),
+            (PanicSource::Event(EventType::TypingStart), 3, None),
+        ]);
+        assert_eq!(threadpool.panic_count(), 0);
+    }
+}
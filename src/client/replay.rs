@@ -0,0 +1,298 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use threadpool::ThreadPool;
+use typemap::ShareMap;
+use log::warn;
+
+use crate::gateway::{InterMessage, RecordedPayload};
+use crate::internal::prelude::*;
+use crate::model::event::{Event, GatewayEvent};
+use crate::CacheAndHttp;
+use super::collector::Collectors;
+use super::dispatch_order::{DispatchOrder, DispatchQueues};
+use super::dispatch::{dispatch, DispatchEvent};
+use super::{DummyRawEventHandler, EventHandler, EventPipeline, RawEventHandler};
+
+#[cfg(feature = "framework")]
+use crate::framework::Framework;
+#[cfg(feature = "framework")]
+use parking_lot::Mutex;
+
+type Dispatcher = dyn Fn(&Replayer, Event, u64) + Send + Sync;
+
+/// Replays gateway traffic recorded by a [`TrafficRecorder`] through event
+/// handlers, without connecting to Discord.
+///
+/// Each recorded dispatch is deserialized, applied to the cache and passed to
+/// the handlers the same way a [`Client`] does when receiving it from the
+/// gateway. This allows reproducing issues such as cache inconsistencies from
+/// traffic recorded in production.
+///
+/// Handlers receive a [`Context`] as usual, although the shard it refers to is
+/// not running, and HTTP requests are made without a token.
+///
+/// # Examples
+///
+/// Replay a log at twice the speed it was recorded at, then inspect the
+/// resulting cache:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # struct Handler;
+/// #
+/// # impl EventHandler for Handler {}
+/// #
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::client::Replayer;
+/// use serenity::gateway::TrafficLog;
+///
+/// let mut replayer = Replayer::new(Handler);
+/// replayer.set_speed(2.0);
+///
+/// let dispatched = replayer.replay(TrafficLog::open("traffic.log.gz")?)?;
+///
+/// # #[cfg(feature = "cache")]
+/// println!(
+///     "Dispatched {} events, ending up with {} guilds",
+///     dispatched,
+///     replayer.cache_and_http.cache.read().guilds.len(),
+/// );
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`Client`]: struct.Client.html
+/// [`Context`]: struct.Context.html
+/// [`TrafficRecorder`]: ../gateway/struct.TrafficRecorder.html
+pub struct Replayer {
+    /// A ShareMap which requires types to be Send + Sync, passed to handlers
+    /// via their [`Context`], as with [`Client::data`].
+    ///
+    /// [`Client::data`]: struct.Client.html#structfield.data
+    /// [`Context`]: struct.Context.html
+    pub data: Arc<RwLock<ShareMap>>,
+    /// The cache updated by replayed events, along with an HTTP client without
+    /// a token.
+    pub cache_and_http: Arc<CacheAndHttp>,
+    dispatcher: Box<Dispatcher>,
+    #[cfg(feature = "framework")]
+    framework: Arc<Mutex<Option<Box<dyn Framework + Send>>>>,
+    runner_tx: Sender<InterMessage>,
+    // Kept so that messages sent to the shard via a `Context` are discarded
+    // rather than failing.
+    _runner_rx: Receiver<InterMessage>,
+    speed: f64,
+    threadpool: ThreadPool,
+}
+
+impl Replayer {
+    /// Creates a replayer dispatching events to the given handler.
+    pub fn new<H>(handler: H) -> Self
+        where H: EventHandler + Send + Sync + 'static {
+
+        Self::new_with_handlers(Some(handler), None::<DummyRawEventHandler>)
+    }
+
+    /// Creates a replayer with optional handlers, as with
+    /// [`Client::new_with_handlers`].
+    ///
+    /// As with a client, the cache is only updated when an [`EventHandler`] is
+    /// given.
+    ///
+    /// [`Client::new_with_handlers`]: struct.Client.html#method.new_with_handlers
+    /// [`EventHandler`]: trait.EventHandler.html
+    pub fn new_with_handlers<H, RH>(handler: Option<H>, raw_handler: Option<RH>) -> Self
+        where H: EventHandler + Send + Sync + 'static,
+              RH: RawEventHandler + Send + Sync + 'static {
+        let event_handler = handler.map(Arc::new);
+        let raw_event_handler = raw_handler.map(Arc::new);
+        let pipeline = EventPipeline::new();
+        let collectors = Collectors::default();
+        let threadpool = ThreadPool::with_name("serenity replayer".to_owned(), 5);
+        let queues = DispatchQueues::new(DispatchOrder::default(), threadpool.clone());
+        let (runner_tx, runner_rx) = mpsc::channel();
+
+        let dispatcher = move |replayer: &Replayer, event: Event, shard_id: u64| {
+            dispatch(
+                DispatchEvent::Model(event),
+                &pipeline,
+                &collectors,
+                &queues,
+                #[cfg(feature = "framework")]
+                &replayer.framework,
+                &replayer.data,
+                &event_handler,
+                &raw_event_handler,
+                &replayer.runner_tx,
+                &replayer.threadpool,
+                shard_id,
+                Arc::clone(&replayer.cache_and_http),
+            );
+        };
+
+        Replayer {
+            data: Arc::new(RwLock::new(ShareMap::custom())),
+            cache_and_http: Arc::new(CacheAndHttp::default()),
+            dispatcher: Box::new(dispatcher),
+            #[cfg(feature = "framework")]
+            framework: Arc::new(Mutex::new(None)),
+            runner_tx,
+            _runner_rx: runner_rx,
+            speed: std::f64::INFINITY,
+            threadpool,
+        }
+    }
+
+    /// Sets the speed to replay at, relative to the speed the traffic was
+    /// recorded at; a speed of `2.0` waits half as long between payloads.
+    ///
+    /// By default, payloads are replayed as fast as possible, which is also
+    /// the case for a speed that is infinite, zero or negative.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+    }
+
+    /// Replays the given payloads, in order, waiting for the handlers to
+    /// finish before returning.
+    ///
+    /// Payloads other than dispatches, such as heartbeat acknowledgements, are
+    /// skipped, as are payloads that fail to deserialize.
+    ///
+    /// Returns the number of events dispatched.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from the payloads, such as when reading the
+    /// [`TrafficLog`] fails.
+    ///
+    /// [`TrafficLog`]: ../gateway/struct.TrafficLog.html
+    pub fn replay<I>(&self, payloads: I) -> Result<u64>
+        where I: IntoIterator<Item = Result<RecordedPayload>> {
+        let started = Instant::now();
+        let mut first = None;
+        let mut dispatched = 0;
+
+        for payload in payloads {
+            let payload = payload?;
+            let first = *first.get_or_insert(payload.timestamp);
+
+            self.wait(started, payload.timestamp.saturating_sub(first));
+
+            match GatewayEvent::deserialize(payload.payload) {
+                Ok(GatewayEvent::Dispatch(_, event)) => {
+                    (self.dispatcher)(self, event, payload.shard_id);
+                    dispatched += 1;
+                },
+                Ok(_) => {},
+                Err(why) => warn!("[Replayer] Error deserializing payload: {:?}", why),
+            }
+        }
+
+        self.threadpool.join();
+
+        Ok(dispatched)
+    }
+
+    // Sleeps until the payload recorded `offset` milliseconds after the first
+    // one is due.
+    fn wait(&self, started: Instant, offset: u64) {
+        if !self.speed.is_finite() || self.speed <= 0.0 {
+            return;
+        }
+
+        let nanos = offset as f64 / self.speed * 1_000_000.0;
+        let due = started + Duration::from_nanos(nanos as u64);
+        let now = Instant::now();
+
+        if due > now {
+            thread::sleep(due - now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::mpsc::{self, Sender},
+        time::{Duration, Instant},
+    };
+    use parking_lot::Mutex;
+    use serde_json::{json, Value};
+    use crate::client::{Context, EventHandler};
+    use crate::gateway::RecordedPayload;
+    use crate::model::channel::Message;
+    use super::Replayer;
+
+    struct Handler(Mutex<Sender<String>>);
+
+    impl EventHandler for Handler {
+        fn message(&self, _: Context, message: Message) {
+            let _ = self.0.lock().send(message.content);
+        }
+    }
+
+    fn payload(timestamp: u64, payload: Value) -> RecordedPayload {
+        RecordedPayload {
+            timestamp,
+            shard_id: 0,
+            payload,
+        }
+    }
+
+    fn dispatch(kind: &str, data: &str) -> Value {
+        json!({
+            "op": 0,
+            "s": 1,
+            "t": kind,
+            "d": serde_json::from_str::<Value>(data).unwrap(),
+        })
+    }
+
+    #[test]
+    fn replays_dispatches_through_handlers() {
+        let (tx, rx) = mpsc::channel();
+        let replayer = Replayer::new(Handler(Mutex::new(tx)));
+
+        let payloads = vec![
+            payload(0, json!({"op": 10, "d": {"heartbeat_interval": 41250}})),
+            payload(1, dispatch("GUILD_CREATE", include_str!("../../tests/resources/guild_create_1.json"))),
+            payload(2, dispatch("MESSAGE_CREATE", include_str!("../../tests/resources/message_create_1.json"))),
+            payload(3, json!({"op": 11})),
+        ];
+
+        assert_eq!(replayer.replay(payloads.into_iter().map(Ok)).unwrap(), 2);
+        assert_eq!(rx.try_recv(), Ok("a".to_string()));
+
+        #[cfg(feature = "cache")]
+        assert_eq!(replayer.cache_and_http.cache.read().guilds.len(), 1);
+    }
+
+    #[test]
+    fn replays_at_the_given_speed() {
+        let (tx, _rx) = mpsc::channel();
+        let mut replayer = Replayer::new(Handler(Mutex::new(tx)));
+        replayer.set_speed(2.0);
+
+        let payloads = vec![
+            payload(1_000, json!({"op": 11})),
+            payload(1_400, json!({"op": 11})),
+        ];
+
+        let started = Instant::now();
+        replayer.replay(payloads.into_iter().map(Ok)).unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+}
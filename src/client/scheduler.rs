@@ -0,0 +1,1263 @@
+//! Running tasks once at a given time, or repeatedly at an interval or on a
+//! cron schedule, with access to the client's HTTP client, cache, data and
+//! shards.
+//!
+//! Refer to [`Scheduler`] for how to schedule tasks, and to [`TaskStore`] for
+//! keeping them across restarts.
+//!
+//! [`Scheduler`]: struct.Scheduler.html
+//! [`TaskStore`]: trait.TaskStore.html
+
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use log::warn;
+use parking_lot::{Condvar, Mutex, MutexGuard, RwLock};
+use serde::{
+    de::{Deserializer, Error as DeError},
+    ser::Serializer,
+    Deserialize,
+    Serialize,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Formatter, Result as FmtResult},
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::Duration as StdDuration,
+};
+use thiserror::Error;
+use threadpool::ThreadPool;
+use typemap::ShareMap;
+use crate::client::bridge::gateway::{ShardId, ShardMessenger, ShardRunnerInfo};
+use crate::http::Http;
+use crate::internal::{files, prelude::*};
+use crate::CacheAndHttp;
+use super::panic::{self, PanicSource};
+
+#[cfg(feature = "cache")]
+use crate::cache::CacheRwLock;
+
+/// An error returned when scheduling a task.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Error)]
+pub enum SchedulerError {
+    /// The cron expression could not be parsed, or never matches a time.
+    #[error("Invalid cron expression: {0:?}")]
+    InvalidCron(String),
+
+    /// No handler was registered for the kind of a stored task.
+    #[error("No handler is registered for tasks of kind {0:?}")]
+    UnknownKind(String),
+
+    #[doc(hidden)]
+    #[error("unreachable")]
+    __Nonexhaustive,
+}
+
+/// A cron schedule, matching the times at which a recurring task runs.
+///
+/// Expressions have five fields separated by whitespace: the minute (`0-59`),
+/// hour (`0-23`), day of the month (`1-31`), month (`1-12`) and day of the
+/// week (`0-7`, where both `0` and `7` are Sunday). Each field is either `*`,
+/// a number, a range such as `1-5`, any of these followed by a step such as
+/// `*/15`, or a comma-separated list of them.
+///
+/// As in cron, if both the day of the month and the day of the week are
+/// restricted, a day matching either of them matches. Times are in UTC.
+///
+/// # Examples
+///
+/// Every 15 minutes during working hours:
+///
+/// ```rust
+/// use serenity::client::Cron;
+///
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// let cron = Cron::parse("*/15 9-17 * * 1-5")?;
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// Parses a cron expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SchedulerError::InvalidCron`] if the expression is not
+    /// valid, or if no time ever matches it, such as `0 0 30 2 *`.
+    ///
+    /// [`SchedulerError::InvalidCron`]: enum.SchedulerError.html#variant.InvalidCron
+    pub fn parse(expression: &str) -> Result<Self> {
+        let invalid = || Error::from(SchedulerError::InvalidCron(expression.to_string()));
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+
+        if fields.len() != 5 {
+            return Err(invalid());
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7).ok_or_else(invalid)?;
+
+        // Sunday may be given as either 0 or 7.
+        if weekdays & 1 << 7 != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        let cron = Cron {
+            expression: expression.to_string(),
+            minutes: parse_field(fields[0], 0, 59).ok_or_else(invalid)?,
+            hours: parse_field(fields[1], 0, 23).ok_or_else(invalid)?,
+            days: parse_field(fields[2], 1, 31).ok_or_else(invalid)?,
+            months: parse_field(fields[3], 1, 12).ok_or_else(invalid)?,
+            weekdays,
+            any_day: fields[2].starts_with('*'),
+            any_weekday: fields[4].starts_with('*'),
+        };
+
+        // Every day of every month comes around within a leap cycle, so an
+        // expression without a match in the next one has none at all.
+        match cron.next_after(Utc::now()) {
+            Some(_) => Ok(cron),
+            None => Err(invalid()),
+        }
+    }
+
+    /// The expression the schedule was parsed from.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// The first matching time after the given one, if any is within the next
+    /// eight years.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let limit = after + Duration::days(8 * 366);
+        let mut time = after
+            - Duration::seconds(i64::from(after.second()))
+            - Duration::nanoseconds(i64::from(after.nanosecond()))
+            + Duration::minutes(1);
+
+        while time < limit {
+            if !has(self.months, time.month()) {
+                time = start_of_day(time) + Duration::days(1);
+
+                while time.day() != 1 {
+                    time += Duration::days(1);
+                }
+            } else if !self.matches_day(time) {
+                time = start_of_day(time) + Duration::days(1);
+            } else if !has(self.hours, time.hour()) {
+                time = time - Duration::minutes(i64::from(time.minute())) + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day = has(self.days, time.day());
+        let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+impl Serialize for Cron {
+    fn serialize<S: Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        serializer.serialize_str(&self.expression)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cron {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        let expression = String::deserialize(deserializer)?;
+
+        Cron::parse(&expression).map_err(DeError::custom)
+    }
+}
+
+// Parses one field of a cron expression into a bitmask of the values it
+// matches.
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut bits = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.find('/') {
+            Some(index) => (&part[..index], Some(part[index + 1..].parse::<usize>().ok().filter(|&step| step > 0)?)),
+            None => (part, None),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some(index) = range.find('-') {
+            (range[..index].parse().ok()?, range[index + 1..].parse().ok()?)
+        } else {
+            let value = range.parse().ok()?;
+
+            // A single value with a step, such as `5/15`, runs to the maximum.
+            (value, if step.is_some() { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return None;
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1)) {
+            bits |= 1 << value;
+        }
+    }
+
+    Some(bits)
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & 1 << value != 0
+}
+
+fn start_of_day(time: DateTime<Utc>) -> DateTime<Utc> {
+    time - Duration::hours(i64::from(time.hour())) - Duration::minutes(i64::from(time.minute()))
+}
+
+fn add(time: DateTime<Utc>, interval: StdDuration) -> Option<DateTime<Utc>> {
+    time.checked_add_signed(Duration::from_std(interval).ok()?)
+}
+
+/// When a task runs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Schedule {
+    /// Once, at the given time. A time in the past runs the task right away.
+    Once(DateTime<Utc>),
+    /// Repeatedly, first after the interval and then every interval after
+    /// that. A zero interval runs the task only once.
+    Every(StdDuration),
+    /// Repeatedly, at the times matching the cron schedule.
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// Once, after the given delay.
+    pub fn after(delay: StdDuration) -> Self {
+        let now = Utc::now();
+
+        Schedule::Once(add(now, delay).unwrap_or(now))
+    }
+
+    /// Once, at the given time.
+    pub fn at(time: DateTime<Utc>) -> Self {
+        Schedule::Once(time)
+    }
+
+    /// Repeatedly, every interval.
+    pub fn every(interval: StdDuration) -> Self {
+        Schedule::Every(interval)
+    }
+
+    /// Repeatedly, on the cron schedule. Refer to [`Cron`] for the syntax.
+    ///
+    /// [`Cron`]: struct.Cron.html
+    pub fn cron(expression: &str) -> Result<Self> {
+        Cron::parse(expression).map(Schedule::Cron)
+    }
+
+    // The time at which a task scheduled now first runs.
+    fn first(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match *self {
+            Schedule::Once(time) => Some(time),
+            Schedule::Every(interval) => add(now, interval),
+            Schedule::Cron(ref cron) => cron.next_after(now),
+        }
+    }
+
+    // The time at which a task which was due at the given time runs next.
+    //
+    // Runs missed while the process was down are not caught up on; an
+    // overdue recurring task runs once, and then on its schedule again.
+    fn following(&self, due: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match *self {
+            Schedule::Once(_) => None,
+            Schedule::Every(interval) if interval == StdDuration::from_secs(0) => None,
+            Schedule::Every(interval) => match add(due, interval) {
+                Some(next) if next > now => Some(next),
+                _ => add(now, interval),
+            },
+            Schedule::Cron(ref cron) => cron.next_after(now),
+        }
+    }
+}
+
+/// The handle given to scheduled tasks, like the [`Context`] given to event
+/// handlers.
+///
+/// [`Context`]: struct.Context.html
+#[derive(Clone)]
+pub struct TaskContext {
+    /// A clone of [`Client::data`].
+    ///
+    /// [`Client::data`]: struct.Client.html#structfield.data
+    pub data: Arc<RwLock<ShareMap>>,
+    #[cfg(feature = "cache")]
+    pub cache: CacheRwLock,
+    pub http: Arc<Http>,
+    runners: Arc<Mutex<HashMap<ShardId, ShardRunnerInfo>>>,
+}
+
+impl TaskContext {
+    pub(crate) fn new(
+        data: &Arc<RwLock<ShareMap>>,
+        cache_and_http: &CacheAndHttp,
+        runners: &Arc<Mutex<HashMap<ShardId, ShardRunnerInfo>>>,
+    ) -> Self {
+        TaskContext {
+            data: Arc::clone(data),
+            #[cfg(feature = "cache")]
+            cache: Arc::clone(&cache_and_http.cache).into(),
+            http: Arc::clone(&cache_and_http.http),
+            runners: Arc::clone(runners),
+        }
+    }
+
+    /// The messenger of the shard with the given ID, if the shard is running.
+    pub fn shard(&self, shard_id: u64) -> Option<ShardMessenger> {
+        self.runners
+            .lock()
+            .get(&ShardId(shard_id))
+            .map(|runner| ShardMessenger::new(runner.runner_tx.clone()))
+    }
+
+    /// The IDs of the shards which are running.
+    pub fn shard_ids(&self) -> Vec<u64> {
+        let mut ids = self.runners.lock().keys().map(|id| id.0).collect::<Vec<_>>();
+        ids.sort();
+
+        ids
+    }
+}
+
+impl AsRef<Http> for TaskContext {
+    fn as_ref(&self) -> &Http { &self.http }
+}
+
+#[cfg(feature = "cache")]
+impl AsRef<CacheRwLock> for TaskContext {
+    fn as_ref(&self) -> &CacheRwLock {
+        &self.cache
+    }
+}
+
+/// A task kept in a [`TaskStore`].
+///
+/// [`TaskStore`]: trait.TaskStore.html
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StoredTask {
+    /// The ID of the task.
+    pub id: u64,
+    /// The kind of the task, naming the handler registered via
+    /// [`Scheduler::register`] which runs it.
+    ///
+    /// [`Scheduler::register`]: struct.Scheduler.html#method.register
+    pub kind: String,
+    /// The value given to the handler.
+    pub payload: Value,
+    /// When the task runs.
+    pub schedule: Schedule,
+    /// When the task runs next.
+    pub due: DateTime<Utc>,
+}
+
+/// A store for tasks scheduled via [`Scheduler::schedule_stored`], allowing
+/// them to survive a restart.
+///
+/// A task is saved when it is scheduled and after each run, and removed once
+/// it is cancelled or has run for the last time. Tasks which became due while
+/// the process was down run as soon as the store is set and a handler for
+/// their kind is registered.
+///
+/// Refer to [`FileTaskStore`] for an implementation persisting tasks to disk.
+///
+/// [`FileTaskStore`]: struct.FileTaskStore.html
+/// [`Scheduler::schedule_stored`]: struct.Scheduler.html#method.schedule_stored
+pub trait TaskStore: Send + Sync {
+    /// Loads all stored tasks.
+    fn load(&self) -> Result<Vec<StoredTask>>;
+
+    /// Saves a task, replacing any previous one with the same ID.
+    fn save(&self, task: &StoredTask) -> Result<()>;
+
+    /// Removes the task with the given ID, if there is one.
+    fn remove(&self, id: u64) -> Result<()>;
+}
+
+impl Debug for dyn TaskStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("TaskStore")
+    }
+}
+
+/// A [`TaskStore`] keeping one JSON file per task in a directory.
+///
+/// [`TaskStore`]: trait.TaskStore.html
+#[derive(Clone, Debug)]
+pub struct FileTaskStore {
+    dir: PathBuf,
+}
+
+impl FileTaskStore {
+    /// Creates a store keeping tasks in the given directory, which is created
+    /// when the first task is saved.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileTaskStore {
+            dir: dir.into(),
+        }
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("task_{}.json", id))
+    }
+}
+
+impl TaskStore for FileTaskStore {
+    fn load(&self) -> Result<Vec<StoredTask>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(ref why) if why.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(why) => return Err(why.into()),
+        };
+
+        let mut tasks = Vec::new();
+
+        for entry in entries {
+            let path = entry?.path();
+
+            // Skips temporary files left behind by an interrupted save.
+            if path.extension().map_or(false, |extension| extension == "json") {
+                tasks.push(serde_json::from_slice(&fs::read(path)?)?);
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    fn save(&self, task: &StoredTask) -> Result<()> {
+        files::write_json(&self.path(task.id), task)
+    }
+
+    fn remove(&self, id: u64) -> Result<()> {
+        files::remove(&self.path(id))
+    }
+}
+
+type TaskFn = dyn Fn(&TaskContext) + Send + Sync;
+type TaskHandler = dyn Fn(&TaskContext, Value) + Send + Sync;
+
+#[derive(Clone)]
+enum Job {
+    Closure(Arc<TaskFn>),
+    Stored {
+        kind: String,
+        payload: Value,
+    },
+}
+
+struct Task {
+    schedule: Schedule,
+    due: DateTime<Utc>,
+    job: Job,
+    // Whether the task is stored and became due while no handler for its kind
+    // was registered, holding it back until one is.
+    awaiting_handler: bool,
+}
+
+impl Task {
+    fn stored(&self, id: u64) -> Option<StoredTask> {
+        match self.job {
+            Job::Closure(_) => None,
+            Job::Stored { ref kind, ref payload } => Some(StoredTask {
+                id,
+                kind: kind.clone(),
+                payload: payload.clone(),
+                schedule: self.schedule.clone(),
+                due: self.due,
+            }),
+        }
+    }
+}
+
+struct State {
+    tasks: HashMap<u64, Task>,
+    next_id: u64,
+    store: Option<Arc<dyn TaskStore>>,
+    shutdown: bool,
+}
+
+impl State {
+    fn save(&self, id: u64) {
+        if let (Some(store), Some(task)) = (&self.store, self.tasks.get(&id).and_then(|task| task.stored(id))) {
+            if let Err(why) = store.save(&task) {
+                warn!("[scheduler] Failed to save task {}: {:?}", id, why);
+            }
+        }
+    }
+
+    fn remove(&mut self, id: u64) -> Option<Task> {
+        let task = self.tasks.remove(&id)?;
+
+        if let (Some(store), Job::Stored { .. }) = (&self.store, &task.job) {
+            if let Err(why) = store.remove(id) {
+                warn!("[scheduler] Failed to remove task {}: {:?}", id, why);
+            }
+        }
+
+        Some(task)
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wakeup: Condvar,
+    context: TaskContext,
+    threadpool: ThreadPool,
+    handlers: RwLock<HashMap<String, Arc<TaskHandler>>>,
+}
+
+impl Shared {
+    fn run(&self) {
+        let mut state = self.state.lock();
+
+        while !state.shutdown {
+            let now = Utc::now();
+            let next = state.tasks
+                .iter()
+                .filter(|&(_, task)| !task.awaiting_handler)
+                .map(|(&id, task)| (task.due, id))
+                .min();
+
+            match next {
+                Some((due, id)) if due <= now => {
+                    if let Some(write) = self.fire(&mut state, id, now) {
+                        // Written without holding the lock, so that scheduling
+                        // and cancelling tasks does not wait on the store.
+                        MutexGuard::unlocked(&mut state, || write.apply());
+
+                        // A task cancelled meanwhile was removed from the
+                        // store before being saved again.
+                        if let Write::Save(ref store, _) = write {
+                            if !state.tasks.contains_key(&id) {
+                                Write::Remove(Arc::clone(store), id).apply();
+                            }
+                        }
+                    }
+                },
+                // Woken at least hourly so that changes to the system clock
+                // are noticed.
+                Some((due, _)) => {
+                    let timeout = (due - now).to_std().unwrap_or_else(|_| StdDuration::from_secs(0));
+                    self.wakeup.wait_for(&mut state, timeout.min(StdDuration::from_secs(3600)));
+                },
+                None => self.wakeup.wait(&mut state),
+            }
+        }
+    }
+
+    // Runs a due task, returning the change to the store to write once the
+    // lock is released.
+    fn fire(&self, state: &mut State, id: u64, now: DateTime<Utc>) -> Option<Write> {
+        let task = state.tasks.get_mut(&id)?;
+
+        // A stored task is kept as is, in the store as well, until a handler
+        // can run it.
+        let handler = match task.job {
+            Job::Closure(_) => None,
+            Job::Stored { ref kind, .. } => match self.handlers.read().get(kind) {
+                Some(handler) => Some(Arc::clone(handler)),
+                None => {
+                    warn!("[scheduler] No handler is registered for task {} of kind {:?}; holding it back", id, kind);
+                    task.awaiting_handler = true;
+
+                    return None;
+                },
+            },
+        };
+
+        let (job, write) = match task.schedule.following(task.due, now) {
+            Some(due) => {
+                task.due = due;
+                let write = match (&state.store, task.stored(id)) {
+                    (Some(store), Some(stored)) => Some(Write::Save(Arc::clone(store), stored)),
+                    _ => None,
+                };
+
+                (task.job.clone(), write)
+            },
+            None => {
+                let task = state.tasks.remove(&id).expect("task is scheduled");
+                let write = match (&state.store, &task.job) {
+                    (Some(store), Job::Stored { .. }) => Some(Write::Remove(Arc::clone(store), id)),
+                    _ => None,
+                };
+
+                (task.job, write)
+            },
+        };
+
+        let context = self.context.clone();
+
+        match (job, handler) {
+            (Job::Closure(f), _) => self.threadpool.execute(move || guard(&context, id, || f(&context))),
+            (Job::Stored { payload, .. }, Some(handler)) => {
+                self.threadpool.execute(move || guard(&context, id, || handler(&context, payload)));
+            },
+            (Job::Stored { .. }, None) => unreachable!("stored tasks only run with a handler"),
+        }
+
+        write
+    }
+}
+
+// A change to the store after a task ran.
+enum Write {
+    Save(Arc<dyn TaskStore>, StoredTask),
+    Remove(Arc<dyn TaskStore>, u64),
+}
+
+impl Write {
+    fn apply(&self) {
+        match *self {
+            Write::Save(ref store, ref task) => {
+                if let Err(why) = store.save(task) {
+                    warn!("[scheduler] Failed to save task {}: {:?}", task.id, why);
+                }
+            },
+            Write::Remove(ref store, id) => {
+                if let Err(why) = store.remove(id) {
+                    warn!("[scheduler] Failed to remove task {}: {:?}", id, why);
+                }
+            },
+        }
+    }
+}
+
+// Reports rather than propagates a task's panic, which would otherwise take
+// its thread of the threadpool down with it.
+fn guard(context: &TaskContext, id: u64, f: impl FnOnce()) {
+    panic::catch(&context.data, 0, PanicSource::Task(id), f);
+}
+
+// Stops the scheduler's thread once the last handle to it is dropped.
+struct Stop(Arc<Shared>);
+
+impl Drop for Stop {
+    fn drop(&mut self) {
+        self.0.state.lock().shutdown = true;
+        self.0.wakeup.notify_one();
+    }
+}
+
+/// Runs tasks once at a given time, or repeatedly at an interval or on a
+/// [`Cron`] schedule, on the client's threadpool.
+///
+/// Tasks are given a [`TaskContext`], with the client's HTTP client, cache,
+/// data and shards. A scheduler is available as [`Client::scheduler`], and can
+/// be cloned into handlers via [`Client::data`] to schedule tasks from them.
+///
+/// Closures scheduled via [`schedule`] only live as long as the process.
+/// Tasks which must survive a restart, such as reminders, are scheduled via
+/// [`schedule_stored`] with a handler [`register`]ed for their kind, and kept
+/// in the [`TaskStore`] given to [`set_store`].
+///
+/// # Examples
+///
+/// Remind a user in 10 minutes, even if the bot restarts in the meantime:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::EventHandler;
+/// # struct Handler;
+/// # impl EventHandler for Handler {}
+/// #
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::client::{Client, FileTaskStore, Schedule};
+/// use serenity::model::id::ChannelId;
+/// use std::time::Duration;
+///
+/// let client = Client::new("token", Handler)?;
+///
+/// client.scheduler.register("remind", |ctx, payload| {
+///     if let Some(channel_id) = payload["channel_id"].as_u64() {
+///         let _ = ChannelId(channel_id).say(ctx, "Time's up!");
+///     }
+/// });
+/// client.scheduler.set_store(FileTaskStore::new("tasks"))?;
+///
+/// let reminder = serde_json::json!({ "channel_id": 381880193700069377u64 });
+/// client.scheduler.schedule_stored("remind", Schedule::after(Duration::from_secs(600)), &reminder)?;
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`Client::data`]: struct.Client.html#structfield.data
+/// [`Client::scheduler`]: struct.Client.html#structfield.scheduler
+/// [`Cron`]: struct.Cron.html
+/// [`TaskContext`]: struct.TaskContext.html
+/// [`TaskStore`]: trait.TaskStore.html
+/// [`register`]: #method.register
+/// [`schedule`]: #method.schedule
+/// [`schedule_stored`]: #method.schedule_stored
+/// [`set_store`]: #method.set_store
+#[derive(Clone)]
+pub struct Scheduler {
+    shared: Arc<Shared>,
+    _stop: Arc<Stop>,
+}
+
+impl Scheduler {
+    pub(crate) fn new(context: TaskContext, threadpool: ThreadPool) -> Result<Self> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                tasks: HashMap::new(),
+                next_id: 1,
+                store: None,
+                shutdown: false,
+            }),
+            wakeup: Condvar::new(),
+            context,
+            threadpool,
+            handlers: RwLock::new(HashMap::new()),
+        });
+
+        let runner = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("serenity scheduler".to_string())
+            .spawn(move || runner.run())?;
+
+        Ok(Scheduler {
+            _stop: Arc::new(Stop(Arc::clone(&shared))),
+            shared,
+        })
+    }
+
+    /// Schedules a closure to run, until it is cancelled or has run for the
+    /// last time.
+    pub fn schedule<F>(&self, schedule: Schedule, job: F) -> TaskHandle
+        where F: Fn(&TaskContext) + Send + Sync + 'static {
+        let mut state = self.shared.state.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+
+        if let Some(due) = schedule.first(Utc::now()) {
+            self.insert(&mut state, id, Task {
+                schedule,
+                due,
+                job: Job::Closure(Arc::new(job)),
+                awaiting_handler: false,
+            });
+        }
+
+        self.handle(id)
+    }
+
+    /// Registers the handler running stored tasks of the given kind, with
+    /// their payload.
+    ///
+    /// Stored tasks of the kind which became due before their handler was
+    /// registered, such as while the process was down, are held back until
+    /// it is, and then run right away.
+    pub fn register<F>(&self, kind: impl Into<String>, handler: F)
+        where F: Fn(&TaskContext, Value) + Send + Sync + 'static {
+        let kind = kind.into();
+        self.shared.handlers.write().insert(kind.clone(), Arc::new(handler));
+
+        let mut state = self.shared.state.lock();
+
+        for task in state.tasks.values_mut() {
+            if let Job::Stored { kind: ref task_kind, .. } = task.job {
+                if *task_kind == kind {
+                    task.awaiting_handler = false;
+                }
+            }
+        }
+
+        self.shared.wakeup.notify_one();
+    }
+
+    /// Schedules a task of the given kind to run with the payload, keeping it
+    /// in the store so that it survives a restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SchedulerError::UnknownKind`] if no handler is registered
+    /// for the kind, or an error if the payload could not be serialized or
+    /// the task could not be saved.
+    ///
+    /// [`SchedulerError::UnknownKind`]: enum.SchedulerError.html#variant.UnknownKind
+    pub fn schedule_stored<T>(&self, kind: &str, schedule: Schedule, payload: &T) -> Result<TaskHandle>
+        where T: Serialize + ?Sized {
+        if !self.shared.handlers.read().contains_key(kind) {
+            return Err(SchedulerError::UnknownKind(kind.to_string()).into());
+        }
+
+        let payload = serde_json::to_value(payload)?;
+        let mut state = self.shared.state.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+
+        if let Some(due) = schedule.first(Utc::now()) {
+            let task = Task {
+                schedule,
+                due,
+                job: Job::Stored {
+                    kind: kind.to_string(),
+                    payload,
+                },
+                awaiting_handler: false,
+            };
+
+            if let (Some(store), Some(stored)) = (&state.store, task.stored(id)) {
+                store.save(&stored)?;
+            }
+
+            self.insert(&mut state, id, task);
+        }
+
+        Ok(self.handle(id))
+    }
+
+    /// Sets the store tasks scheduled via [`schedule_stored`] are kept in,
+    /// and schedules the tasks in it.
+    ///
+    /// Stored tasks scheduled before the store was set are saved to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tasks could not be loaded.
+    ///
+    /// [`schedule_stored`]: #method.schedule_stored
+    pub fn set_store<S: TaskStore + 'static>(&self, store: S) -> Result<()> {
+        let store: Arc<dyn TaskStore> = Arc::new(store);
+        let loaded = store.load()?;
+        let mut state = self.shared.state.lock();
+        state.store = Some(Arc::clone(&store));
+
+        let scheduled = state.tasks.keys().cloned().collect::<Vec<_>>();
+        let next_id = loaded.iter().map(|task| task.id + 1).max().unwrap_or(1);
+        state.next_id = state.next_id.max(next_id);
+
+        let mut renumbered = HashSet::new();
+
+        for task in loaded {
+            let mut id = task.id;
+
+            // Renumbers a stored task whose ID was given to a task scheduled
+            // before the store was set, saving it under its new ID before its
+            // old file is replaced or removed below.
+            if state.tasks.contains_key(&id) {
+                renumbered.insert(id);
+                id = state.next_id;
+                state.next_id += 1;
+            }
+
+            self.insert(&mut state, id, Task {
+                schedule: task.schedule,
+                due: task.due,
+                job: Job::Stored {
+                    kind: task.kind,
+                    payload: task.payload,
+                },
+                awaiting_handler: false,
+            });
+
+            if id != task.id {
+                if let Some(stored) = state.tasks[&id].stored(id) {
+                    store.save(&stored)?;
+                }
+            }
+        }
+
+        for id in scheduled {
+            if state.tasks[&id].stored(id).is_some() {
+                state.save(id);
+            } else if renumbered.contains(&id) {
+                // The file still holds the renumbered task.
+                store.remove(id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Cancels the task with the given ID, removing it from the store.
+    ///
+    /// Returns whether the task was scheduled; a task which already ran for
+    /// the last time is not.
+    pub fn cancel(&self, id: u64) -> bool {
+        let cancelled = self.shared.state.lock().remove(id).is_some();
+        self.shared.wakeup.notify_one();
+
+        cancelled
+    }
+
+    /// When the task with the given ID runs next, if it is scheduled.
+    pub fn next_run(&self, id: u64) -> Option<DateTime<Utc>> {
+        self.shared.state.lock().tasks.get(&id).map(|task| task.due)
+    }
+
+    fn insert(&self, state: &mut State, id: u64, task: Task) {
+        state.tasks.insert(id, task);
+        self.shared.wakeup.notify_one();
+    }
+
+    fn handle(&self, id: u64) -> TaskHandle {
+        TaskHandle {
+            id,
+            scheduler: self.clone(),
+        }
+    }
+}
+
+impl Debug for Scheduler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let state = self.shared.state.lock();
+
+        f.debug_struct("Scheduler")
+            .field("tasks", &state.tasks.len())
+            .field("store", &state.store)
+            .finish()
+    }
+}
+
+/// A handle to a scheduled task, returned when scheduling it.
+///
+/// Dropping the handle does not cancel the task.
+#[derive(Clone, Debug)]
+pub struct TaskHandle {
+    id: u64,
+    scheduler: Scheduler,
+}
+
+impl TaskHandle {
+    /// The ID of the task, which can be given to [`Scheduler::cancel`] in
+    /// place of the handle.
+    ///
+    /// [`Scheduler::cancel`]: struct.Scheduler.html#method.cancel
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Cancels the task. Refer to [`Scheduler::cancel`] for more information.
+    ///
+    /// [`Scheduler::cancel`]: struct.Scheduler.html#method.cancel
+    pub fn cancel(&self) -> bool {
+        self.scheduler.cancel(self.id)
+    }
+
+    /// When the task runs next, if it is scheduled.
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        self.scheduler.next_run(self.id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Duration, Utc};
+    use parking_lot::{Mutex, RwLock};
+    use std::{
+        collections::HashMap,
+        env,
+        fs,
+        sync::{mpsc, Arc},
+        thread,
+        time::{Duration as StdDuration, Instant},
+    };
+    use threadpool::ThreadPool;
+    use typemap::ShareMap;
+    use super::{Cron, FileTaskStore, Schedule, Scheduler, StoredTask, TaskContext, TaskStore};
+    use crate::client::panic::{HandlerPanic, PanicHookKey, PanicSource};
+    use crate::http::Http;
+    use crate::internal::prelude::*;
+
+    fn scheduler() -> Scheduler {
+        scheduler_with(Arc::new(RwLock::new(ShareMap::custom())))
+    }
+
+    fn scheduler_with(data: Arc<RwLock<ShareMap>>) -> Scheduler {
+        let context = TaskContext {
+            data,
+            #[cfg(feature = "cache")]
+            cache: Arc::new(RwLock::new(Default::default())).into(),
+            http: Arc::new(Http::default()),
+            runners: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        Scheduler::new(context, ThreadPool::new(2)).unwrap()
+    }
+
+    fn time(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        format!("2021-01-{:02}T{:02}:{:02}:00Z", day, hour, minute).parse().unwrap()
+    }
+
+    #[test]
+    fn finds_cron_matches() {
+        // 2021-01-01 is a Friday.
+        let cron = Cron::parse("*/15 9-17 * * 1-5").unwrap();
+        assert_eq!(cron.next_after(time(1, 17, 50)), Some(time(4, 9, 0)));
+        assert_eq!(cron.next_after(time(4, 9, 0) + Duration::seconds(30)), Some(time(4, 9, 15)));
+
+        let cron = Cron::parse("0 0 1 * *").unwrap();
+        assert_eq!(cron.next_after(time(31, 12, 0)), "2021-02-01T00:00:00Z".parse().ok());
+
+        // Either the day of the month or of the week matches.
+        let cron = Cron::parse("0 12 13 * 5,7").unwrap();
+        assert_eq!(cron.next_after(time(1, 13, 0)), Some(time(3, 12, 0)));
+
+        for expression in &["60 * * * *", "* * *", "*/0 * * * *", "5-1 * * * *", "0 0 30 2 *"] {
+            assert!(Cron::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn runs_and_cancels_tasks() {
+        let scheduler = scheduler();
+        let (tx, rx) = mpsc::channel();
+
+        let once = Mutex::new(tx.clone());
+        scheduler.schedule(Schedule::after(StdDuration::from_millis(0)), move |_| {
+            once.lock().send("once").unwrap();
+        });
+
+        let every = Mutex::new(tx);
+        let recurring = scheduler.schedule(Schedule::every(StdDuration::from_millis(10)), move |_| {
+            let _ = every.lock().send("every");
+        });
+
+        let later = scheduler.schedule(Schedule::after(StdDuration::from_secs(3600)), |_| unreachable!());
+
+        let mut received = Vec::new();
+        while received.iter().filter(|&&name| name == "every").count() < 3 {
+            received.push(rx.recv_timeout(StdDuration::from_secs(5)).unwrap());
+        }
+
+        assert!(received.contains(&"once"));
+        assert!(recurring.cancel());
+        assert!(!recurring.cancel());
+        assert!(later.next_run().is_some());
+        assert!(later.cancel());
+        assert_eq!(later.next_run(), None);
+    }
+
+    #[test]
+    fn reports_panicking_tasks_to_the_hook() {
+        let data = Arc::new(RwLock::new(ShareMap::custom()));
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        data.write().insert::<PanicHookKey>(Arc::new(move |panic: &HandlerPanic<'_>| {
+            let _ = tx.lock().send((panic.source.clone(), panic.message().map(String::from)));
+        }));
+
+        let scheduler = scheduler_with(data);
+        let task = scheduler.schedule(Schedule::after(StdDuration::from_millis(0)), |_| panic!("expected panic"));
+
+        assert_eq!(
+            rx.recv_timeout(StdDuration::from_secs(5)),
+            Ok((PanicSource::Task(task.id()), Some("expected panic".to_string()))),
+        );
+    }
+
+    #[test]
+    fn stores_tasks_and_runs_overdue_ones() {
+        let dir = env::temp_dir().join(format!("serenity-tasks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = FileTaskStore::new(&dir);
+
+        let first = scheduler();
+        first.register("remind", |_, _| {});
+        first.set_store(store.clone()).unwrap();
+
+        let payload = serde_json::json!({ "text": "later" });
+        let task = first.schedule_stored("remind", Schedule::after(StdDuration::from_secs(3600)), &payload).unwrap();
+        assert!(first.schedule_stored("unknown", Schedule::every(StdDuration::from_secs(1)), &()).is_err());
+
+        let stored = store.load().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].id, &*stored[0].kind, &stored[0].payload), (task.id(), "remind", &payload));
+
+        assert!(task.cancel());
+        assert!(store.load().unwrap().is_empty());
+
+        // A task which became due while the process was down.
+        store.save(&StoredTask {
+            id: 7,
+            kind: "remind".to_string(),
+            payload: serde_json::json!({ "text": "overdue" }),
+            schedule: Schedule::at(time(1, 0, 0)),
+            due: time(1, 0, 0),
+        }).unwrap();
+
+        let restarted = scheduler();
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        restarted.register("remind", move |_, payload| tx.lock().send(payload).unwrap());
+        restarted.set_store(store.clone()).unwrap();
+
+        assert_eq!(rx.recv_timeout(StdDuration::from_secs(5)).unwrap(), serde_json::json!({ "text": "overdue" }));
+        assert_eq!(restarted.next_run(7), None);
+        assert!(store.load().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // Blocks saving tasks after the first, until released.
+    struct SlowStore {
+        saves: Mutex<u32>,
+        saving: Mutex<mpsc::Sender<()>>,
+        release: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl TaskStore for SlowStore {
+        fn load(&self) -> Result<Vec<StoredTask>> {
+            Ok(Vec::new())
+        }
+
+        fn save(&self, _: &StoredTask) -> Result<()> {
+            let mut saves = self.saves.lock();
+            *saves += 1;
+
+            if *saves > 1 {
+                let _ = self.saving.lock().send(());
+                let _ = self.release.lock().recv();
+            }
+
+            Ok(())
+        }
+
+        fn remove(&self, _: u64) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn saves_tasks_without_holding_up_the_scheduler() {
+        let (saving_tx, saving) = mpsc::channel();
+        let (release, release_rx) = mpsc::channel();
+        let scheduler = scheduler();
+        scheduler.register("tick", |_, _| {});
+        scheduler.set_store(SlowStore {
+            saves: Mutex::new(0),
+            saving: Mutex::new(saving_tx),
+            release: Mutex::new(release_rx),
+        }).unwrap();
+
+        let task = scheduler.schedule_stored("tick", Schedule::every(StdDuration::from_millis(10)), &()).unwrap();
+        saving.recv_timeout(StdDuration::from_secs(5)).unwrap();
+
+        // The task ran and its next run is being saved.
+        assert!(task.next_run().is_some());
+        assert!(scheduler.schedule(Schedule::after(StdDuration::from_secs(3600)), |_| {}).cancel());
+        assert!(task.cancel());
+
+        release.send(()).unwrap();
+    }
+
+    #[test]
+    fn holds_back_stored_tasks_until_a_handler_is_registered() {
+        let dir = env::temp_dir().join(format!("serenity-tasks-unhandled-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = FileTaskStore::new(&dir);
+
+        store.save(&StoredTask {
+            id: 3,
+            kind: "remind".to_string(),
+            payload: serde_json::json!({ "text": "overdue" }),
+            schedule: Schedule::at(time(1, 0, 0)),
+            due: time(1, 0, 0),
+        }).unwrap();
+
+        let scheduler = scheduler();
+        scheduler.set_store(store.clone()).unwrap();
+
+        // The scheduler's thread holds back the overdue task.
+        let deadline = Instant::now() + StdDuration::from_secs(5);
+        while !scheduler.shared.state.lock().tasks[&3].awaiting_handler {
+            assert!(Instant::now() < deadline, "the task was not held back");
+            thread::yield_now();
+        }
+
+        assert_eq!(scheduler.next_run(3), Some(time(1, 0, 0)));
+        assert_eq!(store.load().unwrap().len(), 1);
+
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        scheduler.register("remind", move |_, payload| tx.lock().send(payload).unwrap());
+
+        assert_eq!(rx.recv_timeout(StdDuration::from_secs(5)).unwrap(), serde_json::json!({ "text": "overdue" }));
+        assert!(store.load().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn renumbers_stored_tasks_whose_ids_were_given_out() {
+        let dir = env::temp_dir().join(format!("serenity-tasks-collision-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = FileTaskStore::new(&dir);
+        let later = Utc::now() + Duration::days(1);
+
+        for id in 1..=2 {
+            store.save(&StoredTask {
+                id,
+                kind: "remind".to_string(),
+                payload: serde_json::json!(format!("loaded {}", id)),
+                schedule: Schedule::at(later),
+                due: later,
+            }).unwrap();
+        }
+
+        let scheduler = scheduler();
+        scheduler.register("remind", |_, _| {});
+        let closure = scheduler.schedule(Schedule::at(later), |_| {});
+        let stored = scheduler.schedule_stored("remind", Schedule::at(later), "scheduled").unwrap();
+        assert_eq!((closure.id(), stored.id()), (1, 2));
+
+        scheduler.set_store(store.clone()).unwrap();
+
+        // The loaded tasks are renumbered in whichever order they are loaded.
+        let mut tasks = store.load().unwrap()
+            .into_iter()
+            .map(|task| (task.id, task.payload.as_str().unwrap().to_string()))
+            .collect::<Vec<_>>();
+        tasks.sort();
+
+        let ids = tasks.iter().map(|&(id, _)| id).collect::<Vec<_>>();
+        let mut payloads = tasks.into_iter().map(|(_, payload)| payload).collect::<Vec<_>>();
+        payloads[1..].sort();
+
+        assert_eq!(ids, vec![2, 3, 4]);
+        assert_eq!(payloads, vec!["scheduled", "loaded 1", "loaded 2"]);
+        assert!((1..=4).all(|id| scheduler.next_run(id).is_some()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
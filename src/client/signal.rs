@@ -0,0 +1,44 @@
+use std::{
+    io::Error as IoError,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use libc::{c_int, sighandler_t, SIGINT, SIGTERM, SIG_DFL, SIG_ERR};
+
+// Set by the signal handler, which may only do async-signal-safe work; the
+// shutdown itself runs on a watcher thread.
+static RECEIVED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(signal: c_int) {
+    RECEIVED.store(true, Ordering::SeqCst);
+
+    // A second signal terminates the process as usual, in case the shutdown
+    // hangs.
+    unsafe {
+        libc::signal(signal, SIG_DFL);
+    }
+}
+
+// Calls `f` on a separate thread once SIGINT or SIGTERM is received.
+pub(crate) fn on_shutdown_signal<F>(f: F) -> Result<(), IoError>
+    where F: FnOnce() + Send + 'static {
+    for &signal in &[SIGINT, SIGTERM] {
+        let handler = on_signal as extern "C" fn(c_int) as sighandler_t;
+
+        if unsafe { libc::signal(signal, handler) } == SIG_ERR {
+            return Err(IoError::last_os_error());
+        }
+    }
+
+    thread::spawn(move || {
+        while !RECEIVED.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        f();
+    });
+
+    Ok(())
+}
@@ -0,0 +1,469 @@
+//! Persistent key-value storage scoped by guild, user or channel, reachable
+//! from a [`Context`] via [`Context::store`].
+//!
+//! [`Context`]: struct.Context.html
+//! [`Context::store`]: struct.Context.html#method.store
+
+use parking_lot::{Mutex, RwLock};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::Arc,
+};
+use typemap::Key;
+use crate::internal::{files, prelude::*};
+use crate::model::id::{ChannelId, GuildId, UserId};
+
+/// What a stored value belongs to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum Scope {
+    /// A value belonging to a guild, such as its command prefix.
+    Guild(GuildId),
+    /// A value belonging to a user, such as their preferred language.
+    User(UserId),
+    /// A value belonging to a channel, such as whether commands are disabled
+    /// in it.
+    Channel(ChannelId),
+}
+
+impl From<GuildId> for Scope {
+    fn from(guild_id: GuildId) -> Self {
+        Scope::Guild(guild_id)
+    }
+}
+
+impl From<UserId> for Scope {
+    fn from(user_id: UserId) -> Self {
+        Scope::User(user_id)
+    }
+}
+
+impl From<ChannelId> for Scope {
+    fn from(channel_id: ChannelId) -> Self {
+        Scope::Channel(channel_id)
+    }
+}
+
+/// Where a [`Store`] keeps its values.
+///
+/// Values are stored as JSON, converted from and to the types they are used
+/// as by the [`Store`].
+///
+/// Refer to [`MemoryStore`] and [`FileStore`] for the provided backends.
+///
+/// [`FileStore`]: struct.FileStore.html
+/// [`MemoryStore`]: struct.MemoryStore.html
+/// [`Store`]: struct.Store.html
+pub trait StoreBackend: Send + Sync {
+    /// Loads the value stored under the key in the scope, if there is one.
+    fn get(&self, scope: Scope, key: &str) -> Result<Option<Value>>;
+
+    /// Stores a value under the key in the scope, replacing any previous one.
+    fn set(&self, scope: Scope, key: &str, value: Value) -> Result<()>;
+
+    /// Removes the value stored under the key in the scope, if there is one.
+    fn remove(&self, scope: Scope, key: &str) -> Result<()>;
+}
+
+impl Debug for dyn StoreBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("StoreBackend")
+    }
+}
+
+/// A [`StoreBackend`] keeping values in memory, so that they are lost when the
+/// process exits.
+///
+/// This is the backend of a client's store, unless another one is set via
+/// [`Client::set_store`].
+///
+/// [`Client::set_store`]: struct.Client.html#method.set_store
+/// [`StoreBackend`]: trait.StoreBackend.html
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    values: RwLock<HashMap<Scope, JsonMap>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StoreBackend for MemoryStore {
+    fn get(&self, scope: Scope, key: &str) -> Result<Option<Value>> {
+        Ok(self.values.read().get(&scope).and_then(|values| values.get(key)).cloned())
+    }
+
+    fn set(&self, scope: Scope, key: &str, value: Value) -> Result<()> {
+        self.values.write().entry(scope).or_default().insert(key.to_string(), value);
+
+        Ok(())
+    }
+
+    fn remove(&self, scope: Scope, key: &str) -> Result<()> {
+        let mut values = self.values.write();
+
+        if let Some(scoped) = values.get_mut(&scope) {
+            scoped.remove(key);
+
+            if scoped.is_empty() {
+                values.remove(&scope);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`StoreBackend`] keeping one JSON file per scope in a directory, such as
+/// `guild_81384788765712384.json`.
+///
+/// The values of a scope are read from its file when first used, and kept in
+/// memory afterwards; the file is rewritten whenever a value changes. As such,
+/// a directory must only be used by one store at a time.
+///
+/// # Examples
+///
+/// Keep a client's store in the `store` directory:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # struct Handler;
+/// #
+/// # impl EventHandler for Handler {}
+/// #
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::client::{Client, FileStore};
+/// use std::env;
+///
+/// let mut client = Client::new(&env::var("DISCORD_TOKEN")?, Handler)?;
+/// client.set_store(FileStore::new("store"));
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`StoreBackend`]: trait.StoreBackend.html
+#[derive(Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+    scopes: Mutex<HashMap<Scope, Arc<Mutex<JsonMap>>>>,
+}
+
+impl FileStore {
+    /// Creates a store keeping values in the given directory, which is
+    /// created when the first value is stored.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileStore {
+            dir: dir.into(),
+            scopes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn path(&self, scope: Scope) -> PathBuf {
+        let name = match scope {
+            Scope::Guild(id) => format!("guild_{}.json", id.0),
+            Scope::User(id) => format!("user_{}.json", id.0),
+            Scope::Channel(id) => format!("channel_{}.json", id.0),
+        };
+
+        self.dir.join(name)
+    }
+
+    // The values of the scope, read from its file the first time.
+    fn values(&self, scope: Scope) -> Result<Arc<Mutex<JsonMap>>> {
+        if let Some(values) = self.scopes.lock().get(&scope) {
+            return Ok(Arc::clone(values));
+        }
+
+        let values = match fs::read(self.path(scope)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(ref why) if why.kind() == ErrorKind::NotFound => JsonMap::new(),
+            Err(why) => return Err(why.into()),
+        };
+
+        // Another thread may have read the file in the meantime, in which case
+        // its copy is kept.
+        let mut scopes = self.scopes.lock();
+        let values = scopes.entry(scope).or_insert_with(|| Arc::new(Mutex::new(values)));
+
+        Ok(Arc::clone(values))
+    }
+
+}
+
+impl StoreBackend for FileStore {
+    fn get(&self, scope: Scope, key: &str) -> Result<Option<Value>> {
+        Ok(self.values(scope)?.lock().get(key).cloned())
+    }
+
+    // The values are changed on a copy, which replaces them once written, so
+    // that they stay as stored if writing fails.
+    fn set(&self, scope: Scope, key: &str, value: Value) -> Result<()> {
+        let values = self.values(scope)?;
+        let mut values = values.lock();
+
+        let mut changed = values.clone();
+        changed.insert(key.to_string(), value);
+        files::write_json(&self.path(scope), &changed)?;
+        *values = changed;
+
+        Ok(())
+    }
+
+    fn remove(&self, scope: Scope, key: &str) -> Result<()> {
+        let values = self.values(scope)?;
+        let mut values = values.lock();
+
+        if !values.contains_key(key) {
+            return Ok(());
+        }
+
+        let mut changed = values.clone();
+        changed.remove(key);
+
+        if changed.is_empty() {
+            files::remove(&self.path(scope))?;
+        } else {
+            files::write_json(&self.path(scope), &changed)?;
+        }
+
+        *values = changed;
+
+        Ok(())
+    }
+}
+
+/// Typed access to the values of a [`StoreBackend`], shared by every
+/// [`Context`] of a client.
+///
+/// A store is cheap to clone; clones share their backend.
+///
+/// # Examples
+///
+/// Remember a guild's mod-log channel:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # use serenity::model::channel::Message;
+/// #
+/// use serenity::model::id::ChannelId;
+///
+/// struct Handler;
+///
+/// impl EventHandler for Handler {
+///     fn message(&self, ctx: Context, msg: Message) {
+///         let guild = match msg.guild_id {
+///             Some(guild_id) => ctx.store().guild(guild_id),
+///             None => return,
+///         };
+///
+///         if msg.content == "!modlog here" {
+///             let _ = guild.set("mod_log", &msg.channel_id);
+///         } else if msg.content == "!modlog" {
+///             if let Ok(Some(channel_id)) = guild.get::<ChannelId>("mod_log") {
+///                 let _ = msg.channel_id.say(&ctx.http, format!("Logging to {}", channel_id.mention()));
+///             }
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`Context`]: struct.Context.html
+/// [`StoreBackend`]: trait.StoreBackend.html
+#[derive(Clone, Debug)]
+pub struct Store {
+    backend: Arc<dyn StoreBackend>,
+}
+
+impl Store {
+    /// Creates a store with the given backend.
+    pub fn new<B: StoreBackend + 'static>(backend: B) -> Self {
+        Store {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Returns the values belonging to the given scope.
+    pub fn scope(&self, scope: impl Into<Scope>) -> ScopedStore {
+        ScopedStore {
+            backend: Arc::clone(&self.backend),
+            scope: scope.into(),
+        }
+    }
+
+    /// Returns the values belonging to the given guild.
+    pub fn guild(&self, guild_id: impl Into<GuildId>) -> ScopedStore {
+        self.scope(guild_id.into())
+    }
+
+    /// Returns the values belonging to the given user.
+    pub fn user(&self, user_id: impl Into<UserId>) -> ScopedStore {
+        self.scope(user_id.into())
+    }
+
+    /// Returns the values belonging to the given channel.
+    pub fn channel(&self, channel_id: impl Into<ChannelId>) -> ScopedStore {
+        self.scope(channel_id.into())
+    }
+}
+
+impl Default for Store {
+    /// Creates a store keeping values in memory.
+    fn default() -> Self {
+        Store::new(MemoryStore::new())
+    }
+}
+
+/// The values of a [`Store`] belonging to a single scope, such as a guild.
+///
+/// [`Store`]: struct.Store.html
+#[derive(Clone, Debug)]
+pub struct ScopedStore {
+    backend: Arc<dyn StoreBackend>,
+    scope: Scope,
+}
+
+impl ScopedStore {
+    /// The scope the values belong to.
+    pub fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Loads the value stored under the key, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend fails to load the value, or if it
+    /// can not be deserialized as the requested type.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.backend.get(self.scope, key)? {
+            Some(value) => serde_json::from_value(value).map(Some).map_err(From::from),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores a value under the key, replacing any previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value can not be serialized, or if the backend
+    /// fails to store it.
+    pub fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<()> {
+        self.backend.set(self.scope, key, serde_json::to_value(value)?)
+    }
+
+    /// Removes the value stored under the key, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend fails to remove the value.
+    pub fn remove(&self, key: &str) -> Result<()> {
+        self.backend.remove(self.scope, key)
+    }
+}
+
+/// The key a client's [`Store`] is kept under in its data.
+///
+/// [`Store`]: struct.Store.html
+pub(crate) struct StoreKey;
+
+impl Key for StoreKey {
+    type Value = Store;
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use super::{FileStore, MemoryStore, Store};
+    use crate::model::id::{ChannelId, GuildId, UserId};
+
+    fn file_store(name: &str) -> FileStore {
+        let dir = env::temp_dir().join(format!("serenity-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        FileStore::new(dir)
+    }
+
+    fn stores_typed_values_by_scope(store: &Store) {
+        store.guild(1).set("prefix", "?").unwrap();
+        store.guild(1).set("disabled", &vec!["ping".to_string()]).unwrap();
+        store.user(1).set("prefix", "!").unwrap();
+        store.channel(2).set("mod_log", &ChannelId(3)).unwrap();
+
+        assert_eq!(store.guild(1).get::<String>("prefix").unwrap(), Some("?".to_string()));
+        assert_eq!(store.guild(1).get::<Vec<String>>("disabled").unwrap(), Some(vec!["ping".to_string()]));
+        assert_eq!(store.user(UserId(1)).get::<String>("prefix").unwrap(), Some("!".to_string()));
+        assert_eq!(store.guild(GuildId(2)).get::<String>("prefix").unwrap(), None);
+        assert_eq!(store.channel(2).get::<ChannelId>("mod_log").unwrap(), Some(ChannelId(3)));
+
+        // Values of another type are an error rather than missing.
+        assert!(store.guild(1).get::<u64>("prefix").is_err());
+
+        store.guild(1).remove("prefix").unwrap();
+        store.guild(1).remove("prefix").unwrap();
+        assert_eq!(store.guild(1).get::<String>("prefix").unwrap(), None);
+    }
+
+    #[test]
+    fn memory_store_stores_typed_values_by_scope() {
+        stores_typed_values_by_scope(&Store::new(MemoryStore::new()));
+    }
+
+    #[test]
+    fn file_store_stores_typed_values_by_scope() {
+        let backend = file_store("scopes");
+        let dir = backend.dir.clone();
+
+        stores_typed_values_by_scope(&Store::new(backend));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_store_persists_values() {
+        let backend = file_store("persist");
+        let dir = backend.dir.clone();
+
+        let store = Store::new(backend);
+        store.guild(1).set("prefix", "?").unwrap();
+        store.guild(2).set("prefix", "!").unwrap();
+        store.guild(2).remove("prefix").unwrap();
+
+        let store = Store::new(FileStore::new(&dir));
+        assert_eq!(store.guild(1).get::<String>("prefix").unwrap(), Some("?".to_string()));
+        assert_eq!(store.guild(2).get::<String>("prefix").unwrap(), None);
+        assert!(!dir.join("guild_2.json").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn file_store_keeps_values_when_writing_fails() {
+        let backend = file_store("failing");
+        let dir = backend.dir.clone();
+
+        let store = Store::new(backend);
+        store.guild(1).set("prefix", "?").unwrap();
+
+        // Neither writing nor removing the file works once a directory took
+        // its place.
+        fs::remove_file(dir.join("guild_1.json")).unwrap();
+        fs::create_dir(dir.join("guild_1.json")).unwrap();
+
+        assert!(store.guild(1).set("prefix", "!").is_err());
+        assert!(store.guild(1).remove("prefix").is_err());
+        assert_eq!(store.guild(1).get::<String>("prefix").unwrap(), Some("?".to_string()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
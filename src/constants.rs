@@ -244,4 +244,13 @@ pub mod close_codes {
     ///
     /// Cannot reconnect.
     pub const SHARDING_REQUIRED: u16 = 4011;
+    /// An invalid value for the gateway intents was sent.
+    ///
+    /// Cannot reconnect.
+    pub const INVALID_GATEWAY_INTENTS: u16 = 4013;
+    /// A privileged gateway intent was sent that has not been enabled for the
+    /// application.
+    ///
+    /// Cannot reconnect.
+    pub const DISALLOWED_GATEWAY_INTENTS: u16 = 4014;
 }
@@ -87,6 +87,11 @@ use crate::model::channel::Message;
 use threadpool::ThreadPool;
 use std::sync::Arc;
 
+#[cfg(feature = "async")]
+use crate::client::{Async, AsyncContext};
+#[cfg(feature = "async")]
+use futures::future::BoxFuture;
+
 /// A trait for defining your own framework for serenity to use.
 ///
 /// Should you implement this trait, or define a `message` handler, depends on you.
@@ -119,3 +124,23 @@ impl<'a, F: Framework + ?Sized> Framework for &'a mut F {
         (**self).dispatch(ctx, msg, threadpool);
     }
 }
+
+/// The async flavour of [`Framework`], whose dispatch returns a future.
+///
+/// Frameworks are given to a [`Client`] via the [`Async`] adapter, which runs
+/// the futures on an executor.
+///
+/// [`Async`]: ../client/struct.Async.html
+/// [`Client`]: ../client/struct.Client.html
+/// [`Framework`]: trait.Framework.html
+#[cfg(feature = "async")]
+pub trait AsyncFramework: Send + Sync + 'static {
+    fn dispatch(self: Arc<Self>, _: AsyncContext, _: Message) -> BoxFuture<'static, ()>;
+}
+
+#[cfg(feature = "async")]
+impl<F: AsyncFramework> Framework for Async<F> {
+    fn dispatch(&mut self, ctx: Context, msg: Message, _: &ThreadPool) {
+        self.run(ctx, "dispatch", move |framework, ctx| framework.dispatch(ctx, msg));
+    }
+}
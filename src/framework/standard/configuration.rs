@@ -1,6 +1,7 @@
 use super::Delimiter;
 use crate::client::Context;
 use crate::model::{channel::Message, id::{UserId, GuildId, ChannelId}};
+use log::warn;
 use std::collections::HashSet;
 
 type DynamicPrefixHook = dyn Fn(&mut Context, &Message) -> Option<String> + Send + Sync + 'static;
@@ -350,6 +351,58 @@ impl Configuration {
         self
     }
 
+    /// Adds a dynamic prefix reading a guild's prefix from the client's store,
+    /// under the given key. Messages outside of guilds, and guilds with no
+    /// prefix stored, use the inherited prefix.
+    ///
+    /// Unlike [`dynamic_prefix`], this keeps the dynamic prefixes set before.
+    ///
+    /// # Examples
+    ///
+    /// Let guilds change their prefix via a `~prefix <prefix>` message:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::*;
+    /// # use serenity::model::channel::Message;
+    /// # struct Handler;
+    /// #
+    /// # impl EventHandler for Handler {
+    /// fn message(&self, ctx: Context, msg: Message) {
+    ///     if let Some(guild_id) = msg.guild_id {
+    ///         if msg.content.starts_with("~prefix ") {
+    ///             let _ = ctx.store().guild(guild_id).set("prefix", &msg.content[8..]);
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// #
+    /// # let mut client = Client::new("token", Handler).unwrap();
+    /// use serenity::framework::StandardFramework;
+    ///
+    /// client.with_framework(StandardFramework::new()
+    ///     .configure(|c| c.prefix("~").stored_prefix("prefix")));
+    /// ```
+    ///
+    /// [`dynamic_prefix`]: #method.dynamic_prefix
+    pub fn stored_prefix<K: Into<String>>(&mut self, key: K) -> &mut Self {
+        let key = key.into();
+
+        self.dynamic_prefixes.push(Box::new(move |ctx: &mut Context, msg: &Message| {
+            let guild_id = msg.guild_id?;
+
+            match ctx.store().guild(guild_id).get::<String>(&key) {
+                Ok(prefix) => prefix,
+                Err(why) => {
+                    warn!("Failed to load the prefix of guild {}: {:?}", guild_id, why);
+
+                    None
+                },
+            }
+        }));
+
+        self
+    }
+
     /// Whether the bot should respond to other bots.
     ///
     /// For example, if this is set to false, then the bot will respond to any
@@ -6,10 +6,11 @@ use uwl::{UnicodeStream, StrExt};
 use thiserror::Error;
 use anyhow::Error;
 
-use crate::client::Context;
+use crate::client::{Context, GuardedPool, PanicSource};
 use crate::internal::prelude::*;
 use crate::model::{
     channel::{Channel, Message},
+    event::EventType,
     permissions::Permissions,
 };
 
@@ -651,6 +652,13 @@ impl StandardFramework {
 
 impl Framework for StandardFramework {
     fn dispatch(&mut self, mut ctx: Context, msg: Message, threadpool: &ThreadPool) {
+        // Commands and hooks run on the threadpool have their panics reported
+        // to the client's panic hook.
+        let data = Arc::clone(&ctx.data);
+        let shard_id = ctx.shard_id;
+        let pool = |source| GuardedPool::new(threadpool, &data, shard_id, source);
+        let message_pool = || pool(PanicSource::Event(EventType::MessageCreate));
+
         let mut stream = UnicodeStream::new(&msg.content);
 
         stream.take_while(|s| s.is_whitespace());
@@ -663,7 +671,7 @@ impl Framework for StandardFramework {
                 let prefix_only = Arc::clone(&prefix_only);
                 let msg = msg.clone();
 
-                threadpool.execute(move || {
+                message_pool().execute(move || {
                     prefix_only(&mut ctx, &msg);
                 });
             }
@@ -677,7 +685,7 @@ impl Framework for StandardFramework {
                 let normal = Arc::clone(&normal);
                 let msg = msg.clone();
 
-                threadpool.execute(move || {
+                message_pool().execute(move || {
                     normal(&mut ctx, &msg);
                 });
             }
@@ -715,7 +723,7 @@ impl Framework for StandardFramework {
                             let msg = msg.clone();
                             let unreg = unreg.clone();
 
-                            threadpool.execute(move || {
+                            message_pool().execute(move || {
                                 unrecognised_command(&mut ctx, &msg, &unreg);
                             });
                         }
@@ -725,7 +733,7 @@ impl Framework for StandardFramework {
                         let normal = Arc::clone(&normal);
                         let msg = msg.clone();
 
-                        threadpool.execute(move || {
+                        message_pool().execute(move || {
                             normal(&mut ctx, &msg);
                         });
                     }
@@ -758,7 +766,7 @@ impl Framework for StandardFramework {
                 // `parse_command` promises to never return a help invocation if `StandardFramework::help` is `None`.
                 let help = self.help.unwrap();
 
-                threadpool.execute(move || {
+                pool(PanicSource::Command(name)).execute(move || {
                     if let Some(before) = before {
                         if !before(&mut ctx, &msg, name) {
                             return;
@@ -812,7 +820,7 @@ impl Framework for StandardFramework {
                 let after = self.after.clone();
                 let msg = msg.clone();
                 let name = &command.options.names[0];
-                threadpool.execute(move || {
+                pool(PanicSource::Command(*name)).execute(move || {
                     if let Some(before) = before {
                         if !before(&mut ctx, &msg, name) {
                             return;
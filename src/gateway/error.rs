@@ -23,10 +23,20 @@ pub enum GatewayError {
     #[error("Failed sending a heartbeat")]
     HeartbeatFailed,
 
+    /// When an invalid value for the gateway intents was sent in the
+    /// IDENTIFY.
+    #[error("Sent invalid gateway intents")]
+    InvalidGatewayIntents,
+
     /// When invalid authentication (a bad token) was sent in the IDENTIFY.
     #[error("Sent invalid authentication")]
     InvalidAuthentication,
 
+    /// When gateway intents were sent that are privileged but not enabled
+    /// for the application.
+    #[error("Sent disallowed gateway intents")]
+    DisallowedGatewayIntents,
+
     /// Expected a Ready or an InvalidateSession
     #[error("Expected a valid Handshake")]
     InvalidHandshake,
@@ -0,0 +1,51 @@
+use thiserror::Error;
+
+/// An error that occurred while encoding or decoding the Erlang External Term
+/// Format.
+#[derive(Clone, Debug, Error)]
+pub enum EtfError {
+    /// The term was not prefixed with the format version, `131`.
+    #[error("Missing ETF version byte")]
+    InvalidVersion(u8),
+
+    /// The term ended before it was fully decoded.
+    #[error("Unexpected end of ETF data")]
+    UnexpectedEnd,
+
+    /// The term contained a tag that the gateway never sends, such as a PID or
+    /// a function.
+    #[error("Unsupported ETF tag")]
+    UnsupportedTag(u8),
+
+    /// An integer did not fit into 64 bits.
+    #[error("ETF integer out of range")]
+    IntegerOutOfRange,
+
+    /// A float could not be represented in JSON, or was malformed.
+    #[error("Invalid ETF float")]
+    InvalidFloat,
+
+    /// A map key was not an atom, binary or integer.
+    #[error("Invalid ETF map key")]
+    InvalidMapKey,
+
+    /// A binary, atom or string was not valid UTF-8.
+    #[error("Invalid UTF-8 in ETF data")]
+    InvalidUtf8,
+
+    /// There was data left over after decoding the term.
+    #[error("Trailing ETF data")]
+    TrailingData,
+
+    /// A gateway payload was not a map.
+    #[error("ETF payload is not a map")]
+    InvalidPayload,
+
+    /// A value was too large to be encoded.
+    #[error("Value too large to encode as ETF")]
+    TooLarge,
+
+    #[doc(hidden)]
+    #[error("unreachable")]
+    __Nonexhaustive,
+}
@@ -0,0 +1,742 @@
+//! An encoder and decoder for the [Erlang External Term Format][etf], which
+//! the gateway can use instead of JSON.
+//!
+//! Terms are converted to and from [`Value`]s so that the rest of the library
+//! can deserialize events the same way regardless of the encoding.
+//!
+//! The gateway sends map keys as atoms and snowflakes as (big) integers, so a
+//! decoded payload differs slightly from its JSON counterpart: atoms become
+//! strings - except for `nil`, `true` and `false` - and snowflakes become
+//! numbers, which the model's ID types accept as well.
+//!
+//! [etf]: http://erlang.org/doc/apps/erts/erl_ext_dist.html
+//! [`Value`]: ../../../serde_json/enum.Value.html
+
+mod error;
+
+pub use self::error::EtfError;
+
+use flate2::read::ZlibDecoder;
+use serde::de::{Deserialize, Error as DeError};
+use serde_json::Map;
+use std::{
+    convert::TryFrom,
+    io::Read,
+    ops::Range,
+};
+use crate::internal::prelude::*;
+
+const FORMAT_VERSION: u8 = 131;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const COMPRESSED: u8 = 80;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// Decodes a term, including its leading version byte, into a [`Value`].
+///
+/// # Errors
+///
+/// Returns an [`EtfError`] if the data is malformed or contains terms that
+/// can not be represented as JSON.
+///
+/// [`EtfError`]: enum.EtfError.html
+/// [`Value`]: ../../../serde_json/enum.Value.html
+pub fn decode(bytes: &[u8]) -> Result<Value> {
+    let mut decoder = Decoder::new(bytes);
+
+    let version = decoder.u8()?;
+
+    if version != FORMAT_VERSION {
+        return Err(EtfError::InvalidVersion(version).into());
+    }
+
+    let value = if decoder.peek()? == COMPRESSED {
+        decoder.pos += 1;
+        let size = decoder.u32()? as usize;
+
+        let mut inflated = Vec::with_capacity(size);
+        ZlibDecoder::new(decoder.rest()).read_to_end(&mut inflated)?;
+        decoder.pos = bytes.len();
+
+        let mut inner = Decoder::new(&inflated);
+        let value = inner.term()?;
+        inner.finish()?;
+
+        value
+    } else {
+        decoder.term()?
+    };
+
+    decoder.finish()?;
+
+    Ok(value)
+}
+
+/// The envelope of a gateway payload, along with its terms, in which its data
+/// has only been located.
+#[derive(Debug)]
+pub(crate) struct Envelope {
+    pub op: u64,
+    pub seq: Option<u64>,
+    pub kind: Option<String>,
+    /// The payload's terms, inflated if they were compressed.
+    pub terms: Vec<u8>,
+    /// Where the term of the payload's data is within its terms, unless the
+    /// data is missing or `nil`.
+    pub data: Option<Range<usize>>,
+}
+
+/// Decodes the opcode, sequence and event type of a gateway payload, including
+/// its leading version byte, only skipping over its data.
+///
+/// The data can later be decoded via [`decode_data`].
+///
+/// [`decode_data`]: fn.decode_data.html
+pub(crate) fn decode_envelope(bytes: Vec<u8>) -> Result<Envelope> {
+    let (terms, start) = {
+        let mut decoder = Decoder::new(&bytes);
+
+        let version = decoder.u8()?;
+
+        if version != FORMAT_VERSION {
+            return Err(EtfError::InvalidVersion(version).into());
+        }
+
+        if decoder.peek()? == COMPRESSED {
+            decoder.pos += 1;
+            let size = decoder.u32()? as usize;
+
+            let mut inflated = Vec::with_capacity(size);
+            ZlibDecoder::new(decoder.rest()).read_to_end(&mut inflated)?;
+
+            (Some(inflated), 0)
+        } else {
+            (None, decoder.pos)
+        }
+    };
+    let terms = terms.unwrap_or(bytes);
+
+    let mut op = None;
+    let mut seq = None;
+    let mut kind = None;
+    let mut data = None;
+
+    {
+        let mut decoder = Decoder::new(&terms);
+        decoder.pos = start;
+
+        if decoder.u8()? != MAP_EXT {
+            return Err(EtfError::InvalidPayload.into());
+        }
+
+        for _ in 0..decoder.u32()? {
+            match decoder.key()?.as_str() {
+                "op" => op = Some(u64::deserialize(decoder.term()?)?),
+                "s" => seq = Option::<u64>::deserialize(decoder.term()?)?,
+                "t" => kind = Option::<String>::deserialize(decoder.term()?)?,
+                "d" => {
+                    let start = decoder.pos;
+
+                    // A `nil` atom is cheap to decode, and is left out like a
+                    // JSON `null`.
+                    data = if decoder.is_atom()? {
+                        match decoder.term()? {
+                            Value::Null => None,
+                            _ => Some(start..decoder.pos),
+                        }
+                    } else {
+                        decoder.skip()?;
+
+                        Some(start..decoder.pos)
+                    };
+                },
+                _ => decoder.skip()?,
+            }
+        }
+
+        decoder.finish()?;
+    }
+
+    Ok(Envelope {
+        op: op.ok_or_else(|| serde_json::Error::missing_field("op"))?,
+        seq,
+        kind,
+        terms,
+        data,
+    })
+}
+
+/// Decodes the data of a payload, located by [`decode_envelope`].
+///
+/// [`decode_envelope`]: fn.decode_envelope.html
+pub(crate) fn decode_data(term: &[u8]) -> Result<Value> {
+    let mut decoder = Decoder::new(term);
+    let value = decoder.term()?;
+    decoder.finish()?;
+
+    Ok(value)
+}
+
+/// Encodes a [`Value`] into a term, including the leading version byte.
+///
+/// Objects are encoded as maps with binary keys, strings as binaries and
+/// `null` as the `nil` atom.
+///
+/// # Errors
+///
+/// Returns [`EtfError::TooLarge`] if a string, array or object is too large
+/// to be encoded.
+///
+/// [`EtfError::TooLarge`]: enum.EtfError.html#variant.TooLarge
+/// [`Value`]: ../../../serde_json/enum.Value.html
+pub fn encode(value: &Value) -> Result<Vec<u8>> {
+    let mut buf = vec![FORMAT_VERSION];
+    encode_term(value, &mut buf)?;
+
+    Ok(buf)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Decoder {
+            bytes,
+            pos: 0,
+        }
+    }
+
+    fn finish(&self) -> StdResult<(), EtfError> {
+        if self.pos == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(EtfError::TrailingData)
+        }
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+
+    fn peek(&self) -> StdResult<u8, EtfError> {
+        self.bytes.get(self.pos).cloned().ok_or(EtfError::UnexpectedEnd)
+    }
+
+    fn take(&mut self, len: usize) -> StdResult<&'a [u8], EtfError> {
+        let end = self.pos.checked_add(len).ok_or(EtfError::UnexpectedEnd)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or(EtfError::UnexpectedEnd)?;
+        self.pos = end;
+
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> StdResult<u8, EtfError> {
+        let byte = self.peek()?;
+        self.pos += 1;
+
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> StdResult<u16, EtfError> {
+        let bytes = self.take(2)?;
+
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> StdResult<u32, EtfError> {
+        let bytes = self.take(4)?;
+
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn term(&mut self) -> StdResult<Value, EtfError> {
+        match self.u8()? {
+            SMALL_INTEGER_EXT => Ok(Value::from(self.u8()?)),
+            INTEGER_EXT => Ok(Value::from(self.u32()? as i32)),
+            SMALL_BIG_EXT => {
+                let len = self.u8()? as usize;
+
+                self.big(len)
+            },
+            LARGE_BIG_EXT => {
+                let len = self.u32()? as usize;
+
+                self.big(len)
+            },
+            NEW_FLOAT_EXT => {
+                let bytes = self.take(8)?;
+                let mut buf = [0; 8];
+                buf.copy_from_slice(bytes);
+
+                float(f64::from_bits(u64::from_be_bytes(buf)))
+            },
+            FLOAT_EXT => {
+                let bytes = self.take(31)?;
+                let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+
+                std::str::from_utf8(&bytes[..end])
+                    .ok()
+                    .and_then(|s| s.trim().parse().ok())
+                    .ok_or(EtfError::InvalidFloat)
+                    .and_then(float)
+            },
+            ATOM_EXT => {
+                let len = self.u16()? as usize;
+
+                Ok(atom(latin1(self.take(len)?)))
+            },
+            SMALL_ATOM_EXT => {
+                let len = self.u8()? as usize;
+
+                Ok(atom(latin1(self.take(len)?)))
+            },
+            ATOM_UTF8_EXT => {
+                let len = self.u16()? as usize;
+
+                self.utf8(len).map(atom)
+            },
+            SMALL_ATOM_UTF8_EXT => {
+                let len = self.u8()? as usize;
+
+                self.utf8(len).map(atom)
+            },
+            BINARY_EXT => {
+                let len = self.u32()? as usize;
+
+                self.utf8(len).map(Value::String)
+            },
+            STRING_EXT => {
+                // Erlang encodes lists of bytes this way, so this is an array
+                // of integers rather than a string.
+                let len = self.u16()? as usize;
+
+                Ok(Value::Array(self.take(len)?.iter().map(|b| Value::from(*b)).collect()))
+            },
+            NIL_EXT => Ok(Value::Array(vec![])),
+            LIST_EXT => {
+                let len = self.u32()? as usize;
+                let mut list = self.list(len)?;
+
+                // Proper lists end with `NIL_EXT`; keep the tail of improper
+                // ones as a last element.
+                match self.term()? {
+                    Value::Array(ref tail) if tail.is_empty() => {},
+                    tail => list.push(tail),
+                }
+
+                Ok(Value::Array(list))
+            },
+            SMALL_TUPLE_EXT => {
+                let len = self.u8()? as usize;
+
+                self.list(len).map(Value::Array)
+            },
+            LARGE_TUPLE_EXT => {
+                let len = self.u32()? as usize;
+
+                self.list(len).map(Value::Array)
+            },
+            MAP_EXT => {
+                let len = self.u32()? as usize;
+                let mut map = Map::new();
+
+                for _ in 0..len {
+                    let key = self.key()?;
+
+                    map.insert(key, self.term()?);
+                }
+
+                Ok(Value::Object(map))
+            },
+            other => Err(EtfError::UnsupportedTag(other)),
+        }
+    }
+
+    fn key(&mut self) -> StdResult<String, EtfError> {
+        match self.term()? {
+            Value::String(key) => Ok(key),
+            Value::Number(key) => Ok(key.to_string()),
+            Value::Bool(key) => Ok(key.to_string()),
+            Value::Null => Ok("nil".to_string()),
+            _ => Err(EtfError::InvalidMapKey),
+        }
+    }
+
+    fn is_atom(&self) -> StdResult<bool, EtfError> {
+        match self.peek()? {
+            ATOM_EXT | SMALL_ATOM_EXT | ATOM_UTF8_EXT | SMALL_ATOM_UTF8_EXT => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    // Moves past a term without decoding it, only checking that it is
+    // complete.
+    fn skip(&mut self) -> StdResult<(), EtfError> {
+        let len = match self.u8()? {
+            SMALL_INTEGER_EXT => 1,
+            INTEGER_EXT => 4,
+            SMALL_BIG_EXT => self.u8()? as usize + 1,
+            LARGE_BIG_EXT => (self.u32()? as usize).checked_add(1).ok_or(EtfError::UnexpectedEnd)?,
+            NEW_FLOAT_EXT => 8,
+            FLOAT_EXT => 31,
+            ATOM_EXT | ATOM_UTF8_EXT | STRING_EXT => self.u16()? as usize,
+            SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => self.u8()? as usize,
+            BINARY_EXT => self.u32()? as usize,
+            NIL_EXT => 0,
+            LIST_EXT => {
+                let len = self.u32()?;
+
+                // The elements, followed by the tail.
+                self.skip_terms(len)?;
+
+                return self.skip();
+            },
+            SMALL_TUPLE_EXT => {
+                let len = self.u8()?;
+
+                return self.skip_terms(u32::from(len));
+            },
+            LARGE_TUPLE_EXT => {
+                let len = self.u32()?;
+
+                return self.skip_terms(len);
+            },
+            MAP_EXT => {
+                let len = self.u32()?;
+
+                for _ in 0..len {
+                    self.skip()?;
+                    self.skip()?;
+                }
+
+                return Ok(());
+            },
+            other => return Err(EtfError::UnsupportedTag(other)),
+        };
+
+        self.take(len).map(|_| ())
+    }
+
+    fn skip_terms(&mut self, len: u32) -> StdResult<(), EtfError> {
+        for _ in 0..len {
+            self.skip()?;
+        }
+
+        Ok(())
+    }
+
+    fn big(&mut self, len: usize) -> StdResult<Value, EtfError> {
+        let sign = self.u8()?;
+        let digits = self.take(len)?;
+
+        // Digits are stored little-endian; leading zeroes do not matter.
+        if digits.iter().skip(8).any(|d| *d != 0) {
+            return Err(EtfError::IntegerOutOfRange);
+        }
+
+        let magnitude = digits
+            .iter()
+            .take(8)
+            .rev()
+            .fold(0u64, |acc, d| (acc << 8) | u64::from(*d));
+
+        if sign == 0 {
+            Ok(Value::from(magnitude))
+        } else {
+            i64::try_from(-i128::from(magnitude))
+                .map(Value::from)
+                .map_err(|_| EtfError::IntegerOutOfRange)
+        }
+    }
+
+    fn list(&mut self, len: usize) -> StdResult<Vec<Value>, EtfError> {
+        // Do not trust the length for the allocation, as every element takes
+        // at least one byte.
+        let mut list = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+
+        for _ in 0..len {
+            list.push(self.term()?);
+        }
+
+        Ok(list)
+    }
+
+    fn utf8(&mut self, len: usize) -> StdResult<String, EtfError> {
+        let bytes = self.take(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| EtfError::InvalidUtf8)
+    }
+}
+
+fn atom(name: String) -> Value {
+    match name.as_str() {
+        "nil" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(name),
+    }
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| char::from(*b)).collect()
+}
+
+fn float(value: f64) -> StdResult<Value, EtfError> {
+    Number::from_f64(value).map(Value::Number).ok_or(EtfError::InvalidFloat)
+}
+
+fn encode_term(value: &Value, buf: &mut Vec<u8>) -> StdResult<(), EtfError> {
+    match *value {
+        Value::Null => encode_atom("nil", buf),
+        Value::Bool(true) => encode_atom("true", buf),
+        Value::Bool(false) => encode_atom("false", buf),
+        Value::Number(ref n) => {
+            if let Some(n) = n.as_u64() {
+                if n <= u64::from(u8::max_value()) {
+                    buf.push(SMALL_INTEGER_EXT);
+                    buf.push(n as u8);
+                } else if n <= i32::max_value() as u64 {
+                    buf.push(INTEGER_EXT);
+                    buf.extend_from_slice(&(n as i32).to_be_bytes());
+                } else {
+                    encode_big(0, n, buf);
+                }
+            } else if let Some(n) = n.as_i64() {
+                if n >= i64::from(i32::min_value()) {
+                    buf.push(INTEGER_EXT);
+                    buf.extend_from_slice(&(n as i32).to_be_bytes());
+                } else {
+                    encode_big(1, (-i128::from(n)) as u64, buf);
+                }
+            } else if let Some(n) = n.as_f64() {
+                buf.push(NEW_FLOAT_EXT);
+                buf.extend_from_slice(&n.to_bits().to_be_bytes());
+            }
+        },
+        Value::String(ref s) => {
+            buf.push(BINARY_EXT);
+            encode_len(s.len(), buf)?;
+            buf.extend_from_slice(s.as_bytes());
+        },
+        Value::Array(ref list) => {
+            if !list.is_empty() {
+                buf.push(LIST_EXT);
+                encode_len(list.len(), buf)?;
+
+                for value in list {
+                    encode_term(value, buf)?;
+                }
+            }
+
+            buf.push(NIL_EXT);
+        },
+        Value::Object(ref map) => {
+            buf.push(MAP_EXT);
+            encode_len(map.len(), buf)?;
+
+            for (key, value) in map {
+                buf.push(BINARY_EXT);
+                encode_len(key.len(), buf)?;
+                buf.extend_from_slice(key.as_bytes());
+
+                encode_term(value, buf)?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn encode_atom(name: &str, buf: &mut Vec<u8>) {
+    buf.push(SMALL_ATOM_UTF8_EXT);
+    buf.push(name.len() as u8);
+    buf.extend_from_slice(name.as_bytes());
+}
+
+fn encode_big(sign: u8, magnitude: u64, buf: &mut Vec<u8>) {
+    let digits = magnitude.to_le_bytes();
+    let len = 8 - magnitude.leading_zeros() as usize / 8;
+
+    buf.push(SMALL_BIG_EXT);
+    buf.push(len as u8);
+    buf.push(sign);
+    buf.extend_from_slice(&digits[..len]);
+}
+
+fn encode_len(len: usize, buf: &mut Vec<u8>) -> StdResult<(), EtfError> {
+    let len = u32::try_from(len).map_err(|_| EtfError::TooLarge)?;
+    buf.extend_from_slice(&len.to_be_bytes());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use flate2::{write::ZlibEncoder, Compression};
+    use serde_json::json;
+    use std::io::Write;
+    use super::{decode, decode_data, decode_envelope, encode, EtfError};
+
+    fn small_atom(name: &str) -> Vec<u8> {
+        let mut bytes = vec![super::SMALL_ATOM_UTF8_EXT, name.len() as u8];
+        bytes.extend_from_slice(name.as_bytes());
+
+        bytes
+    }
+
+    fn binary(s: &str) -> Vec<u8> {
+        let mut bytes = vec![super::BINARY_EXT];
+        bytes.extend_from_slice(&(s.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(s.as_bytes());
+
+        bytes
+    }
+
+    fn is_etf_error(why: &anyhow::Error, expected: &str) -> bool {
+        why.downcast_ref::<EtfError>()
+            .map_or(false, |e| format!("{:?}", e).starts_with(expected))
+    }
+
+    // The shape the gateway sends a dispatch in: atom keys, `nil` atoms and
+    // snowflakes as big integers.
+    fn dispatch() -> Vec<u8> {
+        let mut bytes = vec![131, super::MAP_EXT, 0, 0, 0, 4];
+        bytes.extend(small_atom("op"));
+        bytes.extend(&[super::SMALL_INTEGER_EXT, 0]);
+        bytes.extend(small_atom("s"));
+        bytes.extend(&[super::INTEGER_EXT, 0, 0, 1, 0]);
+        bytes.extend(small_atom("t"));
+        bytes.extend(small_atom("TYPING_START"));
+        bytes.extend(small_atom("d"));
+        bytes.extend(&[super::MAP_EXT, 0, 0, 0, 4]);
+        bytes.extend(small_atom("channel_id"));
+        bytes.extend(&[super::SMALL_BIG_EXT, 8, 0]);
+        bytes.extend(&302_917_639_565_475_840u64.to_le_bytes());
+        bytes.extend(small_atom("guild_id"));
+        bytes.extend(small_atom("nil"));
+        bytes.extend(small_atom("timestamp"));
+        bytes.extend(&[super::INTEGER_EXT]);
+        bytes.extend(&1_491_775_523i32.to_be_bytes());
+        bytes.extend(small_atom("user_id"));
+        bytes.extend(&[super::SMALL_BIG_EXT, 8, 0]);
+        bytes.extend(&114_941_315_417_899_012u64.to_le_bytes());
+
+        bytes
+    }
+
+    #[test]
+    fn decodes_gateway_dispatch() {
+        let value = decode(&dispatch()).unwrap();
+
+        assert_eq!(value, json!({
+            "op": 0,
+            "s": 256,
+            "t": "TYPING_START",
+            "d": {
+                "channel_id": 302_917_639_565_475_840u64,
+                "guild_id": null,
+                "timestamp": 1_491_775_523,
+                "user_id": 114_941_315_417_899_012u64,
+            },
+        }));
+    }
+
+    #[test]
+    fn decodes_compressed_terms() {
+        let term = dispatch();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&term[1..]).unwrap();
+
+        let mut bytes = vec![131, super::COMPRESSED];
+        bytes.extend_from_slice(&(term.len() as u32 - 1).to_be_bytes());
+        bytes.extend(encoder.finish().unwrap());
+
+        assert_eq!(decode(&bytes).unwrap(), decode(&term).unwrap());
+        assert_eq!(decode_envelope(bytes).unwrap().seq, Some(256));
+    }
+
+    #[test]
+    fn decodes_only_the_envelope() {
+        let envelope = decode_envelope(dispatch()).unwrap();
+
+        assert_eq!(envelope.op, 0);
+        assert_eq!(envelope.seq, Some(256));
+        assert_eq!(envelope.kind.as_ref().map(String::as_str), Some("TYPING_START"));
+
+        let data = decode_data(&envelope.terms[envelope.data.unwrap()]).unwrap();
+        assert_eq!(data, decode(&dispatch()).unwrap()["d"]);
+
+        let mut hello = vec![131, super::MAP_EXT, 0, 0, 0, 2];
+        hello.extend(small_atom("op"));
+        hello.extend(&[super::SMALL_INTEGER_EXT, 11]);
+        hello.extend(small_atom("d"));
+        hello.extend(small_atom("nil"));
+
+        let envelope = decode_envelope(hello).unwrap();
+        assert_eq!(envelope.op, 11);
+        assert!(envelope.data.is_none());
+
+        assert!(is_etf_error(&decode_envelope(vec![131, super::NIL_EXT]).unwrap_err(), "InvalidPayload"));
+    }
+
+    #[test]
+    fn decodes_lists_and_strings() {
+        let mut bytes = vec![131, super::LIST_EXT, 0, 0, 0, 3];
+        bytes.extend(binary("héllo"));
+        bytes.extend(&[super::STRING_EXT, 0, 2, 1, 2]);
+        bytes.extend(&[super::SMALL_TUPLE_EXT, 2, super::SMALL_INTEGER_EXT, 1, super::NIL_EXT]);
+        bytes.push(super::NIL_EXT);
+
+        assert_eq!(decode(&bytes).unwrap(), json!(["héllo", [1, 2], [1, []]]));
+    }
+
+    #[test]
+    fn round_trips_values() {
+        let value = json!({
+            "op": 2,
+            "d": {
+                "token": "Bot abc",
+                "compress": false,
+                "presence": null,
+                "shard": [0, 1],
+                "empty": [],
+                "ids": [302_917_639_565_475_840u64, 255, 256, -1, -2_147_483_648i64, -2_147_483_649i64],
+                "float": 0.5,
+                "min": i64::min_value(),
+                "max": u64::max_value(),
+            },
+        });
+
+        assert_eq!(decode(&encode(&value).unwrap()).unwrap(), value);
+    }
+
+    #[test]
+    fn rejects_malformed_terms() {
+        assert!(is_etf_error(&decode(&[130, super::NIL_EXT]).unwrap_err(), "InvalidVersion"));
+        assert!(is_etf_error(&decode(&[131, super::BINARY_EXT, 0, 0, 0, 5, b'a']).unwrap_err(), "UnexpectedEnd"));
+        assert!(is_etf_error(&decode(&[131, 103]).unwrap_err(), "UnsupportedTag"));
+        assert!(is_etf_error(&decode(&[131, super::NIL_EXT, super::NIL_EXT]).unwrap_err(), "TrailingData"));
+
+        let mut big = vec![131, super::SMALL_BIG_EXT, 9, 0];
+        big.extend(&[1; 9]);
+        assert!(is_etf_error(&decode(&big).unwrap_err(), "IntegerOutOfRange"));
+    }
+}
@@ -0,0 +1,573 @@
+//! A scriptable fake of Discord's gateway, for testing shards - and the
+//! reconnect, resume and dispatch logic built on top of them - without
+//! connecting to Discord.
+//!
+//! A [`FakeGateway`] accepts connections either in-process, by being given to
+//! a shard as its [`Connector`], or over TCP via [`FakeGateway::listen`]. Each
+//! connection is handed out as a [`FakeConnection`], which scripts what the
+//! gateway sends and receives what the shard sent.
+//!
+//! The fake always sends uncompressed JSON, which shards accept regardless of
+//! their encoding and compression.
+//!
+//! This module requires the `fake_gateway` feature.
+//!
+//! # Examples
+//!
+//! Perform the start of a handshake with a shard:
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use serenity::constants::OpCode;
+//! use serenity::gateway::{fake::FakeGateway, GatewayEncoding, Shard, TransportCompression};
+//! use serenity::model::event::GatewayEvent;
+//! use serenity::prelude::Mutex;
+//! use std::sync::Arc;
+//!
+//! # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+//! let gateway = FakeGateway::new();
+//! let mut shard = Shard::new_with_connector(
+//!     Arc::new(Mutex::new(FakeGateway::URL.to_string())),
+//!     "token",
+//!     [0, 1],
+//!     TransportCompression::None,
+//!     GatewayEncoding::Json,
+//!     Arc::new(gateway.clone()),
+//! )?;
+//!
+//! let connection = gateway.accept().expect("The shard did not connect");
+//! connection.send_hello(41250);
+//!
+//! let hello = shard.recv_json()?.expect("Expected a payload");
+//!
+//! match GatewayEvent::deserialize(hello)? {
+//!     GatewayEvent::Hello(interval) => assert_eq!(interval, 41250),
+//!     other => panic!("Expected a Hello, got {:?}", other),
+//! }
+//!
+//! shard.identify()?;
+//!
+//! let identify = connection.recv_op(OpCode::Identify).expect("Expected an identify");
+//! assert_eq!(identify["d"]["token"], "token");
+//! #     Ok(())
+//! # }
+//! #
+//! # fn main() {
+//! #     try_main().unwrap();
+//! # }
+//! ```
+//!
+//! [`Connector`]: ../trait.Connector.html
+//! [`FakeConnection`]: struct.FakeConnection.html
+//! [`FakeGateway`]: struct.FakeGateway.html
+//! [`FakeGateway::listen`]: struct.FakeGateway.html#method.listen
+
+use parking_lot::Mutex;
+use serde_json::json;
+use std::{
+    borrow::Cow,
+    io::{Error as IoError, ErrorKind},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration as StdDuration, Instant},
+};
+use log::warn;
+use tungstenite::{
+    error::Error as TungsteniteError,
+    protocol::{frame::CloseFrame, WebSocket},
+    Message,
+};
+use url::Url;
+use crate::constants::{self, OpCode};
+use crate::internal::{prelude::*, ws_impl::is_timeout};
+
+use super::{etf, Connector, GatewaySocket};
+
+/// How long a shard's read waits for a message before timing out, so that it
+/// can heartbeat and handle messages from its runner in between.
+const READ_TIMEOUT: StdDuration = StdDuration::from_millis(100);
+
+/// How long the fake waits for a connection or a payload from a shard.
+const RECV_TIMEOUT: StdDuration = StdDuration::from_secs(5);
+
+// What the fake sends to a shard.
+enum Frame {
+    Message(Message),
+    // Ends the connection without a closing handshake.
+    Drop,
+}
+
+/// A fake gateway, accepting connections from shards.
+///
+/// Clones share the same queue of accepted connections, so a clone can be
+/// given to a shard as its [`Connector`] while the original is kept to
+/// [`accept`] connections.
+///
+/// Refer to the [module-level documentation] for an example.
+///
+/// [`Connector`]: ../trait.Connector.html
+/// [`accept`]: #method.accept
+/// [module-level documentation]: index.html
+#[derive(Clone, Debug)]
+pub struct FakeGateway {
+    accepted: Arc<Mutex<Receiver<FakeConnection>>>,
+    connections: Arc<Mutex<Sender<FakeConnection>>>,
+}
+
+impl FakeGateway {
+    /// A URL to give shards connecting in-process, which ignore the host.
+    pub const URL: &'static str = "ws://fake.gateway";
+
+    /// Creates a fake gateway.
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        FakeGateway {
+            accepted: Arc::new(Mutex::new(rx)),
+            connections: Arc::new(Mutex::new(tx)),
+        }
+    }
+
+    /// Waits up to 5 seconds for a shard to connect, returning the connection.
+    pub fn accept(&self) -> Option<FakeConnection> {
+        self.accept_timeout(RECV_TIMEOUT)
+    }
+
+    /// Waits up to the given duration for a shard to connect, returning the
+    /// connection.
+    pub fn accept_timeout(&self, timeout: StdDuration) -> Option<FakeConnection> {
+        self.accepted.lock().recv_timeout(timeout).ok()
+    }
+
+    /// Listens for WebSocket connections on the given address, such as
+    /// `127.0.0.1:0`, returning the address listened on.
+    ///
+    /// Shards connect to it with the default [`WebSocketConnector`] by using
+    /// the address as their gateway URL, e.g. `ws://127.0.0.1:41250`. Their
+    /// connections are accepted the same way as in-process ones.
+    ///
+    /// [`WebSocketConnector`]: ../struct.WebSocketConnector.html
+    pub fn listen(&self, addr: impl ToSocketAddrs) -> Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let connections = Arc::clone(&self.connections);
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => break,
+                };
+
+                let client = match tungstenite::accept(stream) {
+                    Ok(client) => client,
+                    Err(why) => {
+                        warn!("[FakeGateway] Err accepting WebSocket: {:?}", why);
+
+                        continue;
+                    },
+                };
+
+                let (connection, frames, messages) = FakeConnection::new();
+
+                if connections.lock().send(connection).is_err() {
+                    break;
+                }
+
+                thread::spawn(move || forward(client, &frames, &messages));
+            }
+        });
+
+        Ok(addr)
+    }
+}
+
+impl Connector for FakeGateway {
+    fn connect(&self, _: Url) -> Result<Box<dyn GatewaySocket>> {
+        let (connection, frames, messages) = FakeConnection::new();
+
+        self.connections
+            .lock()
+            .send(connection)
+            .map_err(|_| IoError::from(ErrorKind::ConnectionRefused))?;
+
+        Ok(Box::new(FakeSocket {
+            closed: false,
+            frames,
+            messages,
+        }))
+    }
+}
+
+impl Default for FakeGateway {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Forwards frames and messages between a TCP connection and its
+// `FakeConnection`, until either side goes away.
+fn forward(mut client: WebSocket<TcpStream>, frames: &Receiver<Frame>, messages: &Sender<Message>) {
+    if client.get_ref().set_read_timeout(Some(StdDuration::from_millis(10))).is_err() {
+        return;
+    }
+
+    loop {
+        loop {
+            match frames.try_recv() {
+                Ok(Frame::Message(message)) => {
+                    if client.write_message(message).is_err() {
+                        return;
+                    }
+                },
+                Ok(Frame::Drop) | Err(TryRecvError::Disconnected) => {
+                    let _ = client.get_ref().shutdown(Shutdown::Both);
+
+                    return;
+                },
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        match client.read_message() {
+            Ok(message) => {
+                if messages.send(message).is_err() {
+                    return;
+                }
+            },
+            Err(TungsteniteError::Io(ref why)) if is_timeout(why) => {},
+            Err(_) => return,
+        }
+    }
+}
+
+// The shard's end of an in-process connection.
+struct FakeSocket {
+    // Whether the connection was closed or dropped by the gateway.
+    closed: bool,
+    frames: Receiver<Frame>,
+    messages: Sender<Message>,
+}
+
+impl GatewaySocket for FakeSocket {
+    fn read_message(&mut self) -> StdResult<Message, TungsteniteError> {
+        if self.closed {
+            return Err(TungsteniteError::AlreadyClosed);
+        }
+
+        match self.frames.recv_timeout(READ_TIMEOUT) {
+            Ok(Frame::Message(message)) => {
+                if let Message::Close(_) = message {
+                    self.closed = true;
+                }
+
+                Ok(message)
+            },
+            Ok(Frame::Drop) | Err(RecvTimeoutError::Disconnected) => {
+                self.closed = true;
+
+                Err(TungsteniteError::Io(ErrorKind::ConnectionReset.into()))
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                Err(TungsteniteError::Io(ErrorKind::WouldBlock.into()))
+            },
+        }
+    }
+
+    fn write_message(&mut self, message: Message) -> StdResult<(), TungsteniteError> {
+        if self.closed {
+            return Err(TungsteniteError::AlreadyClosed);
+        }
+
+        self.messages
+            .send(message)
+            .map_err(|_| TungsteniteError::Io(ErrorKind::BrokenPipe.into()))
+    }
+
+    fn close(&mut self, frame: Option<CloseFrame<'static>>) -> StdResult<(), TungsteniteError> {
+        self.write_message(Message::Close(frame))
+    }
+}
+
+/// The gateway's end of a connection from a shard.
+///
+/// Anything sent after the shard went away is silently discarded. Dropping the
+/// connection ends it as [`drop_connection`] does.
+///
+/// [`drop_connection`]: #method.drop_connection
+#[derive(Debug)]
+pub struct FakeConnection {
+    frames: Sender<Frame>,
+    messages: Receiver<Message>,
+    seq: u64,
+}
+
+impl FakeConnection {
+    // Creates a connection, along with the receiving end of its frames and
+    // the sending end of its messages.
+    fn new() -> (Self, Receiver<Frame>, Sender<Message>) {
+        let (frames_tx, frames_rx) = mpsc::channel();
+        let (messages_tx, messages_rx) = mpsc::channel();
+
+        let connection = FakeConnection {
+            frames: frames_tx,
+            messages: messages_rx,
+            seq: 0,
+        };
+
+        (connection, frames_rx, messages_tx)
+    }
+
+    /// Sends a raw payload.
+    pub fn send(&self, payload: &Value) {
+        let _ = self.frames.send(Frame::Message(Message::Text(payload.to_string())));
+    }
+
+    /// Sends a Hello with the given heartbeat interval in milliseconds.
+    pub fn send_hello(&self, heartbeat_interval: u64) {
+        self.send(&json!({
+            "op": OpCode::Hello.num(),
+            "d": {
+                "heartbeat_interval": heartbeat_interval,
+                "_trace": ["fake-gateway"],
+            },
+        }));
+    }
+
+    /// Sends a dispatch of the given event type, such as `"MESSAGE_CREATE"`,
+    /// with the next sequence number.
+    pub fn send_dispatch(&mut self, kind: &str, data: Value) {
+        self.seq += 1;
+
+        self.send(&json!({
+            "op": OpCode::Event.num(),
+            "s": self.seq,
+            "t": kind,
+            "d": data,
+        }));
+    }
+
+    /// Dispatches a Ready starting a session with the given ID, for a bot
+    /// user without any guilds.
+    pub fn send_ready(&mut self, session_id: &str) {
+        self.send_dispatch("READY", json!({
+            "v": constants::GATEWAY_VERSION,
+            "user": {
+                "id": "210000000000000000",
+                "username": "fake",
+                "discriminator": "0000",
+                "avatar": null,
+                "bot": true,
+                "email": null,
+                "mfa_enabled": false,
+                "verified": true,
+            },
+            "guilds": [],
+            "private_channels": [],
+            "session_id": session_id,
+            "shard": null,
+            "_trace": ["fake-gateway"],
+        }));
+    }
+
+    /// Dispatches a Resumed, completing a resume.
+    pub fn send_resumed(&mut self) {
+        self.send_dispatch("RESUMED", json!({
+            "_trace": ["fake-gateway"],
+        }));
+    }
+
+    /// Acknowledges a heartbeat.
+    pub fn send_heartbeat_ack(&self) {
+        self.send(&json!({
+            "op": OpCode::HeartbeatAck.num(),
+        }));
+    }
+
+    /// Invalidates the session, indicating whether it may be resumed.
+    pub fn send_invalid_session(&self, resumable: bool) {
+        self.send(&json!({
+            "op": OpCode::InvalidSession.num(),
+            "d": resumable,
+        }));
+    }
+
+    /// Requests that the shard reconnects.
+    pub fn send_reconnect(&self) {
+        self.send(&json!({
+            "op": OpCode::Reconnect.num(),
+            "d": null,
+        }));
+    }
+
+    /// Closes the connection with the given close code, such as
+    /// [`close_codes::AUTHENTICATION_FAILED`].
+    ///
+    /// [`close_codes::AUTHENTICATION_FAILED`]: ../../constants/close_codes/constant.AUTHENTICATION_FAILED.html
+    pub fn close(&self, code: u16) {
+        let _ = self.frames.send(Frame::Message(Message::Close(Some(CloseFrame {
+            code: code.into(),
+            reason: Cow::from(""),
+        }))));
+    }
+
+    /// Ends the connection without a closing handshake, as if the network
+    /// failed.
+    pub fn drop_connection(self) {
+        let _ = self.frames.send(Frame::Drop);
+    }
+
+    /// The sequence number of the last dispatch sent.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Waits up to 5 seconds for the next payload sent by the shard.
+    ///
+    /// Returns `None` if the shard closed the connection or sent nothing.
+    pub fn recv(&self) -> Option<Value> {
+        let deadline = Instant::now() + RECV_TIMEOUT;
+
+        loop {
+            let now = Instant::now();
+
+            if now >= deadline {
+                return None;
+            }
+
+            let payload = match self.messages.recv_timeout(deadline - now).ok()? {
+                Message::Text(text) => serde_json::from_str(&text).ok(),
+                Message::Binary(bytes) => etf::decode(&bytes).ok(),
+                Message::Close(_) => return None,
+                _ => None,
+            };
+
+            if payload.is_some() {
+                return payload;
+            }
+        }
+    }
+
+    /// Waits up to 5 seconds for the shard to close the connection, skipping
+    /// any payloads sent before.
+    ///
+    /// Returns the close code, which is 1005 if the shard sent none, or `None`
+    /// if the connection was not closed.
+    pub fn recv_close(&self) -> Option<u16> {
+        let deadline = Instant::now() + RECV_TIMEOUT;
+
+        loop {
+            let now = Instant::now();
+
+            if now >= deadline {
+                return None;
+            }
+
+            if let Message::Close(frame) = self.messages.recv_timeout(deadline - now).ok()? {
+                return Some(frame.map_or(1005, |frame| frame.code.into()));
+            }
+        }
+    }
+
+    /// Waits up to 5 seconds for a payload with the given opcode sent by the
+    /// shard, skipping any others such as heartbeats.
+    pub fn recv_op(&self, op: OpCode) -> Option<Value> {
+        let op = op.num();
+
+        loop {
+            let payload = self.recv()?;
+
+            if payload["op"].as_u64() == Some(op) {
+                return Some(payload);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use tungstenite::{error::Error as TungsteniteError, Message};
+    use super::FakeGateway;
+    use crate::constants::OpCode;
+    use crate::gateway::{
+        Connector,
+        GatewayEncoding,
+        GatewayError,
+        Shard,
+        TransportCompression,
+        WebSocketConnector,
+    };
+
+    fn shard(url: String, connector: Arc<dyn Connector>) -> Shard {
+        Shard::new_with_connector(
+            Arc::new(Mutex::new(url)),
+            "token",
+            [0, 1],
+            TransportCompression::None,
+            GatewayEncoding::Json,
+            connector,
+        ).unwrap()
+    }
+
+    #[test]
+    fn exchanges_payloads_in_process() {
+        let gateway = FakeGateway::new();
+        let mut shard = shard(FakeGateway::URL.to_string(), Arc::new(gateway.clone()));
+        let mut connection = gateway.accept().unwrap();
+
+        connection.send_hello(41250);
+        assert_eq!(shard.recv_json().unwrap().unwrap()["d"]["heartbeat_interval"], 41250);
+
+        shard.identify().unwrap();
+        assert_eq!(connection.recv_op(OpCode::Identify).unwrap()["d"]["shard"], json!([0, 1]));
+
+        connection.send_ready("session");
+        assert_eq!(shard.recv_json().unwrap().unwrap()["s"], 1);
+    }
+
+    #[test]
+    fn closes_and_drops_connections() {
+        let gateway = FakeGateway::new();
+        let mut shard = shard(FakeGateway::URL.to_string(), Arc::new(gateway.clone()));
+
+        gateway.accept().unwrap().close(4000);
+        match shard.recv_json().unwrap_err().downcast_ref() {
+            Some(GatewayError::Closed(Some(frame))) => assert_eq!(frame.code, 4000.into()),
+            other => panic!("Expected the connection to be closed, got {:?}", other),
+        }
+
+        shard.client = shard.initialize().unwrap();
+        gateway.accept().unwrap().drop_connection();
+
+        match shard.client.read_message() {
+            Err(TungsteniteError::Io(_)) => {},
+            other => panic!("Expected an IO error, got {:?}", other),
+        }
+
+        match shard.client.write_message(Message::Text(String::new())) {
+            Err(TungsteniteError::AlreadyClosed) => {},
+            other => panic!("Expected the connection to be closed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_connections_over_tcp() {
+        let gateway = FakeGateway::new();
+        let addr = gateway.listen("127.0.0.1:0").unwrap();
+        let mut shard = shard(format!("ws://{}", addr), Arc::new(WebSocketConnector));
+        let connection = gateway.accept().unwrap();
+
+        connection.send_hello(41250);
+        assert_eq!(shard.recv_json().unwrap().unwrap()["op"], 10);
+
+        shard.identify().unwrap();
+        assert!(connection.recv_op(OpCode::Identify).is_some());
+    }
+}
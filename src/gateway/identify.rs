@@ -0,0 +1,160 @@
+use std::env::consts;
+use crate::constants::LARGE_THRESHOLD;
+use super::CurrentPresence;
+
+/// The options sent by a [`Shard`] when identifying with the gateway.
+///
+/// # Examples
+///
+/// Appear as "Do Not Disturb" right away, and stop receiving presence and
+/// typing events:
+///
+/// ```rust
+/// use serenity::gateway::IdentifyOptions;
+/// use serenity::model::{gateway::Activity, user::OnlineStatus};
+///
+/// let options = IdentifyOptions {
+///     presence: Some((Some(Activity::playing("with the cache")), OnlineStatus::DoNotDisturb)),
+///     guild_subscriptions: false,
+///     ..Default::default()
+/// };
+/// ```
+///
+/// [`Shard`]: struct.Shard.html
+#[derive(Clone, Debug)]
+pub struct IdentifyOptions {
+    /// The number of members above which a guild is considered large, between
+    /// 50 and 250.
+    ///
+    /// Offline members of large guilds are not sent in [`GuildCreate`]
+    /// events, and must be requested via [`Shard::chunk_guilds`].
+    ///
+    /// Defaults to [`LARGE_THRESHOLD`].
+    ///
+    /// [`GuildCreate`]: ../model/event/struct.GuildCreateEvent.html
+    /// [`LARGE_THRESHOLD`]: ../constants/constant.LARGE_THRESHOLD.html
+    /// [`Shard::chunk_guilds`]: struct.Shard.html#method.chunk_guilds
+    pub large_threshold: u8,
+    /// The connection properties reported to Discord.
+    pub properties: IdentifyProperties,
+    /// The presence to appear with as soon as the shard is identified.
+    ///
+    /// If `None`, no presence is sent and the bot appears online without an
+    /// activity until a presence update is sent. Otherwise, the shard's
+    /// current presence is sent each time it identifies, so that changes made
+    /// via [`Shard::set_presence`] survive reconnects.
+    ///
+    /// [`Shard::set_presence`]: struct.Shard.html#method.set_presence
+    pub presence: Option<CurrentPresence>,
+    /// Whether to receive presence and typing events.
+    ///
+    /// Defaults to `true`.
+    pub guild_subscriptions: bool,
+}
+
+impl Default for IdentifyOptions {
+    fn default() -> Self {
+        IdentifyOptions {
+            large_threshold: LARGE_THRESHOLD,
+            properties: IdentifyProperties::default(),
+            presence: None,
+            guild_subscriptions: true,
+        }
+    }
+}
+
+/// The connection properties sent when identifying.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IdentifyProperties {
+    /// The operating system. Defaults to the one the library was compiled
+    /// for.
+    pub os: String,
+    /// The library or browser name. Defaults to `"serenity"`.
+    pub browser: String,
+    /// The library or device name. Defaults to `"serenity"`.
+    pub device: String,
+}
+
+impl Default for IdentifyProperties {
+    fn default() -> Self {
+        IdentifyProperties {
+            os: consts::OS.to_string(),
+            browser: "serenity".to_string(),
+            device: "serenity".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env::consts, sync::Arc};
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use crate::constants::OpCode;
+    use crate::gateway::{
+        fake::{FakeConnection, FakeGateway},
+        GatewayEncoding,
+        Shard,
+        TransportCompression,
+    };
+    use crate::model::{gateway::Activity, user::OnlineStatus};
+    use super::{IdentifyOptions, IdentifyProperties};
+
+    fn connect() -> (Shard, FakeConnection) {
+        let gateway = FakeGateway::new();
+        let shard = Shard::new_with_connector(
+            Arc::new(Mutex::new(FakeGateway::URL.to_string())),
+            "token",
+            [0, 1],
+            TransportCompression::None,
+            GatewayEncoding::Json,
+            Arc::new(gateway.clone()),
+        ).unwrap();
+
+        (shard, gateway.accept().unwrap())
+    }
+
+    #[test]
+    fn identifies_with_defaults() {
+        let (mut shard, mut connection) = connect();
+
+        shard.identify().unwrap();
+        let identify = connection.recv_op(OpCode::Identify).unwrap();
+
+        assert_eq!(identify["d"]["large_threshold"], 250);
+        assert_eq!(identify["d"]["guild_subscriptions"], true);
+        assert_eq!(identify["d"]["properties"]["$os"], consts::OS);
+        assert_eq!(identify["d"]["properties"]["$browser"], "serenity");
+        assert!(identify["d"].get("presence").is_none());
+    }
+
+    #[test]
+    fn identifies_with_options() {
+        let (mut shard, mut connection) = connect();
+
+        shard.set_identify_options(IdentifyOptions {
+            large_threshold: 50,
+            properties: IdentifyProperties {
+                os: "plan9".to_string(),
+                browser: "bot".to_string(),
+                device: "bot".to_string(),
+            },
+            presence: Some((None, OnlineStatus::Idle)),
+            guild_subscriptions: false,
+        });
+        shard.set_activity(Some(Activity::listening("the gateway")));
+
+        shard.identify().unwrap();
+        let identify = connection.recv_op(OpCode::Identify).unwrap();
+
+        assert_eq!(identify["d"]["large_threshold"], 50);
+        assert_eq!(identify["d"]["guild_subscriptions"], false);
+        assert_eq!(identify["d"]["properties"], json!({
+            "$os": "plan9",
+            "$browser": "bot",
+            "$device": "bot",
+        }));
+        assert_eq!(identify["d"]["presence"]["status"], "idle");
+        assert_eq!(identify["d"]["presence"]["game"]["name"], "the gateway");
+    }
+}
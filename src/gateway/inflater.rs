@@ -0,0 +1,181 @@
+use flate2::{Decompress, FlushDecompress, Status};
+
+use crate::internal::prelude::*;
+
+/// The suffix Discord terminates every complete payload with when using
+/// `zlib-stream` transport compression; this is what a `Z_SYNC_FLUSH` emits.
+const ZLIB_SUFFIX: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// The initial size of the buffer holding inflated payloads.
+const INITIAL_BUFFER_SIZE: usize = 32 * 1024;
+
+/// A decompressor for `zlib-stream` transport compression.
+///
+/// Unlike payload compression, where every binary frame is a complete zlib
+/// stream, the transport variant shares a single inflate context across the
+/// whole connection. A payload may additionally be split over multiple
+/// frames, so frames are buffered until the `Z_SYNC_FLUSH` suffix arrives.
+///
+/// The context must be [`reset`] whenever a new connection is made.
+///
+/// [`reset`]: #method.reset
+pub(crate) struct Inflater {
+    compressed: Vec<u8>,
+    decompress: Decompress,
+    inflated: Vec<u8>,
+}
+
+impl Inflater {
+    pub fn new() -> Self {
+        Inflater {
+            compressed: Vec::new(),
+            decompress: Decompress::new(true),
+            inflated: Vec::with_capacity(INITIAL_BUFFER_SIZE),
+        }
+    }
+
+    /// Discards any buffered data and the shared inflate context, readying
+    /// the inflater for a new connection.
+    pub fn reset(&mut self) {
+        self.compressed.clear();
+        self.decompress.reset(true);
+        self.inflated.clear();
+    }
+
+    /// Buffers a binary frame, returning the inflated payload once a complete
+    /// one has been received.
+    ///
+    /// Returns `None` if the frame did not end with the `Z_SYNC_FLUSH` suffix
+    /// and more frames are needed.
+    pub fn extend(&mut self, frame: &[u8]) -> Result<Option<&[u8]>> {
+        self.compressed.extend_from_slice(frame);
+
+        if !self.compressed.ends_with(&ZLIB_SUFFIX) {
+            return Ok(None);
+        }
+
+        self.inflated.clear();
+        let mut offset = 0;
+
+        loop {
+            if self.inflated.len() == self.inflated.capacity() {
+                self.inflated.reserve(INITIAL_BUFFER_SIZE);
+            }
+
+            let total_in = self.decompress.total_in();
+            let total_out = self.decompress.total_out();
+
+            let status = self.decompress.decompress_vec(
+                &self.compressed[offset..],
+                &mut self.inflated,
+                FlushDecompress::Sync,
+            );
+
+            let status = match status {
+                Ok(status) => status,
+                Err(why) => {
+                    self.compressed.clear();
+
+                    return Err(why.into());
+                },
+            };
+
+            offset += (self.decompress.total_in() - total_in) as usize;
+            let progressed = self.decompress.total_out() != total_out
+                || self.decompress.total_in() != total_in;
+
+            // Inflating is only finished once all input is consumed and the
+            // output buffer was not filled up, as otherwise output may still
+            // be pending inside the inflate context.
+            let output_pending = self.inflated.len() == self.inflated.capacity();
+
+            if status == Status::StreamEnd
+                || !progressed
+                || (offset == self.compressed.len() && !output_pending) {
+                break;
+            }
+        }
+
+        self.compressed.clear();
+
+        Ok(Some(&self.inflated))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use flate2::{Compress, Compression, FlushCompress};
+    use super::Inflater;
+
+    // Compresses each payload with a sync flush, sharing the context just
+    // like the gateway does.
+    fn compress_stream(payloads: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut compress = Compress::new(Compression::default(), true);
+
+        payloads.iter().map(|payload| {
+            let mut out = Vec::with_capacity(payload.len() + 64);
+            let mut offset = 0;
+
+            loop {
+                if out.len() == out.capacity() {
+                    out.reserve(64);
+                }
+
+                let before = compress.total_in();
+                compress.compress_vec(&payload[offset..], &mut out, FlushCompress::Sync).unwrap();
+                offset += (compress.total_in() - before) as usize;
+
+                if offset == payload.len() && out.len() < out.capacity() {
+                    break;
+                }
+            }
+
+            out
+        }).collect()
+    }
+
+    #[test]
+    fn inflates_payloads_sharing_a_context() {
+        let payloads: &[&[u8]] = &[br#"{"op":10,"d":{"heartbeat_interval":41250}}"#, br#"{"op":11}"#];
+        let frames = compress_stream(payloads);
+        let mut inflater = Inflater::new();
+
+        for (frame, payload) in frames.iter().zip(payloads) {
+            assert_eq!(inflater.extend(frame).unwrap(), Some(*payload));
+        }
+    }
+
+    #[test]
+    fn buffers_until_suffix() {
+        let payload = br#"{"op":0,"t":"READY","d":{}}"#;
+        let frames = compress_stream(&[payload]);
+        let (first, second) = frames[0].split_at(frames[0].len() / 2);
+        let mut inflater = Inflater::new();
+
+        assert_eq!(inflater.extend(first).unwrap(), None);
+        assert_eq!(inflater.extend(second).unwrap(), Some(&payload[..]));
+    }
+
+    #[test]
+    fn inflates_payloads_larger_than_the_buffer() {
+        let payload = "a".repeat(super::INITIAL_BUFFER_SIZE * 3).into_bytes();
+        let frames = compress_stream(&[&payload]);
+        let mut inflater = Inflater::new();
+
+        assert_eq!(inflater.extend(&frames[0]).unwrap(), Some(&payload[..]));
+    }
+
+    #[test]
+    fn reset_starts_a_new_stream() {
+        let payload = br#"{"op":10,"d":{"heartbeat_interval":41250}}"#;
+        let mut inflater = Inflater::new();
+
+        let first = compress_stream(&[payload]);
+        assert_eq!(inflater.extend(&first[0]).unwrap(), Some(&payload[..]));
+
+        // A new connection starts a new zlib stream, header included.
+        inflater.reset();
+        let second = compress_stream(&[payload]);
+        assert_eq!(inflater.extend(&second[0]).unwrap(), Some(&payload[..]));
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use thiserror::Error;
+use crate::model::{
+    gateway::Presence,
+    guild::Member,
+    id::{GuildId, UserId},
+};
+
+/// A request for the members of a guild, sent to the gateway by a shard.
+///
+/// Members are either searched for by a username prefix, or looked up by
+/// their IDs, and sent back in one or more [`GuildMembersChunkEvent`]s.
+///
+/// Refer to [`ShardMessenger::request_members`] to wait for all of the
+/// requested members.
+///
+/// # Examples
+///
+/// Request up to 10 members whose username starts with `"zey"`, along with
+/// their presences:
+///
+/// ```rust
+/// use serenity::gateway::MemberRequest;
+/// use serenity::model::id::GuildId;
+///
+/// let request = MemberRequest {
+///     presences: true,
+///     ..MemberRequest::query(GuildId(81384788765712384), "zey", 10)
+/// };
+/// ```
+///
+/// [`GuildMembersChunkEvent`]: ../model/event/struct.GuildMembersChunkEvent.html
+/// [`ShardMessenger::request_members`]: ../client/bridge/gateway/struct.ShardMessenger.html#method.request_members
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemberRequest {
+    /// The guild to request members of.
+    pub guild_id: GuildId,
+    /// The prefix that usernames must start with. An empty query requests
+    /// all members, which requires the guild members intent.
+    ///
+    /// Ignored if `user_ids` is not empty.
+    pub query: String,
+    /// The maximum number of members to send for a query, where `0` means no
+    /// limit.
+    pub limit: u16,
+    /// The IDs of the users to request.
+    pub user_ids: Vec<UserId>,
+    /// Whether to also send the presences of the members, which requires the
+    /// guild presences intent.
+    pub presences: bool,
+}
+
+impl MemberRequest {
+    /// Creates a request for up to `limit` members whose username starts with
+    /// `query`.
+    pub fn query(guild_id: GuildId, query: impl Into<String>, limit: u16) -> Self {
+        MemberRequest {
+            guild_id,
+            query: query.into(),
+            limit,
+            user_ids: Vec::new(),
+            presences: false,
+        }
+    }
+
+    /// Creates a request for the members with the given user IDs.
+    pub fn user_ids(guild_id: GuildId, user_ids: impl IntoIterator<Item = UserId>) -> Self {
+        MemberRequest {
+            guild_id,
+            query: String::new(),
+            limit: 0,
+            user_ids: user_ids.into_iter().collect(),
+            presences: false,
+        }
+    }
+}
+
+/// The members received in response to a [`MemberRequest`], combined from
+/// all of its chunks.
+///
+/// [`MemberRequest`]: struct.MemberRequest.html
+#[derive(Clone, Debug, Default)]
+pub struct RequestedMembers {
+    /// The members found.
+    pub members: HashMap<UserId, Member>,
+    /// The requested user IDs that are not members of the guild.
+    pub not_found: Vec<UserId>,
+    /// The presences of the members found, if they were requested.
+    pub presences: Vec<Presence>,
+}
+
+/// An error returned when waiting for the response to a [`MemberRequest`].
+///
+/// [`MemberRequest`]: struct.MemberRequest.html
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Error)]
+pub enum MemberRequestError {
+    /// Not all chunks were received before the timeout.
+    #[error("Timed out after receiving {received} member chunks")]
+    TimedOut {
+        /// The number of chunks received.
+        received: u32,
+        /// The number of chunks sent for the request, if any was received.
+        expected: Option<u32>,
+    },
+
+    /// The shard shut down or reconnected before all chunks were received.
+    #[error("The shard disconnected before all member chunks were received")]
+    Disconnected,
+
+    #[doc(hidden)]
+    #[error("unreachable")]
+    __Nonexhaustive,
+}
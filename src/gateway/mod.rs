@@ -46,13 +46,30 @@
 //! [`Client::start_shards`]: ../client/struct.Client.html#method.start_shards
 //! [docs]: https://discordapp.com/developers/docs/topics/gateway#sharding
 
+pub mod etf;
+#[cfg(any(test, feature = "fake_gateway"))]
+pub mod fake;
+
 mod error;
+mod identify;
+mod inflater;
+mod member_request;
+mod payload;
+mod recorder;
+mod session_store;
 mod shard;
+mod socket;
 mod ws_client_ext;
 
 pub use self::{
     error::GatewayError,
+    identify::{IdentifyOptions, IdentifyProperties},
+    member_request::{MemberRequest, MemberRequestError, RequestedMembers},
+    payload::GatewayPayload,
+    recorder::{RecordedPayload, TrafficLog, TrafficRecorder},
+    session_store::{FileSessionStore, SessionInfo, SessionStore},
     shard::Shard,
+    socket::{Connector, GatewaySocket, WebSocketConnector},
     ws_client_ext::WebSocketGatewayClientExt
 };
 
@@ -199,6 +216,70 @@ pub enum InterMessage {
     __Nonexhaustive,
 }
 
+/// The encoding of payloads sent over the gateway.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum GatewayEncoding {
+    /// Payloads are encoded as JSON.
+    ///
+    /// This is the default.
+    Json,
+    /// Payloads are encoded using the [Erlang External Term Format][etf],
+    /// which is considerably cheaper to decode than JSON.
+    ///
+    /// [etf]: etf/index.html
+    Etf,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl GatewayEncoding {
+    /// The value of the `encoding` query parameter of the gateway URL.
+    pub fn name(self) -> &'static str {
+        match self {
+            GatewayEncoding::Json => "json",
+            GatewayEncoding::Etf => "etf",
+            GatewayEncoding::__Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
+impl Default for GatewayEncoding {
+    fn default() -> Self {
+        GatewayEncoding::Json
+    }
+}
+
+/// The compression used for data received from the gateway.
+///
+/// Regardless of the choice, payloads sent to the gateway are never
+/// compressed.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TransportCompression {
+    /// No compression; every payload is received as text.
+    None,
+    /// Large payloads are compressed individually, as requested by the
+    /// `compression` field of the IDENTIFY payload.
+    ///
+    /// This is the default.
+    Payload,
+    /// The whole connection is compressed as a single zlib stream, as
+    /// requested by the `compress=zlib-stream` query parameter.
+    ///
+    /// This gives a noticeably better compression ratio than [`Payload`], at
+    /// the cost of keeping an inflate context around for each shard.
+    ///
+    /// [`Payload`]: #variant.Payload
+    ZlibStream,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Default for TransportCompression {
+    fn default() -> Self {
+        TransportCompression::Payload
+    }
+}
+
 pub enum ShardAction {
     Heartbeat,
     Identify,
@@ -0,0 +1,347 @@
+use serde::de::{
+    value::{Error as ValueError, StrDeserializer},
+    Deserialize,
+    Error as DeError,
+    IntoDeserializer,
+};
+use serde::ser::{Error as SerError, Serialize, Serializer};
+use serde_json::value::RawValue;
+use std::ops::Range;
+use crate::constants::OpCode;
+use crate::gateway::etf;
+use crate::internal::prelude::*;
+use crate::model::event::{EventType, GatewayEvent};
+
+/// A payload received from the gateway, of which only the envelope - the
+/// opcode, sequence and event type - has been read.
+///
+/// The event data of a JSON payload is kept as the received text, and is only
+/// deserialized by [`into_event`], straight from that text rather than through
+/// an intermediate `Value`. This allows a shard to look at a payload, such as
+/// to discard events nothing listens to, before paying for deserializing it.
+///
+/// Likewise, the data of an ETF payload is kept as its terms, and is only
+/// decoded by [`into_event`].
+///
+/// # Examples
+///
+/// ```rust
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::gateway::GatewayPayload;
+/// use serenity::model::event::GatewayEvent;
+///
+/// let text = r#"{"op":10,"s":null,"t":null,"d":{"heartbeat_interval":41250}}"#;
+/// let payload = GatewayPayload::from_json(text.to_string())?;
+///
+/// assert_eq!(payload.op(), 10);
+///
+/// match payload.into_event()? {
+///     GatewayEvent::Hello(interval) => assert_eq!(interval, 41250),
+///     other => panic!("Expected a Hello, got {:?}", other),
+/// }
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`into_event`]: #method.into_event
+#[derive(Clone, Debug)]
+pub struct GatewayPayload {
+    op: u64,
+    seq: Option<u64>,
+    kind: Option<String>,
+    data: Data,
+}
+
+#[derive(Clone, Debug)]
+enum Data {
+    // The received text, and where the `d` field's value is within it.
+    Json {
+        text: String,
+        range: Option<Range<usize>>,
+    },
+    // The received terms, and where the `d` field's term is within them.
+    Etf {
+        terms: Vec<u8>,
+        range: Option<Range<usize>>,
+    },
+    Value(Option<Value>),
+}
+
+// The fields of a JSON payload, other than its data, which is only located.
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    op: u64,
+    #[serde(default)]
+    s: Option<u64>,
+    #[serde(default)]
+    t: Option<String>,
+    #[serde(borrow, default)]
+    d: Option<&'a RawValue>,
+}
+
+impl GatewayPayload {
+    /// Reads the envelope of a payload received as JSON text.
+    ///
+    /// The text is checked to be valid JSON, but its data is left as is.
+    pub fn from_json(text: String) -> Result<Self> {
+        let (op, seq, kind, range) = {
+            let envelope: Envelope<'_> = serde_json::from_str(&text)?;
+            let range = envelope.d.map(|data| {
+                let start = data.get().as_ptr() as usize - text.as_ptr() as usize;
+
+                start..start + data.get().len()
+            });
+
+            (envelope.op, envelope.s, envelope.t, range)
+        };
+
+        Ok(GatewayPayload {
+            op,
+            seq,
+            kind,
+            data: Data::Json {
+                text,
+                range,
+            },
+        })
+    }
+
+    /// Reads the envelope of a payload received as ETF, including the leading
+    /// version byte.
+    ///
+    /// The payload is checked to be a complete term, but its data is left as
+    /// is.
+    pub fn from_etf(bytes: Vec<u8>) -> Result<Self> {
+        let envelope = etf::decode_envelope(bytes)?;
+
+        Ok(GatewayPayload {
+            op: envelope.op,
+            seq: envelope.seq,
+            kind: envelope.kind,
+            data: Data::Etf {
+                terms: envelope.terms,
+                range: envelope.data,
+            },
+        })
+    }
+
+    /// Reads the envelope of a payload that has already been decoded.
+    pub fn from_value(value: Value) -> Result<Self> {
+        let mut map = JsonMap::deserialize(value)?;
+
+        let op = map.remove("op")
+            .ok_or_else(|| serde_json::Error::missing_field("op"))
+            .and_then(u64::deserialize)?;
+        let seq = match map.remove("s") {
+            Some(s) => Option::<u64>::deserialize(s)?,
+            None => None,
+        };
+        let kind = match map.remove("t") {
+            Some(t) => Option::<String>::deserialize(t)?,
+            None => None,
+        };
+
+        Ok(GatewayPayload {
+            op,
+            seq,
+            kind,
+            data: Data::Value(map.remove("d").filter(|data| !data.is_null())),
+        })
+    }
+
+    /// The payload's opcode.
+    pub fn op(&self) -> u64 {
+        self.op
+    }
+
+    /// The sequence number of a dispatched event.
+    pub fn seq(&self) -> Option<u64> {
+        self.seq
+    }
+
+    /// The name of a dispatched event's type, such as `"MESSAGE_CREATE"`.
+    pub fn kind(&self) -> Option<&str> {
+        self.kind.as_ref().map(String::as_str)
+    }
+
+    /// The type of a dispatched event.
+    pub fn event_type(&self) -> Option<EventType> {
+        let deserializer: StrDeserializer<'_, ValueError> = self.kind()?.into_deserializer();
+
+        EventType::deserialize(deserializer).ok()
+    }
+
+    /// Decodes the payload's data into a `Value`.
+    ///
+    /// This is as expensive as deserializing the payload as a whole, and is
+    /// only meant for where its data must be inspected generically.
+    pub(crate) fn data_value(&self) -> Result<Option<Value>> {
+        match self.data {
+            Data::Json { ref text, range: Some(ref range) } => {
+                serde_json::from_str(&text[range.clone()]).map(Some).map_err(From::from)
+            },
+            Data::Etf { ref terms, range: Some(ref range) } => {
+                etf::decode_data(&terms[range.clone()]).map(Some)
+            },
+            Data::Json { range: None, .. } | Data::Etf { range: None, .. } => Ok(None),
+            Data::Value(ref data) => Ok(data.clone()),
+        }
+    }
+
+    /// Deserializes the payload into a gateway event.
+    pub fn into_event(self) -> Result<GatewayEvent> {
+        let op = OpCode::deserialize(Value::from(self.op))?;
+        let kind = self.event_type();
+
+        match self.data {
+            Data::Json { ref text, range: Some(ref range) } => {
+                let mut deserializer = serde_json::Deserializer::from_str(&text[range.clone()]);
+
+                GatewayEvent::from_parts(op, self.seq, kind, Some(&mut deserializer))
+                    .map_err(From::from)
+            },
+            Data::Json { range: None, .. } => {
+                GatewayEvent::from_parts(op, self.seq, kind, None::<Value>).map_err(From::from)
+            },
+            Data::Etf { .. } | Data::Value(_) => {
+                let data = self.data_value()?;
+
+                GatewayEvent::from_parts(op, self.seq, kind, data).map_err(From::from)
+            },
+        }
+    }
+
+    /// Decodes the payload into a `Value` as a whole.
+    pub fn into_value(self) -> Result<Value> {
+        if let Data::Json { ref text, .. } = self.data {
+            return serde_json::from_str(text).map_err(From::from);
+        }
+
+        let data = self.data_value()?;
+
+        let mut map = JsonMap::new();
+        map.insert("op".to_string(), Value::from(self.op));
+        map.insert("s".to_string(), self.seq.map_or(Value::Null, Value::from));
+        map.insert("t".to_string(), self.kind.map_or(Value::Null, Value::from));
+        map.insert("d".to_string(), data.unwrap_or(Value::Null));
+
+        Ok(Value::Object(map))
+    }
+}
+
+impl Serialize for GatewayPayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        match self.data {
+            // The text was checked to be valid JSON when the payload was read.
+            Data::Json { ref text, .. } => serde_json::from_str::<&RawValue>(text)
+                .map_err(S::Error::custom)?
+                .serialize(serializer),
+            Data::Etf { .. } | Data::Value(_) => self.clone().into_value()
+                .map_err(S::Error::custom)?
+                .serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+    use serde_json::json;
+    use super::GatewayPayload;
+    use crate::model::event::{Event, EventType, GatewayEvent};
+
+    const MESSAGE_CREATE: &str = r#"{
+        "op": 0,
+        "s": 3,
+        "t": "MESSAGE_CREATE",
+        "d": {
+            "id": "3",
+            "channel_id": "2",
+            "author": {
+                "id": "1",
+                "username": "user",
+                "discriminator": "0001",
+                "avatar": null
+            },
+            "content": "hello \"world\"",
+            "timestamp": "2020-01-01T00:00:00+00:00",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0
+        }
+    }"#;
+
+    #[test]
+    fn reads_the_envelope_of_json_payloads() {
+        let payload = GatewayPayload::from_json(MESSAGE_CREATE.to_string()).unwrap();
+
+        assert_eq!(payload.op(), 0);
+        assert_eq!(payload.seq(), Some(3));
+        assert_eq!(payload.kind(), Some("MESSAGE_CREATE"));
+        assert_eq!(payload.event_type(), Some(EventType::MessageCreate));
+
+        let value: serde_json::Value = serde_json::from_str(MESSAGE_CREATE).unwrap();
+        assert_eq!(payload.data_value().unwrap().as_ref(), value.get("d"));
+        assert_eq!(serde_json::to_value(&payload).unwrap(), value);
+    }
+
+    #[test]
+    fn json_and_value_payloads_deserialize_alike() {
+        let value: serde_json::Value = serde_json::from_str(MESSAGE_CREATE).unwrap();
+
+        let from_json = GatewayPayload::from_json(MESSAGE_CREATE.to_string())
+            .unwrap()
+            .into_event()
+            .unwrap();
+        let from_value = GatewayPayload::from_value(value.clone())
+            .unwrap()
+            .into_event()
+            .unwrap();
+        let expected = GatewayEvent::deserialize(value).unwrap();
+
+        for event in &[from_json, from_value] {
+            match (event, &expected) {
+                (
+                    GatewayEvent::Dispatch(3, Event::MessageCreate(ref event)),
+                    GatewayEvent::Dispatch(3, Event::MessageCreate(ref expected)),
+                ) => {
+                    assert_eq!(event.message.content, "hello \"world\"");
+                    assert_eq!(event.message.id, expected.message.id);
+                    assert_eq!(event.message.author.id, expected.message.author.id);
+                },
+                other => panic!("Expected a MessageCreate, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn payloads_without_data_deserialize() {
+        let payload = GatewayPayload::from_json(r#"{"op":11}"#.to_string()).unwrap();
+
+        assert_eq!(payload.seq(), None);
+        assert_eq!(payload.kind(), None);
+        assert_eq!(payload.data_value().unwrap(), None);
+
+        match payload.into_event().unwrap() {
+            GatewayEvent::HeartbeatAck => {},
+            other => panic!("Expected a HeartbeatAck, got {:?}", other),
+        }
+
+        let payload = GatewayPayload::from_value(json!({"op": 9, "d": false})).unwrap();
+
+        match payload.into_event().unwrap() {
+            GatewayEvent::InvalidateSession(false) => {},
+            other => panic!("Expected an InvalidateSession, got {:?}", other),
+        }
+    }
+}
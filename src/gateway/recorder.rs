@@ -0,0 +1,210 @@
+use flate2::{
+    read::MultiGzDecoder,
+    write::GzEncoder,
+    Compression,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use crate::internal::prelude::*;
+
+/// A raw gateway payload received by a shard, as stored by a
+/// [`TrafficRecorder`].
+///
+/// [`TrafficRecorder`]: struct.TrafficRecorder.html
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RecordedPayload {
+    /// When the payload was received, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The ID of the shard that received the payload.
+    pub shard_id: u64,
+    /// The payload, as received from the gateway.
+    pub payload: Value,
+}
+
+#[derive(Serialize)]
+struct BorrowedPayload<'a, P: ?Sized> {
+    timestamp: u64,
+    shard_id: u64,
+    payload: &'a P,
+}
+
+/// Records every payload the shards of a client receive to a log file.
+///
+/// Payloads are written as one JSON object per line, compressed with gzip.
+/// The log is flushed after each payload, so that it can be read even if the
+/// process exits without the recorder being dropped.
+///
+/// Logs can be read via [`TrafficLog`], and replayed through a client's event
+/// handlers via a [`Replayer`].
+///
+/// # Examples
+///
+/// Record the traffic of all shards started by a client:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # struct Handler;
+/// #
+/// # impl EventHandler for Handler {}
+/// #
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::gateway::TrafficRecorder;
+///
+/// let client = Client::builder()
+///     .token("my token")
+///     .event_handler(Handler)
+///     .recorder(TrafficRecorder::create("traffic.log.gz")?)
+///     .build()?;
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`Replayer`]: ../client/struct.Replayer.html
+/// [`TrafficLog`]: struct.TrafficLog.html
+#[derive(Clone, Debug)]
+pub struct TrafficRecorder {
+    writer: Arc<Mutex<GzEncoder<File>>>,
+}
+
+impl TrafficRecorder {
+    /// Creates a recorder writing to the file at the given path, truncating it
+    /// if it exists.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::create(path)?;
+
+        Ok(TrafficRecorder {
+            writer: Arc::new(Mutex::new(GzEncoder::new(file, Compression::default()))),
+        })
+    }
+
+    /// Records a payload received by the shard with the given ID, timestamped
+    /// with the current time.
+    ///
+    /// The payload is usually a `Value` or a [`GatewayPayload`].
+    ///
+    /// [`GatewayPayload`]: struct.GatewayPayload.html
+    pub fn record<P>(&self, shard_id: u64, payload: &P) -> Result<()>
+        where P: Serialize + ?Sized {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+
+        let mut writer = self.writer.lock();
+        serde_json::to_writer(&mut *writer, &BorrowedPayload {
+            timestamp,
+            shard_id,
+            payload,
+        })?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Reads the payloads recorded by a [`TrafficRecorder`], in the order they
+/// were received.
+///
+/// # Examples
+///
+/// Count the payloads received by each shard:
+///
+/// ```rust,no_run
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::gateway::TrafficLog;
+/// use std::collections::HashMap;
+///
+/// let mut counts = HashMap::new();
+///
+/// for payload in TrafficLog::open("traffic.log.gz")? {
+///     *counts.entry(payload?.shard_id).or_insert(0) += 1;
+/// }
+///
+/// println!("{:?}", counts);
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`TrafficRecorder`]: struct.TrafficRecorder.html
+#[derive(Debug)]
+pub struct TrafficLog {
+    reader: BufReader<MultiGzDecoder<File>>,
+    line: String,
+}
+
+impl TrafficLog {
+    /// Opens the log at the given path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+
+        Ok(TrafficLog {
+            reader: BufReader::new(MultiGzDecoder::new(file)),
+            line: String::new(),
+        })
+    }
+}
+
+impl Iterator for TrafficLog {
+    type Item = Result<RecordedPayload>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.line.clear();
+
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => None,
+            // The log ends without a gzip trailer if the recorder was never
+            // dropped, such as when the process was killed. Every payload was
+            // flushed by then, so this is the end of the log.
+            Err(ref why) if why.kind() == ErrorKind::UnexpectedEof => None,
+            Err(why) => Some(Err(why.into())),
+            Ok(_) => Some(serde_json::from_str(&self.line).map_err(From::from)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use std::{env, fs};
+    use super::{TrafficLog, TrafficRecorder};
+
+    #[test]
+    fn reads_recorded_payloads() {
+        let path = env::temp_dir().join(format!("serenity-traffic-{}.log.gz", std::process::id()));
+        let recorder = TrafficRecorder::create(&path).unwrap();
+
+        recorder.record(0, &json!({"op": 10, "d": {"heartbeat_interval": 41250}})).unwrap();
+        recorder.record(1, &json!({"op": 11})).unwrap();
+
+        // The recorder is still alive, so the log has no trailer yet.
+        let payloads = TrafficLog::open(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(payloads[0].shard_id, 0);
+        assert_eq!(payloads[0].payload["d"]["heartbeat_interval"], 41250);
+        assert_eq!(payloads[1].shard_id, 1);
+        assert_eq!(payloads[1].payload, json!({"op": 11}));
+        assert!(payloads[0].timestamp <= payloads[1].timestamp);
+
+        drop(recorder);
+        assert_eq!(TrafficLog::open(&path).unwrap().count(), 2);
+
+        let _ = fs::remove_file(&path);
+    }
+}
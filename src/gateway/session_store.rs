@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+};
+use crate::internal::{files, prelude::*};
+
+/// The information required to resume a shard's gateway session.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SessionInfo {
+    /// The ID of the session, as received in the [`Ready`] event.
+    ///
+    /// [`Ready`]: ../model/event/struct.ReadyEvent.html
+    pub session_id: String,
+    /// The sequence of the last event received.
+    pub seq: u64,
+    /// The shard's ID and the total number of shards the session was started
+    /// with.
+    ///
+    /// A session can not be resumed with a different number of shards.
+    pub shard_info: [u64; 2],
+}
+
+/// A store for shards' gateway sessions, allowing them to be resumed after
+/// the process restarts instead of identifying again.
+///
+/// Resuming avoids replaying a [`GuildCreate`] for every guild and does not
+/// count against the [`SessionStartLimit`].
+///
+/// Sessions are saved when a shard is shut down, and taken out of the store
+/// when the shard starts again; if the gateway rejects the resume, the shard
+/// identifies as usual.
+///
+/// Refer to [`FileSessionStore`] for an implementation persisting sessions to
+/// disk.
+///
+/// [`FileSessionStore`]: struct.FileSessionStore.html
+/// [`GuildCreate`]: ../model/event/struct.GuildCreateEvent.html
+/// [`SessionStartLimit`]: ../model/gateway/struct.SessionStartLimit.html
+pub trait SessionStore: Send + Sync {
+    /// Loads the session of the shard with the given ID, if there is one.
+    fn load(&self, shard_id: u64) -> Result<Option<SessionInfo>>;
+
+    /// Saves the session of the shard with the given ID, replacing any
+    /// previous one.
+    fn save(&self, shard_id: u64, session: &SessionInfo) -> Result<()>;
+
+    /// Removes the session of the shard with the given ID, if there is one.
+    fn remove(&self, shard_id: u64) -> Result<()>;
+}
+
+impl Debug for dyn SessionStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("SessionStore")
+    }
+}
+
+/// A [`SessionStore`] keeping one JSON file per shard in a directory.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use serenity::gateway::{FileSessionStore, SessionInfo, SessionStore};
+///
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// let store = FileSessionStore::new("sessions");
+///
+/// store.save(0, &SessionInfo {
+///     session_id: "f97bdc3210d0da33a3c61d8363e68579".to_string(),
+///     seq: 42,
+///     shard_info: [0, 1],
+/// })?;
+///
+/// assert_eq!(store.load(0)?.map(|session| session.seq), Some(42));
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+///
+/// [`SessionStore`]: trait.SessionStore.html
+#[derive(Clone, Debug)]
+pub struct FileSessionStore {
+    dir: PathBuf,
+}
+
+impl FileSessionStore {
+    /// Creates a store keeping sessions in the given directory, which is
+    /// created when the first session is saved.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileSessionStore {
+            dir: dir.into(),
+        }
+    }
+
+    fn path(&self, shard_id: u64) -> PathBuf {
+        self.dir.join(format!("shard_{}.json", shard_id))
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn load(&self, shard_id: u64) -> Result<Option<SessionInfo>> {
+        match fs::read(self.path(shard_id)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(From::from),
+            Err(ref why) if why.kind() == ErrorKind::NotFound => Ok(None),
+            Err(why) => Err(why.into()),
+        }
+    }
+
+    fn save(&self, shard_id: u64, session: &SessionInfo) -> Result<()> {
+        files::write_json(&self.path(shard_id), session)
+    }
+
+    fn remove(&self, shard_id: u64) -> Result<()> {
+        files::remove(&self.path(shard_id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use super::{FileSessionStore, SessionInfo, SessionStore};
+
+    fn store(name: &str) -> FileSessionStore {
+        let dir = env::temp_dir().join(format!("serenity-sessions-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        FileSessionStore::new(dir)
+    }
+
+    fn session(seq: u64) -> SessionInfo {
+        SessionInfo {
+            session_id: "f97bdc3210d0da33a3c61d8363e68579".to_string(),
+            seq,
+            shard_info: [1, 2],
+        }
+    }
+
+    #[test]
+    fn saves_and_loads_sessions() {
+        let store = store("save");
+
+        assert_eq!(store.load(1).unwrap(), None);
+
+        store.save(1, &session(10)).unwrap();
+        store.save(1, &session(20)).unwrap();
+
+        assert_eq!(store.load(1).unwrap(), Some(session(20)));
+        assert_eq!(store.load(0).unwrap(), None);
+
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn removes_sessions() {
+        let store = store("remove");
+
+        store.remove(1).unwrap();
+        store.save(1, &session(10)).unwrap();
+        store.remove(1).unwrap();
+
+        assert_eq!(store.load(1).unwrap(), None);
+
+        let _ = fs::remove_dir_all(&store.dir);
+    }
+}
@@ -9,29 +9,36 @@ use log::{error, debug, info, trace, warn};
 use tungstenite::{
     error::Error as TungsteniteError,
     protocol::frame::CloseFrame,
+    Message,
 };
-#[cfg(feature = "native_tls_backend")]
-use tungstenite::handshake::client::Request;
 
 use crate::constants::{self, close_codes};
 use crate::internal::prelude::*;
 use crate::model::{
     event::{Event, GatewayEvent},
-    gateway::Activity,
+    gateway::{Activity, GatewayIntents},
     id::GuildId,
     user::OnlineStatus
 };
 
-#[cfg(not(feature = "native_tls_backend"))]
-use crate::internal::ws_impl::create_rustls_client;
+use crate::internal::ws_impl;
 
 use super::{
+    inflater::Inflater,
     ConnectionStage,
+    Connector,
     CurrentPresence,
+    GatewayEncoding,
+    GatewayPayload,
+    GatewaySocket,
+    IdentifyOptions,
+    MemberRequest,
     ShardAction,
     GatewayError,
     ReconnectType,
-    WsClient,
+    SessionInfo,
+    TransportCompression,
+    WebSocketConnector,
     WebSocketGatewayClientExt,
 };
 
@@ -71,8 +78,11 @@ use super::{
 /// [docs]: https://discordapp.com/developers/docs/topics/gateway#sharding
 /// [module docs]: index.html#sharding
 pub struct Shard {
-    pub client: WsClient,
+    pub client: Box<dyn GatewaySocket>,
+    compression: TransportCompression,
+    connector: Arc<dyn Connector>,
     current_presence: CurrentPresence,
+    encoding: GatewayEncoding,
     /// A tuple of:
     ///
     /// - the last instant that a heartbeat was sent
@@ -83,6 +93,13 @@ pub struct Shard {
     /// [`latency`]: fn.latency.html
     heartbeat_instants: (Option<Instant>, Option<Instant>),
     heartbeat_interval: Option<u64>,
+    /// The inflate context shared by the connection when using
+    /// [`TransportCompression::ZlibStream`].
+    ///
+    /// [`TransportCompression::ZlibStream`]: enum.TransportCompression.html#variant.ZlibStream
+    identify_options: IdentifyOptions,
+    inflater: Inflater,
+    intents: Option<GatewayIntents>,
     /// This is used by the heartbeater to determine whether the last
     /// heartbeat was sent without an acknowledgement, and whether to reconnect.
     // This _must_ be set to `true` in `Shard::handle_event`'s
@@ -141,12 +158,58 @@ impl Shard {
         token: &str,
         shard_info: [u64; 2],
     ) -> Result<Shard> {
-        let mut client = connect(&*ws_url.lock())?;
+        Self::new_with_transport(
+            ws_url,
+            token,
+            shard_info,
+            TransportCompression::default(),
+            GatewayEncoding::default(),
+        )
+    }
 
-        // Configure timeout and buffer sizes. See the respective
-        // methods for the reasoning behind changing the defaults.
-        let _ = set_client_timeout(&mut client);
-        set_client_buffer_sizes(&mut client);
+    /// Instantiates a new instance of a Shard using the given compression for
+    /// data received from the gateway and the given payload encoding.
+    ///
+    /// Refer to [`new`] for more information.
+    ///
+    /// [`new`]: #method.new
+    pub fn new_with_transport(
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: [u64; 2],
+        compression: TransportCompression,
+        encoding: GatewayEncoding,
+    ) -> Result<Shard> {
+        Self::new_with_connector(
+            ws_url,
+            token,
+            shard_info,
+            compression,
+            encoding,
+            Arc::new(WebSocketConnector),
+        )
+    }
+
+    /// Instantiates a new instance of a Shard, opening its connections to the
+    /// gateway via the given [`Connector`] instead of over the network.
+    ///
+    /// Refer to [`new`] for more information, and to the [`fake`] module -
+    /// behind the `fake_gateway` feature - for running a shard against a fake
+    /// gateway.
+    ///
+    /// [`Connector`]: trait.Connector.html
+    /// [`fake`]: fake/index.html
+    /// [`new`]: #method.new
+    pub fn new_with_connector(
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: [u64; 2],
+        compression: TransportCompression,
+        encoding: GatewayEncoding,
+        connector: Arc<dyn Connector>,
+    ) -> Result<Shard> {
+        let url = build_gateway_url(&ws_url.lock(), compression, encoding)?;
+        let client = connector.connect(url)?;
 
         let current_presence = (None, OnlineStatus::Online);
         let heartbeat_instants = (None, None);
@@ -159,9 +222,15 @@ impl Shard {
         Ok(Shard {
             shutdown: false,
             client,
+            compression,
+            connector,
             current_presence,
+            encoding,
             heartbeat_instants,
             heartbeat_interval,
+            identify_options: IdentifyOptions::default(),
+            inflater: Inflater::new(),
+            intents: None,
             last_heartbeat_acknowledged,
             seq,
             stage,
@@ -173,6 +242,18 @@ impl Shard {
         })
     }
 
+    /// Retrieves the compression used for data received from the gateway.
+    #[inline]
+    pub fn compression(&self) -> TransportCompression {
+        self.compression
+    }
+
+    /// Retrieves the encoding of payloads sent over the gateway.
+    #[inline]
+    pub fn encoding(&self) -> GatewayEncoding {
+        self.encoding
+    }
+
     /// Retrieves the current presence of the shard.
     #[inline]
     pub fn current_presence(&self) -> &CurrentPresence {
@@ -223,7 +304,7 @@ impl Shard {
     ///
     /// [`GatewayError::HeartbeatFailed`]: enum.GatewayError.html#variant.HeartbeatFailed
     pub fn heartbeat(&mut self) -> Result<()> {
-        match self.client.send_heartbeat(&self.shard_info, Some(self.seq)) {
+        match self.client.send_heartbeat(&self.shard_info, Some(self.seq), self.encoding) {
             Ok(()) => {
                 self.heartbeat_instants.0 = Some(Instant::now());
                 self.last_heartbeat_acknowledged = false;
@@ -291,6 +372,43 @@ impl Shard {
         self.current_presence.0 = activity;
     }
 
+    /// Retrieves the gateway intents sent when identifying, if any.
+    #[inline]
+    pub fn intents(&self) -> Option<GatewayIntents> {
+        self.intents
+    }
+
+    /// Sets the gateway intents to send when identifying.
+    ///
+    /// If `None`, no intents are sent and Discord dispatches every event.
+    ///
+    /// **Note**: This only takes effect on the next identify, and does not
+    /// change the events received by an already identified session.
+    #[inline]
+    pub fn set_intents(&mut self, intents: Option<GatewayIntents>) {
+        self.intents = intents;
+    }
+
+    /// Retrieves the options sent when identifying.
+    #[inline]
+    pub fn identify_options(&self) -> &IdentifyOptions {
+        &self.identify_options
+    }
+
+    /// Sets the options to send when identifying.
+    ///
+    /// If the options include a presence, it replaces the shard's current
+    /// presence.
+    ///
+    /// **Note**: This only takes effect on the next identify.
+    pub fn set_identify_options(&mut self, options: IdentifyOptions) {
+        if let Some((ref activity, status)) = options.presence {
+            self.set_presence(status, activity.clone());
+        }
+
+        self.identify_options = options;
+    }
+
     #[inline]
     pub fn set_presence(&mut self, status: OnlineStatus, activity: Option<Activity>) {
         self.set_activity(activity);
@@ -344,12 +462,6 @@ impl Shard {
 
     fn handle_gateway_dispatch(&mut self, seq: u64, event: &Event)
                                -> Result<Option<ShardAction>> {
-
-        
-        if seq > self.seq + 1 {
-            warn!("[Shard {:?}] Sequence off; them: {}, us: {}", self.shard_info, seq, self.seq);
-        }
-
         match *event {
             Event::Ready(ref ready) => {
                 debug!("[Shard {:?}] Received Ready", self.shard_info);
@@ -367,11 +479,21 @@ impl Shard {
             _ => {},
         }
 
-        self.seq = seq;
+        self.handle_discarded_dispatch(seq);
 
         Ok(None)
     }
 
+    /// Handles the sequence number of a dispatch that was discarded without
+    /// being deserialized, so that the shard can still resume after it.
+    pub(crate) fn handle_discarded_dispatch(&mut self, seq: u64) {
+        if seq > self.seq + 1 {
+            warn!("[Shard {:?}] Sequence off; them: {}, us: {}", self.shard_info, seq, self.seq);
+        }
+
+        self.seq = seq;
+    }
+
     fn handle_heartbeat_event(&mut self, s: u64) -> Result<Option<ShardAction>> {
         info!("[Shard {:?}] Received shard heartbeat", self.shard_info);
 
@@ -452,6 +574,19 @@ impl Shard {
 
                             return Err(GatewayError::OverloadedShard.into());
                         },
+                        Some(close_codes::INVALID_GATEWAY_INTENTS) => {
+                            error!("[Shard {:?}] Sent invalid gateway intents",
+                                   self.shard_info);
+
+                            return Err(GatewayError::InvalidGatewayIntents.into());
+                        },
+                        Some(close_codes::DISALLOWED_GATEWAY_INTENTS) => {
+                            error!("[Shard {:?}] Sent disallowed gateway intents; \
+                                    privileged intents must be enabled for the application",
+                                   self.shard_info);
+
+                            return Err(GatewayError::DisallowedGatewayIntents.into());
+                        },
                         Some(4006) | Some(close_codes::SESSION_TIMEOUT) => {
                             info!("[Shard {:?}] Invalid session", self.shard_info);
 
@@ -504,6 +639,10 @@ impl Shard {
     ///
     /// Returns a `GatewayError::OverloadedShard` if the shard would have too
     /// many guilds assigned to it.
+    ///
+    /// Returns a `GatewayError::InvalidGatewayIntents` or
+    /// `GatewayError::DisallowedGatewayIntents` if the intents sent in the
+    /// IDENTIFY were invalid or not enabled for the application.
     pub(crate) fn handle_event(&mut self, event: &Result<GatewayEvent>)
         -> Result<Option<ShardAction>> {
         match *event {
@@ -525,14 +664,16 @@ impl Shard {
                        self.shard_info,
                        interval);
 
-                if self.stage == ConnectionStage::Resuming {
-                    return Ok(None);
-                }
-
                 if interval > 0 {
                     self.heartbeat_interval = Some(interval);
                 }
 
+                // A RESUME is sent without waiting for the Hello, and a shard
+                // resuming a saved session has no interval yet.
+                if self.stage == ConnectionStage::Resuming {
+                    return Ok(None);
+                }
+
                     Ok(Some(if self.stage == ConnectionStage::Handshake {
                     ShardAction::Identify
                 } else {
@@ -757,15 +898,43 @@ impl Shard {
             &self.shard_info,
             limit,
             query,
+            self.encoding,
         )
     }
 
+    /// Requests members of a guild, with a nonce that is included in each
+    /// [`GuildMembersChunkEvent`] sent in response.
+    ///
+    /// Refer to [`ShardMessenger::request_members`] to wait for the response.
+    ///
+    /// [`GuildMembersChunkEvent`]: ../model/event/struct.GuildMembersChunkEvent.html
+    /// [`ShardMessenger::request_members`]: ../client/bridge/gateway/struct.ShardMessenger.html#method.request_members
+    pub fn request_members(&mut self, request: &MemberRequest, nonce: &str) -> Result<()> {
+        self.client.send_request_members(&self.shard_info, request, nonce, self.encoding)
+    }
+
     // Sets the shard as going into identifying stage, which sets:
     //
     // - the time that the last heartbeat sent as being now
     // - the `stage` to `Identifying`
     pub fn identify(&mut self) -> Result<()> {
-        self.client.send_identify(&self.shard_info, &self.token)?;
+        let compress = self.compression == TransportCompression::Payload;
+
+        // Send the current presence rather than the initial one, so that
+        // presence changes survive identifying again.
+        let mut options = self.identify_options.clone();
+        if options.presence.is_some() {
+            options.presence = Some(self.current_presence.clone());
+        }
+
+        self.client.send_identify(
+            &self.shard_info,
+            &self.token,
+            self.intents,
+            &options,
+            compress,
+            self.encoding,
+        )?;
 
         self.heartbeat_instants.0 = Some(Instant::now());
         self.stage = ConnectionStage::Identifying;
@@ -777,7 +946,7 @@ impl Shard {
     ///
     /// This will set the stage of the shard before and after instantiation of
     /// the client.
-    pub fn initialize(&mut self) -> Result<WsClient> {
+    pub fn initialize(&mut self) -> Result<Box<dyn GatewaySocket>> {
         debug!("[Shard {:?}] Initializing", self.shard_info);
 
         // We need to do two, sort of three things here:
@@ -790,11 +959,13 @@ impl Shard {
         // accurate when a Hello is received.
         self.stage = ConnectionStage::Connecting;
         self.started = Instant::now();
-        let mut client = connect(&self.ws_url.lock())?;
+        // A new connection starts a new zlib stream, so anything left over
+        // from the previous one must be discarded.
+        self.inflater.reset();
+        let url = build_gateway_url(&self.ws_url.lock(), self.compression, self.encoding)?;
+        let client = self.connector.connect(url)?;
         self.stage = ConnectionStage::Handshake;
 
-        let _ = set_client_timeout(&mut client);
-
         Ok(client)
     }
 
@@ -811,6 +982,48 @@ impl Shard {
         debug!("Shard {:?}] Attempting to resume", self.shard_info);
 
         self.client = self.initialize()?;
+
+        self.send_resume()
+    }
+
+    /// Resumes a session saved by a previous process over the current
+    /// connection, instead of identifying.
+    ///
+    /// This must be called before the shard identifies. If the gateway rejects
+    /// the session, the shard will reconnect and identify as usual.
+    ///
+    /// Returns `false` without sending anything if the session was started
+    /// with a different shard ID or total.
+    pub fn restore_session(&mut self, session: SessionInfo) -> Result<bool> {
+        if session.shard_info != self.shard_info {
+            debug!(
+                "[Shard {:?}] Not resuming session of shard {:?}",
+                self.shard_info,
+                session.shard_info,
+            );
+
+            return Ok(false);
+        }
+
+        info!("[Shard {:?}] Resuming saved session", self.shard_info);
+
+        self.session_id = Some(session.session_id);
+        self.seq = session.seq;
+
+        self.send_resume().map(|_| true)
+    }
+
+    /// Retrieves the information needed to resume the current session later,
+    /// if one has been established.
+    pub fn session_info(&self) -> Option<SessionInfo> {
+        self.session_id.as_ref().map(|session_id| SessionInfo {
+            session_id: session_id.clone(),
+            seq: self.seq,
+            shard_info: self.shard_info,
+        })
+    }
+
+    fn send_resume(&mut self) -> Result<()> {
         self.stage = ConnectionStage::Resuming;
 
         match self.session_id.as_ref() {
@@ -820,6 +1033,7 @@ impl Shard {
                     session_id,
                     self.seq,
                     &self.token,
+                    self.encoding,
                 )
             },
             None => Err(GatewayError::NoSessionId.into()),
@@ -835,61 +1049,102 @@ impl Shard {
         Ok(())
     }
 
+    /// Receives the next payload from the gateway, decompressing and decoding
+    /// it as necessary.
+    ///
+    /// Prefer [`recv_payload`], which leaves the data of JSON payloads to be
+    /// deserialized straight into an event.
+    ///
+    /// Returns `None` if the received message did not contain a payload, or
+    /// if a payload compressed via [`TransportCompression::ZlibStream`] has
+    /// only been partially received.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::Closed`] if the gateway closed the connection.
+    ///
+    /// [`GatewayError::Closed`]: enum.GatewayError.html#variant.Closed
+    /// [`TransportCompression::ZlibStream`]: enum.TransportCompression.html#variant.ZlibStream
+    /// [`recv_payload`]: #method.recv_payload
+    pub fn recv_json(&mut self) -> Result<Option<Value>> {
+        match self.recv_payload()? {
+            Some(payload) => payload.into_value().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Receives the next payload from the gateway, decompressing it as
+    /// necessary.
+    ///
+    /// Only the envelope of JSON payloads is read; their data is deserialized
+    /// straight from the received text once the payload is turned into an
+    /// event via [`GatewayPayload::into_event`].
+    ///
+    /// Returns `None` if the received message did not contain a payload, or
+    /// if a payload compressed via [`TransportCompression::ZlibStream`] has
+    /// only been partially received.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::Closed`] if the gateway closed the connection.
+    ///
+    /// [`GatewayError::Closed`]: enum.GatewayError.html#variant.Closed
+    /// [`GatewayPayload::into_event`]: struct.GatewayPayload.html#method.into_event
+    /// [`TransportCompression::ZlibStream`]: enum.TransportCompression.html#variant.ZlibStream
+    pub fn recv_payload(&mut self) -> Result<Option<GatewayPayload>> {
+        let encoding = self.encoding;
+        let shard_info = self.shard_info;
+        let zlib_stream = self.compression == TransportCompression::ZlibStream;
+
+        match self.client.read_message()? {
+            Message::Close(frame) => Err(GatewayError::Closed(frame).into()),
+            Message::Binary(bytes) if zlib_stream => match self.inflater.extend(&bytes)? {
+                Some(payload) => {
+                    ws_impl::decode_gateway_payload(payload.to_vec(), encoding).map(Some).map_err(|why| {
+                        warn!(
+                            "[Shard {:?}] Err deserializing inflated payload: {:?}; payload: {}",
+                            shard_info,
+                            why,
+                            String::from_utf8_lossy(payload),
+                        );
+
+                        why
+                    })
+                },
+                None => Ok(None),
+            },
+            message => ws_impl::convert_gateway_message(message, encoding),
+        }
+    }
+
     pub fn update_presence(&mut self) -> Result<()> {
         self.client.send_presence_update(
             &self.shard_info,
             &self.current_presence,
+            self.encoding,
         )
     }
 }
 
-#[cfg(not(feature = "native_tls_backend"))]
-fn connect(base_url: &str) -> Result<WsClient> {
-    let url = build_gateway_url(base_url)?;
-    Ok(create_rustls_client(url)?)
-}
-
-#[cfg(feature = "native_tls_backend")]
-fn connect(base_url: &str) -> Result<WsClient> {
-    let url = build_gateway_url(base_url)?;
-    let client = tungstenite::connect(Request::from(url))?;
-
-    Ok(client.0)
-}
-
-fn set_client_timeout(client: &mut WsClient) -> Result<()> {
-    #[cfg(not(feature = "native_tls_backend"))]
-    let stream = &client.get_mut().sock;
-
-    #[cfg(feature = "native_tls_backend")]
-    let stream = match client.get_mut() {
-        tungstenite::stream::Stream::Plain(stream) => stream,
-        tungstenite::stream::Stream::Tls(stream) => stream.get_mut(),
+fn build_gateway_url(
+    base: &str,
+    compression: TransportCompression,
+    encoding: GatewayEncoding,
+) -> Result<Url> {
+    let compress = match compression {
+        TransportCompression::ZlibStream => "&compress=zlib-stream",
+        _ => "",
     };
 
-    stream.set_read_timeout(Some(StdDuration::from_millis(500)))?;
-    stream.set_write_timeout(Some(StdDuration::from_secs(50)))?;
-    Ok(())
-}
-
-fn set_client_buffer_sizes(client: &mut WsClient) {
-    // Despite chunking members inside larger guilds, Discord will
-    // still send us the online state of all members at the same time
-    // in a single frame. By default, tungstenite only allows frames
-    // with a maximum of 16mb at a time. Larger guilds can easily surpass
-    // this limit.
-    //
-    // Since we know all traffic is coming from a trusted source (Discord),
-    // we can remove the buffer limit entirely. This eliminates the issue
-    // where we have to keep upping buffer sizes because of growing guilds.
-    client.set_config(|c| {
-        c.max_frame_size = None;
-        c.max_message_size = None;
-    })
-}
+    let url = format!(
+        "{}?v={}&encoding={}{}",
+        base,
+        constants::GATEWAY_VERSION,
+        encoding.name(),
+        compress,
+    );
 
-fn build_gateway_url(base: &str) -> Result<Url> {
-    Url::parse(&format!("{}?v={}", base, constants::GATEWAY_VERSION))
+    Url::parse(&url)
         .map_err(|why| {
             warn!("Error building gateway URL with base `{}`: {:?}", base, why);
 
@@ -0,0 +1,174 @@
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    io::{ErrorKind, Read, Write},
+    net::TcpStream,
+    time::Duration as StdDuration,
+};
+use tungstenite::{
+    error::Error as TungsteniteError,
+    handshake::HandshakeError,
+    protocol::{frame::CloseFrame, WebSocket},
+    Message,
+};
+#[cfg(feature = "native_tls_backend")]
+use tungstenite::handshake::client::Request;
+use url::Url;
+use crate::internal::prelude::*;
+
+#[cfg(not(feature = "native_tls_backend"))]
+use crate::internal::ws_impl::create_rustls_client;
+
+use super::WsClient;
+
+/// A WebSocket connection to the gateway, as used by a [`Shard`].
+///
+/// This mirrors the parts of tungstenite's `WebSocket` used by shards, which
+/// implements it, so that a shard can be run over something other than a real
+/// connection to Discord, such as a [`FakeGateway`].
+///
+/// [`FakeGateway`]: fake/struct.FakeGateway.html
+/// [`Shard`]: struct.Shard.html
+pub trait GatewaySocket: Send {
+    /// Reads the next message.
+    ///
+    /// If no message arrives within a short timeout, an [`Io`] error of kind
+    /// `WouldBlock` or `TimedOut` is returned, so that the shard can heartbeat
+    /// and handle messages from its runner in between.
+    ///
+    /// [`Io`]: https://docs.rs/tungstenite/*/tungstenite/error/enum.Error.html#variant.Io
+    fn read_message(&mut self) -> StdResult<Message, TungsteniteError>;
+
+    /// Writes a message.
+    fn write_message(&mut self, message: Message) -> StdResult<(), TungsteniteError>;
+
+    /// Starts the closing handshake, optionally with a close code and reason.
+    fn close(&mut self, frame: Option<CloseFrame<'static>>) -> StdResult<(), TungsteniteError>;
+}
+
+impl<S: Read + Write + Send> GatewaySocket for WebSocket<S> {
+    fn read_message(&mut self) -> StdResult<Message, TungsteniteError> {
+        WebSocket::read_message(self)
+    }
+
+    fn write_message(&mut self, message: Message) -> StdResult<(), TungsteniteError> {
+        WebSocket::write_message(self, message)
+    }
+
+    fn close(&mut self, frame: Option<CloseFrame<'static>>) -> StdResult<(), TungsteniteError> {
+        WebSocket::close(self, frame)
+    }
+}
+
+/// Opens [`GatewaySocket`]s for shards, whenever they connect or reconnect.
+///
+/// The default is a [`WebSocketConnector`], connecting to the gateway over
+/// the network.
+///
+/// [`GatewaySocket`]: trait.GatewaySocket.html
+/// [`WebSocketConnector`]: struct.WebSocketConnector.html
+pub trait Connector: Send + Sync {
+    /// Opens a connection to the given gateway URL, which includes the
+    /// version, encoding and compression as query parameters.
+    fn connect(&self, url: Url) -> Result<Box<dyn GatewaySocket>>;
+}
+
+impl Debug for dyn Connector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("Connector")
+    }
+}
+
+/// A [`Connector`] opening WebSocket connections over the network.
+///
+/// `wss` URLs are connected to over TLS, using the TLS backend chosen via the
+/// crate's features, while `ws` URLs - such as a [`FakeGateway`] listening on
+/// localhost - are connected to in plain text.
+///
+/// [`Connector`]: trait.Connector.html
+/// [`FakeGateway`]: fake/struct.FakeGateway.html
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WebSocketConnector;
+
+impl Connector for WebSocketConnector {
+    fn connect(&self, url: Url) -> Result<Box<dyn GatewaySocket>> {
+        if url.scheme() == "ws" {
+            return connect_plain(url).map(|client| Box::new(client) as Box<dyn GatewaySocket>);
+        }
+
+        let mut client = connect(url)?;
+
+        // Configure timeout and buffer sizes. See the respective
+        // functions for the reasoning behind changing the defaults.
+        let _ = set_client_timeout(&mut client);
+        set_client_buffer_sizes(&mut client);
+
+        Ok(Box::new(client))
+    }
+}
+
+#[cfg(not(feature = "native_tls_backend"))]
+fn connect(url: Url) -> Result<WsClient> {
+    Ok(create_rustls_client(url)?)
+}
+
+#[cfg(feature = "native_tls_backend")]
+fn connect(url: Url) -> Result<WsClient> {
+    let client = tungstenite::connect(Request::from(url))?;
+
+    Ok(client.0)
+}
+
+fn connect_plain(url: Url) -> Result<WebSocket<TcpStream>> {
+    let host = url.host_str()
+        .ok_or_else(|| SerenityError::Url("No host name in the URL.".into()))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = url.port_or_known_default()
+        .ok_or_else(|| SerenityError::Url("No port number in the URL.".into()))?;
+
+    let stream = TcpStream::connect((&host[..], port))?;
+    stream.set_read_timeout(Some(StdDuration::from_millis(500)))?;
+    stream.set_write_timeout(Some(StdDuration::from_secs(50)))?;
+
+    let (mut client, _) = tungstenite::client(url, stream).map_err(|why| match why {
+        HandshakeError::Failure(why) => why,
+        // The stream is blocking, so the handshake is only interrupted by the
+        // read timeout.
+        HandshakeError::Interrupted(_) => TungsteniteError::Io(ErrorKind::TimedOut.into()),
+    })?;
+    set_client_buffer_sizes(&mut client);
+
+    Ok(client)
+}
+
+fn set_client_timeout(client: &mut WsClient) -> Result<()> {
+    #[cfg(not(feature = "native_tls_backend"))]
+    let stream = &client.get_mut().sock;
+
+    #[cfg(feature = "native_tls_backend")]
+    let stream = match client.get_mut() {
+        tungstenite::stream::Stream::Plain(stream) => stream,
+        tungstenite::stream::Stream::Tls(stream) => stream.get_mut(),
+    };
+
+    stream.set_read_timeout(Some(StdDuration::from_millis(500)))?;
+    stream.set_write_timeout(Some(StdDuration::from_secs(50)))?;
+    Ok(())
+}
+
+fn set_client_buffer_sizes<S: Read + Write>(client: &mut WebSocket<S>) {
+    // Despite chunking members inside larger guilds, Discord will
+    // still send us the online state of all members at the same time
+    // in a single frame. By default, tungstenite only allows frames
+    // with a maximum of 16mb at a time. Larger guilds can easily surpass
+    // this limit.
+    //
+    // Since we know all traffic is coming from a trusted source (Discord),
+    // we can remove the buffer limit entirely. This eliminates the issue
+    // where we have to keep upping buffer sizes because of growing guilds.
+    client.set_config(|c| {
+        c.max_frame_size = None;
+        c.max_message_size = None;
+    })
+}
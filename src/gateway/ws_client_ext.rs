@@ -1,13 +1,11 @@
-use std::env::consts;
-
 use log::{debug, trace};
 use chrono::Utc;
 use serde_json::json;
 
-use crate::gateway::{CurrentPresence, WsClient};
+use crate::gateway::{CurrentPresence, GatewayEncoding, GatewaySocket, IdentifyOptions, MemberRequest};
 use crate::internal::prelude::*;
 use crate::internal::ws_impl::SenderExt;
-use crate::model::id::GuildId;
+use crate::model::{gateway::GatewayIntents, id::GuildId};
 use crate::constants::{self, *};
 
 pub trait WebSocketGatewayClientExt {
@@ -17,18 +15,35 @@ pub trait WebSocketGatewayClientExt {
         shard_info: &[u64; 2],
         limit: Option<u16>,
         query: Option<&str>,
+        encoding: GatewayEncoding,
     ) -> Result<()> where It: IntoIterator<Item=GuildId>;
 
-    fn send_heartbeat(&mut self, shard_info: &[u64; 2], seq: Option<u64>)
+    fn send_heartbeat(&mut self, shard_info: &[u64; 2], seq: Option<u64>, encoding: GatewayEncoding)
         -> Result<()>;
 
-    fn send_identify(&mut self, shard_info: &[u64; 2], token: &str)
-        -> Result<()>;
+    fn send_identify(
+        &mut self,
+        shard_info: &[u64; 2],
+        token: &str,
+        intents: Option<GatewayIntents>,
+        options: &IdentifyOptions,
+        compress: bool,
+        encoding: GatewayEncoding,
+    ) -> Result<()>;
 
     fn send_presence_update(
         &mut self,
         shard_info: &[u64; 2],
         current_presence: &CurrentPresence,
+        encoding: GatewayEncoding,
+    ) -> Result<()>;
+
+    fn send_request_members(
+        &mut self,
+        shard_info: &[u64; 2],
+        request: &MemberRequest,
+        nonce: &str,
+        encoding: GatewayEncoding,
     ) -> Result<()>;
 
     fn send_resume(
@@ -37,83 +52,123 @@ pub trait WebSocketGatewayClientExt {
         session_id: &str,
         seq: u64,
         token: &str,
+        encoding: GatewayEncoding,
     ) -> Result<()>;
 }
 
-impl WebSocketGatewayClientExt for WsClient {
+impl<T: GatewaySocket + ?Sized> WebSocketGatewayClientExt for T {
     fn send_chunk_guilds<It>(
         &mut self,
         guild_ids: It,
         shard_info: &[u64; 2],
         limit: Option<u16>,
         query: Option<&str>,
+        encoding: GatewayEncoding,
     ) -> Result<()> where It: IntoIterator<Item=GuildId> {
         debug!("[Shard {:?}] Requesting member chunks", shard_info);
 
-        self.send_json(&json!({
+        self.send_encoded(&json!({
             "op": OpCode::GetGuildMembers.num(),
             "d": {
                 "guild_id": guild_ids.into_iter().map(|x| x.as_ref().0).collect::<Vec<u64>>(),
                 "limit": limit.unwrap_or(0),
                 "query": query.unwrap_or(""),
             },
-        })).map_err(From::from)
+        }), encoding).map_err(From::from)
     }
 
-    fn send_heartbeat(&mut self, shard_info: &[u64; 2], seq: Option<u64>)
+    fn send_heartbeat(&mut self, shard_info: &[u64; 2], seq: Option<u64>, encoding: GatewayEncoding)
         -> Result<()> {
         trace!("[Shard {:?}] Sending heartbeat d: {:?}", shard_info, seq);
 
-        self.send_json(&json!({
+        self.send_encoded(&json!({
             "d": seq,
             "op": OpCode::Heartbeat.num(),
-        })).map_err(From::from)
+        }), encoding).map_err(From::from)
     }
 
-    fn send_identify(&mut self, shard_info: &[u64; 2], token: &str)
-        -> Result<()> {
+    fn send_identify(
+        &mut self,
+        shard_info: &[u64; 2],
+        token: &str,
+        intents: Option<GatewayIntents>,
+        options: &IdentifyOptions,
+        compress: bool,
+        encoding: GatewayEncoding,
+    ) -> Result<()> {
         debug!("[Shard {:?}] Identifying", shard_info);
 
-        self.send_json(&json!({
-            "op": OpCode::Identify.num(),
-            "d": {
-                "compression": true,
-                "large_threshold": constants::LARGE_THRESHOLD,
-                "shard": shard_info,
-                "token": token,
-                "v": constants::GATEWAY_VERSION,
-                "properties": {
-                    "$browser": "serenity",
-                    "$device": "serenity",
-                    "$os": consts::OS,
-                },
+        let mut payload = json!({
+            "compression": compress,
+            "guild_subscriptions": options.guild_subscriptions,
+            "large_threshold": options.large_threshold,
+            "shard": shard_info,
+            "token": token,
+            "v": constants::GATEWAY_VERSION,
+            "properties": {
+                "$browser": options.properties.browser,
+                "$device": options.properties.device,
+                "$os": options.properties.os,
             },
-        }))
+        });
+
+        if let Some(ref presence) = options.presence {
+            payload["presence"] = presence_data(presence);
+        }
+
+        // Omitting the field entirely keeps the gateway dispatching every
+        // event, which is what users without intents expect.
+        if let Some(intents) = intents {
+            payload["intents"] = json!(intents.bits());
+        }
+
+        self.send_encoded(&json!({
+            "op": OpCode::Identify.num(),
+            "d": payload,
+        }), encoding)
     }
 
     fn send_presence_update(
         &mut self,
         shard_info: &[u64; 2],
         current_presence: &CurrentPresence,
+        encoding: GatewayEncoding,
     ) -> Result<()> {
-        let &(ref activity, ref status) = current_presence;
-        let now = Utc::now().timestamp() as u64;
-
         debug!("[Shard {:?}] Sending presence update", shard_info);
 
-        self.send_json(&json!({
+        self.send_encoded(&json!({
             "op": OpCode::StatusUpdate.num(),
-            "d": {
-                "afk": false,
-                "since": now,
-                "status": status.name(),
-                "game": activity.as_ref().map(|x| json!({
-                    "name": x.name,
-                    "type": x.kind,
-                    "url": x.url,
-                })),
-            },
-        }))
+            "d": presence_data(current_presence),
+        }), encoding)
+    }
+
+    fn send_request_members(
+        &mut self,
+        shard_info: &[u64; 2],
+        request: &MemberRequest,
+        nonce: &str,
+        encoding: GatewayEncoding,
+    ) -> Result<()> {
+        debug!("[Shard {:?}] Requesting members with nonce {}", shard_info, nonce);
+
+        let mut payload = json!({
+            "guild_id": request.guild_id.0,
+            "limit": request.limit,
+            "nonce": nonce,
+            "presences": request.presences,
+        });
+
+        // The gateway rejects requests with both a query and user IDs.
+        if request.user_ids.is_empty() {
+            payload["query"] = json!(request.query);
+        } else {
+            payload["user_ids"] = json!(request.user_ids.iter().map(|id| id.0).collect::<Vec<u64>>());
+        }
+
+        self.send_encoded(&json!({
+            "op": OpCode::GetGuildMembers.num(),
+            "d": payload,
+        }), encoding)
     }
 
     fn send_resume(
@@ -122,16 +177,34 @@ impl WebSocketGatewayClientExt for WsClient {
         session_id: &str,
         seq: u64,
         token: &str,
+        encoding: GatewayEncoding,
     ) -> Result<()> {
         debug!("[Shard {:?}] Sending resume; seq: {}", shard_info, seq);
 
-        self.send_json(&json!({
+        self.send_encoded(&json!({
             "op": OpCode::Resume.num(),
             "d": {
                 "session_id": session_id,
                 "seq": seq,
                 "token": token,
             },
-        })).map_err(From::from)
+        }), encoding).map_err(From::from)
     }
 }
+
+// The data of a presence update, also sent when identifying.
+fn presence_data(current_presence: &CurrentPresence) -> Value {
+    let (activity, status) = current_presence;
+    let now = Utc::now().timestamp() as u64;
+
+    json!({
+        "afk": false,
+        "since": now,
+        "status": status.name(),
+        "game": activity.as_ref().map(|x| json!({
+            "name": x.name,
+            "type": x.kind,
+            "url": x.url,
+        })),
+    })
+}
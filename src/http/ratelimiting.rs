@@ -99,10 +99,13 @@ pub(super) fn perform(http: &Http, req: Request<'_>) -> Result<Response> {
             }));
 
         let mut lock = bucket.lock();
-        lock.pre_hook(&route);
+        lock.pre_hook(http, &route);
 
         let response = http.retry(&req)?;
 
+        #[cfg(feature = "metrics")]
+        http.metrics.record_request(&route, response.status().as_u16());
+
         // Check if an offset has been calculated yet to determine the time
         // difference from Discord can the client.
         //
@@ -136,7 +139,7 @@ pub(super) fn perform(http: &Http, req: Request<'_>) -> Result<Response> {
                 Ok(
                     if let Some(retry_after) = parse_header(&response.headers(), "retry-after")? {
                         debug!("Ratelimited on route {:?} for {:?}ms", route, retry_after);
-                        thread::sleep(Duration::from_millis(retry_after as u64));
+                        sleep(http, &route, "global", Duration::from_millis(retry_after as u64));
 
                         true
                     } else {
@@ -144,7 +147,7 @@ pub(super) fn perform(http: &Http, req: Request<'_>) -> Result<Response> {
                     },
                 )
             } else {
-                lock.post_hook(&response, &route)
+                lock.post_hook(http, &response, &route)
             };
 
             if !redo.unwrap_or(true) {
@@ -180,7 +183,7 @@ pub struct RateLimit {
 }
 
 impl RateLimit {
-    pub(crate) fn pre_hook(&mut self, route: &Route) {
+    pub(crate) fn pre_hook(&mut self, http: &Http, route: &Route) {
         if self.limit == 0 {
             return;
         }
@@ -207,7 +210,7 @@ impl RateLimit {
                 delay
             );
 
-            thread::sleep(Duration::from_millis(delay));
+            sleep(http, route, "preemptive", Duration::from_millis(delay));
 
             return;
         }
@@ -215,7 +218,7 @@ impl RateLimit {
         self.remaining -= 1;
     }
 
-    pub(crate) fn post_hook(&mut self, response: &Response, route: &Route) -> Result<bool> {
+    pub(crate) fn post_hook(&mut self, http: &Http, response: &Response, route: &Route) -> Result<bool> {
         if let Some(limit) = parse_header(&response.headers(), "x-ratelimit-limit")? {
             self.limit = limit;
         }
@@ -232,7 +235,7 @@ impl RateLimit {
             false
         } else if let Some(retry_after) = parse_header(&response.headers(), "retry-after")? {
             debug!("Ratelimited on route {:?} for {:?}ms", route, retry_after);
-            thread::sleep(Duration::from_millis(retry_after as u64));
+            sleep(http, route, "route", Duration::from_millis(retry_after as u64));
 
             true
         } else {
@@ -241,6 +244,15 @@ impl RateLimit {
     }
 }
 
+// Waits for a ratelimit on the route, recording why in the metrics.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+fn sleep(http: &Http, route: &Route, reason: &'static str, delay: Duration) {
+    #[cfg(feature = "metrics")]
+    http.metrics.record_sleep(route, reason, delay);
+
+    thread::sleep(delay);
+}
+
 /// The calculated offset of the time difference between Discord and the client
 /// in seconds.
 ///
@@ -22,6 +22,9 @@ use crate::constants;
 use crate::internal::prelude::*;
 use crate::model::prelude::*;
 
+#[cfg(feature = "metrics")]
+use crate::metrics::HttpMetrics;
+
 use super::{
     ratelimiting::{perform, RateLimit},
     request::Request,
@@ -60,6 +63,12 @@ pub struct Http {
     /// [`RateLimit`]: struct.RateLimit.html
     /// [`Route`]: ../routing/enum.Route.html
     pub routes: Arc<Mutex<HashMap<Route, Arc<Mutex<RateLimit>>>>>,
+    /// The number of requests made and the time spent waiting for
+    /// ratelimits, as exported by a [`MetricsExporter`].
+    ///
+    /// [`MetricsExporter`]: ../../metrics/struct.MetricsExporter.html
+    #[cfg(feature = "metrics")]
+    pub metrics: Arc<HttpMetrics>,
 }
 
 impl Http {
@@ -69,6 +78,8 @@ impl Http {
             token: token.to_string(),
             limiter: Arc::new(Mutex::new(())),
             routes: Arc::new(Mutex::new(HashMap::default())),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::new(HttpMetrics::default()),
         }
     }
 
@@ -85,6 +96,8 @@ impl Http {
             token: token.to_string(),
             limiter: Arc::new(Mutex::new(())),
             routes: Arc::new(Mutex::new(HashMap::default())),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::new(HttpMetrics::default()),
         }
     }
 
@@ -242,6 +255,37 @@ impl Http {
         })
     }
 
+    /// Creates a follow-up message for an interaction.
+    ///
+    /// The payload is the same as for [`execute_webhook`].
+    ///
+    /// [`execute_webhook`]: #method.execute_webhook
+    pub fn create_followup_message(&self, application_id: u64, interaction_token: &str, map: &JsonMap) -> Result<Message> {
+        let body = serde_json::to_vec(map)?;
+
+        self.fire(Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::CreateFollowupMessage { application_id, interaction_token },
+        })
+    }
+
+    /// Creates a global application command, overwriting an existing command
+    /// with the same name.
+    ///
+    /// Refer to Discord's [docs] for field information.
+    ///
+    /// [docs]: https://discord.com/developers/docs/interactions/slash-commands#create-global-application-command
+    pub fn create_global_application_command(&self, application_id: u64, map: &Value) -> Result<ApplicationCommand> {
+        let body = serde_json::to_vec(map)?;
+
+        self.fire(Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::CreateGlobalApplicationCommand { application_id },
+        })
+    }
+
     /// Creates a guild with the data provided.
     ///
     /// Only a [`PartialGuild`] will be immediately returned, and a full [`Guild`]
@@ -284,6 +328,20 @@ impl Http {
         })
     }
 
+    /// Creates an application command only available in the given guild,
+    /// overwriting an existing command with the same name.
+    ///
+    /// Unlike global commands, guild commands are available immediately.
+    pub fn create_guild_application_command(&self, application_id: u64, guild_id: u64, map: &Value) -> Result<ApplicationCommand> {
+        let body = serde_json::to_vec(map)?;
+
+        self.fire(Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::CreateGuildApplicationCommand { application_id, guild_id },
+        })
+    }
+
     /// Creates an [`Integration`] for a [`Guild`].
     ///
     /// Refer to Discord's [docs] for field information.
@@ -302,6 +360,21 @@ impl Http {
         })
     }
 
+    /// Creates the initial response to an interaction.
+    ///
+    /// Refer to Discord's [docs] for the response object.
+    ///
+    /// [docs]: https://discord.com/developers/docs/interactions/slash-commands#interaction-response
+    pub fn create_interaction_response(&self, interaction_id: u64, interaction_token: &str, map: &Value) -> Result<()> {
+        let body = serde_json::to_vec(map)?;
+
+        self.wind(204, Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::CreateInteractionResponse { interaction_id, interaction_token },
+        })
+    }
+
     /// Creates a [`RichInvite`] for the given [channel][`GuildChannel`].
     ///
     /// Refer to Discord's [docs] for field information.
@@ -429,6 +502,24 @@ impl Http {
         })
     }
 
+    /// Deletes a follow-up message of an interaction.
+    pub fn delete_followup_message(&self, application_id: u64, interaction_token: &str, message_id: u64) -> Result<()> {
+        self.wind(204, Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::DeleteFollowupMessage { application_id, interaction_token, message_id },
+        })
+    }
+
+    /// Deletes a global application command.
+    pub fn delete_global_application_command(&self, application_id: u64, command_id: u64) -> Result<()> {
+        self.wind(204, Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::DeleteGlobalApplicationCommand { application_id, command_id },
+        })
+    }
+
     /// Deletes a guild, only if connected account owns it.
     pub fn delete_guild(&self, guild_id: u64) -> Result<PartialGuild> {
         self.fire(Request {
@@ -438,6 +529,15 @@ impl Http {
         })
     }
 
+    /// Deletes an application command of a guild.
+    pub fn delete_guild_application_command(&self, application_id: u64, guild_id: u64, command_id: u64) -> Result<()> {
+        self.wind(204, Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::DeleteGuildApplicationCommand { application_id, guild_id, command_id },
+        })
+    }
+
     /// Removes an integration from a guild.
     pub fn delete_guild_integration(&self, guild_id: u64, integration_id: u64) -> Result<()> {
         self.wind(204, Request {
@@ -503,6 +603,15 @@ impl Http {
         })
     }
 
+    /// Deletes the initial response to an interaction.
+    pub fn delete_original_interaction_response(&self, application_id: u64, interaction_token: &str) -> Result<()> {
+        self.wind(204, Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::DeleteOriginalInteractionResponse { application_id, interaction_token },
+        })
+    }
+
     /// Deletes a permission override from a role or a member in a channel.
     pub fn delete_permission(&self, channel_id: u64, target_id: u64) -> Result<()> {
         self.wind(204, Request {
@@ -625,6 +734,28 @@ impl Http {
         })
     }
 
+    /// Edits a follow-up message of an interaction.
+    pub fn edit_followup_message(&self, application_id: u64, interaction_token: &str, message_id: u64, map: &Value) -> Result<Message> {
+        let body = serde_json::to_vec(map)?;
+
+        self.fire(Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::EditFollowupMessage { application_id, interaction_token, message_id },
+        })
+    }
+
+    /// Edits a global application command.
+    pub fn edit_global_application_command(&self, application_id: u64, command_id: u64, map: &Value) -> Result<ApplicationCommand> {
+        let body = serde_json::to_vec(map)?;
+
+        self.fire(Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::EditGlobalApplicationCommand { application_id, command_id },
+        })
+    }
+
     /// Changes guild information.
     pub fn edit_guild(&self, guild_id: u64, map: &JsonMap) -> Result<PartialGuild> {
         let body = serde_json::to_vec(map)?;
@@ -636,6 +767,17 @@ impl Http {
         })
     }
 
+    /// Edits an application command of a guild.
+    pub fn edit_guild_application_command(&self, application_id: u64, guild_id: u64, command_id: u64, map: &Value) -> Result<ApplicationCommand> {
+        let body = serde_json::to_vec(map)?;
+
+        self.fire(Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::EditGuildApplicationCommand { application_id, guild_id, command_id },
+        })
+    }
+
     /// Edits the positions of a guild's channels.
     pub fn edit_guild_channel_positions(&self, guild_id: u64, value: &Value)
                                         -> Result<()> {
@@ -701,6 +843,17 @@ impl Http {
         })
     }
 
+    /// Edits the initial response to an interaction.
+    pub fn edit_original_interaction_response(&self, application_id: u64, interaction_token: &str, map: &Value) -> Result<Message> {
+        let body = serde_json::to_vec(map)?;
+
+        self.fire(Request {
+            body: Some(&body),
+            headers: None,
+            route: RouteInfo::EditOriginalInteractionResponse { application_id, interaction_token },
+        })
+    }
+
     /// Edits the current user's profile settings.
     pub fn edit_profile(&self, map: &JsonMap) -> Result<CurrentUser> {
         let body = serde_json::to_vec(map)?;
@@ -1043,6 +1196,24 @@ impl Http {
         })
     }
 
+    /// Gets a global application command by its Id.
+    pub fn get_global_application_command(&self, application_id: u64, command_id: u64) -> Result<ApplicationCommand> {
+        self.fire(Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::GetGlobalApplicationCommand { application_id, command_id },
+        })
+    }
+
+    /// Gets all global application commands.
+    pub fn get_global_application_commands(&self, application_id: u64) -> Result<Vec<ApplicationCommand>> {
+        self.fire(Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::GetGlobalApplicationCommands { application_id },
+        })
+    }
+
     /// Gets guild information.
     pub fn get_guild(&self, guild_id: u64) -> Result<PartialGuild> {
         self.fire(Request {
@@ -1052,6 +1223,24 @@ impl Http {
         })
     }
 
+    /// Gets an application command of a guild by its Id.
+    pub fn get_guild_application_command(&self, application_id: u64, guild_id: u64, command_id: u64) -> Result<ApplicationCommand> {
+        self.fire(Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::GetGuildApplicationCommand { application_id, guild_id, command_id },
+        })
+    }
+
+    /// Gets all application commands of a guild.
+    pub fn get_guild_application_commands(&self, application_id: u64, guild_id: u64) -> Result<Vec<ApplicationCommand>> {
+        self.fire(Request {
+            body: None,
+            headers: None,
+            route: RouteInfo::GetGuildApplicationCommands { application_id, guild_id },
+        })
+    }
+
     /// Gets a guild embed information.
     pub fn get_guild_embed(&self, guild_id: u64) -> Result<GuildEmbed> {
         self.fire(Request {
@@ -1790,6 +1979,8 @@ impl Default for Http {
             token: "".to_string(),
             limiter: Arc::new(Mutex::new(())),
             routes: Arc::new(Mutex::new(HashMap::default())),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::new(HttpMetrics::default()),
         }
     }
 }
@@ -11,6 +11,33 @@ use super::LightMethod;
 /// [`http`]: ../index.html
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Route {
+    /// Route for the `/applications/:application_id/commands` path.
+    ///
+    /// The data is the relevant [`ApplicationId`].
+    ///
+    /// [`ApplicationId`]: ../../model/id/struct.ApplicationId.html
+    ApplicationsIdCommands(u64),
+    /// Route for the `/applications/:application_id/commands/:command_id` path.
+    ///
+    /// The data is the relevant [`ApplicationId`].
+    ///
+    /// [`ApplicationId`]: ../../model/id/struct.ApplicationId.html
+    ApplicationsIdCommandsId(u64),
+    /// Route for the `/applications/:application_id/guilds/:guild_id/commands`
+    /// path.
+    ///
+    /// The data is the relevant [`ApplicationId`].
+    ///
+    /// [`ApplicationId`]: ../../model/id/struct.ApplicationId.html
+    ApplicationsIdGuildsIdCommands(u64),
+    /// Route for the
+    /// `/applications/:application_id/guilds/:guild_id/commands/:command_id`
+    /// path.
+    ///
+    /// The data is the relevant [`ApplicationId`].
+    ///
+    /// [`ApplicationId`]: ../../model/id/struct.ApplicationId.html
+    ApplicationsIdGuildsIdCommandsId(u64),
     /// Route for the `/channels/:channel_id` path.
     ///
     /// The data is the relevant [`ChannelId`].
@@ -239,6 +266,12 @@ pub enum Route {
     ///
     /// [`GuildId`]: ../../model/id/struct.GuildId.html
     GuildsIdWebhooks(u64),
+    /// Route for the `/interactions/:interaction_id/:token/callback` path.
+    ///
+    /// The data is the relevant [`InteractionId`].
+    ///
+    /// [`InteractionId`]: ../../model/id/struct.InteractionId.html
+    InteractionsIdTokenCallback(u64),
     /// Route for the `/invites/:code` path.
     InvitesCode,
     /// Route for the `/users/:user_id` path.
@@ -255,6 +288,13 @@ pub enum Route {
     VoiceRegions,
     /// Route for the `/webhooks/:webhook_id` path.
     WebhooksId(u64),
+    /// Route for the `/webhooks/:application_id/:token/messages/:message_id`
+    /// path, used for interaction responses and follow-up messages.
+    ///
+    /// The data is the relevant [`ApplicationId`].
+    ///
+    /// [`ApplicationId`]: ../../model/id/struct.ApplicationId.html
+    WebhooksApplicationIdTokenMessagesId(u64),
     /// Route where no ratelimit headers are in place (i.e. user account-only
     /// routes).
     ///
@@ -266,6 +306,31 @@ pub enum Route {
 }
 
 impl Route {
+    pub fn application_command(application_id: u64, command_id: u64) -> String {
+        format!(api!("/applications/{}/commands/{}"), application_id, command_id)
+    }
+
+    pub fn application_commands(application_id: u64) -> String {
+        format!(api!("/applications/{}/commands"), application_id)
+    }
+
+    pub fn application_guild_command(
+        application_id: u64,
+        guild_id: u64,
+        command_id: u64,
+    ) -> String {
+        format!(
+            api!("/applications/{}/guilds/{}/commands/{}"),
+            application_id,
+            guild_id,
+            command_id,
+        )
+    }
+
+    pub fn application_guild_commands(application_id: u64, guild_id: u64) -> String {
+        format!(api!("/applications/{}/guilds/{}/commands"), application_id, guild_id)
+    }
+
     pub fn channel(channel_id: u64) -> String {
         format!(api!("/channels/{}"), channel_id)
     }
@@ -537,6 +602,11 @@ impl Route {
         api!("/guilds")
     }
 
+    pub fn interaction_response<D>(interaction_id: u64, token: D) -> String
+        where D: Display {
+        format!(api!("/interactions/{}/{}/callback"), interaction_id, token)
+    }
+
     pub fn invite(code: &str) -> String {
         format!(api!("/invites/{}"), code)
     }
@@ -608,6 +678,11 @@ impl Route {
         format!(api!("/webhooks/{}"), webhook_id)
     }
 
+    pub fn webhook_message<D, M>(webhook_id: u64, token: D, message_id: M) -> String
+        where D: Display, M: Display {
+        format!(api!("/webhooks/{}/{}/messages/{}"), webhook_id, token, message_id)
+    }
+
     pub fn webhook_with_token<D>(webhook_id: u64, token: D) -> String
         where D: Display {
         format!(api!("/webhooks/{}/{}"), webhook_id, token)
@@ -645,11 +720,26 @@ pub enum RouteInfo<'a> {
     CreateEmoji {
         guild_id: u64,
     },
+    CreateFollowupMessage {
+        application_id: u64,
+        interaction_token: &'a str,
+    },
+    CreateGlobalApplicationCommand {
+        application_id: u64,
+    },
     CreateGuild,
+    CreateGuildApplicationCommand {
+        application_id: u64,
+        guild_id: u64,
+    },
     CreateGuildIntegration {
         guild_id: u64,
         integration_id: u64,
     },
+    CreateInteractionResponse {
+        interaction_id: u64,
+        interaction_token: &'a str,
+    },
     CreateInvite {
         channel_id: u64,
     },
@@ -679,9 +769,23 @@ pub enum RouteInfo<'a> {
         guild_id: u64,
         emoji_id: u64,
     },
+    DeleteFollowupMessage {
+        application_id: u64,
+        interaction_token: &'a str,
+        message_id: u64,
+    },
+    DeleteGlobalApplicationCommand {
+        application_id: u64,
+        command_id: u64,
+    },
     DeleteGuild {
         guild_id: u64,
     },
+    DeleteGuildApplicationCommand {
+        application_id: u64,
+        guild_id: u64,
+        command_id: u64,
+    },
     DeleteGuildIntegration {
         guild_id: u64,
         integration_id: u64,
@@ -700,6 +804,10 @@ pub enum RouteInfo<'a> {
         channel_id: u64,
         message_id: u64,
     },
+    DeleteOriginalInteractionResponse {
+        application_id: u64,
+        interaction_token: &'a str,
+    },
     DeletePermission {
         channel_id: u64,
         target_id: u64,
@@ -728,9 +836,23 @@ pub enum RouteInfo<'a> {
         guild_id: u64,
         emoji_id: u64,
     },
+    EditFollowupMessage {
+        application_id: u64,
+        interaction_token: &'a str,
+        message_id: u64,
+    },
+    EditGlobalApplicationCommand {
+        application_id: u64,
+        command_id: u64,
+    },
     EditGuild {
         guild_id: u64,
     },
+    EditGuildApplicationCommand {
+        application_id: u64,
+        guild_id: u64,
+        command_id: u64,
+    },
     EditGuildChannels {
         guild_id: u64,
     },
@@ -748,6 +870,10 @@ pub enum RouteInfo<'a> {
     EditNickname {
         guild_id: u64,
     },
+    EditOriginalInteractionResponse {
+        application_id: u64,
+        interaction_token: &'a str,
+    },
     EditProfile,
     EditRole {
         guild_id: u64,
@@ -795,9 +921,25 @@ pub enum RouteInfo<'a> {
     GetCurrentApplicationInfo,
     GetCurrentUser,
     GetGateway,
+    GetGlobalApplicationCommand {
+        application_id: u64,
+        command_id: u64,
+    },
+    GetGlobalApplicationCommands {
+        application_id: u64,
+    },
     GetGuild {
         guild_id: u64,
     },
+    GetGuildApplicationCommand {
+        application_id: u64,
+        guild_id: u64,
+        command_id: u64,
+    },
+    GetGuildApplicationCommands {
+        application_id: u64,
+        guild_id: u64,
+    },
     GetGuildEmbed {
         guild_id: u64,
     },
@@ -963,16 +1105,36 @@ impl<'a> RouteInfo<'a> {
                 Route::GuildsIdEmojis(guild_id),
                 Cow::from(Route::guild_emojis(guild_id)),
             ),
+            RouteInfo::CreateFollowupMessage { application_id, interaction_token } => (
+                LightMethod::Post,
+                Route::WebhooksId(application_id),
+                Cow::from(Route::webhook_with_token(application_id, interaction_token)),
+            ),
+            RouteInfo::CreateGlobalApplicationCommand { application_id } => (
+                LightMethod::Post,
+                Route::ApplicationsIdCommands(application_id),
+                Cow::from(Route::application_commands(application_id)),
+            ),
             RouteInfo::CreateGuild => (
                 LightMethod::Post,
                 Route::Guilds,
                 Cow::from(Route::guilds()),
             ),
+            RouteInfo::CreateGuildApplicationCommand { application_id, guild_id } => (
+                LightMethod::Post,
+                Route::ApplicationsIdGuildsIdCommands(application_id),
+                Cow::from(Route::application_guild_commands(application_id, guild_id)),
+            ),
             RouteInfo::CreateGuildIntegration { guild_id, integration_id } => (
                 LightMethod::Post,
                 Route::GuildsIdIntegrationsId(guild_id),
                 Cow::from(Route::guild_integration(guild_id, integration_id)),
             ),
+            RouteInfo::CreateInteractionResponse { interaction_id, interaction_token } => (
+                LightMethod::Post,
+                Route::InteractionsIdTokenCallback(interaction_id),
+                Cow::from(Route::interaction_response(interaction_id, interaction_token)),
+            ),
             RouteInfo::CreateInvite { channel_id } => (
                 LightMethod::Post,
                 Route::ChannelsIdInvites(channel_id),
@@ -1023,11 +1185,26 @@ impl<'a> RouteInfo<'a> {
                 Route::GuildsIdEmojisId(guild_id),
                 Cow::from(Route::guild_emoji(guild_id, emoji_id)),
             ),
+            RouteInfo::DeleteFollowupMessage { application_id, interaction_token, message_id } => (
+                LightMethod::Delete,
+                Route::WebhooksApplicationIdTokenMessagesId(application_id),
+                Cow::from(Route::webhook_message(application_id, interaction_token, message_id)),
+            ),
+            RouteInfo::DeleteGlobalApplicationCommand { application_id, command_id } => (
+                LightMethod::Delete,
+                Route::ApplicationsIdCommandsId(application_id),
+                Cow::from(Route::application_command(application_id, command_id)),
+            ),
             RouteInfo::DeleteGuild { guild_id } => (
                 LightMethod::Delete,
                 Route::GuildsId(guild_id),
                 Cow::from(Route::guild(guild_id)),
             ),
+            RouteInfo::DeleteGuildApplicationCommand { application_id, guild_id, command_id } => (
+                LightMethod::Delete,
+                Route::ApplicationsIdGuildsIdCommandsId(application_id),
+                Cow::from(Route::application_guild_command(application_id, guild_id, command_id)),
+            ),
             RouteInfo::DeleteGuildIntegration { guild_id, integration_id } => (
                 LightMethod::Delete,
                 Route::GuildsIdIntegrationsId(guild_id),
@@ -1056,6 +1233,11 @@ impl<'a> RouteInfo<'a> {
                 Route::ChannelsIdMessagesBulkDelete(channel_id),
                 Cow::from(Route::channel_messages_bulk_delete(channel_id)),
             ),
+            RouteInfo::DeleteOriginalInteractionResponse { application_id, interaction_token } => (
+                LightMethod::Delete,
+                Route::WebhooksApplicationIdTokenMessagesId(application_id),
+                Cow::from(Route::webhook_message(application_id, interaction_token, "@original")),
+            ),
             RouteInfo::DeletePermission { channel_id, target_id } => (
                 LightMethod::Delete,
                 Route::ChannelsIdPermissionsOverwriteId(channel_id),
@@ -1101,11 +1283,26 @@ impl<'a> RouteInfo<'a> {
                 Route::GuildsIdEmojisId(guild_id),
                 Cow::from(Route::guild_emoji(guild_id, emoji_id)),
             ),
+            RouteInfo::EditFollowupMessage { application_id, interaction_token, message_id } => (
+                LightMethod::Patch,
+                Route::WebhooksApplicationIdTokenMessagesId(application_id),
+                Cow::from(Route::webhook_message(application_id, interaction_token, message_id)),
+            ),
+            RouteInfo::EditGlobalApplicationCommand { application_id, command_id } => (
+                LightMethod::Patch,
+                Route::ApplicationsIdCommandsId(application_id),
+                Cow::from(Route::application_command(application_id, command_id)),
+            ),
             RouteInfo::EditGuild { guild_id } => (
                 LightMethod::Patch,
                 Route::GuildsId(guild_id),
                 Cow::from(Route::guild(guild_id)),
             ),
+            RouteInfo::EditGuildApplicationCommand { application_id, guild_id, command_id } => (
+                LightMethod::Patch,
+                Route::ApplicationsIdGuildsIdCommandsId(application_id),
+                Cow::from(Route::application_guild_command(application_id, guild_id, command_id)),
+            ),
             RouteInfo::EditGuildChannels { guild_id } => (
                 LightMethod::Patch,
                 Route::GuildsIdChannels(guild_id),
@@ -1131,6 +1328,11 @@ impl<'a> RouteInfo<'a> {
                 Route::GuildsIdMembersMeNick(guild_id),
                 Cow::from(Route::guild_nickname(guild_id)),
             ),
+            RouteInfo::EditOriginalInteractionResponse { application_id, interaction_token } => (
+                LightMethod::Patch,
+                Route::WebhooksApplicationIdTokenMessagesId(application_id),
+                Cow::from(Route::webhook_message(application_id, interaction_token, "@original")),
+            ),
             RouteInfo::EditProfile => (
                 LightMethod::Patch,
                 Route::UsersMe,
@@ -1232,11 +1434,31 @@ impl<'a> RouteInfo<'a> {
                 Route::Gateway,
                 Cow::from(Route::gateway()),
             ),
+            RouteInfo::GetGlobalApplicationCommand { application_id, command_id } => (
+                LightMethod::Get,
+                Route::ApplicationsIdCommandsId(application_id),
+                Cow::from(Route::application_command(application_id, command_id)),
+            ),
+            RouteInfo::GetGlobalApplicationCommands { application_id } => (
+                LightMethod::Get,
+                Route::ApplicationsIdCommands(application_id),
+                Cow::from(Route::application_commands(application_id)),
+            ),
             RouteInfo::GetGuild { guild_id } => (
                 LightMethod::Get,
                 Route::GuildsId(guild_id),
                 Cow::from(Route::guild(guild_id)),
             ),
+            RouteInfo::GetGuildApplicationCommand { application_id, guild_id, command_id } => (
+                LightMethod::Get,
+                Route::ApplicationsIdGuildsIdCommandsId(application_id),
+                Cow::from(Route::application_guild_command(application_id, guild_id, command_id)),
+            ),
+            RouteInfo::GetGuildApplicationCommands { application_id, guild_id } => (
+                LightMethod::Get,
+                Route::ApplicationsIdGuildsIdCommands(application_id),
+                Cow::from(Route::application_guild_commands(application_id, guild_id)),
+            ),
             RouteInfo::GetGuildEmbed { guild_id } => (
                 LightMethod::Get,
                 Route::GuildsIdEmbed(guild_id),
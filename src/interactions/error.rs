@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+/// An error returned while handling a request made to an interactions
+/// endpoint.
+#[derive(Debug, Error)]
+pub enum InteractionEndpointError {
+    /// The application's public key was not a valid hex-encoded Ed25519 key.
+    #[error("invalid public key")]
+    InvalidPublicKey,
+
+    /// The signature header was not a valid hex-encoded Ed25519 signature.
+    #[error("invalid signature")]
+    InvalidSignature,
+
+    /// The signature did not match the timestamp and body of the request.
+    ///
+    /// Discord expects the endpoint to respond with `401 Unauthorized` in this
+    /// case.
+    #[error("signature did not match the request")]
+    SignatureMismatch,
+
+    #[doc(hidden)]
+    #[error("unreachable")]
+    __Nonexhaustive,
+}
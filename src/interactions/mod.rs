@@ -0,0 +1,296 @@
+//! A framework-agnostic handler for receiving [`Interaction`]s over HTTP.
+//!
+//! Instead of receiving interactions over the gateway, an application may set
+//! an "Interactions Endpoint URL" in its settings. Discord then sends every
+//! interaction as a `POST` request to that URL, signed with the application's
+//! Ed25519 key.
+//!
+//! [`InteractionEndpoint`] verifies the signature of such a request, answers
+//! the [`Ping`] Discord sends to validate the URL, and turns every other
+//! interaction into a response body using a builder closure. Plugging it into
+//! a web server only requires passing the [`SIGNATURE_HEADER`] and
+//! [`TIMESTAMP_HEADER`] header values together with the raw request body.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use serenity::interactions::{InteractionEndpoint, InteractionEndpointError};
+//! use serenity::model::interactions::InteractionResponseType;
+//!
+//! # fn run(signature: &str, timestamp: &str, body: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+//! let endpoint = InteractionEndpoint::new("a1b2...")?;
+//!
+//! match endpoint.handle(signature, timestamp, body, |interaction, response| {
+//!     let name = interaction.data.as_ref().map_or("", |d| d.name.as_str());
+//!
+//!     response
+//!         .kind(InteractionResponseType::ChannelMessageWithSource)
+//!         .interaction_response_data(|d| d.content(format!("Invoked `{}`", name)))
+//! }) {
+//!     Ok(body) => {
+//!         // Respond with `200 OK` and `body` as JSON.
+//!     },
+//!     Err(why) => match why.downcast_ref::<InteractionEndpointError>() {
+//!         // Respond with `401 Unauthorized`.
+//!         Some(_) => {},
+//!         // The body was malformed: respond with `400 Bad Request`.
+//!         None => {},
+//!     },
+//! }
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! [`Interaction`]: ../model/interactions/struct.Interaction.html
+//! [`InteractionEndpoint`]: struct.InteractionEndpoint.html
+//! [`Ping`]: ../model/interactions/enum.InteractionType.html#variant.Ping
+//! [`SIGNATURE_HEADER`]: constant.SIGNATURE_HEADER.html
+//! [`TIMESTAMP_HEADER`]: constant.TIMESTAMP_HEADER.html
+
+mod error;
+
+pub use self::error::InteractionEndpointError;
+
+use ed25519_dalek::{PublicKey, Signature};
+use std::convert::TryFrom;
+use crate::builder::CreateInteractionResponse;
+use crate::internal::prelude::*;
+use crate::model::interactions::{Interaction, InteractionResponseType, InteractionType};
+use crate::utils;
+
+/// The header containing the hex-encoded signature of a request.
+pub const SIGNATURE_HEADER: &str = "X-Signature-Ed25519";
+/// The header containing the timestamp that was signed along with the body.
+pub const TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+
+/// Verifies and handles requests made to an interactions endpoint.
+///
+/// Refer to the [module-level documentation] for an example.
+///
+/// [module-level documentation]: index.html
+#[derive(Clone, Debug)]
+pub struct InteractionEndpoint {
+    public_key: PublicKey,
+}
+
+impl InteractionEndpoint {
+    /// Creates a new endpoint handler from the hex-encoded public key shown
+    /// in the application's settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InteractionEndpointError::InvalidPublicKey`] if the key is
+    /// not a valid hex-encoded Ed25519 public key.
+    ///
+    /// [`InteractionEndpointError::InvalidPublicKey`]: enum.InteractionEndpointError.html#variant.InvalidPublicKey
+    pub fn new(public_key: &str) -> Result<Self> {
+        let public_key = decode_hex(public_key)
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .ok_or(InteractionEndpointError::InvalidPublicKey)?;
+
+        Ok(Self {
+            public_key,
+        })
+    }
+
+    /// Verifies that a request was signed by Discord.
+    ///
+    /// `signature` and `timestamp` are the values of the
+    /// [`SIGNATURE_HEADER`] and [`TIMESTAMP_HEADER`] headers, and `body` is
+    /// the raw, unparsed request body.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InteractionEndpointError::InvalidSignature`] if the signature
+    /// is malformed, or [`InteractionEndpointError::SignatureMismatch`] if it
+    /// does not match the request.
+    ///
+    /// [`SIGNATURE_HEADER`]: constant.SIGNATURE_HEADER.html
+    /// [`TIMESTAMP_HEADER`]: constant.TIMESTAMP_HEADER.html
+    /// [`InteractionEndpointError::InvalidSignature`]: enum.InteractionEndpointError.html#variant.InvalidSignature
+    /// [`InteractionEndpointError::SignatureMismatch`]: enum.InteractionEndpointError.html#variant.SignatureMismatch
+    pub fn verify(&self, signature: &str, timestamp: &str, body: &[u8]) -> Result<()> {
+        let signature = decode_hex(signature)
+            .and_then(|bytes| Signature::try_from(&bytes[..]).ok())
+            .ok_or(InteractionEndpointError::InvalidSignature)?;
+
+        let mut message = Vec::with_capacity(timestamp.len() + body.len());
+        message.extend_from_slice(timestamp.as_bytes());
+        message.extend_from_slice(body);
+
+        self.public_key
+            .verify_strict(&message, &signature)
+            .map_err(|_| InteractionEndpointError::SignatureMismatch.into())
+    }
+
+    /// Verifies a request and parses its body into an [`Interaction`].
+    ///
+    /// [`Interaction`]: ../model/interactions/struct.Interaction.html
+    pub fn parse(&self, signature: &str, timestamp: &str, body: &[u8]) -> Result<Interaction> {
+        self.verify(signature, timestamp, body)?;
+
+        serde_json::from_slice(body).map_err(From::from)
+    }
+
+    /// Verifies a request and builds the JSON body to respond with.
+    ///
+    /// A [`Ping`] is answered with a [`Pong`] without calling `f`. Every other
+    /// interaction is passed to `f` together with a response builder, which
+    /// defaults to [`ChannelMessageWithSource`].
+    ///
+    /// [`Ping`]: ../model/interactions/enum.InteractionType.html#variant.Ping
+    /// [`Pong`]: ../model/interactions/enum.InteractionResponseType.html#variant.Pong
+    /// [`ChannelMessageWithSource`]: ../model/interactions/enum.InteractionResponseType.html#variant.ChannelMessageWithSource
+    pub fn handle<F>(&self, signature: &str, timestamp: &str, body: &[u8], f: F) -> Result<Value>
+        where F: for<'a> FnOnce(&Interaction, &'a mut CreateInteractionResponse) -> &'a mut CreateInteractionResponse {
+        let interaction = self.parse(signature, timestamp, body)?;
+        let mut response = CreateInteractionResponse::default();
+
+        if interaction.kind == InteractionType::Ping {
+            response.kind(InteractionResponseType::Pong);
+        } else {
+            f(&interaction, &mut response);
+        }
+
+        Ok(Value::Object(utils::hashmap_to_json_map(response.0)))
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+    use serde_json::json;
+    use super::{InteractionEndpoint, InteractionEndpointError};
+    use crate::model::interactions::InteractionResponseType;
+
+    const TIMESTAMP: &str = "1608593019";
+
+    fn keypair() -> Keypair {
+        let secret = SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+
+        Keypair { secret, public }
+    }
+
+    fn encode_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn sign(keypair: &Keypair, body: &[u8]) -> String {
+        let mut message = TIMESTAMP.as_bytes().to_vec();
+        message.extend_from_slice(body);
+
+        encode_hex(&keypair.sign(&message).to_bytes())
+    }
+
+    fn endpoint(keypair: &Keypair) -> InteractionEndpoint {
+        InteractionEndpoint::new(&encode_hex(keypair.public.as_bytes())).unwrap()
+    }
+
+    fn command_body() -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "id": "786008729715212338",
+            "application_id": "771373536307306526",
+            "type": 2,
+            "data": {
+                "id": "771825006014889984",
+                "name": "blep",
+                "options": [{"name": "animal", "value": "animal_dog"}],
+            },
+            "guild_id": "290926798626357999",
+            "channel_id": "645027906669510667",
+            "member": {
+                "user": {
+                    "id": "53908232506183680",
+                    "username": "Mason",
+                    "avatar": null,
+                    "discriminator": "1337",
+                },
+                "roles": [],
+                "premium_since": null,
+                "nick": null,
+                "mute": false,
+                "joined_at": "2017-03-13T19:19:14.040000+00:00",
+                "deaf": false,
+            },
+            "token": "A_UNIQUE_TOKEN",
+            "version": 1,
+        })).unwrap()
+    }
+
+    fn is_endpoint_error(why: &anyhow::Error, expected: &str) -> bool {
+        why.downcast_ref::<InteractionEndpointError>()
+            .map_or(false, |e| format!("{:?}", e) == expected)
+    }
+
+    #[test]
+    fn ping_is_answered_with_pong() {
+        let keypair = keypair();
+        let body = br#"{"id":"1","type":1,"token":"t","version":1}"#;
+        let signature = sign(&keypair, body);
+
+        let response = endpoint(&keypair)
+            .handle(&signature, TIMESTAMP, body, |_, _| panic!("handler called for ping"))
+            .unwrap();
+
+        assert_eq!(response, json!({"type": InteractionResponseType::Pong.num()}));
+    }
+
+    #[test]
+    fn command_is_passed_to_handler() {
+        let keypair = keypair();
+        let body = command_body();
+        let signature = sign(&keypair, &body);
+
+        let response = endpoint(&keypair).handle(&signature, TIMESTAMP, &body, |interaction, r| {
+            let data = interaction.data.as_ref().unwrap();
+            let member = interaction.member.as_ref().unwrap();
+            assert_eq!(data.name, "blep");
+            assert_eq!(member.guild_id.0, 290_926_798_626_357_999);
+
+            r.interaction_response_data(|d| d.content("pong"))
+        }).unwrap();
+
+        assert_eq!(response, json!({
+            "type": InteractionResponseType::ChannelMessageWithSource.num(),
+            "data": {"content": "pong"},
+        }));
+    }
+
+    #[test]
+    fn tampered_body_is_rejected() {
+        let keypair = keypair();
+        let body = command_body();
+        let signature = sign(&keypair, &body);
+        let mut tampered = body.clone();
+        tampered.push(b' ');
+
+        let why = endpoint(&keypair).verify(&signature, TIMESTAMP, &tampered).unwrap_err();
+        assert!(is_endpoint_error(&why, "SignatureMismatch"));
+
+        let why = endpoint(&keypair).verify(&signature, "1608593020", &body).unwrap_err();
+        assert!(is_endpoint_error(&why, "SignatureMismatch"));
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let keypair = keypair();
+
+        let why = InteractionEndpoint::new("not hex").unwrap_err();
+        assert!(is_endpoint_error(&why, "InvalidPublicKey"));
+
+        let why = endpoint(&keypair).verify("abc", TIMESTAMP, b"{}").unwrap_err();
+        assert!(is_endpoint_error(&why, "InvalidSignature"));
+    }
+}
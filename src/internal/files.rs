@@ -0,0 +1,35 @@
+//! Writing and removing the files of the file-backed stores.
+
+use serde::Serialize;
+use std::{fs, io::ErrorKind, path::Path};
+use super::prelude::*;
+
+/// Writes a value as JSON to the file at `path`, creating its directory if
+/// needed.
+///
+/// The value is written to a temporary file next to it first, which is then
+/// moved into place, so that a crash mid-write never leaves a truncated file
+/// behind. The temporary file's name is that of the file with `.tmp`
+/// appended.
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    fs::write(&tmp, serde_json::to_vec(value)?)?;
+    fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+/// Removes the file at `path`, if there is one.
+pub fn remove(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(ref why) if why.kind() == ErrorKind::NotFound => Ok(()),
+        Err(why) => Err(why.into()),
+    }
+}
@@ -91,3 +91,200 @@ macro_rules! enum_number {
         }
     }
 }
+
+// Invokes the given macro with the methods of `EventHandler` - other than
+// `event_types` - for implementing it by forwarding each method elsewhere.
+#[cfg(feature = "client")]
+macro_rules! event_handler_methods {
+    ($callback:ident) => {
+        $callback! {
+            /// Refer to [`EventHandler::cache_ready`](trait.EventHandler.html#method.cache_ready).
+            #[cfg(feature = "cache")]
+            fn cache_ready(guilds: Vec<GuildId>);
+
+            /// Refer to [`EventHandler::channel_create`](trait.EventHandler.html#method.channel_create).
+            fn channel_create(channel: Arc<RwLock<GuildChannel>>);
+
+            /// Refer to [`EventHandler::category_create`](trait.EventHandler.html#method.category_create).
+            fn category_create(category: Arc<RwLock<ChannelCategory>>);
+
+            /// Refer to [`EventHandler::category_delete`](trait.EventHandler.html#method.category_delete).
+            fn category_delete(category: Arc<RwLock<ChannelCategory>>);
+
+            /// Refer to [`EventHandler::private_channel_create`](trait.EventHandler.html#method.private_channel_create).
+            fn private_channel_create(channel: Arc<RwLock<PrivateChannel>>);
+
+            /// Refer to [`EventHandler::channel_delete`](trait.EventHandler.html#method.channel_delete).
+            fn channel_delete(channel: Arc<RwLock<GuildChannel>>);
+
+            /// Refer to [`EventHandler::channel_pins_update`](trait.EventHandler.html#method.channel_pins_update).
+            fn channel_pins_update(pin: ChannelPinsUpdateEvent);
+
+            /// Refer to [`EventHandler::channel_recipient_addition`](trait.EventHandler.html#method.channel_recipient_addition).
+            fn channel_recipient_addition(group_id: ChannelId, user: User);
+
+            /// Refer to [`EventHandler::channel_recipient_removal`](trait.EventHandler.html#method.channel_recipient_removal).
+            fn channel_recipient_removal(group_id: ChannelId, user: User);
+
+            /// Refer to [`EventHandler::channel_update`](trait.EventHandler.html#method.channel_update).
+            #[cfg(feature = "cache")]
+            fn channel_update(old: Option<Channel>, new: Channel);
+
+            /// Refer to [`EventHandler::channel_update`](trait.EventHandler.html#method.channel_update).
+            #[cfg(not(feature = "cache"))]
+            fn channel_update(new_data: Channel);
+
+            /// Refer to [`EventHandler::guild_ban_addition`](trait.EventHandler.html#method.guild_ban_addition).
+            fn guild_ban_addition(guild_id: GuildId, banned_user: User);
+
+            /// Refer to [`EventHandler::guild_ban_removal`](trait.EventHandler.html#method.guild_ban_removal).
+            fn guild_ban_removal(guild_id: GuildId, unbanned_user: User);
+
+            /// Refer to [`EventHandler::guild_create`](trait.EventHandler.html#method.guild_create).
+            #[cfg(feature = "cache")]
+            fn guild_create(guild: Guild, is_new: bool);
+
+            /// Refer to [`EventHandler::guild_create`](trait.EventHandler.html#method.guild_create).
+            #[cfg(not(feature = "cache"))]
+            fn guild_create(guild: Guild);
+
+            /// Refer to [`EventHandler::guild_delete`](trait.EventHandler.html#method.guild_delete).
+            #[cfg(feature = "cache")]
+            fn guild_delete(incomplete: PartialGuild, full: Option<Arc<RwLock<Guild>>>);
+
+            /// Refer to [`EventHandler::guild_delete`](trait.EventHandler.html#method.guild_delete).
+            #[cfg(not(feature = "cache"))]
+            fn guild_delete(incomplete: PartialGuild);
+
+            /// Refer to [`EventHandler::guild_emojis_update`](trait.EventHandler.html#method.guild_emojis_update).
+            fn guild_emojis_update(guild_id: GuildId, current_state: HashMap<EmojiId, Emoji>);
+
+            /// Refer to [`EventHandler::guild_integrations_update`](trait.EventHandler.html#method.guild_integrations_update).
+            fn guild_integrations_update(guild_id: GuildId);
+
+            /// Refer to [`EventHandler::guild_member_addition`](trait.EventHandler.html#method.guild_member_addition).
+            fn guild_member_addition(guild_id: GuildId, new_member: Member);
+
+            /// Refer to [`EventHandler::guild_member_removal`](trait.EventHandler.html#method.guild_member_removal).
+            #[cfg(feature = "cache")]
+            fn guild_member_removal(guild: GuildId, user: User, member_data_if_available: Option<Member>);
+
+            /// Refer to [`EventHandler::guild_member_removal`](trait.EventHandler.html#method.guild_member_removal).
+            #[cfg(not(feature = "cache"))]
+            fn guild_member_removal(guild_id: GuildId, kicked: User);
+
+            /// Refer to [`EventHandler::guild_member_update`](trait.EventHandler.html#method.guild_member_update).
+            #[cfg(feature = "cache")]
+            fn guild_member_update(old_if_available: Option<Member>, new: Member);
+
+            /// Refer to [`EventHandler::guild_member_update`](trait.EventHandler.html#method.guild_member_update).
+            #[cfg(not(feature = "cache"))]
+            fn guild_member_update(new: GuildMemberUpdateEvent);
+
+            /// Refer to [`EventHandler::guild_members_chunk`](trait.EventHandler.html#method.guild_members_chunk).
+            fn guild_members_chunk(guild_id: GuildId, offline_members: HashMap<UserId, Member>);
+
+            /// Refer to [`EventHandler::guild_role_create`](trait.EventHandler.html#method.guild_role_create).
+            fn guild_role_create(guild_id: GuildId, new: Role);
+
+            /// Refer to [`EventHandler::guild_role_delete`](trait.EventHandler.html#method.guild_role_delete).
+            #[cfg(feature = "cache")]
+            fn guild_role_delete(guild_id: GuildId, removed_role_id: RoleId, removed_role_data_if_available: Option<Role>);
+
+            /// Refer to [`EventHandler::guild_role_delete`](trait.EventHandler.html#method.guild_role_delete).
+            #[cfg(not(feature = "cache"))]
+            fn guild_role_delete(guild_id: GuildId, removed_role_id: RoleId);
+
+            /// Refer to [`EventHandler::guild_role_update`](trait.EventHandler.html#method.guild_role_update).
+            #[cfg(feature = "cache")]
+            fn guild_role_update(guild_id: GuildId, old_data_if_available: Option<Role>, new: Role);
+
+            /// Refer to [`EventHandler::guild_role_update`](trait.EventHandler.html#method.guild_role_update).
+            #[cfg(not(feature = "cache"))]
+            fn guild_role_update(guild_id: GuildId, new_data: Role);
+
+            /// Refer to [`EventHandler::guild_unavailable`](trait.EventHandler.html#method.guild_unavailable).
+            fn guild_unavailable(guild_id: GuildId);
+
+            /// Refer to [`EventHandler::guild_update`](trait.EventHandler.html#method.guild_update).
+            #[cfg(feature = "cache")]
+            fn guild_update(old_data_if_available: Option<Arc<RwLock<Guild>>>, new_but_incomplete: PartialGuild);
+
+            /// Refer to [`EventHandler::guild_update`](trait.EventHandler.html#method.guild_update).
+            #[cfg(not(feature = "cache"))]
+            fn guild_update(new_but_incomplete_data: PartialGuild);
+
+            /// Refer to [`EventHandler::message`](trait.EventHandler.html#method.message).
+            fn message(new_message: Message);
+
+            /// Refer to [`EventHandler::message_delete`](trait.EventHandler.html#method.message_delete).
+            fn message_delete(channel_id: ChannelId, deleted_message_id: MessageId);
+
+            /// Refer to [`EventHandler::message_delete_bulk`](trait.EventHandler.html#method.message_delete_bulk).
+            fn message_delete_bulk(channel_id: ChannelId, multiple_deleted_messages_ids: Vec<MessageId>);
+
+            /// Refer to [`EventHandler::message_update`](trait.EventHandler.html#method.message_update).
+            #[cfg(feature = "cache")]
+            fn message_update(old_if_available: Option<Message>, new: Option<Message>, event: MessageUpdateEvent);
+
+            /// Refer to [`EventHandler::message_update`](trait.EventHandler.html#method.message_update).
+            #[cfg(not(feature = "cache"))]
+            fn message_update(new_data: MessageUpdateEvent);
+
+            /// Refer to [`EventHandler::reaction_add`](trait.EventHandler.html#method.reaction_add).
+            fn reaction_add(add_reaction: Reaction);
+
+            /// Refer to [`EventHandler::reaction_remove`](trait.EventHandler.html#method.reaction_remove).
+            fn reaction_remove(removed_reaction: Reaction);
+
+            /// Refer to [`EventHandler::reaction_remove_all`](trait.EventHandler.html#method.reaction_remove_all).
+            fn reaction_remove_all(channel_id: ChannelId, removed_from_message_id: MessageId);
+
+            /// Refer to [`EventHandler::interaction_create`](trait.EventHandler.html#method.interaction_create).
+            fn interaction_create(interaction: Interaction);
+
+            /// Refer to [`EventHandler::presence_replace`](trait.EventHandler.html#method.presence_replace).
+            fn presence_replace(presences: Vec<Presence>);
+
+            /// Refer to [`EventHandler::presence_update`](trait.EventHandler.html#method.presence_update).
+            fn presence_update(new_data: PresenceUpdateEvent);
+
+            /// Refer to [`EventHandler::ready`](trait.EventHandler.html#method.ready).
+            fn ready(data_about_bot: Ready);
+
+            /// Refer to [`EventHandler::resume`](trait.EventHandler.html#method.resume).
+            fn resume(event: ResumedEvent);
+
+            /// Refer to [`EventHandler::shard_stage_update`](trait.EventHandler.html#method.shard_stage_update).
+            fn shard_stage_update(event: ShardStageUpdateEvent);
+
+            /// Refer to [`EventHandler::typing_start`](trait.EventHandler.html#method.typing_start).
+            fn typing_start(event: TypingStartEvent);
+
+            /// Refer to [`EventHandler::unknown`](trait.EventHandler.html#method.unknown).
+            fn unknown(name: String, raw: Value);
+
+            /// Refer to [`EventHandler::user_update`](trait.EventHandler.html#method.user_update).
+            #[cfg(feature = "cache")]
+            fn user_update(old_data: CurrentUser, new: CurrentUser);
+
+            /// Refer to [`EventHandler::user_update`](trait.EventHandler.html#method.user_update).
+            #[cfg(not(feature = "cache"))]
+            fn user_update(new_data: CurrentUser);
+
+            /// Refer to [`EventHandler::voice_server_update`](trait.EventHandler.html#method.voice_server_update).
+            fn voice_server_update(event: VoiceServerUpdateEvent);
+
+            /// Refer to [`EventHandler::voice_state_update`](trait.EventHandler.html#method.voice_state_update).
+            #[cfg(feature = "cache")]
+            fn voice_state_update(guild_id: Option<GuildId>, old: Option<VoiceState>, new: VoiceState);
+
+            /// Refer to [`EventHandler::voice_state_update`](trait.EventHandler.html#method.voice_state_update).
+            #[cfg(not(feature = "cache"))]
+            fn voice_state_update(guild_id: Option<GuildId>, new: VoiceState);
+
+            /// Refer to [`EventHandler::webhook_update`](trait.EventHandler.html#method.webhook_update).
+            fn webhook_update(guild_id: GuildId, belongs_to_channel_id: ChannelId);
+        }
+    };
+}
@@ -3,6 +3,9 @@ pub mod macros;
 
 pub mod prelude;
 
+#[cfg(feature = "gateway")]
+pub mod files;
+
 mod rwlock_ext;
 
 pub use self::rwlock_ext::RwLockExt;
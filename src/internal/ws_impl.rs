@@ -1,13 +1,14 @@
 use flate2::read::ZlibDecoder;
-use crate::gateway::WsClient;
+use crate::gateway::{etf, GatewayEncoding, GatewayPayload, GatewaySocket};
 use crate::internal::prelude::*;
 use serde_json;
-use tungstenite::{
-    util::NonBlockingResult,
-    Message,
-};
+use std::io::{Error as IoError, ErrorKind, Read};
+use tungstenite::Message;
 use log::warn;
 
+#[cfg(feature = "voice")]
+use tungstenite::util::NonBlockingResult;
+
 #[cfg(not(feature = "native_tls_backend"))]
 use std::{
     error::Error as StdError,
@@ -16,15 +17,22 @@ use std::{
         Formatter,
         Result as FmtResult,
     },
-    io::Error as IoError,
     net::TcpStream,
     sync::Arc,
 };
 #[cfg(not(feature = "native_tls_backend"))]
 use url::Url;
 #[cfg(not(feature = "native_tls_backend"))]
+use crate::gateway::WsClient;
+#[cfg(not(feature = "native_tls_backend"))]
 use std::net::ToSocketAddrs;
 
+/// Whether a read failed only because no message arrived in time.
+pub fn is_timeout(why: &IoError) -> bool {
+    why.kind() == ErrorKind::WouldBlock || why.kind() == ErrorKind::TimedOut
+}
+
+#[cfg(feature = "voice")]
 pub trait ReceiverExt {
     fn recv_json(&mut self) -> Result<Option<Value>>;
     fn try_recv_json(&mut self) -> Result<Option<Value>>;
@@ -32,9 +40,11 @@ pub trait ReceiverExt {
 
 pub trait SenderExt {
     fn send_json(&mut self, value: &Value) -> Result<()>;
+    fn send_encoded(&mut self, value: &Value, encoding: GatewayEncoding) -> Result<()>;
 }
 
-impl ReceiverExt for WsClient {
+#[cfg(feature = "voice")]
+impl<T: GatewaySocket + ?Sized> ReceiverExt for T {
     fn recv_json(&mut self) -> Result<Option<Value>> {
         convert_ws_message(Some(self.read_message()?))
     }
@@ -44,15 +54,89 @@ impl ReceiverExt for WsClient {
     }
 }
 
-impl SenderExt for WsClient {
+impl<T: GatewaySocket + ?Sized> SenderExt for T {
     fn send_json(&mut self, value: &Value) -> Result<()> {
         serde_json::to_string(value)
             .map(Message::Text)
             .map_err(Error::from)
             .and_then(|m| self.write_message(m).map_err(Error::from))
     }
+
+    fn send_encoded(&mut self, value: &Value, encoding: GatewayEncoding) -> Result<()> {
+        match encoding {
+            GatewayEncoding::Etf => etf::encode(value)
+                .map(Message::Binary)
+                .and_then(|m| self.write_message(m).map_err(Error::from)),
+            _ => self.send_json(value),
+        }
+    }
+}
+
+/// Decodes an uncompressed gateway payload in the given encoding, reading
+/// only the envelope of JSON payloads.
+pub(crate) fn decode_gateway_payload(bytes: Vec<u8>, encoding: GatewayEncoding) -> Result<GatewayPayload> {
+    match encoding {
+        GatewayEncoding::Etf => GatewayPayload::from_etf(bytes),
+        _ => GatewayPayload::from_json(String::from_utf8(bytes)?),
+    }
+}
+
+/// Converts a message received from the gateway into a payload, reading only
+/// the envelope of JSON payloads.
+pub(crate) fn convert_gateway_message(message: Message, encoding: GatewayEncoding) -> Result<Option<GatewayPayload>> {
+    let payload = match message {
+        // Payloads compressed by the gateway start with a zlib header rather
+        // than the ETF version byte.
+        Message::Binary(bytes) if encoding == GatewayEncoding::Etf => {
+            let payload = if bytes.first() == Some(&131) {
+                GatewayPayload::from_etf(bytes)
+            } else {
+                let mut inflated = Vec::new();
+
+                ZlibDecoder::new(&bytes[..])
+                    .read_to_end(&mut inflated)
+                    .map_err(Error::from)
+                    .and_then(|_| GatewayPayload::from_etf(inflated))
+            };
+
+            // As with text, the bytes are not worth copying for the rare
+            // error.
+            payload.map_err(|why| {
+                warn!("Err decoding ETF: {:?}", why);
+
+                why
+            })?
+        },
+        Message::Binary(bytes) => {
+            let mut text = String::new();
+
+            ZlibDecoder::new(&bytes[..])
+                .read_to_string(&mut text)
+                .map_err(Error::from)
+                .and_then(|_| GatewayPayload::from_json(text))
+                .map_err(|why| {
+                    warn!("Err deserializing bytes: {:?}; bytes: {:?}", why, bytes);
+
+                    why
+                })?
+        },
+        Message::Text(text) => {
+            // The text is only needed again to log an error, which is rare
+            // enough not to be worth copying every payload for.
+            GatewayPayload::from_json(text).map_err(|why| {
+                warn!("Err deserializing text: {:?}", why);
+
+                why
+            })?
+        },
+        // Ping/Pong message behaviour is internally handled by tungstenite.
+        _ => return Ok(None),
+    };
+
+    Ok(Some(payload))
 }
 
+#[cfg(feature = "voice")]
 #[inline]
 fn convert_ws_message(message: Option<Message>) -> Result<Option<Value>>{
     Ok(match message {
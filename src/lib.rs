@@ -77,6 +77,10 @@ pub mod framework;
 pub mod gateway;
 #[cfg(feature = "http")]
 pub mod http;
+#[cfg(feature = "interactions_endpoint")]
+pub mod interactions;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 #[cfg(feature = "utils")]
 pub mod utils;
 #[cfg(feature = "voice")]
@@ -0,0 +1,555 @@
+//! Operational metrics of a [`Client`], served in the Prometheus text format.
+//!
+//! Counters are collected as shards receive events and as HTTP requests are
+//! made, while the state of shards, of the dispatch threadpool and of the
+//! cache is read when the metrics are rendered. Serve them via
+//! [`Client::serve_metrics`], or render them yourself via a
+//! [`MetricsExporter`].
+//!
+//! The following metrics are exported:
+//!
+//! - `serenity_shard_stage`: `1` for the current [`ConnectionStage`] of each
+//! shard, and `0` for the others
+//! - `serenity_shard_latency_seconds`: the heartbeat latency of each shard
+//! - `serenity_shard_queued_commands`: the gateway commands each shard holds
+//! back to stay within the gateway's ratelimit
+//! - `serenity_shard_reconnects_total`: the number of times each shard resumed
+//! or restarted
+//! - `serenity_gateway_events_total`: the number of events received, by type
+//! - `serenity_dispatch_queued_jobs` and `serenity_dispatch_active_jobs`: the
+//! event handler calls waiting for and running in the threadpool
+//! - `serenity_http_requests_total`: the number of HTTP requests, by route
+//! and status
+//! - `serenity_http_ratelimit_sleeps_total` and
+//! `serenity_http_ratelimit_sleep_seconds_total`: the number of times and the
+//! time spent waiting for ratelimits, by route and reason
+//! - `serenity_cache_entities`: the number of cached entities, by kind
+//!
+//! [`Client`]: ../client/struct.Client.html
+//! [`Client::serve_metrics`]: ../client/struct.Client.html#method.serve_metrics
+//! [`ConnectionStage`]: ../gateway/enum.ConnectionStage.html
+//! [`MetricsExporter`]: struct.MetricsExporter.html
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write as FmtWrite},
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use log::warn;
+use parking_lot::Mutex;
+use threadpool::ThreadPool;
+
+use crate::client::bridge::gateway::ShardManager;
+use crate::gateway::ConnectionStage;
+use crate::http::routing::Route;
+use crate::internal::prelude::*;
+use crate::CacheAndHttp;
+
+/// How long a scrape may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+const STAGES: [ConnectionStage; 6] = [
+    ConnectionStage::Connected,
+    ConnectionStage::Connecting,
+    ConnectionStage::Disconnected,
+    ConnectionStage::Handshake,
+    ConnectionStage::Identifying,
+    ConnectionStage::Resuming,
+];
+
+/// Counters updated by the shard runners of a [`ShardManager`].
+///
+/// [`ShardManager`]: ../client/bridge/gateway/struct.ShardManager.html
+#[derive(Debug, Default)]
+pub struct ShardMetrics {
+    events: Mutex<HashMap<String, u64>>,
+    reconnects: Mutex<HashMap<(u64, &'static str), u64>>,
+}
+
+impl ShardMetrics {
+    /// Returns the number of events received, by type.
+    pub fn events(&self) -> HashMap<String, u64> {
+        self.events.lock().clone()
+    }
+
+    /// Returns the number of times the shard with the given ID resumed or
+    /// restarted.
+    pub fn reconnects(&self, shard_id: u64) -> u64 {
+        self.reconnects
+            .lock()
+            .iter()
+            .filter(|&(&(id, _), _)| id == shard_id)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    pub(crate) fn record_event(&self, kind: &str) {
+        let mut events = self.events.lock();
+
+        match events.get_mut(kind) {
+            Some(count) => *count += 1,
+            None => {
+                events.insert(kind.to_string(), 1);
+            },
+        }
+    }
+
+    pub(crate) fn record_reconnect(&self, shard_id: u64, kind: &'static str) {
+        *self.reconnects.lock().entry((shard_id, kind)).or_insert(0) += 1;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Sleeps {
+    count: u64,
+    total: Duration,
+}
+
+/// Counters updated by an [`Http`] client.
+///
+/// Routes are labelled by name, without their major parameter, so that each
+/// channel or guild does not get its own series.
+///
+/// [`Http`]: ../http/raw/struct.Http.html
+#[derive(Debug, Default)]
+pub struct HttpMetrics {
+    requests: Mutex<HashMap<(String, u16), u64>>,
+    sleeps: Mutex<HashMap<(String, &'static str), Sleeps>>,
+}
+
+impl HttpMetrics {
+    /// Returns the number of requests made, by route name and status code.
+    pub fn requests(&self) -> HashMap<(String, u16), u64> {
+        self.requests.lock().clone()
+    }
+
+    pub(crate) fn record_request(&self, route: &Route, status: u16) {
+        *self.requests.lock().entry((route_name(route), status)).or_insert(0) += 1;
+    }
+
+    pub(crate) fn record_sleep(&self, route: &Route, reason: &'static str, delay: Duration) {
+        let mut sleeps = self.sleeps.lock();
+        let sleeps = sleeps.entry((route_name(route), reason)).or_default();
+
+        sleeps.count += 1;
+        sleeps.total += delay;
+    }
+}
+
+// The name of the route's variant, such as `ChannelsIdMessages`.
+fn route_name(route: &Route) -> String {
+    let name = format!("{:?}", route);
+
+    match name.find('(') {
+        Some(end) => name[..end].to_string(),
+        None => name,
+    }
+}
+
+/// Renders the metrics of a client in the Prometheus text format, and serves
+/// them over HTTP.
+///
+/// # Examples
+///
+/// Serve the metrics of a client on port 9000, for a Prometheus server on the
+/// same machine to scrape:
+///
+/// ```rust,no_run
+/// # use serenity::prelude::*;
+/// # struct Handler;
+/// #
+/// # impl EventHandler for Handler {}
+/// #
+/// # fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+/// use serenity::metrics::MetricsExporter;
+/// use std::sync::Arc;
+///
+/// let mut client = Client::new("my token", Handler)?;
+///
+/// let exporter = MetricsExporter::new(
+///     Arc::clone(&client.shard_manager),
+///     Arc::clone(&client.cache_and_http),
+///     client.threadpool.clone(),
+/// );
+/// exporter.serve("127.0.0.1:9000")?;
+///
+/// client.start()?;
+/// #     Ok(())
+/// # }
+/// #
+/// # fn main() {
+/// #     try_main().unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct MetricsExporter {
+    shard_manager: Arc<Mutex<ShardManager>>,
+    cache_and_http: Arc<CacheAndHttp>,
+    threadpool: ThreadPool,
+}
+
+impl MetricsExporter {
+    /// Creates an exporter for the given shard manager, cache and HTTP
+    /// client, and dispatch threadpool, such as those of a [`Client`].
+    ///
+    /// [`Client`]: ../client/struct.Client.html
+    pub fn new(
+        shard_manager: Arc<Mutex<ShardManager>>,
+        cache_and_http: Arc<CacheAndHttp>,
+        threadpool: ThreadPool,
+    ) -> Self {
+        MetricsExporter {
+            shard_manager,
+            cache_and_http,
+            threadpool,
+        }
+    }
+
+    /// Renders the current metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = Output::default();
+
+        self.render_shards(&mut out);
+        self.render_dispatch(&mut out);
+        self.render_http(&mut out);
+        #[cfg(feature = "cache")]
+        self.render_cache(&mut out);
+
+        out.0
+    }
+
+    /// Binds to the given address and serves the rendered metrics to every
+    /// request in a background thread, regardless of its path.
+    ///
+    /// Returns the address bound to, which is useful when binding to port
+    /// `0`.
+    pub fn serve(self, addr: impl ToSocketAddrs) -> Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream
+                    .map_err(From::from)
+                    .and_then(|stream| self.respond(stream));
+
+                if let Err(why) = result {
+                    warn!("[Metrics] Err serving metrics: {:?}", why);
+                }
+            }
+        });
+
+        Ok(addr)
+    }
+
+    fn respond(&self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+        // The request itself is irrelevant, but is read up to the end of its
+        // headers so that the client does not see the connection reset.
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+
+        while reader.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+
+        let body = self.render();
+        let mut writer = stream;
+        write!(
+            writer,
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            body.len(),
+            body,
+        )?;
+
+        Ok(())
+    }
+
+    fn render_shards(&self, out: &mut Output) {
+        let (mut runners, metrics) = {
+            let manager = self.shard_manager.lock();
+            let runners = manager.runners
+                .lock()
+                .iter()
+                .map(|(id, runner)| (id.0, runner.stage, runner.latency, *runner.queued_commands.lock()))
+                .collect::<Vec<_>>();
+
+            (runners, manager.metrics())
+        };
+        runners.sort_by_key(|&(id, _, _, _)| id);
+
+        out.family("serenity_shard_stage", "gauge", "Whether each shard is in the given connection stage.");
+        for &(id, current, _, _) in &runners {
+            let shard = id.to_string();
+
+            for &stage in &STAGES {
+                let value = if stage == current { 1 } else { 0 };
+                let stage = format!("{:?}", stage);
+
+                out.sample("serenity_shard_stage", &[("shard", &shard), ("stage", &stage)], value);
+            }
+        }
+
+        out.family("serenity_shard_latency_seconds", "gauge", "The heartbeat latency of each shard.");
+        for &(id, _, latency, _) in &runners {
+            if let Some(latency) = latency {
+                let seconds = latency.as_secs() as f64 + f64::from(latency.subsec_nanos()) / 1e9;
+
+                out.sample("serenity_shard_latency_seconds", &[("shard", &id.to_string())], seconds);
+            }
+        }
+
+        out.family("serenity_shard_queued_commands", "gauge", "The gateway commands each shard holds back.");
+        for &(id, _, _, queued) in &runners {
+            let count = queued.commands + if queued.presence { 1 } else { 0 };
+
+            out.sample("serenity_shard_queued_commands", &[("shard", &id.to_string())], count);
+        }
+
+        let mut reconnects = metrics.reconnects.lock().iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+        reconnects.sort();
+
+        out.family("serenity_shard_reconnects_total", "counter", "The number of times each shard reconnected.");
+        for ((id, kind), count) in reconnects {
+            out.sample("serenity_shard_reconnects_total", &[("shard", &id.to_string()), ("type", kind)], count);
+        }
+
+        let mut events = metrics.events().into_iter().collect::<Vec<_>>();
+        events.sort();
+
+        out.family("serenity_gateway_events_total", "counter", "The number of gateway events received.");
+        for (kind, count) in events {
+            out.sample("serenity_gateway_events_total", &[("type", &kind)], count);
+        }
+    }
+
+    fn render_dispatch(&self, out: &mut Output) {
+        out.family("serenity_dispatch_queued_jobs", "gauge", "The event handler calls waiting for a thread.");
+        out.sample("serenity_dispatch_queued_jobs", &[], self.threadpool.queued_count());
+
+        out.family("serenity_dispatch_active_jobs", "gauge", "The event handler calls running.");
+        out.sample("serenity_dispatch_active_jobs", &[], self.threadpool.active_count());
+    }
+
+    fn render_http(&self, out: &mut Output) {
+        let metrics = &self.cache_and_http.http.metrics;
+
+        let mut requests = metrics.requests().into_iter().collect::<Vec<_>>();
+        requests.sort();
+
+        out.family("serenity_http_requests_total", "counter", "The number of HTTP requests made.");
+        for ((route, status), count) in requests {
+            let status = status.to_string();
+
+            out.sample("serenity_http_requests_total", &[("route", &route), ("status", &status)], count);
+        }
+
+        let mut sleeps = metrics.sleeps.lock().iter().map(|(k, &v)| (k.clone(), v)).collect::<Vec<_>>();
+        sleeps.sort_by(|a, b| a.0.cmp(&b.0));
+
+        out.family("serenity_http_ratelimit_sleeps_total", "counter", "The number of times a request waited for a ratelimit.");
+        for &((ref route, reason), sleeps) in &sleeps {
+            out.sample("serenity_http_ratelimit_sleeps_total", &[("route", route), ("reason", reason)], sleeps.count);
+        }
+
+        out.family("serenity_http_ratelimit_sleep_seconds_total", "counter", "The time spent waiting for ratelimits.");
+        for &((ref route, reason), sleeps) in &sleeps {
+            let seconds = sleeps.total.as_secs() as f64 + f64::from(sleeps.total.subsec_nanos()) / 1e9;
+
+            out.sample("serenity_http_ratelimit_sleep_seconds_total", &[("route", route), ("reason", reason)], seconds);
+        }
+    }
+
+    #[cfg(feature = "cache")]
+    fn render_cache(&self, out: &mut Output) {
+        let counts = {
+            let cache = self.cache_and_http.cache.read();
+
+            [
+                ("categories", cache.categories.len()),
+                ("channels", cache.channels.len()),
+                ("groups", cache.groups.len()),
+                ("guilds", cache.guilds.len()),
+                ("messages", cache.messages.values().map(HashMap::len).sum()),
+                ("presences", cache.presences.len()),
+                ("private_channels", cache.private_channels.len()),
+                ("unavailable_guilds", cache.unavailable_guilds.len()),
+                ("users", cache.users.len()),
+            ]
+        };
+
+        out.family("serenity_cache_entities", "gauge", "The number of cached entities.");
+        for &(kind, count) in &counts {
+            out.sample("serenity_cache_entities", &[("kind", kind)], count);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Output(String);
+
+impl Output {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {} {}", name, help);
+        let _ = writeln!(self.0, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.0.push_str(name);
+
+        if !labels.is_empty() {
+            self.0.push('{');
+
+            for (i, &(label, value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.0.push(',');
+                }
+
+                let _ = write!(self.0, "{}=\"", label);
+
+                for c in value.chars() {
+                    match c {
+                        '\\' => self.0.push_str("\\\\"),
+                        '"' => self.0.push_str("\\\""),
+                        '\n' => self.0.push_str("\\n"),
+                        c => self.0.push(c),
+                    }
+                }
+
+                self.0.push('"');
+            }
+
+            self.0.push('}');
+        }
+
+        let _ = writeln!(self.0, " {}", value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        sync::Arc,
+        time::Duration,
+    };
+    use parking_lot::{Mutex, RwLock};
+    use threadpool::ThreadPool;
+    use typemap::ShareMap;
+    use crate::client::{
+        bridge::gateway::{ShardManager, ShardManagerOptions},
+        EventHandler,
+        EventPipeline,
+        RawEventHandler,
+    };
+    use crate::http::routing::Route;
+    use crate::CacheAndHttp;
+    use super::{HttpMetrics, MetricsExporter, Output, ShardMetrics};
+
+    #[cfg(feature = "voice")]
+    use crate::{client::bridge::voice::ClientVoiceManager, model::id::UserId};
+
+    struct Handler;
+
+    impl EventHandler for Handler {}
+    impl RawEventHandler for Handler {}
+
+    #[test]
+    fn escapes_label_values() {
+        let mut out = Output::default();
+        out.family("serenity_test", "counter", "A test.");
+        out.sample("serenity_test", &[("a", "x\"y\\z\n"), ("b", "c")], 3);
+        out.sample("serenity_test", &[], 1.5);
+
+        assert_eq!(out.0, "# HELP serenity_test A test.\n\
+                           # TYPE serenity_test counter\n\
+                           serenity_test{a=\"x\\\"y\\\\z\\n\",b=\"c\"} 3\n\
+                           serenity_test 1.5\n");
+    }
+
+    #[test]
+    fn labels_routes_without_major_parameters() {
+        let metrics = HttpMetrics::default();
+        metrics.record_request(&Route::ChannelsIdMessages(1), 200);
+        metrics.record_request(&Route::ChannelsIdMessages(2), 200);
+        metrics.record_request(&Route::Gateway, 429);
+        metrics.record_sleep(&Route::Gateway, "route", Duration::from_millis(500));
+
+        let requests = metrics.requests();
+        assert_eq!(requests[&("ChannelsIdMessages".to_string(), 200)], 2);
+        assert_eq!(requests[&("Gateway".to_string(), 429)], 1);
+    }
+
+    #[test]
+    fn counts_events_and_reconnects() {
+        let metrics = ShardMetrics::default();
+        metrics.record_event("MESSAGE_CREATE");
+        metrics.record_event("MESSAGE_CREATE");
+        metrics.record_event("READY");
+        metrics.record_reconnect(1, "resume");
+        metrics.record_reconnect(1, "restart");
+
+        assert_eq!(metrics.events()["MESSAGE_CREATE"], 2);
+        assert_eq!(metrics.events()["READY"], 1);
+        assert_eq!(metrics.reconnects(1), 2);
+        assert_eq!(metrics.reconnects(0), 0);
+    }
+
+    #[test]
+    fn serves_rendered_metrics() {
+        let cache_and_http = Arc::new(CacheAndHttp::default());
+        let threadpool = ThreadPool::new(1);
+        let (shard_manager, _monitor) = ShardManager::new(ShardManagerOptions {
+            data: &Arc::new(RwLock::new(ShareMap::custom())),
+            event_handler: &None::<Arc<Handler>>,
+            raw_event_handler: &None::<Arc<Handler>>,
+            #[cfg(feature = "framework")]
+            framework: &Arc::new(Mutex::new(None)),
+            pipeline: &Arc::new(EventPipeline::new()),
+            compression: Default::default(),
+            connector: None,
+            encoding: Default::default(),
+            intents: None,
+            identify_options: Default::default(),
+            session_store: None,
+            recorder: None,
+            event_types: None,
+            dispatch_order: Default::default(),
+            identify_lock: None,
+            shard_index: 0,
+            shard_init: 0,
+            shard_total: 1,
+            threadpool: threadpool.clone(),
+            #[cfg(feature = "voice")]
+            voice_manager: &Arc::new(Mutex::new(ClientVoiceManager::new(0, UserId(0)))),
+            ws_url: &Arc::new(Mutex::new(String::new())),
+            cache_and_http: &cache_and_http,
+        });
+
+        cache_and_http.http.metrics.record_request(&Route::Gateway, 200);
+        shard_manager.lock().metrics().record_event("READY");
+
+        let exporter = MetricsExporter::new(shard_manager, cache_and_http, threadpool);
+        let addr = exporter.serve("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("serenity_http_requests_total{route=\"Gateway\",status=\"200\"} 1\n"));
+        assert!(response.contains("serenity_gateway_events_total{type=\"READY\"} 1\n"));
+        assert!(response.contains("serenity_dispatch_queued_jobs 0\n"));
+    }
+}
@@ -66,6 +66,9 @@ pub struct GuildChannel {
     ///
     /// The original voice channel has an Id equal to the guild's Id,
     /// incremented by one.
+    ///
+    /// Filled in from the guild when received as part of one.
+    #[serde(default)]
     pub guild_id: GuildId,
     /// The type of the channel.
     #[serde(rename = "type")]
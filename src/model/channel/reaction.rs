@@ -31,6 +31,11 @@ pub struct Reaction {
     pub channel_id: ChannelId,
     /// The reactive emoji used.
     pub emoji: ReactionType,
+    /// The Id of the [`Guild`] the reaction was made in, if it was made in
+    /// one.
+    ///
+    /// [`Guild`]: ../guild/struct.Guild.html
+    pub guild_id: Option<GuildId>,
     /// The Id of the [`Message`] that was reacted to.
     ///
     /// [`Message`]: struct.Message.html
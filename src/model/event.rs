@@ -10,6 +10,7 @@ use serde::ser::{
 use serde_json;
 use std::collections::HashMap;
 use super::utils::{deserialize_emojis, deserialize_u64};
+use super::guild::GuildOrUnavailable;
 use super::prelude::*;
 use crate::constants::{OpCode, VoiceOpCode};
 use crate::internal::prelude::*;
@@ -637,6 +638,17 @@ impl CacheUpdate for GuildMemberUpdateEvent {
 pub struct GuildMembersChunkEvent {
     pub guild_id: GuildId,
     pub members: HashMap<UserId, Member>,
+    /// The index of this chunk among the chunks sent for the request, starting
+    /// at `0`.
+    pub chunk_index: u32,
+    /// The number of chunks sent for the request.
+    pub chunk_count: u32,
+    /// The IDs of requested users that are not members of the guild.
+    pub not_found: Vec<UserId>,
+    /// The presences of the members, if they were requested.
+    pub presences: Vec<Presence>,
+    /// The nonce of the request the chunk was sent for, if it had one.
+    pub nonce: Option<String>,
     #[serde(skip)]
     pub(crate) _nonexhaustive: (),
 }
@@ -654,6 +666,10 @@ impl CacheUpdate for GuildMembersChunkEvent {
             guild.with_mut(|g| g.members.extend(self.members.clone()))
         }
 
+        for presence in &self.presences {
+            cache.presences.insert(presence.user_id, presence.clone());
+        }
+
         None
     }
 }
@@ -692,9 +708,33 @@ impl<'de> Deserialize<'de> for GuildMembersChunkEvent {
                 }))
             .map_err(DeError::custom)?;
 
+        // Chunks sent before these fields existed are always complete.
+        let chunk_index = map.get("chunk_index").and_then(Value::as_u64).unwrap_or(0) as u32;
+        let chunk_count = map.get("chunk_count").and_then(Value::as_u64).unwrap_or(1) as u32;
+
+        let not_found = match map.remove("not_found").filter(|v| !v.is_null()) {
+            Some(v) => serde_json::from_value::<Vec<UserId>>(v).map_err(DeError::custom)?,
+            None => Vec::new(),
+        };
+
+        let presences = match map.remove("presences").filter(|v| !v.is_null()) {
+            Some(v) => serde_json::from_value::<Vec<Presence>>(v).map_err(DeError::custom)?,
+            None => Vec::new(),
+        };
+
+        let nonce = match map.remove("nonce") {
+            Some(Value::String(nonce)) => Some(nonce),
+            _ => None,
+        };
+
         Ok(GuildMembersChunkEvent {
             guild_id,
             members,
+            chunk_index,
+            chunk_count,
+            not_found,
+            presences,
+            nonce,
             _nonexhaustive: (),
         })
     }
@@ -1162,6 +1202,32 @@ pub struct ReactionRemoveAllEvent {
     pub(crate) _nonexhaustive: (),
 }
 
+/// An [`Interaction`] was created, e.g. by a user invoking an application
+/// command.
+///
+/// [`Interaction`]: ../interactions/struct.Interaction.html
+#[derive(Clone, Debug)]
+pub struct InteractionCreateEvent {
+    pub interaction: Interaction,
+    pub(crate) _nonexhaustive: (),
+}
+
+impl<'de> Deserialize<'de> for InteractionCreateEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        Ok(Self {
+            interaction: Interaction::deserialize(deserializer)?,
+            _nonexhaustive: (),
+        })
+    }
+}
+
+impl Serialize for InteractionCreateEvent {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+        where S: Serializer {
+        Interaction::serialize(&self.interaction, serializer)
+    }
+}
+
 /// The "Ready" event, containing initial ready cache
 #[derive(Clone, Debug)]
 pub struct ReadyEvent {
@@ -1382,68 +1448,53 @@ pub enum GatewayEvent {
     __Nonexhaustive,
 }
 
-impl<'de> Deserialize<'de> for GatewayEvent {
-    fn deserialize<D: Deserializer<'de>>(deserializer: D)
-        -> StdResult<Self, D::Error> {
-        let mut map = JsonMap::deserialize(deserializer)?;
-
-        let op = map.remove("op")
-            .ok_or_else(|| DeError::custom("expected op"))
-            .and_then(OpCode::deserialize)
-            .map_err(DeError::custom)?;
+impl GatewayEvent {
+    /// Creates an event from the fields of a gateway payload, deserializing
+    /// its `d` field - if present - with the given deserializer.
+    pub(crate) fn from_parts<'de, D>(
+        op: OpCode,
+        seq: Option<u64>,
+        kind: Option<EventType>,
+        data: Option<D>,
+    ) -> StdResult<Self, D::Error> where D: Deserializer<'de> {
+        #[derive(Deserialize)]
+        struct HelloData {
+            heartbeat_interval: u64,
+        }
 
         Ok(match op {
             OpCode::Event => {
-                let s = map.remove("s")
-                    .ok_or_else(|| DeError::custom("expected gateway event sequence"))
-                    .and_then(u64::deserialize)
-                    .map_err(DeError::custom)?;
-                let kind = map.remove("t")
-                    .ok_or_else(|| DeError::custom("expected gateway event type"))
-                    .and_then(EventType::deserialize)
-                    .map_err(DeError::custom)?;
-                let payload = map.remove("d").ok_or_else(|| {
-                    SerenityError::Decode {
-                        msg: "expected gateway event d",
-                        value: Value::Object(map),
-                    }
-                }).map_err(DeError::custom)?;
-
-                let x = deserialize_event_with_type(kind, payload)
-                    .map_err(DeError::custom)?;
-
-                GatewayEvent::Dispatch(s, x)
+                let s = seq.ok_or_else(|| {
+                    DeError::custom("expected gateway event sequence")
+                })?;
+                let kind = kind.ok_or_else(|| {
+                    DeError::custom("expected gateway event type")
+                })?;
+                let data = data.ok_or_else(|| {
+                    DeError::custom("expected gateway event d")
+                })?;
+
+                GatewayEvent::Dispatch(s, deserialize_event(kind, data)?)
             },
             OpCode::Heartbeat => {
-                let s = map.remove("s")
-                    .ok_or_else(|| DeError::custom("Expected heartbeat s"))
-                    .and_then(u64::deserialize)
-                    .map_err(DeError::custom)?;
+                let s = seq.ok_or_else(|| DeError::custom("Expected heartbeat s"))?;
 
                 GatewayEvent::Heartbeat(s)
             },
             OpCode::Reconnect => GatewayEvent::Reconnect,
             OpCode::InvalidSession => {
-                let resumable = map.remove("d")
-                    .ok_or_else(|| {
-                        DeError::custom("expected gateway invalid session d")
-                    })
-                    .and_then(bool::deserialize)
-                    .map_err(DeError::custom)?;
+                let data = data.ok_or_else(|| {
+                    DeError::custom("expected gateway invalid session d")
+                })?;
 
-                GatewayEvent::InvalidateSession(resumable)
+                GatewayEvent::InvalidateSession(bool::deserialize(data)?)
             },
             OpCode::Hello => {
-                let mut d = map.remove("d")
-                    .ok_or_else(|| DeError::custom("expected gateway hello d"))
-                    .and_then(JsonMap::deserialize)
-                    .map_err(DeError::custom)?;
-                let interval = d.remove("heartbeat_interval")
-                    .ok_or_else(|| DeError::custom("expected gateway hello interval"))
-                    .and_then(u64::deserialize)
-                    .map_err(DeError::custom)?;
+                let data = data.ok_or_else(|| {
+                    DeError::custom("expected gateway hello d")
+                })?;
 
-                GatewayEvent::Hello(interval)
+                GatewayEvent::Hello(HelloData::deserialize(data)?.heartbeat_interval)
             },
             OpCode::HeartbeatAck => GatewayEvent::HeartbeatAck,
             _ => return Err(DeError::custom("invalid opcode")),
@@ -1451,6 +1502,29 @@ impl<'de> Deserialize<'de> for GatewayEvent {
     }
 }
 
+impl<'de> Deserialize<'de> for GatewayEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D)
+        -> StdResult<Self, D::Error> {
+        let mut map = JsonMap::deserialize(deserializer)?;
+
+        let op = map.remove("op")
+            .ok_or_else(|| DeError::custom("expected op"))
+            .and_then(OpCode::deserialize)
+            .map_err(DeError::custom)?;
+        let seq = match map.remove("s") {
+            Some(s) => Option::<u64>::deserialize(s).map_err(DeError::custom)?,
+            None => None,
+        };
+        let kind = match map.remove("t") {
+            Some(t) => Option::<EventType>::deserialize(t).map_err(DeError::custom)?,
+            None => None,
+        };
+
+        GatewayEvent::from_parts(op, seq, kind, map.remove("d"))
+            .map_err(DeError::custom)
+    }
+}
+
 /// Event received over a websocket connection
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -1545,6 +1619,12 @@ pub enum Event {
     /// [`Reaction`]: struct.Reaction.html
     /// [`EventHandler::reaction_remove_all`]: ../../client/trait.EventHandler.html#method.reaction_remove_all
     ReactionRemoveAll(ReactionRemoveAllEvent),
+    /// An application command was invoked.
+    ///
+    /// Fires the [`EventHandler::interaction_create`] event handler.
+    ///
+    /// [`EventHandler::interaction_create`]: ../../client/trait.EventHandler.html#method.interaction_create
+    InteractionCreate(InteractionCreateEvent),
     /// The first event in a connection, containing the initial ready cache.
     ///
     /// May also be received at a later time in the event of a reconnect.
@@ -1570,6 +1650,55 @@ pub enum Event {
     __Nonexhaustive,
 }
 
+impl Event {
+    /// The type of the event, as used to filter the events a shard
+    /// dispatches.
+    pub fn event_type(&self) -> EventType {
+        match *self {
+            Event::ChannelCreate(_) => EventType::ChannelCreate,
+            Event::ChannelDelete(_) => EventType::ChannelDelete,
+            Event::ChannelPinsUpdate(_) => EventType::ChannelPinsUpdate,
+            Event::ChannelRecipientAdd(_) => EventType::ChannelRecipientAdd,
+            Event::ChannelRecipientRemove(_) => EventType::ChannelRecipientRemove,
+            Event::ChannelUpdate(_) => EventType::ChannelUpdate,
+            Event::GuildBanAdd(_) => EventType::GuildBanAdd,
+            Event::GuildBanRemove(_) => EventType::GuildBanRemove,
+            Event::GuildCreate(_) => EventType::GuildCreate,
+            Event::GuildDelete(_) => EventType::GuildDelete,
+            Event::GuildEmojisUpdate(_) => EventType::GuildEmojisUpdate,
+            Event::GuildIntegrationsUpdate(_) => EventType::GuildIntegrationsUpdate,
+            Event::GuildMemberAdd(_) => EventType::GuildMemberAdd,
+            Event::GuildMemberRemove(_) => EventType::GuildMemberRemove,
+            Event::GuildMemberUpdate(_) => EventType::GuildMemberUpdate,
+            Event::GuildMembersChunk(_) => EventType::GuildMembersChunk,
+            Event::GuildRoleCreate(_) => EventType::GuildRoleCreate,
+            Event::GuildRoleDelete(_) => EventType::GuildRoleDelete,
+            Event::GuildRoleUpdate(_) => EventType::GuildRoleUpdate,
+            Event::GuildUnavailable(_) => EventType::GuildUnavailable,
+            Event::GuildUpdate(_) => EventType::GuildUpdate,
+            Event::MessageCreate(_) => EventType::MessageCreate,
+            Event::MessageDelete(_) => EventType::MessageDelete,
+            Event::MessageDeleteBulk(_) => EventType::MessageDeleteBulk,
+            Event::MessageUpdate(_) => EventType::MessageUpdate,
+            Event::PresenceUpdate(_) => EventType::PresenceUpdate,
+            Event::PresencesReplace(_) => EventType::PresencesReplace,
+            Event::ReactionAdd(_) => EventType::ReactionAdd,
+            Event::ReactionRemove(_) => EventType::ReactionRemove,
+            Event::ReactionRemoveAll(_) => EventType::ReactionRemoveAll,
+            Event::InteractionCreate(_) => EventType::InteractionCreate,
+            Event::Ready(_) => EventType::Ready,
+            Event::Resumed(_) => EventType::Resumed,
+            Event::TypingStart(_) => EventType::TypingStart,
+            Event::UserUpdate(_) => EventType::UserUpdate,
+            Event::VoiceStateUpdate(_) => EventType::VoiceStateUpdate,
+            Event::VoiceServerUpdate(_) => EventType::VoiceServerUpdate,
+            Event::WebhookUpdate(_) => EventType::WebhookUpdate,
+            Event::Unknown(ref event) => EventType::Other(event.kind.clone()),
+            Event::__Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
 /// Deserializes a `serde_json::Value` into an `Event`.
 ///
 /// The given `EventType` is used to determine what event to deserialize into.
@@ -1588,109 +1717,143 @@ pub enum Event {
 /// [`ChannelCreateEvent`]: struct.ChannelCreateEvent.html
 /// [`GuildUnavailableEvent`]: struct.GuildUnavailableEvent.html
 pub fn deserialize_event_with_type(kind: EventType, v: Value) -> Result<Event> {
+    deserialize_event(kind, v).map_err(From::from)
+}
+
+/// The data of a `GUILD_DELETE`, which is only an outage when the guild is
+/// marked as unavailable.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GuildDeleteData {
+    Unavailable {
+        id: GuildId,
+        #[serde(rename = "unavailable", deserialize_with = "deserialize_true")]
+        _unavailable: bool,
+    },
+    Deleted(PartialGuild),
+}
+
+fn deserialize_true<'de, D: Deserializer<'de>>(deserializer: D) -> StdResult<bool, D::Error> {
+    if bool::deserialize(deserializer)? {
+        Ok(true)
+    } else {
+        Err(DeError::custom("guild is available"))
+    }
+}
+
+/// Deserializes an `Event` of the given type from any deserializer, such as
+/// one reading the `d` field of a gateway payload straight from its text.
+///
+/// Refer to [`deserialize_event_with_type`] for how the type is used.
+///
+/// [`deserialize_event_with_type`]: fn.deserialize_event_with_type.html
+pub(crate) fn deserialize_event<'de, D>(kind: EventType, deserializer: D)
+    -> StdResult<Event, D::Error> where D: Deserializer<'de> {
     Ok(match kind {
-        EventType::ChannelCreate => Event::ChannelCreate(serde_json::from_value(v)?),
-        EventType::ChannelDelete => Event::ChannelDelete(serde_json::from_value(v)?),
+        EventType::ChannelCreate => Event::ChannelCreate(Deserialize::deserialize(deserializer)?),
+        EventType::ChannelDelete => Event::ChannelDelete(Deserialize::deserialize(deserializer)?),
         EventType::ChannelPinsUpdate => {
-            Event::ChannelPinsUpdate(serde_json::from_value(v)?)
+            Event::ChannelPinsUpdate(Deserialize::deserialize(deserializer)?)
         },
         EventType::ChannelRecipientAdd => {
-            Event::ChannelRecipientAdd(serde_json::from_value(v)?)
+            Event::ChannelRecipientAdd(Deserialize::deserialize(deserializer)?)
         },
         EventType::ChannelRecipientRemove => {
-            Event::ChannelRecipientRemove(serde_json::from_value(v)?)
+            Event::ChannelRecipientRemove(Deserialize::deserialize(deserializer)?)
         },
-        EventType::ChannelUpdate => Event::ChannelUpdate(serde_json::from_value(v)?),
-        EventType::GuildBanAdd => Event::GuildBanAdd(serde_json::from_value(v)?),
-        EventType::GuildBanRemove => Event::GuildBanRemove(serde_json::from_value(v)?),
+        EventType::ChannelUpdate => Event::ChannelUpdate(Deserialize::deserialize(deserializer)?),
+        EventType::GuildBanAdd => Event::GuildBanAdd(Deserialize::deserialize(deserializer)?),
+        EventType::GuildBanRemove => Event::GuildBanRemove(Deserialize::deserialize(deserializer)?),
         EventType::GuildCreate | EventType::GuildUnavailable => {
             // GuildUnavailable isn't actually received from the gateway, so it
             // can be lumped in with GuildCreate's arm.
-
-            let mut map = JsonMap::deserialize(v)?;
-
-            if map.remove("unavailable")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false) {
-                let guild_data = serde_json::from_value(Value::Object(map))?;
-
-                Event::GuildUnavailable(guild_data)
-            } else {
-                Event::GuildCreate(serde_json::from_value(Value::Object(map))?)
+            match GuildOrUnavailable::deserialize(deserializer)? {
+                GuildOrUnavailable::Available(guild) => Event::GuildCreate(GuildCreateEvent {
+                    guild,
+                    _nonexhaustive: (),
+                }),
+                GuildOrUnavailable::Unavailable(guild_id) => {
+                    Event::GuildUnavailable(GuildUnavailableEvent {
+                        guild_id,
+                        _nonexhaustive: (),
+                    })
+                },
             }
         },
-        EventType::GuildDelete => {
-            let mut map = JsonMap::deserialize(v)?;
-
-            if map.remove("unavailable")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false) {
-                let guild_data = serde_json::from_value(Value::Object(map))?;
-
-                Event::GuildUnavailable(guild_data)
-            } else {
-                Event::GuildDelete(serde_json::from_value(Value::Object(map))?)
-            }
+        EventType::GuildDelete => match GuildDeleteData::deserialize(deserializer)? {
+            GuildDeleteData::Unavailable { id, .. } => {
+                Event::GuildUnavailable(GuildUnavailableEvent {
+                    guild_id: id,
+                    _nonexhaustive: (),
+                })
+            },
+            GuildDeleteData::Deleted(guild) => Event::GuildDelete(GuildDeleteEvent {
+                guild,
+                _nonexhaustive: (),
+            }),
         },
         EventType::GuildEmojisUpdate => {
-            Event::GuildEmojisUpdate(serde_json::from_value(v)?)
+            Event::GuildEmojisUpdate(Deserialize::deserialize(deserializer)?)
         },
         EventType::GuildIntegrationsUpdate => {
-            Event::GuildIntegrationsUpdate(serde_json::from_value(v)?)
+            Event::GuildIntegrationsUpdate(Deserialize::deserialize(deserializer)?)
         },
-        EventType::GuildMemberAdd => Event::GuildMemberAdd(serde_json::from_value(v)?),
+        EventType::GuildMemberAdd => Event::GuildMemberAdd(Deserialize::deserialize(deserializer)?),
         EventType::GuildMemberRemove => {
-            Event::GuildMemberRemove(serde_json::from_value(v)?)
+            Event::GuildMemberRemove(Deserialize::deserialize(deserializer)?)
         },
         EventType::GuildMemberUpdate => {
-            Event::GuildMemberUpdate(serde_json::from_value(v)?)
+            Event::GuildMemberUpdate(Deserialize::deserialize(deserializer)?)
         },
         EventType::GuildMembersChunk => {
-            Event::GuildMembersChunk(serde_json::from_value(v)?)
+            Event::GuildMembersChunk(Deserialize::deserialize(deserializer)?)
         },
         EventType::GuildRoleCreate => {
-            Event::GuildRoleCreate(serde_json::from_value(v)?)
+            Event::GuildRoleCreate(Deserialize::deserialize(deserializer)?)
         },
         EventType::GuildRoleDelete => {
-            Event::GuildRoleDelete(serde_json::from_value(v)?)
+            Event::GuildRoleDelete(Deserialize::deserialize(deserializer)?)
         },
         EventType::GuildRoleUpdate => {
-            Event::GuildRoleUpdate(serde_json::from_value(v)?)
+            Event::GuildRoleUpdate(Deserialize::deserialize(deserializer)?)
         },
-        EventType::GuildUpdate => Event::GuildUpdate(serde_json::from_value(v)?),
-        EventType::MessageCreate => Event::MessageCreate(serde_json::from_value(v)?),
-        EventType::MessageDelete => Event::MessageDelete(serde_json::from_value(v)?),
+        EventType::GuildUpdate => Event::GuildUpdate(Deserialize::deserialize(deserializer)?),
+        EventType::InteractionCreate => {
+            Event::InteractionCreate(Deserialize::deserialize(deserializer)?)
+        },
+        EventType::MessageCreate => Event::MessageCreate(Deserialize::deserialize(deserializer)?),
+        EventType::MessageDelete => Event::MessageDelete(Deserialize::deserialize(deserializer)?),
         EventType::MessageDeleteBulk => {
-            Event::MessageDeleteBulk(serde_json::from_value(v)?)
+            Event::MessageDeleteBulk(Deserialize::deserialize(deserializer)?)
         },
         EventType::ReactionAdd => {
-            Event::ReactionAdd(serde_json::from_value(v)?)
+            Event::ReactionAdd(Deserialize::deserialize(deserializer)?)
         },
         EventType::ReactionRemove => {
-            Event::ReactionRemove(serde_json::from_value(v)?)
+            Event::ReactionRemove(Deserialize::deserialize(deserializer)?)
         },
         EventType::ReactionRemoveAll => {
-            Event::ReactionRemoveAll(serde_json::from_value(v)?)
+            Event::ReactionRemoveAll(Deserialize::deserialize(deserializer)?)
         },
-        EventType::MessageUpdate => Event::MessageUpdate(serde_json::from_value(v)?),
-        EventType::PresenceUpdate => Event::PresenceUpdate(serde_json::from_value(v)?),
+        EventType::MessageUpdate => Event::MessageUpdate(Deserialize::deserialize(deserializer)?),
+        EventType::PresenceUpdate => Event::PresenceUpdate(Deserialize::deserialize(deserializer)?),
         EventType::PresencesReplace => {
-            Event::PresencesReplace(serde_json::from_value(v)?)
+            Event::PresencesReplace(Deserialize::deserialize(deserializer)?)
         },
-        EventType::Ready => Event::Ready(serde_json::from_value(v)?),
-        EventType::Resumed => Event::Resumed(serde_json::from_value(v)?),
-        EventType::TypingStart => Event::TypingStart(serde_json::from_value(v)?),
-        EventType::UserUpdate => Event::UserUpdate(serde_json::from_value(v)?),
+        EventType::Ready => Event::Ready(Deserialize::deserialize(deserializer)?),
+        EventType::Resumed => Event::Resumed(Deserialize::deserialize(deserializer)?),
+        EventType::TypingStart => Event::TypingStart(Deserialize::deserialize(deserializer)?),
+        EventType::UserUpdate => Event::UserUpdate(Deserialize::deserialize(deserializer)?),
         EventType::VoiceServerUpdate => {
-            Event::VoiceServerUpdate(serde_json::from_value(v)?)
+            Event::VoiceServerUpdate(Deserialize::deserialize(deserializer)?)
         },
         EventType::VoiceStateUpdate => {
-            Event::VoiceStateUpdate(serde_json::from_value(v)?)
+            Event::VoiceStateUpdate(Deserialize::deserialize(deserializer)?)
         },
-        EventType::WebhookUpdate => Event::WebhookUpdate(serde_json::from_value(v)?),
+        EventType::WebhookUpdate => Event::WebhookUpdate(Deserialize::deserialize(deserializer)?),
         EventType::Other(kind) => Event::Unknown(UnknownEvent {
             kind: kind.to_owned(),
-            value: v,
+            value: Value::deserialize(deserializer)?,
             _nonexhaustive: (),
         }),
         EventType::__Nonexhaustive => unreachable!(),
@@ -1706,7 +1869,7 @@ pub fn deserialize_event_with_type(kind: EventType, v: Value) -> Result<Event> {
 /// [`EventType::ChannelCreate`].
 ///
 /// [`EventType::ChannelCreate`]: enum.EventType.html#variant.ChannelCreate
-#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub enum EventType {
     /// Indicator that a channel create payload was received.
     ///
@@ -1888,6 +2051,12 @@ pub enum EventType {
     ///
     /// [`ReactionRemoveAllEvent`]: struct.ReactionRemoveAllEvent.html
     ReactionRemoveAll,
+    /// Indicator that an interaction create payload was received.
+    ///
+    /// This maps to [`InteractionCreateEvent`].
+    ///
+    /// [`InteractionCreateEvent`]: struct.InteractionCreateEvent.html
+    InteractionCreate,
     /// Indicator that a ready payload was received.
     ///
     /// This maps to [`ReadyEvent`].
@@ -1974,6 +2143,7 @@ impl<'de> Deserialize<'de> for EventType {
                     "GUILD_ROLE_DELETE" => EventType::GuildRoleDelete,
                     "GUILD_ROLE_UPDATE" => EventType::GuildRoleUpdate,
                     "GUILD_UPDATE" => EventType::GuildUpdate,
+                    "INTERACTION_CREATE" => EventType::InteractionCreate,
                     "MESSAGE_CREATE" => EventType::MessageCreate,
                     "MESSAGE_DELETE" => EventType::MessageDelete,
                     "MESSAGE_DELETE_BULK" => EventType::MessageDeleteBulk,
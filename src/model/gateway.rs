@@ -288,6 +288,100 @@ pub struct ActivityAssets {
     pub(crate) _nonexhaustive: (),
 }
 
+bitflags! {
+    /// The [gateway intents] of a shard, sent when identifying to subscribe to
+    /// groups of events.
+    ///
+    /// When intents are given, Discord only sends the events belonging to
+    /// them, which saves both bandwidth and deserialization time. Events that
+    /// are not subscribed to are never dispatched to the [`EventHandler`], and
+    /// the cache is not updated by them. Refer to the [`EventHandler`]
+    /// documentation for which methods require which intent.
+    ///
+    /// [`GUILD_MEMBERS`] and [`GUILD_PRESENCES`] are _privileged_ intents, and
+    /// must additionally be enabled in the application's settings on the
+    /// developer portal.
+    ///
+    /// # Examples
+    ///
+    /// Only receive guild and guild message events:
+    ///
+    /// ```rust
+    /// use serenity::model::gateway::GatewayIntents;
+    ///
+    /// let intents = GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES;
+    ///
+    /// assert!(!intents.is_privileged());
+    /// ```
+    ///
+    /// [`EventHandler`]: ../../client/trait.EventHandler.html
+    /// [`GUILD_MEMBERS`]: #associatedconstant.GUILD_MEMBERS
+    /// [`GUILD_PRESENCES`]: #associatedconstant.GUILD_PRESENCES
+    /// [gateway intents]: https://discord.com/developers/docs/topics/gateway#gateway-intents
+    #[derive(Default)]
+    pub struct GatewayIntents: u64 {
+        /// Guild, channel and role create, update and delete events, as well
+        /// as channel pins updates.
+        ///
+        /// This is required for the cache to know about guilds.
+        const GUILDS = 1;
+        /// Guild member add, update and remove events.
+        ///
+        /// **Note**: This is a privileged intent.
+        const GUILD_MEMBERS = 1 << 1;
+        /// Guild ban add and remove events.
+        const GUILD_BANS = 1 << 2;
+        /// Guild emojis update events.
+        const GUILD_EMOJIS = 1 << 3;
+        /// Guild integrations update events.
+        const GUILD_INTEGRATIONS = 1 << 4;
+        /// Webhooks update events.
+        const GUILD_WEBHOOKS = 1 << 5;
+        /// Invite create and delete events.
+        const GUILD_INVITES = 1 << 6;
+        /// Voice state update events.
+        const GUILD_VOICE_STATES = 1 << 7;
+        /// Presence update events.
+        ///
+        /// **Note**: This is a privileged intent.
+        const GUILD_PRESENCES = 1 << 8;
+        /// Message create, update and delete events in guilds.
+        const GUILD_MESSAGES = 1 << 9;
+        /// Reaction add and remove events in guilds.
+        const GUILD_MESSAGE_REACTIONS = 1 << 10;
+        /// Typing start events in guilds.
+        const GUILD_MESSAGE_TYPING = 1 << 11;
+        /// Message create, update and delete events in direct messages.
+        const DIRECT_MESSAGES = 1 << 12;
+        /// Reaction add and remove events in direct messages.
+        const DIRECT_MESSAGE_REACTIONS = 1 << 13;
+        /// Typing start events in direct messages.
+        const DIRECT_MESSAGE_TYPING = 1 << 14;
+    }
+}
+
+impl GatewayIntents {
+    /// Returns the intents that must be enabled on the developer portal before
+    /// they can be used.
+    pub fn privileged() -> Self {
+        Self::GUILD_MEMBERS | Self::GUILD_PRESENCES
+    }
+
+    /// Returns all intents except the [privileged] ones.
+    ///
+    /// [privileged]: #method.privileged
+    pub fn non_privileged() -> Self {
+        Self::all() - Self::privileged()
+    }
+
+    /// Whether any of the intents is [privileged].
+    ///
+    /// [privileged]: #method.privileged
+    pub fn is_privileged(self) -> bool {
+        self.intersects(Self::privileged())
+    }
+}
+
 bitflags! {
     /// A set of flags defining what is in an activity's payload.
     #[derive(Deserialize, Serialize)]
@@ -509,6 +603,12 @@ pub struct Ready {
 /// ratelimit period.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SessionStartLimit {
+    /// The number of shards that may identify at the same time.
+    ///
+    /// Shards are grouped into buckets by `shard_id % max_concurrency`, and
+    /// each bucket may identify once every 5 seconds.
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: u64,
     /// The number of sessions that you can still initiate within the current
     /// ratelimit period.
     pub remaining: u64,
@@ -519,6 +619,11 @@ pub struct SessionStartLimit {
     #[serde(skip)]
     pub(crate) _nonexhaustive: (),
 }
+
+fn default_max_concurrency() -> u64 {
+    1
+}
+
 /// Timestamps of when a user started and/or is ending their activity.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ActivityTimestamps {
@@ -5,7 +5,7 @@ use crate::{model::prelude::*};
 #[cfg(all(feature = "cache", feature = "model"))]
 use crate::cache::CacheRwLock;
 #[cfg(feature = "model")]
-use crate::builder::{EditGuild, EditMember, EditRole};
+use crate::builder::{CreateApplicationCommand, EditGuild, EditMember, EditRole};
 #[cfg(feature = "model")]
 use crate::internal::prelude::*;
 #[cfg(feature = "model")]
@@ -170,6 +170,33 @@ impl GuildId {
         http.as_ref().create_emoji(self.0, &map)
     }
 
+    /// Creates an application command only available in the guild,
+    /// overwriting an existing guild command with the same name.
+    ///
+    /// Unlike global commands, guild commands are available immediately,
+    /// which makes them useful while developing a command.
+    ///
+    /// Refer to [`CreateApplicationCommand`] for an example.
+    ///
+    /// [`CreateApplicationCommand`]: ../../builder/struct.CreateApplicationCommand.html
+    #[cfg(feature = "http")]
+    pub fn create_application_command<A, F>(self, http: impl AsRef<Http>, application_id: A, f: F) -> Result<ApplicationCommand>
+        where A: Into<ApplicationId>, F: FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+        let mut command = CreateApplicationCommand::default();
+        f(&mut command);
+        let map = utils::hashmap_to_json_map(command.0);
+
+        http.as_ref().create_guild_application_command(application_id.into().0, self.0, &Value::Object(map))
+    }
+
+    /// Gets all application commands only available in the guild.
+    #[cfg(feature = "http")]
+    #[inline]
+    pub fn application_commands<A>(self, http: impl AsRef<Http>, application_id: A) -> Result<Vec<ApplicationCommand>>
+        where A: Into<ApplicationId> {
+        http.as_ref().get_guild_application_commands(application_id.into().0, self.0)
+    }
+
     /// Creates an integration for the guild.
     ///
     /// Requires the [Manage Guild] permission.
@@ -266,6 +293,14 @@ impl GuildId {
         http.as_ref().delete_guild_integration(self.0, integration_id.0)
     }
 
+    /// Deletes an application command of the guild.
+    #[cfg(feature = "http")]
+    #[inline]
+    pub fn delete_application_command<A, C>(self, http: impl AsRef<Http>, application_id: A, command_id: C) -> Result<()>
+        where A: Into<ApplicationId>, C: Into<CommandId> {
+        http.as_ref().delete_guild_application_command(application_id.into().0, self.0, command_id.into().0)
+    }
+
     /// Deletes a [`Role`] by Id from the guild.
     ///
     /// Also see [`Role::delete`] if you have the `cache` and `methods` features
@@ -374,6 +409,30 @@ impl GuildId {
         http.as_ref().edit_nickname(self.0, new_nickname)
     }
 
+    /// Edits an application command of the guild.
+    #[cfg(feature = "http")]
+    pub fn edit_application_command<A, C, F>(
+        self,
+        http: impl AsRef<Http>,
+        application_id: A,
+        command_id: C,
+        f: F,
+    ) -> Result<ApplicationCommand>
+        where A: Into<ApplicationId>,
+              C: Into<CommandId>,
+              F: FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+        let mut command = CreateApplicationCommand::default();
+        f(&mut command);
+        let map = utils::hashmap_to_json_map(command.0);
+
+        http.as_ref().edit_guild_application_command(
+            application_id.into().0,
+            self.0,
+            command_id.into().0,
+            &Value::Object(map),
+        )
+    }
+
     /// Edits a [`Role`], optionally setting its new fields.
     ///
     /// Requires the [Manage Roles] permission.
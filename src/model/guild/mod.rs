@@ -22,17 +22,18 @@ pub use self::premium_tier::*;
 
 use chrono::{DateTime, FixedOffset};
 use crate::model::prelude::*;
-use serde::de::Error as DeError;
+use parking_lot::RwLock;
+use serde::de::{Error as DeError, IgnoredAny, MapAccess, Visitor};
+use std::{
+    fmt::{Formatter, Result as FmtResult},
+    sync::Arc,
+};
 use super::utils::*;
 
 #[cfg(all(feature = "cache", feature = "model"))]
 use crate::cache::CacheRwLock;
-#[cfg(all(feature = "cache", feature = "model"))]
-use parking_lot::RwLock;
 #[cfg(all(feature = "http", feature = "model"))]
 use serde_json::json;
-#[cfg(all(feature = "cache", feature = "model"))]
-use std::sync::Arc;
 #[cfg(feature = "model")]
 use crate::builder::{CreateChannel, EditGuild, EditMember, EditRole};
 #[cfg(feature = "model")]
@@ -1701,194 +1702,239 @@ impl Guild {
     }
 }
 
+/// A guild as received in a `GUILD_CREATE`, which only holds the guild's ID
+/// when the guild is unavailable.
+pub(crate) enum GuildOrUnavailable {
+    Available(Guild),
+    Unavailable(GuildId),
+}
+
+impl<'de> Deserialize<'de> for GuildOrUnavailable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        deserializer.deserialize_map(GuildVisitor {
+            allow_unavailable: true,
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for Guild {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
-        let mut map = JsonMap::deserialize(deserializer)?;
-
-        let id = map.get("id")
-            .and_then(|x| x.as_str())
-            .and_then(|x| x.parse::<u64>().ok());
-
-        if let Some(guild_id) = id {
-            if let Some(array) = map.get_mut("channels").and_then(|x| x.as_array_mut()) {
-                for value in array {
-                    if let Some(channel) = value.as_object_mut() {
-                        channel
-                            .insert("guild_id".to_string(), Value::Number(Number::from(guild_id)));
-                    }
-                }
-            }
+        match deserializer.deserialize_map(GuildVisitor {
+            allow_unavailable: false,
+        })? {
+            GuildOrUnavailable::Available(guild) => Ok(guild),
+            GuildOrUnavailable::Unavailable(_) => unreachable!("unavailable guilds are not allowed"),
+        }
+    }
+}
 
-            if let Some(array) = map.get_mut("members").and_then(|x| x.as_array_mut()) {
-                for value in array {
-                    if let Some(member) = value.as_object_mut() {
-                        member
-                            .insert("guild_id".to_string(), Value::Number(Number::from(guild_id)));
-                    }
-                }
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum GuildField {
+    AfkChannelId,
+    AfkTimeout,
+    ApplicationId,
+    Banner,
+    Channels,
+    DefaultMessageNotifications,
+    Description,
+    Emojis,
+    ExplicitContentFilter,
+    Features,
+    Icon,
+    Id,
+    JoinedAt,
+    Large,
+    MemberCount,
+    Members,
+    MfaLevel,
+    Name,
+    OwnerId,
+    PreferredLocale,
+    PremiumSubscriptionCount,
+    PremiumTier,
+    Presences,
+    Region,
+    Roles,
+    Splash,
+    SystemChannelId,
+    Unavailable,
+    VanityUrlCode,
+    VerificationLevel,
+    VoiceStates,
+    #[serde(other)]
+    Unknown,
+}
+
+// Reads a guild field by field, straight from the deserializer, as guilds are
+// by far the largest payloads received.
+struct GuildVisitor {
+    allow_unavailable: bool,
+}
+
+impl<'de> Visitor<'de> for GuildVisitor {
+    type Value = GuildOrUnavailable;
+
+    fn expecting(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        formatter.write_str("struct Guild")
+    }
+
+    fn visit_map<V: MapAccess<'de>>(self, mut map: V) -> StdResult<Self::Value, V::Error> {
+        let mut afk_channel_id = None;
+        let mut afk_timeout = None;
+        let mut application_id = None;
+        let mut banner = None;
+        let mut channels = None;
+        let mut default_message_notifications = None;
+        let mut description = None;
+        let mut emojis = None;
+        let mut explicit_content_filter = None;
+        let mut features = None;
+        let mut icon = None;
+        let mut id = None;
+        let mut joined_at = None;
+        let mut large = None;
+        let mut member_count = None;
+        let mut members = None;
+        let mut mfa_level = None;
+        let mut name = None;
+        let mut owner_id = None;
+        let mut preferred_locale = None;
+        let mut premium_subscription_count = None;
+        let mut premium_tier = None;
+        let mut presences = None;
+        let mut region = None;
+        let mut roles = None;
+        let mut splash = None;
+        let mut system_channel_id = None;
+        let mut unavailable = None;
+        let mut vanity_url_code = None;
+        let mut verification_level = None;
+        let mut voice_states = None;
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                GuildField::AfkChannelId => afk_channel_id = map.next_value()?,
+                GuildField::AfkTimeout => afk_timeout = Some(map.next_value()?),
+                GuildField::ApplicationId => application_id = map.next_value()?,
+                GuildField::Banner => banner = map.next_value()?,
+                GuildField::Channels => channels = Some(map.next_value::<Vec<GuildChannel>>()?),
+                GuildField::DefaultMessageNotifications => {
+                    default_message_notifications = Some(map.next_value()?);
+                },
+                GuildField::Description => description = map.next_value()?,
+                GuildField::Emojis => emojis = Some(map.next_value::<Vec<Emoji>>()?),
+                GuildField::ExplicitContentFilter => explicit_content_filter = Some(map.next_value()?),
+                GuildField::Features => features = Some(map.next_value()?),
+                GuildField::Icon => icon = map.next_value()?,
+                GuildField::Id => id = Some(map.next_value()?),
+                GuildField::JoinedAt => joined_at = Some(map.next_value()?),
+                GuildField::Large => large = Some(map.next_value()?),
+                GuildField::MemberCount => member_count = Some(map.next_value()?),
+                GuildField::Members => members = Some(map.next_value::<Vec<Member>>()?),
+                GuildField::MfaLevel => mfa_level = Some(map.next_value()?),
+                GuildField::Name => name = Some(map.next_value()?),
+                GuildField::OwnerId => owner_id = Some(map.next_value()?),
+                GuildField::PreferredLocale => preferred_locale = Some(map.next_value()?),
+                // In some cases Discord sends `null` rather than 0.
+                GuildField::PremiumSubscriptionCount => premium_subscription_count = map.next_value()?,
+                GuildField::PremiumTier => premium_tier = Some(map.next_value()?),
+                GuildField::Presences => presences = Some(map.next_value::<Vec<Presence>>()?),
+                GuildField::Region => region = Some(map.next_value()?),
+                GuildField::Roles => roles = Some(map.next_value::<Vec<Role>>()?),
+                GuildField::Splash => splash = map.next_value()?,
+                GuildField::SystemChannelId => system_channel_id = map.next_value()?,
+                GuildField::Unavailable => unavailable = map.next_value()?,
+                GuildField::VanityUrlCode => vanity_url_code = map.next_value()?,
+                GuildField::VerificationLevel => verification_level = Some(map.next_value()?),
+                GuildField::VoiceStates => voice_states = Some(map.next_value::<Vec<VoiceState>>()?),
+                GuildField::Unknown => {
+                    map.next_value::<IgnoredAny>()?;
+                },
             }
         }
 
-        let afk_channel_id = match map.remove("afk_channel_id") {
-            Some(v) => serde_json::from_value::<Option<ChannelId>>(v)
-                .map_err(DeError::custom)?,
-            None => None,
-        };
-        let afk_timeout = map.remove("afk_timeout")
-            .ok_or_else(|| DeError::custom("expected guild afk_timeout"))
-            .and_then(u64::deserialize)
-            .map_err(DeError::custom)?;
-        let application_id = match map.remove("application_id") {
-            Some(v) => serde_json::from_value::<Option<ApplicationId>>(v)
-                .map_err(DeError::custom)?,
-            None => None,
-        };
-        let channels = map.remove("channels")
-            .ok_or_else(|| DeError::custom("expected guild channels"))
-            .and_then(deserialize_guild_channels)
-            .map_err(DeError::custom)?;
-        let default_message_notifications = map.remove("default_message_notifications")
-            .ok_or_else(|| {
-                DeError::custom("expected guild default_message_notifications")
-            })
-            .and_then(DefaultMessageNotificationLevel::deserialize)
-            .map_err(DeError::custom)?;
-        let emojis = map.remove("emojis")
-            .ok_or_else(|| DeError::custom("expected guild emojis"))
-            .and_then(deserialize_emojis)
-            .map_err(DeError::custom)?;
-        let explicit_content_filter = map.remove("explicit_content_filter")
-            .ok_or_else(|| DeError::custom(
-                "expected guild explicit_content_filter"
-            ))
-            .and_then(ExplicitContentFilter::deserialize)
-            .map_err(DeError::custom)?;
-        let features = map.remove("features")
-            .ok_or_else(|| DeError::custom("expected guild features"))
-            .and_then(serde_json::from_value::<Vec<String>>)
-            .map_err(DeError::custom)?;
-        let icon = match map.remove("icon") {
-            Some(v) => Option::<String>::deserialize(v).map_err(DeError::custom)?,
-            None => None,
-        };
-        let id = map.remove("id")
-            .ok_or_else(|| DeError::custom("expected guild id"))
-            .and_then(GuildId::deserialize)
-            .map_err(DeError::custom)?;
-        let joined_at = map.remove("joined_at")
-            .ok_or_else(|| DeError::custom("expected guild joined_at"))
-            .and_then(DateTime::deserialize)
-            .map_err(DeError::custom)?;
-        let large = map.remove("large")
-            .ok_or_else(|| DeError::custom("expected guild large"))
-            .and_then(bool::deserialize)
-            .map_err(DeError::custom)?;
-        let member_count = map.remove("member_count")
-            .ok_or_else(|| DeError::custom("expected guild member_count"))
-            .and_then(u64::deserialize)
-            .map_err(DeError::custom)?;
-        let members = map.remove("members")
-            .ok_or_else(|| DeError::custom("expected guild members"))
-            .and_then(deserialize_members)
-            .map_err(DeError::custom)?;
-        let mfa_level = map.remove("mfa_level")
-            .ok_or_else(|| DeError::custom("expected guild mfa_level"))
-            .and_then(MfaLevel::deserialize)
-            .map_err(DeError::custom)?;
-        let name = map.remove("name")
-            .ok_or_else(|| DeError::custom("expected guild name"))
-            .and_then(String::deserialize)
-            .map_err(DeError::custom)?;
-        let owner_id = map.remove("owner_id")
-            .ok_or_else(|| DeError::custom("expected guild owner_id"))
-            .and_then(UserId::deserialize)
-            .map_err(DeError::custom)?;
-        let presences = map.remove("presences")
-            .ok_or_else(|| DeError::custom("expected guild presences"))
-            .and_then(deserialize_presences)
-            .map_err(DeError::custom)?;
-        let region = map.remove("region")
-            .ok_or_else(|| DeError::custom("expected guild region"))
-            .and_then(String::deserialize)
-            .map_err(DeError::custom)?;
-        let roles = map.remove("roles")
-            .ok_or_else(|| DeError::custom("expected guild roles"))
-            .and_then(deserialize_roles)
-            .map_err(DeError::custom)?;
-        let splash = match map.remove("splash") {
-            Some(v) => Option::<String>::deserialize(v).map_err(DeError::custom)?,
-            None => None,
-        };
-        let system_channel_id = match map.remove("system_channel_id") {
-            Some(v) => Option::<ChannelId>::deserialize(v).map_err(DeError::custom)?,
-            None => None,
-        };
-        let verification_level = map.remove("verification_level")
-            .ok_or_else(|| DeError::custom("expected guild verification_level"))
-            .and_then(VerificationLevel::deserialize)
-            .map_err(DeError::custom)?;
-        let voice_states = map.remove("voice_states")
-            .ok_or_else(|| DeError::custom("expected guild voice_states"))
-            .and_then(deserialize_voice_states)
-            .map_err(DeError::custom)?;
-        let description = match map.remove("description") {
-            Some(v) => Option::<String>::deserialize(v).map_err(DeError::custom)?,
-            None => None,
-        };
-        let premium_tier = match map.remove("premium_tier") {
-            Some(v) => PremiumTier::deserialize(v).map_err(DeError::custom)?,
-            None => PremiumTier::default(),
-        };
-        let premium_subscription_count = match map.remove("premium_subscription_count") {
-            Some(Value::Null) | None => 0,
-            Some(v) => u64::deserialize(v).map_err(DeError::custom)?,
-        };
-        let banner = match map.remove("banner") {
-            Some(v) => Option::<String>::deserialize(v).map_err(DeError::custom)?,
-            None => None,
-        };
-        let vanity_url_code = match map.remove("vanity_url_code") {
-            Some(v) => Option::<String>::deserialize(v).map_err(DeError::custom)?,
-            None => None,
-        };
-        let preferred_locale = map.remove("preferred_locale") 
-            .ok_or_else(|| DeError::custom("expected preferred locale"))
-            .and_then(String::deserialize)
-            .map_err(DeError::custom)?;
+        let id: GuildId = id.ok_or_else(|| DeError::missing_field("id"))?;
+
+        if self.allow_unavailable && unavailable == Some(true) {
+            return Ok(GuildOrUnavailable::Unavailable(id));
+        }
+
+        // Channels and members don't repeat the ID of the guild they are
+        // received in.
+        let mut channels = channels.ok_or_else(|| DeError::missing_field("channels"))?;
+        let mut members = members.ok_or_else(|| DeError::missing_field("members"))?;
 
-        Ok(Self {
+        for channel in &mut channels {
+            channel.guild_id = id;
+        }
+
+        for member in &mut members {
+            member.guild_id = id;
+        }
+
+        Ok(GuildOrUnavailable::Available(Guild {
             afk_channel_id,
             application_id,
-            afk_timeout,
-            channels,
-            default_message_notifications,
-            emojis,
-            explicit_content_filter,
-            features,
+            afk_timeout: afk_timeout.ok_or_else(|| DeError::missing_field("afk_timeout"))?,
+            channels: channels
+                .into_iter()
+                .map(|channel| (channel.id, Arc::new(RwLock::new(channel))))
+                .collect(),
+            default_message_notifications: default_message_notifications
+                .ok_or_else(|| DeError::missing_field("default_message_notifications"))?,
+            emojis: emojis
+                .ok_or_else(|| DeError::missing_field("emojis"))?
+                .into_iter()
+                .map(|emoji| (emoji.id, emoji))
+                .collect(),
+            explicit_content_filter: explicit_content_filter
+                .ok_or_else(|| DeError::missing_field("explicit_content_filter"))?,
+            features: features.ok_or_else(|| DeError::missing_field("features"))?,
             icon,
             id,
-            joined_at,
-            large,
-            member_count,
-            members,
-            mfa_level,
-            name,
-            owner_id,
-            presences,
-            region,
-            roles,
+            joined_at: joined_at.ok_or_else(|| DeError::missing_field("joined_at"))?,
+            large: large.ok_or_else(|| DeError::missing_field("large"))?,
+            member_count: member_count.ok_or_else(|| DeError::missing_field("member_count"))?,
+            members: members
+                .into_iter()
+                .map(|member| {
+                    let user_id = member.user.read().id;
+
+                    (user_id, member)
+                })
+                .collect(),
+            mfa_level: mfa_level.ok_or_else(|| DeError::missing_field("mfa_level"))?,
+            name: name.ok_or_else(|| DeError::missing_field("name"))?,
+            owner_id: owner_id.ok_or_else(|| DeError::missing_field("owner_id"))?,
+            presences: presences
+                .ok_or_else(|| DeError::missing_field("presences"))?
+                .into_iter()
+                .map(|presence| (presence.user_id, presence))
+                .collect(),
+            region: region.ok_or_else(|| DeError::missing_field("region"))?,
+            roles: roles
+                .ok_or_else(|| DeError::missing_field("roles"))?
+                .into_iter()
+                .map(|role| (role.id, role))
+                .collect(),
             splash,
             system_channel_id,
-            verification_level,
-            voice_states,
+            verification_level: verification_level
+                .ok_or_else(|| DeError::missing_field("verification_level"))?,
+            voice_states: voice_states
+                .ok_or_else(|| DeError::missing_field("voice_states"))?
+                .into_iter()
+                .map(|voice_state| (voice_state.user_id, voice_state))
+                .collect(),
             description,
-            premium_tier,
-            premium_subscription_count,
+            premium_tier: premium_tier.unwrap_or_default(),
+            premium_subscription_count: premium_subscription_count.unwrap_or(0),
             banner,
             vanity_url_code,
-            preferred_locale,
+            preferred_locale: preferred_locale.ok_or_else(|| DeError::missing_field("preferred_locale"))?,
             _nonexhaustive: (),
-        })
+        }))
     }
 }
 
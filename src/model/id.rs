@@ -131,10 +131,20 @@ pub struct AuditLogEntryId(pub u64);
 #[derive(Copy, Clone, Default, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
 pub struct AttachmentId(u64);
 
+/// An identifier for an [`ApplicationCommand`](../interactions/struct.ApplicationCommand.html).
+#[derive(Copy, Clone, Default, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
+pub struct CommandId(pub u64);
+
+/// An identifier for an [`Interaction`](../interactions/struct.Interaction.html).
+#[derive(Copy, Clone, Default, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
+pub struct InteractionId(pub u64);
+
 id_u64! {
     AttachmentId;
     ApplicationId;
     ChannelId;
+    CommandId;
+    InteractionId;
     EmojiId;
     GuildId;
     IntegrationId;
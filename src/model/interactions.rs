@@ -0,0 +1,536 @@
+//! Models for application (slash) commands and the interactions that invoke
+//! them.
+
+use serde::de::Error as DeError;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use super::prelude::*;
+use crate::internal::prelude::*;
+
+#[cfg(feature = "model")]
+use crate::builder::{
+    CreateApplicationCommand,
+    CreateInteractionResponse,
+    CreateInteractionResponseData,
+    ExecuteWebhook,
+};
+#[cfg(feature = "model")]
+use crate::http::Http;
+#[cfg(feature = "model")]
+use crate::utils;
+
+/// An interaction received from Discord, either over the gateway as an
+/// [`InteractionCreateEvent`] or over an outgoing webhook to an
+/// [interactions endpoint].
+///
+/// [`InteractionCreateEvent`]: ../event/struct.InteractionCreateEvent.html
+/// [interactions endpoint]: ../../interactions/index.html
+#[derive(Clone, Debug)]
+pub struct Interaction {
+    /// The Id of the interaction.
+    pub id: InteractionId,
+    /// The Id of the application this interaction is for.
+    pub application_id: Option<ApplicationId>,
+    /// The type of interaction.
+    pub kind: InteractionType,
+    /// The command data, present for [`InteractionType::ApplicationCommand`].
+    ///
+    /// [`InteractionType::ApplicationCommand`]: enum.InteractionType.html#variant.ApplicationCommand
+    pub data: Option<ApplicationCommandInteractionData>,
+    /// The guild the interaction was invoked in, if any.
+    pub guild_id: Option<GuildId>,
+    /// The channel the interaction was invoked in.
+    pub channel_id: Option<ChannelId>,
+    /// The member that invoked the interaction, when invoked in a guild.
+    pub member: Option<Member>,
+    /// The user that invoked the interaction, when invoked in a direct message.
+    pub user: Option<User>,
+    /// A continuation token for responding to the interaction.
+    ///
+    /// The token is valid for 15 minutes.
+    pub token: String,
+    /// The version of the interaction payload, always `1`.
+    pub version: u8,
+    pub(crate) _nonexhaustive: (),
+}
+
+#[cfg(feature = "model")]
+impl Interaction {
+    /// Creates the initial response to the interaction.
+    ///
+    /// This must be called within 3 seconds of receiving the interaction,
+    /// otherwise Discord will show the invocation as failed.
+    ///
+    /// # Examples
+    ///
+    /// Respond to a command with a message that shows the invocation:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::http::Http;
+    /// # use serenity::model::interactions::{Interaction, InteractionResponseType};
+    /// #
+    /// # fn run(http: &Http, interaction: Interaction) {
+    /// let _ = interaction.create_interaction_response(http, |r| {
+    ///     r.kind(InteractionResponseType::ChannelMessageWithSource)
+    ///         .interaction_response_data(|d| d.content("Pong!"))
+    /// });
+    /// # }
+    /// ```
+    pub fn create_interaction_response<F>(&self, http: impl AsRef<Http>, f: F) -> Result<()>
+        where F: FnOnce(&mut CreateInteractionResponse) -> &mut CreateInteractionResponse {
+        let mut response = CreateInteractionResponse::default();
+        f(&mut response);
+        let map = utils::hashmap_to_json_map(response.0);
+
+        http.as_ref().create_interaction_response(self.id.0, &self.token, &Value::Object(map))
+    }
+
+    /// Edits the initial response to the interaction.
+    ///
+    /// **Note**: Requires the [`application_id`] to be present, which is the
+    /// case for all interactions received from Discord.
+    ///
+    /// [`application_id`]: #structfield.application_id
+    pub fn edit_original_interaction_response<F>(&self, http: impl AsRef<Http>, f: F) -> Result<Message>
+        where F: FnOnce(&mut CreateInteractionResponseData) -> &mut CreateInteractionResponseData {
+        let application_id = self.application_id()?;
+        let mut data = CreateInteractionResponseData::default();
+        f(&mut data);
+        let map = utils::hashmap_to_json_map(data.0);
+
+        http.as_ref().edit_original_interaction_response(application_id.0, &self.token, &Value::Object(map))
+    }
+
+    /// Deletes the initial response to the interaction.
+    pub fn delete_original_interaction_response(&self, http: impl AsRef<Http>) -> Result<()> {
+        let application_id = self.application_id()?;
+
+        http.as_ref().delete_original_interaction_response(application_id.0, &self.token)
+    }
+
+    /// Sends a follow-up message for the interaction.
+    ///
+    /// Follow-up messages accept the same payload as a webhook execution.
+    pub fn create_followup_message<F>(&self, http: impl AsRef<Http>, f: F) -> Result<Message>
+        where F: FnOnce(&mut ExecuteWebhook) -> &mut ExecuteWebhook {
+        let application_id = self.application_id()?;
+        let mut execute_webhook = ExecuteWebhook::default();
+        f(&mut execute_webhook);
+        let map = utils::hashmap_to_json_map(execute_webhook.0);
+
+        http.as_ref().create_followup_message(application_id.0, &self.token, &map)
+    }
+
+    /// Edits a follow-up message previously sent for the interaction.
+    pub fn edit_followup_message<F, M>(&self, http: impl AsRef<Http>, message_id: M, f: F) -> Result<Message>
+        where F: FnOnce(&mut ExecuteWebhook) -> &mut ExecuteWebhook,
+              M: Into<MessageId> {
+        let application_id = self.application_id()?;
+        let mut execute_webhook = ExecuteWebhook::default();
+        f(&mut execute_webhook);
+        let map = utils::hashmap_to_json_map(execute_webhook.0);
+
+        http.as_ref().edit_followup_message(
+            application_id.0,
+            &self.token,
+            message_id.into().0,
+            &Value::Object(map),
+        )
+    }
+
+    /// Deletes a follow-up message previously sent for the interaction.
+    pub fn delete_followup_message<M: Into<MessageId>>(&self, http: impl AsRef<Http>, message_id: M) -> Result<()> {
+        let application_id = self.application_id()?;
+
+        http.as_ref().delete_followup_message(application_id.0, &self.token, message_id.into().0)
+    }
+
+    fn application_id(&self) -> Result<ApplicationId> {
+        self.application_id
+            .ok_or_else(|| Error::from(SerenityError::Other("Interaction has no application id")))
+    }
+}
+
+impl<'de> Deserialize<'de> for Interaction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        let mut map = JsonMap::deserialize(deserializer)?;
+
+        let guild_id = match map.get("guild_id") {
+            Some(&Value::Null) | None => None,
+            Some(v) => Some(GuildId::deserialize(v.clone()).map_err(DeError::custom)?),
+        };
+
+        // Members sent with an interaction do not carry their guild Id, so it
+        // is taken from the interaction itself.
+        let member = match map.remove("member") {
+            Some(Value::Object(mut member)) => {
+                if let Some(guild_id) = guild_id {
+                    member.insert("guild_id".to_string(), Value::from(guild_id.0));
+                }
+
+                Some(Member::deserialize(Value::Object(member)).map_err(DeError::custom)?)
+            },
+            _ => None,
+        };
+
+        let id = map.remove("id")
+            .ok_or_else(|| DeError::custom("expected interaction id"))
+            .and_then(InteractionId::deserialize)
+            .map_err(DeError::custom)?;
+        let application_id = match map.remove("application_id") {
+            Some(Value::Null) | None => None,
+            Some(v) => Some(ApplicationId::deserialize(v).map_err(DeError::custom)?),
+        };
+        let kind = map.remove("type")
+            .ok_or_else(|| DeError::custom("expected interaction type"))
+            .and_then(InteractionType::deserialize)
+            .map_err(DeError::custom)?;
+        let data = match map.remove("data") {
+            Some(Value::Null) | None => None,
+            Some(v) => Some(ApplicationCommandInteractionData::deserialize(v).map_err(DeError::custom)?),
+        };
+        let channel_id = match map.remove("channel_id") {
+            Some(Value::Null) | None => None,
+            Some(v) => Some(ChannelId::deserialize(v).map_err(DeError::custom)?),
+        };
+        let user = match map.remove("user") {
+            Some(Value::Null) | None => None,
+            Some(v) => Some(User::deserialize(v).map_err(DeError::custom)?),
+        };
+        let token = map.remove("token")
+            .ok_or_else(|| DeError::custom("expected interaction token"))
+            .and_then(String::deserialize)
+            .map_err(DeError::custom)?;
+        let version = match map.remove("version") {
+            Some(v) => u8::deserialize(v).map_err(DeError::custom)?,
+            None => 1,
+        };
+
+        Ok(Self {
+            id,
+            application_id,
+            kind,
+            data,
+            guild_id,
+            channel_id,
+            member,
+            user,
+            token,
+            version,
+            _nonexhaustive: (),
+        })
+    }
+}
+
+impl Serialize for Interaction {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+        where S: Serializer {
+        let mut state = serializer.serialize_struct("Interaction", 10)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("application_id", &self.application_id)?;
+        state.serialize_field("type", &self.kind)?;
+        state.serialize_field("data", &self.data)?;
+        state.serialize_field("guild_id", &self.guild_id)?;
+        state.serialize_field("channel_id", &self.channel_id)?;
+        state.serialize_field("member", &self.member)?;
+        state.serialize_field("user", &self.user)?;
+        state.serialize_field("token", &self.token)?;
+        state.serialize_field("version", &self.version)?;
+
+        state.end()
+    }
+}
+
+/// The type of an [`Interaction`].
+///
+/// [`Interaction`]: struct.Interaction.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum InteractionType {
+    /// A ping sent by Discord to verify an interactions endpoint.
+    Ping = 1,
+    /// An application command was invoked.
+    ApplicationCommand = 2,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+enum_number!(
+    InteractionType {
+        Ping,
+        ApplicationCommand,
+    }
+);
+
+impl InteractionType {
+    pub fn num(self) -> u64 {
+        match self {
+            InteractionType::Ping => 1,
+            InteractionType::ApplicationCommand => 2,
+            InteractionType::__Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
+/// The command data of an [`Interaction`].
+///
+/// [`Interaction`]: struct.Interaction.html
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApplicationCommandInteractionData {
+    /// The Id of the invoked command.
+    pub id: CommandId,
+    /// The name of the invoked command.
+    pub name: String,
+    /// The options the user filled in.
+    #[serde(default)]
+    pub options: Vec<ApplicationCommandInteractionDataOption>,
+    #[serde(skip)]
+    pub(crate) _nonexhaustive: (),
+}
+
+/// An option the user filled in when invoking a command.
+///
+/// Options of type [`SubCommand`] and [`SubCommandGroup`] carry nested
+/// [`options`] instead of a [`value`].
+///
+/// [`SubCommand`]: enum.ApplicationCommandOptionType.html#variant.SubCommand
+/// [`SubCommandGroup`]: enum.ApplicationCommandOptionType.html#variant.SubCommandGroup
+/// [`options`]: #structfield.options
+/// [`value`]: #structfield.value
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApplicationCommandInteractionDataOption {
+    /// The name of the parameter.
+    pub name: String,
+    /// The value of the parameter.
+    pub value: Option<Value>,
+    /// The nested options of a sub-command or sub-command group.
+    #[serde(default)]
+    pub options: Vec<ApplicationCommandInteractionDataOption>,
+    #[serde(skip)]
+    pub(crate) _nonexhaustive: (),
+}
+
+/// An application command registered either globally or for a single guild.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApplicationCommand {
+    /// The Id of the command.
+    pub id: CommandId,
+    /// The Id of the application the command belongs to.
+    pub application_id: ApplicationId,
+    /// The name of the command, 3 to 32 characters long.
+    pub name: String,
+    /// The description of the command, 1 to 100 characters long.
+    pub description: String,
+    /// The parameters of the command.
+    #[serde(default)]
+    pub options: Vec<ApplicationCommandOption>,
+    #[serde(skip)]
+    pub(crate) _nonexhaustive: (),
+}
+
+#[cfg(feature = "model")]
+impl ApplicationCommand {
+    /// Creates a global command, or overwrites an existing global command
+    /// with the same name.
+    ///
+    /// **Note**: Global commands may take up to an hour to become available
+    /// in all guilds. Use [`GuildId::create_application_command`] while
+    /// testing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::http::Http;
+    /// # use serenity::model::interactions::{ApplicationCommand, ApplicationCommandOptionType};
+    /// #
+    /// # fn run(http: &Http) {
+    /// let command = ApplicationCommand::create_global_application_command(http, 1, |c| {
+    ///     c.name("ping")
+    ///         .description("Replies with pong")
+    ///         .create_option(|o| {
+    ///             o.name("loud")
+    ///                 .description("Whether to shout")
+    ///                 .kind(ApplicationCommandOptionType::Boolean)
+    ///         })
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// [`GuildId::create_application_command`]: ../id/struct.GuildId.html#method.create_application_command
+    pub fn create_global_application_command<F>(
+        http: impl AsRef<Http>,
+        application_id: impl Into<ApplicationId>,
+        f: F,
+    ) -> Result<ApplicationCommand>
+        where F: FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+        let mut command = CreateApplicationCommand::default();
+        f(&mut command);
+        let map = utils::hashmap_to_json_map(command.0);
+
+        http.as_ref().create_global_application_command(application_id.into().0, &Value::Object(map))
+    }
+
+    /// Fetches all global commands of the application.
+    pub fn get_global_application_commands(
+        http: impl AsRef<Http>,
+        application_id: impl Into<ApplicationId>,
+    ) -> Result<Vec<ApplicationCommand>> {
+        http.as_ref().get_global_application_commands(application_id.into().0)
+    }
+
+    /// Edits this command, which must be a global command.
+    pub fn edit_global_application_command<F>(&mut self, http: impl AsRef<Http>, f: F) -> Result<()>
+        where F: FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+        let mut command = CreateApplicationCommand::default();
+        f(&mut command);
+        let map = utils::hashmap_to_json_map(command.0);
+
+        *self = http.as_ref().edit_global_application_command(
+            self.application_id.0,
+            self.id.0,
+            &Value::Object(map),
+        )?;
+
+        Ok(())
+    }
+
+    /// Deletes this command, which must be a global command.
+    pub fn delete_global_application_command(&self, http: impl AsRef<Http>) -> Result<()> {
+        http.as_ref().delete_global_application_command(self.application_id.0, self.id.0)
+    }
+}
+
+/// A parameter of an [`ApplicationCommand`].
+///
+/// [`ApplicationCommand`]: struct.ApplicationCommand.html
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApplicationCommandOption {
+    /// The type of the parameter.
+    #[serde(rename = "type")]
+    pub kind: ApplicationCommandOptionType,
+    /// The name of the parameter, 1 to 32 characters long.
+    pub name: String,
+    /// The description of the parameter, 1 to 100 characters long.
+    pub description: String,
+    /// Whether this is the first required parameter.
+    #[serde(default)]
+    pub default: bool,
+    /// Whether the parameter must be filled in.
+    #[serde(default)]
+    pub required: bool,
+    /// The values the user has to pick from, if any.
+    #[serde(default)]
+    pub choices: Vec<ApplicationCommandOptionChoice>,
+    /// The nested parameters of a sub-command or sub-command group.
+    #[serde(default)]
+    pub options: Vec<ApplicationCommandOption>,
+    #[serde(skip)]
+    pub(crate) _nonexhaustive: (),
+}
+
+/// A value the user can pick for an [`ApplicationCommandOption`].
+///
+/// [`ApplicationCommandOption`]: struct.ApplicationCommandOption.html
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApplicationCommandOptionChoice {
+    /// The name shown to the user.
+    pub name: String,
+    /// The value sent back in the interaction, either a string or an integer.
+    pub value: Value,
+    #[serde(skip)]
+    pub(crate) _nonexhaustive: (),
+}
+
+/// The type of an [`ApplicationCommandOption`].
+///
+/// [`ApplicationCommandOption`]: struct.ApplicationCommandOption.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum ApplicationCommandOptionType {
+    SubCommand = 1,
+    SubCommandGroup = 2,
+    String = 3,
+    Integer = 4,
+    Boolean = 5,
+    User = 6,
+    Channel = 7,
+    Role = 8,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+enum_number!(
+    ApplicationCommandOptionType {
+        SubCommand,
+        SubCommandGroup,
+        String,
+        Integer,
+        Boolean,
+        User,
+        Channel,
+        Role,
+    }
+);
+
+impl ApplicationCommandOptionType {
+    pub fn num(self) -> u64 {
+        use self::ApplicationCommandOptionType::*;
+
+        match self {
+            SubCommand => 1,
+            SubCommandGroup => 2,
+            String => 3,
+            Integer => 4,
+            Boolean => 5,
+            User => 6,
+            Channel => 7,
+            Role => 8,
+            __Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
+/// The type of response to an [`Interaction`].
+///
+/// [`Interaction`]: struct.Interaction.html
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum InteractionResponseType {
+    /// Acknowledges a [`Ping`].
+    ///
+    /// [`Ping`]: enum.InteractionType.html#variant.Ping
+    Pong = 1,
+    /// Acknowledges a command without sending a message, eating the user's
+    /// input.
+    Acknowledge = 2,
+    /// Responds with a message, eating the user's input.
+    ChannelMessage = 3,
+    /// Responds with a message, showing the user's input.
+    ChannelMessageWithSource = 4,
+    /// Acknowledges a command without sending a message, showing the user's
+    /// input.
+    AcknowledgeWithSource = 5,
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+enum_number!(
+    InteractionResponseType {
+        Pong,
+        Acknowledge,
+        ChannelMessage,
+        ChannelMessageWithSource,
+        AcknowledgeWithSource,
+    }
+);
+
+impl InteractionResponseType {
+    pub fn num(self) -> u64 {
+        use self::InteractionResponseType::*;
+
+        match self {
+            Pong => 1,
+            Acknowledge => 2,
+            ChannelMessage => 3,
+            ChannelMessageWithSource => 4,
+            AcknowledgeWithSource => 5,
+            __Nonexhaustive => unreachable!(),
+        }
+    }
+}
@@ -29,6 +29,7 @@ pub mod event;
 pub mod gateway;
 pub mod guild;
 pub mod id;
+pub mod interactions;
 pub mod invite;
 pub mod misc;
 pub mod permissions;
@@ -29,17 +29,12 @@ use crate::model::{
         UserId,
         GuildId,
     },
-    channel::{
-        Channel,
-        GuildChannel,
-    },
+    channel::Channel,
     user::User,
-    voice::VoiceState,
     ModelError,
     gateway::Presence,
     guild::{
         Emoji,
-        Member,
         Role,
     },
 };
@@ -73,35 +68,6 @@ pub fn serialize_emojis<S: Serializer>(
     seq.end()
 }
 
-pub fn deserialize_guild_channels<'de, D: Deserializer<'de>>(
-    deserializer: D)
-    -> StdResult<HashMap<ChannelId, Arc<RwLock<GuildChannel>>>, D::Error> {
-    let vec: Vec<GuildChannel> = Deserialize::deserialize(deserializer)?;
-    let mut map = HashMap::new();
-
-    for channel in vec {
-        map.insert(channel.id, Arc::new(RwLock::new(channel)));
-    }
-
-    Ok(map)
-}
-
-
-pub fn deserialize_members<'de, D: Deserializer<'de>>(
-    deserializer: D)
-    -> StdResult<HashMap<UserId, Member>, D::Error> {
-    let vec: Vec<Member> = Deserialize::deserialize(deserializer)?;
-    let mut members = HashMap::new();
-
-    for member in vec {
-        let user_id = member.user.read().id;
-
-        members.insert(user_id, member);
-    }
-
-    Ok(members)
-}
-
 pub fn deserialize_presences<'de, D: Deserializer<'de>>(
     deserializer: D)
     -> StdResult<HashMap<UserId, Presence>, D::Error> {
@@ -268,19 +234,6 @@ pub fn serialize_u64<S: Serializer>(data: &u64, ser: S) -> StdResult<S::Ok, S::E
     ser.serialize_str(&data.to_string())
 }
 
-pub fn deserialize_voice_states<'de, D: Deserializer<'de>>(
-    deserializer: D)
-    -> StdResult<HashMap<UserId, VoiceState>, D::Error> {
-    let vec: Vec<VoiceState> = Deserialize::deserialize(deserializer)?;
-    let mut voice_states = HashMap::new();
-
-    for voice_state in vec {
-        voice_states.insert(voice_state.user_id, voice_state);
-    }
-
-    Ok(voice_states)
-}
-
 pub fn serialize_gen_map<K: Eq + Hash, S: Serializer, V: Serialize>(
     map: &HashMap<K, V>,
     serializer: S,
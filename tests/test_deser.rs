@@ -127,6 +127,11 @@ fn guild_update() {
     p!(GuildUpdateEvent, "guild_update_1");
 }
 
+#[test]
+fn interaction_create() {
+    p!(InteractionCreateEvent, "interaction_create_1");
+}
+
 #[test]
 fn message_create() {
     // standard
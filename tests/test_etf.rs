@@ -0,0 +1,104 @@
+#![cfg(feature = "gateway")]
+
+use serde::de::Deserialize;
+use serde_json::Value;
+use serenity::gateway::etf;
+use serenity::model::prelude::*;
+use std::fs::File;
+
+// Round-trips a captured payload through ETF, once as-is and once the way the
+// gateway sends it, with snowflakes as integers rather than strings.
+macro_rules! p {
+    ($s:ident, $filename:expr) => {{
+        let f = File::open(concat!("./tests/resources/", $filename, ".json"))
+            .expect("Opening test file");
+
+        let v = serde_json::from_reader::<File, Value>(f).expect("Loading test file");
+
+        let encoded = etf::encode(&v).expect("Encoding file");
+        let decoded = etf::decode(&encoded).expect("Decoding file");
+        assert_eq!(decoded, v);
+        $s::deserialize(decoded).expect("Deserializing file");
+
+        let snowflakes = integer_snowflakes(v);
+        let encoded = etf::encode(&snowflakes).expect("Encoding file (2)");
+        let decoded = etf::decode(&encoded).expect("Decoding file (2)");
+        assert_eq!(decoded, snowflakes);
+        $s::deserialize(decoded).expect("Deserializing file (2)")
+    }};
+}
+
+fn integer_snowflakes(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| {
+            let value = match value {
+                Value::String(ref s) if key.ends_with("id") => s
+                    .parse::<u64>()
+                    .map(Value::from)
+                    .unwrap_or(value),
+                value => integer_snowflakes(value),
+            };
+
+            (key, value)
+        }).collect()),
+        Value::Array(list) => Value::Array(list.into_iter().map(integer_snowflakes).collect()),
+        value => value,
+    }
+}
+
+#[test]
+fn channel_create() {
+    p!(ChannelCreateEvent, "channel_create_1");
+}
+
+#[test]
+fn guild_create() {
+    p!(GuildCreateEvent, "guild_create_1");
+    p!(GuildCreateEvent, "guild_create_2");
+}
+
+#[test]
+fn guild_member_update() {
+    p!(GuildMemberUpdateEvent, "guild_member_update_1");
+}
+
+#[test]
+fn message_create() {
+    let event = p!(MessageCreateEvent, "message_create_1");
+    assert_eq!(event.message.id, MessageId(302_917_639_565_475_840));
+
+    p!(MessageCreateEvent, "message_create_2");
+}
+
+#[test]
+fn message_reaction_add() {
+    p!(ReactionAddEvent, "message_reaction_add_1");
+}
+
+#[test]
+fn ready() {
+    p!(ReadyEvent, "ready_1");
+}
+
+#[test]
+fn typing_start() {
+    p!(TypingStartEvent, "typing_start_1");
+}
+
+#[test]
+fn voice_state_update() {
+    p!(VoiceStateUpdateEvent, "voice_state_update_1");
+}
+
+#[test]
+fn gateway_event() {
+    let hello: Value = serde_json::from_str(r#"{"op":10,"d":{"heartbeat_interval":41250,"_trace":["gateway-prd-main-1"]},"s":null,"t":null}"#)
+        .unwrap();
+
+    let decoded = etf::decode(&etf::encode(&hello).unwrap()).unwrap();
+
+    match GatewayEvent::deserialize(decoded).unwrap() {
+        GatewayEvent::Hello(interval) => assert_eq!(interval, 41250),
+        other => panic!("Expected a Hello, got {:?}", other),
+    }
+}